@@ -0,0 +1,2 @@
+-- Detected language (ISO 639-3, via whatlang) of document text_clean
+ALTER TABLE rag.document ADD COLUMN IF NOT EXISTS lang TEXT;
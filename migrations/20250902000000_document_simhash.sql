@@ -0,0 +1,3 @@
+-- Near-duplicate detection: SimHash of text_clean and pointer to the doc it duplicates
+ALTER TABLE rag.document ADD COLUMN IF NOT EXISTS simhash BIGINT;
+ALTER TABLE rag.document ADD COLUMN IF NOT EXISTS canonical_doc_id BIGINT REFERENCES rag.document(doc_id) ON DELETE SET NULL;
@@ -0,0 +1,2 @@
+-- Ingest high-water mark: newest item published_at seen per feed
+ALTER TABLE rag.feed ADD COLUMN IF NOT EXISTS last_item_at TIMESTAMPTZ;
@@ -0,0 +1,9 @@
+-- content_hash the current chunks were built from; chunk --force skips docs where it still matches
+ALTER TABLE rag.document ADD COLUMN IF NOT EXISTS chunked_hash TEXT;
+
+-- docs chunked before the column existed were chunked from their current content
+UPDATE rag.document d
+SET chunked_hash = d.content_hash
+WHERE d.chunked_hash IS NULL
+  AND (d.status IN ('chunked', 'embedded')
+       OR EXISTS (SELECT 1 FROM rag.chunk c WHERE c.doc_id = d.doc_id));
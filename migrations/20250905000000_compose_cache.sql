@@ -0,0 +1,11 @@
+-- compose answers keyed on md5(model, system, prompt); chunk_ids let gc drop answers whose sources changed
+CREATE TABLE IF NOT EXISTS rag.compose_cache (
+  cache_key          TEXT PRIMARY KEY,
+  model              TEXT NOT NULL,
+  answer             TEXT NOT NULL,
+  prompt_tokens      INT,
+  completion_tokens  INT,
+  total_tokens       INT,
+  chunk_ids          BIGINT[] NOT NULL,
+  created_at         TIMESTAMPTZ NOT NULL DEFAULT now()
+);
@@ -0,0 +1,6 @@
+-- Ingest error taxonomy: rename the pre-taxonomy error tags to their IngestError kinds
+UPDATE rag.document SET error_msg = 'non-html'
+WHERE status = 'error' AND error_msg = 'unsupported-content-type';
+
+UPDATE rag.document SET error_msg = 'extract-empty'
+WHERE status = 'error' AND error_msg = 'extract-failed';
@@ -0,0 +1,10 @@
+-- per-document centroid (mean of its chunk vectors) for query --level doc; refreshed by embed
+-- vec is unconstrained so it follows whatever width rag.embedding.vec uses; doc counts are small enough to scan
+CREATE TABLE IF NOT EXISTS rag.doc_embedding (
+  doc_id       BIGINT PRIMARY KEY REFERENCES rag.document(doc_id) ON DELETE CASCADE,
+  model        TEXT NOT NULL,
+  dim          INTEGER NOT NULL,
+  vec          vector NOT NULL,
+  chunk_count  INTEGER NOT NULL,
+  updated_at   TIMESTAMPTZ NOT NULL DEFAULT now()
+);
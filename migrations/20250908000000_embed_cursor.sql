@@ -0,0 +1,9 @@
+-- progress marker of an unfinished embed --apply per model tag, so restarts keep a stable X-of-Y denominator
+CREATE TABLE IF NOT EXISTS rag.embed_cursor (
+  model          TEXT PRIMARY KEY,
+  scope          TEXT NOT NULL,     -- --feed/--since the run was started with
+  last_chunk_id  BIGINT NOT NULL,
+  done           BIGINT NOT NULL,
+  total          BIGINT NOT NULL,
+  updated_at     TIMESTAMPTZ NOT NULL DEFAULT now()
+);
@@ -0,0 +1,12 @@
+-- sign-bit code of each embedding (bit i = vec[i] > 0), written by embed for query --quantized:
+-- a Hamming-distance pre-filter over these codes before exact re-ranking on vec
+ALTER TABLE rag.embedding ADD COLUMN IF NOT EXISTS vec_bits BIT VARYING;
+
+-- backfill existing rows with the same rule as util::quantize::bit_string (bit set when > 0);
+-- spelled out over real[] so it doesn't need pgvector's binary_quantize
+UPDATE rag.embedding e
+SET vec_bits = (
+  SELECT string_agg(CASE WHEN x > 0 THEN '1' ELSE '0' END, '' ORDER BY i)
+  FROM unnest(e.vec::real[]) WITH ORDINALITY AS t(x, i)
+)::varbit
+WHERE e.vec_bits IS NULL;
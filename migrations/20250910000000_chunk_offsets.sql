@@ -0,0 +1,4 @@
+-- [char_start, char_end) of each chunk in document.text_clean, in characters (substring() positions
+-- minus one); NULL for chunks written before this column or cut from NFKC-normalized text
+ALTER TABLE rag.chunk ADD COLUMN IF NOT EXISTS char_start INT;
+ALTER TABLE rag.chunk ADD COLUMN IF NOT EXISTS char_end INT;
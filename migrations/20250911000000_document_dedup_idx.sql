@@ -0,0 +1,5 @@
+-- ingest --dedup-threshold compares against recent canonical docs only; this keeps that scan
+-- to the window instead of the whole table
+CREATE INDEX IF NOT EXISTS document_dedup_candidates_idx
+  ON rag.document (fetched_at)
+  WHERE simhash IS NOT NULL AND canonical_doc_id IS NULL AND status IN ('ingest', 'chunked', 'embedded');
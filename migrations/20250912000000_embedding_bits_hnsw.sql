@@ -0,0 +1,21 @@
+-- query --quantized: fixed-width sign-bit codes with an HNSW Hamming index (pgvector >= 0.7), so the
+-- pre-filter is an index scan ordered by `<~>` instead of a sequential scan and sort over every code.
+-- The width follows the declared vector(N); codes of another length are cleared (re-embed refills them).
+DO $$
+DECLARE
+  dim int;
+BEGIN
+  SELECT a.atttypmod INTO dim
+  FROM pg_attribute a
+  WHERE a.attrelid = 'rag.embedding'::regclass AND a.attname = 'vec' AND NOT a.attisdropped;
+  IF dim IS NULL OR dim <= 0 THEN
+    RAISE NOTICE 'rag.embedding.vec has no declared dimension; vec_bits stays BIT VARYING without an index';
+    RETURN;
+  END IF;
+  EXECUTE format(
+    'ALTER TABLE rag.embedding ALTER COLUMN vec_bits TYPE bit(%1$s)
+       USING CASE WHEN bit_length(vec_bits) = %1$s THEN vec_bits::bit(%1$s) END',
+    dim);
+  CREATE INDEX IF NOT EXISTS embedding_vec_bits_hnsw_idx
+    ON rag.embedding USING hnsw (vec_bits bit_hamming_ops);
+END $$;
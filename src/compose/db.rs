@@ -0,0 +1,46 @@
+use anyhow::Result;
+use sqlx::PgPool;
+
+pub struct CachedAnswer {
+    pub answer: String,
+    pub prompt_tokens: Option<i32>,
+    pub completion_tokens: Option<i32>,
+    pub total_tokens: Option<i32>,
+}
+
+pub async fn lookup(pool: &PgPool, key: &str) -> Result<Option<CachedAnswer>> {
+    let row = sqlx::query_as!(
+        CachedAnswer,
+        "SELECT answer, prompt_tokens, completion_tokens, total_tokens FROM rag.compose_cache WHERE cache_key = $1",
+        key
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn store(pool: &PgPool, key: &str, model: &str, cached: &CachedAnswer, chunk_ids: &[i64]) -> Result<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO rag.compose_cache (cache_key, model, answer, prompt_tokens, completion_tokens, total_tokens, chunk_ids)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (cache_key) DO UPDATE
+          SET answer = EXCLUDED.answer,
+              prompt_tokens = EXCLUDED.prompt_tokens,
+              completion_tokens = EXCLUDED.completion_tokens,
+              total_tokens = EXCLUDED.total_tokens,
+              chunk_ids = EXCLUDED.chunk_ids,
+              created_at = now()
+        "#,
+        key,
+        model,
+        cached.answer,
+        cached.prompt_tokens,
+        cached.completion_tokens,
+        cached.total_tokens,
+        chunk_ids
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
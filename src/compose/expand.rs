@@ -0,0 +1,165 @@
+// compose --multi-query: have the LLM rewrite the question into N search queries, retrieve for
+// the original plus each rewrite, and fuse the ranked lists with reciprocal rank fusion.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+
+use crate::llm::openai::{ChatCompletionRequest, ChatMessage, ChatRole, LlmClient};
+use crate::query::service::QueryOutcome;
+use crate::telemetry::ctx::LogCtx;
+use crate::telemetry::ops::compose::Compose as ComposeOp;
+
+use super::call_llm;
+
+// RRF damping constant from Cormack et al.; large enough that rank 1 vs 2 of one list
+// doesn't outweigh agreement across lists
+const RRF_K: f64 = 60.0;
+
+const EXPAND_SYSTEM: &str = "You rewrite questions into search queries for a document retrieval system. \
+Reply with only the queries, one per line, without numbering or commentary. \
+Each query should approach the question differently: a paraphrase, a narrower sub-question, or the key terms.";
+
+pub(super) fn expansion_request(query: &str, n: usize, model: &str, sampling: (Option<f32>, Option<f32>, Option<u64>)) -> ChatCompletionRequest {
+    let (temperature, top_p, seed) = sampling;
+    ChatCompletionRequest {
+        model: Some(model.to_string()),
+        messages: vec![
+            ChatMessage::new(ChatRole::System, EXPAND_SYSTEM),
+            ChatMessage::new(ChatRole::User, format!("Write {n} search queries for this question:\n{query}")),
+        ],
+        max_tokens: None,
+        temperature,
+        top_p,
+        response_format: None,
+        seed,
+    }
+}
+
+/// Up to `n` distinct rewrites from the reply; list markers and quotes are stripped, and lines
+/// repeating the original question are dropped.
+pub(super) fn parse_sub_queries(reply: &str, original: &str, n: usize) -> Vec<String> {
+    let mut seen = vec![original.trim().to_lowercase()];
+    let mut out = Vec::new();
+    for line in reply.lines() {
+        let line = line.trim().trim_start_matches(['-', '*', '•']).trim_start();
+        let line = match line.split_once(['.', ')']) {
+            Some((num, rest)) if !num.is_empty() && num.chars().all(|c| c.is_ascii_digit()) => rest.trim(),
+            _ => line,
+        };
+        let line = line.trim_matches(['"', '\'', '`']).trim();
+        if line.is_empty() || seen.contains(&line.to_lowercase()) { continue; }
+        seen.push(line.to_lowercase());
+        out.push(line.to_string());
+        if out.len() == n { break; }
+    }
+    out
+}
+
+pub(super) async fn sub_queries(
+    client: &dyn LlmClient,
+    query: &str,
+    n: usize,
+    model: &str,
+    sampling: (Option<f32>, Option<f32>, Option<u64>),
+    log: &LogCtx<ComposeOp>,
+) -> Result<Vec<String>> {
+    let reply = call_llm(client, expansion_request(query, n, model, sampling), log).await?;
+    let subs = parse_sub_queries(&reply.content, query, n);
+    if subs.len() < n {
+        log.warn(format!("⚠️  Asked for {} sub-queries, the LLM returned {} usable", n, subs.len()));
+    }
+    Ok(subs)
+}
+
+/// Reciprocal rank fusion: each chunk scores Σ 1/(RRF_K + rank) over the lists it appears in.
+/// Keeps the best `topk` with at most `doc_cap` chunks per doc (as in `shape_results`), re-ranked from 1;
+/// rows/hits come from the first list that returned the chunk, so the original query's
+/// distance wins when it found the chunk too.
+pub(super) fn fuse(outcomes: Vec<QueryOutcome>, topk: usize, doc_cap: usize) -> QueryOutcome {
+    let mut scores: HashMap<i64, f64> = HashMap::new();
+    let mut order: Vec<i64> = Vec::new();
+    let mut rows = HashMap::new();
+    let mut hits = HashMap::new();
+    let mut fused = QueryOutcome { rows: Vec::new(), hits: Vec::new(), probes: None, ef_search: None, too_distant: 0, explain: None };
+    for outcome in outcomes {
+        fused.probes = fused.probes.or(outcome.probes);
+        fused.ef_search = fused.ef_search.or(outcome.ef_search);
+        fused.too_distant += outcome.too_distant;
+        for row in outcome.rows {
+            *scores.entry(row.chunk_id).or_insert_with(|| { order.push(row.chunk_id); 0.0 }) += 1.0 / (RRF_K + row.rank as f64);
+            rows.entry(row.chunk_id).or_insert(row);
+        }
+        for hit in outcome.hits {
+            hits.entry(hit.chunk_id).or_insert(hit);
+        }
+    }
+
+    // stable sort keeps first-seen order (original query first) on ties
+    order.sort_by(|a, b| scores[b].total_cmp(&scores[a]));
+    let mut per_doc: HashMap<i64, usize> = HashMap::new();
+    for chunk_id in order {
+        if fused.rows.len() == topk { break; }
+        let Some(mut row) = rows.remove(&chunk_id) else { continue };
+        let n = per_doc.entry(row.doc_id).or_insert(0);
+        if *n >= doc_cap { continue; }
+        *n += 1;
+        row.rank = fused.rows.len() + 1;
+        if let Some(mut hit) = hits.remove(&chunk_id) {
+            hit.rank = row.rank;
+            fused.hits.push(hit);
+        }
+        fused.rows.push(row);
+    }
+    fused
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::service::QueryHit;
+    use crate::query::QueryResultRow;
+
+    fn outcome(ids: &[(i64, i64)]) -> QueryOutcome {
+        let rows = ids.iter().enumerate().map(|(i, &(chunk_id, doc_id))| QueryResultRow {
+            rank: i + 1, distance: 0.1 * (i + 1) as f32, chunk_id, doc_id, title: None, preview: None, text: None, rerank_score: None,
+        }).collect();
+        let hits = ids.iter().enumerate().map(|(i, &(chunk_id, doc_id))| QueryHit {
+            rank: i + 1, distance: 0.1 * (i + 1) as f32, chunk_id, doc_id, chunk_index: None, title: None, preview: None, text: None,
+        }).collect();
+        QueryOutcome { rows, hits, probes: None, ef_search: None, too_distant: 1, explain: None }
+    }
+
+    #[test]
+    fn sub_queries_drop_markers_duplicates_and_the_original() {
+        let reply = "1. How does attention work?\n- \"transformer self-attention\"\n\n2) What is attention\nHow does attention work?\n* attention heads\n";
+        assert_eq!(
+            parse_sub_queries(reply, "what is attention", 5),
+            vec!["How does attention work?", "transformer self-attention", "attention heads"]
+        );
+        assert_eq!(parse_sub_queries(reply, "q", 1), vec!["How does attention work?"]);
+    }
+
+    #[test]
+    fn rrf_favours_chunks_found_by_several_queries() {
+        // chunk 30 is only third for the original query but appears in every list
+        let fused = fuse(vec![
+            outcome(&[(10, 1), (20, 2), (30, 3)]),
+            outcome(&[(30, 3), (40, 4)]),
+            outcome(&[(50, 5), (30, 3)]),
+        ], 3, 3);
+        let ids: Vec<i64> = fused.rows.iter().map(|r| r.chunk_id).collect();
+        assert_eq!(ids, vec![30, 10, 50]);
+        assert_eq!(fused.rows.iter().map(|r| r.rank).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(fused.hits.iter().map(|h| h.chunk_id).collect::<Vec<_>>(), ids);
+        // distance of chunk 30 is the original query's
+        assert!((fused.rows[0].distance - 0.3).abs() < 1e-6);
+        assert_eq!(fused.too_distant, 3);
+    }
+
+    #[test]
+    fn fusion_respects_doc_cap() {
+        let fused = fuse(vec![outcome(&[(10, 1), (11, 1), (20, 2)]), outcome(&[(11, 1), (10, 1)])], 3, 1);
+        assert_eq!(fused.rows.iter().map(|r| r.chunk_id).collect::<Vec<_>>(), vec![10, 20]);
+    }
+}
@@ -1,19 +1,30 @@
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use clap::Args;
 use chrono::{DateTime, Utc};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use sqlx::PgPool;
+use std::path::{Path, PathBuf};
 
 use crate::llm::openai::{
-    ChatCompletionRequest, ChatMessage, ChatRole, LlmClient, OpenAiClient,
-    OpenAiClientConfig, OpenAiError,
+    ChatCompletionRequest, ChatCompletionResponse, ChatMessage, ChatRole, LlmClient,
+    OpenAiError, ResponseFormat,
 };
+use crate::llm::pricing::{self, PriceTable};
+use crate::llm::LlmProvider;
+use crate::tokenizer::E5Tokenizer;
+use crate::llm::schema;
 use crate::query::service::{QueryRequest, QueryOutcome};
 use crate::telemetry;
-use crate::telemetry::ops::compose::Phase as ComposePhase;
+use crate::telemetry::ctx::LogCtx;
+use crate::telemetry::ops::compose::{Compose as ComposeOp, Phase as ComposePhase};
+use crate::util::exit;
 use crate::util::time::parse_since_opt;
 use crate::encoder::Device;
 
+mod db;
+mod expand;
+
 #[derive(Args, Debug)]
 pub struct ComposeCmd {
     query: String,
@@ -26,26 +37,67 @@ pub struct ComposeCmd {
     #[arg(long)]
     probes: Option<i32>,
     #[arg(long)]
+    ef_search: Option<i32>,
+    #[arg(long)]
     feed: Option<i32>,
     #[arg(long)]
     since: Option<String>,
+    /// Stitch N preceding/following chunks of the same doc around each hit
+    #[arg(long, default_value_t = 0)]
+    expand_neighbors: usize,
+    /// Drop hits whose L2 distance exceeds D
+    #[arg(long)]
+    max_distance: Option<f32>,
+    /// Drop hits below this cosine similarity
+    #[arg(long)]
+    min_similarity: Option<f32>,
+    /// Chat API to call (default: RAG_LLM_PROVIDER, else openai)
+    #[arg(long, value_enum)]
+    llm_provider: Option<LlmProvider>,
     #[arg(long)]
     model: Option<String>,
     #[arg(long)]
     system: Option<String>,
+    /// System prompt template file ({query}, {num_sources}, {date}); --system wins when both are set
     #[arg(long)]
-    max_tokens: Option<u32>,
+    system_file: Option<PathBuf>,
+    /// User message template file; {context} is replaced by the retrieved sources
     #[arg(long)]
-    temperature: Option<f32>,
+    prompt_file: Option<PathBuf>,
     #[arg(long)]
+    max_tokens: Option<u32>,
+    #[arg(long, conflicts_with = "deterministic")]
+    temperature: Option<f32>,
+    #[arg(long, conflicts_with = "deterministic")]
     top_p: Option<f32>,
+    /// temperature=0, top_p=1 and a fixed seed (--seed, default 0) for replayable answers
+    #[arg(long, default_value_t = false)]
+    deterministic: bool,
+    /// Sampling seed sent to the API (OpenAI `seed`; Anthropic has no equivalent and ignores it)
+    #[arg(long)]
+    seed: Option<u64>,
+    /// JSON Schema file; the model must reply with matching JSON (one corrective retry)
+    #[arg(long)]
+    response_schema: Option<PathBuf>,
+    /// Ask the LLM for N rewrites of the question, retrieve for each plus the original, and fuse the hits (RRF)
+    #[arg(long, default_value_t = 0)]
+    multi_query: usize,
     #[arg(long, default_value_t = false)]
     dry_run: bool,
-    #[arg(long, default_value = "intfloat/e5-small-v2")]
+    /// On empty retrieval, still ask the LLM (told no sources were found); the result has grounded=false
+    #[arg(long, default_value_t = false)]
+    allow_no_context: bool,
+    /// Always call the LLM, ignoring (and not writing) rag.compose_cache
+    #[arg(long, default_value_t = false)]
+    no_cache: bool,
+    /// Write the exact JSON body sent (or, with --dry-run / a cache hit, that would be sent) to the LLM
+    #[arg(long)]
+    dump_prompt: Option<PathBuf>,
+    #[arg(long, env = "RAG_EMBED_MODEL", default_value = "intfloat/e5-small-v2")]
     embed_model: String,
     #[arg(long)]
     embed_onnx_filename: Option<String>,
-    #[arg(long, value_enum, default_value_t = Device::Cpu)]
+    #[arg(long, env = "RAG_DEVICE", value_enum, default_value_t = Device::Cpu)]
     device: Device,
 }
 
@@ -57,6 +109,9 @@ struct ComposePlan<'a> {
     system_message: &'a str,
     hit_count: usize,
     dry_run: bool,
+    // --multi-query rewrites retrieved alongside the original question
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    sub_queries: Vec<String>,
     hits: Vec<ComposeHit>,
     prompt_sections: Vec<PromptSection<'a>>,
 }
@@ -66,12 +121,25 @@ struct ComposeResult<'a> {
     query: &'a str,
     model: String,
     answer: &'a str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    sub_queries: Vec<String>,
     hits: Vec<ComposeHit>,
     retrieved_chunks: usize,
     usage: Option<UsageDto>,
+    // from usage and the model's price (RAG_LLM_PRICING); None when the model has no price
+    cost_usd: Option<f64>,
+    cached: bool,
+    // parsed answer under --response-schema
+    #[serde(skip_serializing_if = "Option::is_none")]
+    structured: Option<Value>,
+    // sampling seed sent with the request, so the run can be replayed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+    // false when the answer came from model knowledge only (--allow-no-context, nothing retrieved)
+    grounded: bool,
 }
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 struct ComposeHit {
     rank: usize,
     doc_id: i64,
@@ -88,11 +156,20 @@ struct PromptSection<'a> {
     source: &'a str,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 struct UsageDto {
     prompt_tokens: Option<u32>,
     completion_tokens: Option<u32>,
     total_tokens: Option<u32>,
+    // counted with the local tokenizer because the API returned no usage
+    estimated: bool,
+}
+
+impl UsageDto {
+    fn cost_usd(&self, model: &str, prices: &PriceTable) -> Option<f64> {
+        let price = prices.lookup(model)?;
+        Some(pricing::estimate_usd(price, self.prompt_tokens.unwrap_or(0), self.completion_tokens.unwrap_or(0)))
+    }
 }
 
 pub async fn run(pool: &PgPool, args: ComposeCmd) -> Result<()> {
@@ -103,14 +180,28 @@ pub async fn run(pool: &PgPool, args: ComposeCmd) -> Result<()> {
             ("topk", args.topk.to_string()),
             ("doc_cap", args.doc_cap.to_string()),
             ("probes", format!("{:?}", args.probes)),
+            ("ef_search", format!("{:?}", args.ef_search)),
             ("feed", format!("{:?}", args.feed)),
             ("since", format!("{:?}", args.since)),
+            ("expand_neighbors", args.expand_neighbors.to_string()),
+            ("max_distance", format!("{:?}", args.max_distance)),
+            ("min_similarity", format!("{:?}", args.min_similarity)),
+            ("llm_provider", format!("{:?}", args.llm_provider)),
             ("model", format!("{:?}", args.model)),
+            ("system_file", format!("{:?}", args.system_file)),
+            ("prompt_file", format!("{:?}", args.prompt_file)),
             ("embed_model", args.embed_model.clone()),
             ("embed_onnx", format!("{:?}", args.embed_onnx_filename)),
+            ("multi_query", args.multi_query.to_string()),
             ("dry_run", args.dry_run.to_string()),
+            ("no_cache", args.no_cache.to_string()),
+            ("allow_no_context", args.allow_no_context.to_string()),
+            ("dump_prompt", format!("{:?}", args.dump_prompt)),
+            ("deterministic", args.deterministic.to_string()),
+            ("seed", format!("{:?}", args.seed)),
             ("temperature", format!("{:?}", args.temperature)),
             ("top_p", format!("{:?}", args.top_p)),
+            ("response_schema", format!("{:?}", args.response_schema)),
             ("max_tokens", format!("{:?}", args.max_tokens)),
             ("device", format!("{:?}", args.device)),
         ])
@@ -118,13 +209,42 @@ pub async fn run(pool: &PgPool, args: ComposeCmd) -> Result<()> {
 
     let _prepare_span = log.span(&ComposePhase::Prepare).entered();
     let since_ts: Option<DateTime<Utc>> = parse_since_opt(&args.since)?;
+    let provider = LlmProvider::resolve(args.llm_provider);
+    let model_name = args
+        .model
+        .clone()
+        .unwrap_or_else(|| provider.default_model());
+    let client = provider.client().map_err(to_anyhow).context("init LLM client")?;
+    // a malformed RAG_LLM_PRICING fails here, before any paid call
+    let prices = PriceTable::from_env()?;
     drop(_prepare_span);
 
+    // runs under --dry-run too: the plan shows the generated sub-queries
+    let sub_queries = if args.multi_query > 0 {
+        let _expand_span = log.span(&ComposePhase::Expand).entered();
+        let subs = expand::sub_queries(client.as_ref(), &args.query, args.multi_query, &model_name, sampling(&args), &log).await?;
+        log.info(format!("🧠 Expanded into {} sub-quer{}", subs.len(), if subs.len() == 1 { "y" } else { "ies" }));
+        for q in &subs { log.info(format!("   - {q}")); }
+        subs
+    } else {
+        Vec::new()
+    };
+
     let _retrieve_span = log.span(&ComposePhase::Retrieve).entered();
-    let outcome = fetch_hits(pool, &args, since_ts).await?;
+    let outcome = fetch_hits(pool, &args, since_ts, &sub_queries).await?;
     drop(_retrieve_span);
 
-    if outcome.rows.is_empty() {
+    let grounded = !outcome.rows.is_empty();
+    if !grounded && args.allow_no_context {
+        log.warn("⚠️  No sources found — answering from model knowledge only (--allow-no-context)");
+    } else if !grounded && outcome.too_distant > 0 {
+        log.info(format!(
+            "ℹ️  No sufficiently relevant context found — {} candidate(s) exceeded the distance threshold; not calling the LLM",
+            outcome.too_distant
+        ));
+        exit::mark_empty();
+        return Ok(());
+    } else if !grounded {
         let hint = if args.feed.is_some() || args.since.is_some() {
             let mut details = Vec::new();
             if let Some(feed) = args.feed { details.push(format!("feed={feed}")); }
@@ -141,23 +261,65 @@ pub async fn run(pool: &PgPool, args: ComposeCmd) -> Result<()> {
             "ensure documents have been ingested, chunked, and embedded before composing".to_string()
         };
         log.info(format!("ℹ️  No results — {hint}"));
+        exit::mark_empty();
         return Ok(());
     }
 
-    let system_message = args
-        .system
-        .clone()
-        .unwrap_or_else(|| "You are a helpful assistant.".to_string());
-    let client_cfg = OpenAiClientConfig::from_env();
-    let model_name = args
-        .model
-        .clone()
-        .unwrap_or_else(|| client_cfg.default_model.clone());
-
+    let system_template = match (&args.system, &args.system_file) {
+        (Some(system), _) => system.clone(),
+        (None, Some(path)) => read_template(path)?,
+        (None, None) => DEFAULT_SYSTEM.to_string(),
+    };
+    let user_template = match &args.prompt_file {
+        Some(path) => read_template(path)?,
+        None => DEFAULT_USER_TEMPLATE.to_string(),
+    };
+    let today = Utc::now().format("%Y-%m-%d").to_string();
+    let mut system_message = render_template(&system_template, &TemplateVars {
+        query: &args.query,
+        num_sources: outcome.hits.len(),
+        date: &today,
+        context: "",
+    });
+    if !grounded {
+        system_message.push_str(NO_CONTEXT_NOTE);
+    }
     let hits = extract_hits(&outcome);
     let hit_count = hits.len();
     log.info(format!("📚 Retrieved {hit_count} chunk{}", if hit_count == 1 { "" } else { "s" }));
 
+    let prompt = build_prompt(&args.query, &outcome, &user_template, &today);
+
+    let response_schema: Option<Value> = match &args.response_schema {
+        Some(path) => Some(
+            serde_json::from_str(&read_template(path)?)
+                .with_context(|| format!("parse JSON schema {}", path.display()))?,
+        ),
+        None => None,
+    };
+
+    let (temperature, top_p, seed) = sampling(&args);
+    let request = ChatCompletionRequest {
+        model: Some(model_name.clone()),
+        messages: vec![
+            ChatMessage::new(ChatRole::System, system_message.clone()),
+            ChatMessage::new(ChatRole::User, prompt.clone()),
+        ],
+        max_tokens: args.max_tokens,
+        temperature,
+        top_p,
+        response_format: response_schema.as_ref().map(|schema| ResponseFormat::JsonSchema {
+            name: "compose_answer".to_string(),
+            schema: schema.clone(),
+        }),
+        seed,
+    };
+
+    if let Some(path) = &args.dump_prompt {
+        dump_prompt(path, &client.request_body(&request))?;
+        log.info_kv("📝 Prompt payload written", [("path", path.display().to_string())]);
+    }
+
     if args.dry_run {
         let prompt_sections = build_prompt_sections(&outcome);
         let plan = ComposePlan {
@@ -167,6 +329,7 @@ pub async fn run(pool: &PgPool, args: ComposeCmd) -> Result<()> {
             system_message: &system_message,
             hit_count,
             dry_run: args.dry_run,
+            sub_queries,
             hits: hits.clone(),
             prompt_sections,
         };
@@ -175,70 +338,99 @@ pub async fn run(pool: &PgPool, args: ComposeCmd) -> Result<()> {
         return Ok(());
     }
 
-    let prompt = build_prompt(&args.query, &outcome);
+    let key = cache_key(&model_name, &system_message, &prompt, response_schema.as_ref(), (temperature, top_p, seed), args.max_tokens);
+    let chunk_ids: Vec<i64> = outcome.hits.iter().map(|h| h.chunk_id).collect();
+    if !args.no_cache {
+        let _cache_span = log.span(&ComposePhase::Cache).entered();
+        if let Some(hit) = db::lookup(pool, &key).await? {
+            log.info_kv("♻️  Cached answer", [("cache_key", key.clone())]);
+            log.info(format!("💡 Answer:\n{}", hit.answer));
+            let result = ComposeResult {
+                query: &args.query,
+                model: model_name,
+                answer: &hit.answer,
+                sub_queries,
+                hits,
+                retrieved_chunks: hit_count,
+                usage: Some(UsageDto {
+                    prompt_tokens: hit.prompt_tokens.map(|n| n as u32),
+                    completion_tokens: hit.completion_tokens.map(|n| n as u32),
+                    total_tokens: hit.total_tokens.map(|n| n as u32),
+                    estimated: false,
+                }),
+                // nothing was spent on a cache hit
+                cost_usd: Some(0.0),
+                cached: true,
+                structured: response_schema.as_ref().and_then(|_| serde_json::from_str(&hit.answer).ok()),
+                seed,
+                grounded,
+            };
+            drop(_cache_span);
+            let _out_span = log.span(&ComposePhase::Output).entered();
+            log.result(&result)?;
+            return Ok(());
+        }
+    }
 
     let _prompt_span = log.span(&ComposePhase::Prompt).entered();
-    log.info("🧠 Calling OpenAI compose endpoint");
+    log.info(format!("🧠 Calling {} chat endpoint", provider.as_str()));
     drop(_prompt_span);
 
-    let client = OpenAiClient::new(client_cfg.clone())
-        .context("init OpenAI client")?;
-
-    let request = ChatCompletionRequest {
-        model: Some(model_name.clone()),
-        messages: vec![
-            ChatMessage::new(ChatRole::System, system_message.clone()),
-            ChatMessage::new(ChatRole::User, prompt.clone()),
-        ],
-        max_tokens: args.max_tokens,
-        temperature: args.temperature,
-        top_p: args.top_p,
-    };
-
     let _call_span = log.span(&ComposePhase::CallLlm).entered();
-    let response = match client.chat_completion(request).await {
-        Ok(resp) => resp,
-        Err(err) => {
-            match &err {
-                OpenAiError::MissingApiKey => {
-                    log.warn("⚠️  Missing OPENAI_API_KEY — set it or use --dry-run / OPENAI_BASE_URL for a compatible proxy.");
-                }
-                OpenAiError::Api { status, error } => {
-                    log.warn(format!(
-                        "⚠️  OpenAI API error {} — {}",
-                        status,
-                        error.message
-                    ));
-                }
-                OpenAiError::Timeout => {
-                    log.warn("⚠️  OpenAI request timed out — consider retrying or increasing OPENAI_TIMEOUT_SECS.");
-                }
-                _ => {
-                    log.warn("⚠️  OpenAI request failed — see error details below.");
-                }
-            }
-            drop(_call_span);
-            return Err(to_anyhow(err).context("call OpenAI chat completion"));
+    let (response, structured) = match &response_schema {
+        Some(schema) => {
+            let (resp, value) = complete_structured(client.as_ref(), request, schema, &log).await?;
+            (resp, Some(value))
         }
+        None => (call_llm(client.as_ref(), request, &log).await?, None),
     };
     drop(_call_span);
 
     let answer = response.content.trim().to_string();
     log.info(format!("💡 Answer:\n{answer}"));
 
-    let usage = response.usage.map(|u| UsageDto {
-        prompt_tokens: u.prompt_tokens,
-        completion_tokens: u.completion_tokens,
-        total_tokens: u.total_tokens,
-    });
+    let usage = match response.usage.filter(|u| u.prompt_tokens.is_some()) {
+        Some(u) => Some(UsageDto {
+            prompt_tokens: u.prompt_tokens,
+            completion_tokens: u.completion_tokens,
+            total_tokens: u.total_tokens,
+            estimated: false,
+        }),
+        None => {
+            log.warn("⚠️  API returned no token usage — estimating with the local tokenizer");
+            local_usage(&system_message, &prompt, &answer)
+        }
+    };
+    let cost_usd = usage.as_ref().and_then(|u| u.cost_usd(&model_name, &prices));
+    match cost_usd {
+        Some(cost) => log.info(format!("💵 Estimated cost: ${cost:.6}")),
+        None => log.info(format!("💵 No price for model {model_name} (set RAG_LLM_PRICING)")),
+    }
+
+    if !args.no_cache {
+        let _cache_span = log.span(&ComposePhase::Cache).entered();
+        let entry = db::CachedAnswer {
+            answer: answer.clone(),
+            prompt_tokens: usage.as_ref().and_then(|u| u.prompt_tokens).map(|n| n as i32),
+            completion_tokens: usage.as_ref().and_then(|u| u.completion_tokens).map(|n| n as i32),
+            total_tokens: usage.as_ref().and_then(|u| u.total_tokens).map(|n| n as i32),
+        };
+        db::store(pool, &key, &model_name, &entry, &chunk_ids).await?;
+    }
 
     let result = ComposeResult {
         query: &args.query,
         model: model_name,
         answer: &answer,
+        sub_queries,
         hits,
         retrieved_chunks: hit_count,
         usage,
+        cost_usd,
+        cached: false,
+        structured,
+        seed,
+        grounded,
     };
 
     let _out_span = log.span(&ComposePhase::Output).entered();
@@ -252,6 +444,7 @@ async fn fetch_hits(
     pool: &PgPool,
     args: &ComposeCmd,
     since: Option<DateTime<Utc>>,
+    sub_queries: &[String],
 ) -> Result<QueryOutcome> {
     let top_n = args.top_n.max(args.topk as i64).max(1);
     let request = QueryRequest {
@@ -260,16 +453,41 @@ async fn fetch_hits(
         topk: args.topk,
         doc_cap: args.doc_cap,
         probes: args.probes,
+        ef_search: args.ef_search,
         feed: args.feed,
         since,
+        title_like: None,
+        feed_name: None,
+        max_distance: crate::query::post::distance_cutoff(args.max_distance, args.min_similarity),
         include_preview: true,
         include_text: true,
+        preview_chars: 300,
+        highlight: false,
+        expand_neighbors: args.expand_neighbors,
+        explain: false,
+        rerank_llm: false,
+        rerank_candidates: 20,
+        rerank_model: None,
+        overfetch_rounds: 2,
+        level: crate::query::service::QueryLevel::Chunk,
+        strict_model: false,
+        dedup_results: false,
+        dedup_bits: 3,
+        quantized: false,
+        quantized_pool: 1000,
         model_id: &args.embed_model,
         onnx_filename: args.embed_onnx_filename.as_deref(),
         device: args.device,
+        max_seq_len: None,
     };
 
-    crate::query::service::execute(pool, request, None).await
+    if sub_queries.is_empty() {
+        return crate::query::service::execute(pool, request, None).await;
+    }
+    // one encoder pass for the original and every rewrite; each list is already topk/doc-capped
+    let queries: Vec<String> = std::iter::once(args.query.clone()).chain(sub_queries.iter().cloned()).collect();
+    let outcomes = crate::query::service::execute_many(pool, request, &queries, None).await?;
+    Ok(expand::fuse(outcomes, args.topk, args.doc_cap))
 }
 
 fn extract_hits(outcome: &QueryOutcome) -> Vec<ComposeHit> {
@@ -303,7 +521,62 @@ fn build_prompt_sections(outcome: &QueryOutcome) -> Vec<PromptSection<'_>> {
         .collect()
 }
 
-fn build_prompt(query: &str, outcome: &QueryOutcome) -> String {
+// appended to the system message under --allow-no-context when retrieval came back empty
+const NO_CONTEXT_NOTE: &str = "\n\nNo sources were found for this question. Answer from general knowledge and state clearly that the answer is not backed by any retrieved source.";
+
+// seed for --deterministic when --seed is not given
+const DETERMINISTIC_SEED: u64 = 0;
+
+const DEFAULT_SYSTEM: &str = "You are a helpful assistant.";
+
+const DEFAULT_USER_TEMPLATE: &str = "Context:\n{context}\n\nQuestion:\n{query}\n\nPlease answer using the provided context. If the answer is not contained within the context, say so explicitly.";
+
+struct TemplateVars<'a> {
+    query: &'a str,
+    num_sources: usize,
+    date: &'a str,
+    context: &'a str,
+}
+
+fn read_template(path: &Path) -> Result<String> {
+    std::fs::read_to_string(path).with_context(|| format!("read template {}", path.display()))
+}
+
+// Single pass so placeholder-looking text inside the query or sources is left alone;
+// unknown {names} are kept verbatim.
+fn render_template(template: &str, vars: &TemplateVars<'_>) -> String {
+    let mut out = String::with_capacity(template.len() + vars.context.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let Some(close) = after.find('}') else {
+            out.push_str(&rest[open..]);
+            return out;
+        };
+        let value = match &after[..close] {
+            "query" => Some(vars.query.to_string()),
+            "num_sources" => Some(vars.num_sources.to_string()),
+            "date" => Some(vars.date.to_string()),
+            "context" => Some(vars.context.to_string()),
+            _ => None,
+        };
+        match value {
+            Some(v) => {
+                out.push_str(&v);
+                rest = &after[close + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn build_prompt(query: &str, outcome: &QueryOutcome, template: &str, date: &str) -> String {
     let mut context_blocks: Vec<String> = Vec::new();
     for hit in &outcome.hits {
         let mut block =
@@ -322,9 +595,125 @@ fn build_prompt(query: &str, outcome: &QueryOutcome) -> String {
 
     let context = context_blocks.join("\n\n---\n\n");
 
-    format!(
-        "Context:\n{context}\n\nQuestion:\n{query}\n\nPlease answer using the provided context. If the answer is not contained within the context, say so explicitly."
-    )
+    render_template(template, &TemplateVars { query, num_sources: outcome.hits.len(), date, context: &context })
+}
+
+// md5 over (model, system, prompt, schema, sampling, max_tokens); NUL-separated so fields can't run
+// into each other. Sampling and max_tokens are part of the key: a --temperature 1 answer or one cut
+// at --max-tokens must not be replayed for a --deterministic or longer request.
+fn cache_key(
+    model: &str,
+    system: &str,
+    prompt: &str,
+    schema: Option<&Value>,
+    sampling: (Option<f32>, Option<f32>, Option<u64>),
+    max_tokens: Option<u32>,
+) -> String {
+    let schema = schema.map(|s| s.to_string()).unwrap_or_default();
+    let (temperature, top_p, seed) = sampling;
+    let params = format!("{temperature:?}\0{top_p:?}\0{seed:?}\0{max_tokens:?}");
+    format!("{:x}", md5::compute(format!("{model}\0{system}\0{prompt}\0{schema}\0{params}")))
+}
+
+// (temperature, top_p, seed); --deterministic pins all three, otherwise the flags pass through
+fn sampling(args: &ComposeCmd) -> (Option<f32>, Option<f32>, Option<u64>) {
+    if args.deterministic {
+        (Some(0.0), Some(1.0), Some(args.seed.unwrap_or(DETERMINISTIC_SEED)))
+    } else {
+        (args.temperature, args.top_p, args.seed)
+    }
+}
+
+// Pretty JSON so dumps diff cleanly between prompt iterations
+fn dump_prompt(path: &Path, body: &Value) -> Result<()> {
+    let json = serde_json::to_string_pretty(body)?;
+    std::fs::write(path, json + "\n").with_context(|| format!("write prompt dump {}", path.display()))
+}
+
+async fn call_llm(
+    client: &dyn LlmClient,
+    request: ChatCompletionRequest,
+    log: &LogCtx<ComposeOp>,
+) -> Result<ChatCompletionResponse> {
+    match client.chat_completion(request).await {
+        Ok(resp) => Ok(resp),
+        Err(err) => {
+            match &err {
+                OpenAiError::MissingApiKey => {
+                    log.warn("⚠️  Missing OPENAI_API_KEY — set it or use --dry-run / OPENAI_BASE_URL for a compatible proxy.");
+                }
+                OpenAiError::MissingEnv(var) => {
+                    log.warn(format!("⚠️  Missing {var} — set it or use --dry-run."));
+                }
+                OpenAiError::Api { status, error } => {
+                    log.warn(format!(
+                        "⚠️  LLM API error {} — {}",
+                        status,
+                        error.message
+                    ));
+                }
+                OpenAiError::Timeout => {
+                    log.warn("⚠️  LLM request timed out — consider retrying or increasing OPENAI_TIMEOUT_SECS / ANTHROPIC_TIMEOUT_SECS.");
+                }
+                _ => {
+                    log.warn("⚠️  LLM request failed — see error details below.");
+                }
+            }
+            Err(to_anyhow(err).context("call chat completion"))
+        }
+    }
+}
+
+// Parse the reply as JSON and check it against the schema; on failure, show the model
+// its reply plus the problems and ask once more.
+async fn complete_structured(
+    client: &dyn LlmClient,
+    request: ChatCompletionRequest,
+    schema: &Value,
+    log: &LogCtx<ComposeOp>,
+) -> Result<(ChatCompletionResponse, Value)> {
+    let first = call_llm(client, request.clone(), log).await?;
+    let problems = match check_structured(schema, &first.content) {
+        Ok(value) => return Ok((first, value)),
+        Err(problems) => problems,
+    };
+    log.warn_kv("⚠️  Reply does not match --response-schema; retrying once", [("errors", problems.join("; "))]);
+
+    let mut retry = request;
+    retry.messages.push(ChatMessage::new(ChatRole::Assistant, first.content.clone()));
+    retry.messages.push(ChatMessage::new(
+        ChatRole::User,
+        format!(
+            "Your reply does not match the required JSON schema:\n- {}\nReply again with only a JSON value that matches the schema.",
+            problems.join("\n- ")
+        ),
+    ));
+    let second = call_llm(client, retry, log).await?;
+    match check_structured(schema, &second.content) {
+        Ok(value) => Ok((second, value)),
+        Err(problems) => Err(anyhow!("reply does not match --response-schema after retry: {}", problems.join("; "))),
+    }
+}
+
+fn check_structured(schema: &Value, content: &str) -> std::result::Result<Value, Vec<String>> {
+    let value: Value = serde_json::from_str(content.trim()).map_err(|e| vec![format!("not valid JSON: {e}")])?;
+    let problems = schema::validate(schema, &value);
+    if problems.is_empty() { Ok(value) } else { Err(problems) }
+}
+
+// Approximate prompt/completion counts for APIs (some proxies) that omit usage; the E5
+// WordPiece vocabulary differs from the chat model's, so treat these as estimates.
+fn local_usage(system: &str, prompt: &str, answer: &str) -> Option<UsageDto> {
+    let tok = E5Tokenizer::for_chunking().ok()?;
+    let count = |text: &str| tok.inner().encode(text, false).ok().map(|e| e.len() as u32);
+    let prompt_tokens = count(system)? + count(prompt)?;
+    let completion_tokens = count(answer)?;
+    Some(UsageDto {
+        prompt_tokens: Some(prompt_tokens),
+        completion_tokens: Some(completion_tokens),
+        total_tokens: Some(prompt_tokens + completion_tokens),
+        estimated: true,
+    })
 }
 
 fn to_anyhow(err: OpenAiError) -> anyhow::Error {
@@ -346,29 +735,117 @@ mod tests {
                 doc_id: 3,
                 title: Some("Doc title".into()),
                 preview: Some("preview text".into()),
+                text: None,
+                rerank_score: None,
             }],
             hits: vec![QueryHit {
                 rank: 1,
                 distance: 0.12,
                 chunk_id: 7,
                 doc_id: 3,
+                chunk_index: Some(0),
                 title: Some("Doc title".into()),
                 preview: Some("preview text".into()),
                 text: Some("full chunk text".into()),
             }],
             probes: Some(4),
+            ef_search: None,
+            too_distant: 0,
+            explain: None,
         }
     }
 
     #[test]
     fn build_prompt_includes_question_and_context() {
         let outcome = sample_outcome();
-        let prompt = build_prompt("What is rust?", &outcome);
+        let prompt = build_prompt("What is rust?", &outcome, DEFAULT_USER_TEMPLATE, "2025-09-01");
         assert!(prompt.contains("What is rust?"));
         assert!(prompt.contains("full chunk text"));
         assert!(prompt.contains("Source #1"));
     }
 
+    #[test]
+    fn render_template_fills_placeholders() {
+        let vars = TemplateVars { query: "q?", num_sources: 3, date: "2025-09-01", context: "" };
+        assert_eq!(
+            render_template("Answer {query} from {num_sources} sources as of {date}.", &vars),
+            "Answer q? from 3 sources as of 2025-09-01."
+        );
+        // unknown names and unmatched braces pass through
+        assert_eq!(render_template("{other} {query", &vars), "{other} {query");
+    }
+
+    #[test]
+    fn custom_user_template_is_not_reexpanded() {
+        let outcome = sample_outcome();
+        let prompt = build_prompt("why {date}?", &outcome, "Q: {query}\nN={num_sources}\n{context}", "2025-09-01");
+        assert!(prompt.starts_with("Q: why {date}?\nN=1\nSource #1 (doc 3)"));
+        assert!(prompt.contains("full chunk text"));
+    }
+
+    #[test]
+    fn cache_key_covers_model_system_and_prompt() {
+        const NO_SAMPLING: (Option<f32>, Option<f32>, Option<u64>) = (None, None, None);
+        let base = cache_key("m", "sys", "prompt", None, NO_SAMPLING, None);
+        assert_eq!(base, cache_key("m", "sys", "prompt", None, NO_SAMPLING, None));
+        assert_ne!(base, cache_key("m2", "sys", "prompt", None, NO_SAMPLING, None));
+        assert_ne!(base, cache_key("m", "sys2", "prompt", None, NO_SAMPLING, None));
+        assert_ne!(base, cache_key("m", "sys", "prompt2", None, NO_SAMPLING, None));
+        assert_ne!(base, cache_key("m", "sys", "prompt", Some(&serde_json::json!({"type": "object"})), NO_SAMPLING, None));
+        assert_ne!(cache_key("a", "bc", "p", None, NO_SAMPLING, None), cache_key("ab", "c", "p", None, NO_SAMPLING, None));
+        // sampling and length limits change the answer, so they change the key
+        assert_ne!(base, cache_key("m", "sys", "prompt", None, (Some(0.0), None, None), None));
+        assert_ne!(base, cache_key("m", "sys", "prompt", None, (None, Some(0.9), None), None));
+        assert_ne!(base, cache_key("m", "sys", "prompt", None, (None, None, Some(7)), None));
+        assert_ne!(base, cache_key("m", "sys", "prompt", None, NO_SAMPLING, Some(256)));
+        assert_ne!(
+            cache_key("m", "sys", "prompt", None, (Some(0.0), Some(1.0), Some(0)), None),
+            cache_key("m", "sys", "prompt", None, (Some(0.0), Some(1.0), Some(1)), None)
+        );
+    }
+
+    fn reply(content: &str) -> std::result::Result<ChatCompletionResponse, OpenAiError> {
+        Ok(ChatCompletionResponse { content: content.into(), raw: Value::Null, usage: None })
+    }
+
+    fn structured_request() -> ChatCompletionRequest {
+        ChatCompletionRequest {
+            model: None,
+            messages: vec![ChatMessage::new(ChatRole::User, "q")],
+            max_tokens: None,
+            temperature: None,
+            top_p: None,
+            response_format: None,
+            seed: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn structured_reply_retries_once_with_errors() {
+        let schema = serde_json::json!({ "type": "object", "required": ["answer"] });
+        let mock = crate::llm::openai::MockClient::new();
+        mock.push_response(reply("not json"));
+        mock.push_response(reply(r#"{"answer": "42"}"#));
+
+        let (_, value) = complete_structured(&mock, structured_request(), &schema, &telemetry::compose()).await.unwrap();
+        assert_eq!(value["answer"], "42");
+        let calls = mock.calls();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[1].messages.len(), 3);
+        assert_eq!(calls[1].messages[1].content, "not json");
+        assert!(calls[1].messages[2].content.contains("not valid JSON"));
+    }
+
+    #[tokio::test]
+    async fn structured_reply_fails_after_second_mismatch() {
+        let schema = serde_json::json!({ "type": "object", "required": ["answer"] });
+        let mock = crate::llm::openai::MockClient::new();
+        mock.push_response(reply("{}"));
+        mock.push_response(reply("{}"));
+        let err = complete_structured(&mock, structured_request(), &schema, &telemetry::compose()).await.unwrap_err();
+        assert!(err.to_string().contains("missing required property 'answer'"));
+    }
+
     #[test]
     fn extract_hits_captures_rank_and_preview() {
         let outcome = sample_outcome();
@@ -378,4 +855,27 @@ mod tests {
         assert_eq!(hits[0].chunk_id, 7);
         assert_eq!(hits[0].preview.as_deref(), Some("preview text"));
     }
+
+    #[test]
+    fn hits_and_usage_round_trip_through_json() {
+        let hits = serde_json::to_value(extract_hits(&sample_outcome())).unwrap();
+        let back: Vec<ComposeHit> = serde_json::from_value(hits.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&back).unwrap(), hits);
+
+        let usage = serde_json::to_value(UsageDto { prompt_tokens: Some(10), completion_tokens: None, total_tokens: Some(10), estimated: true }).unwrap();
+        let back: UsageDto = serde_json::from_value(usage.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&back).unwrap(), usage);
+    }
+
+    #[test]
+    fn deterministic_pins_sampling_and_seed() {
+        use clap::Parser;
+        #[derive(Parser)]
+        struct Cli { #[command(flatten)] args: ComposeCmd }
+        let parse = |argv: &[&str]| Cli::try_parse_from(std::iter::once("rag").chain(argv.iter().copied()));
+        assert_eq!(sampling(&parse(&["q", "--deterministic"]).unwrap().args), (Some(0.0), Some(1.0), Some(DETERMINISTIC_SEED)));
+        assert_eq!(sampling(&parse(&["q", "--deterministic", "--seed", "9"]).unwrap().args), (Some(0.0), Some(1.0), Some(9)));
+        assert_eq!(sampling(&parse(&["q", "--temperature", "0.7"]).unwrap().args), (Some(0.7), None, None));
+        assert!(parse(&["q", "--deterministic", "--top-p", "0.5"]).is_err());
+    }
 }
@@ -10,8 +10,9 @@ use ort::session::builder::{GraphOptimizationLevel, SessionBuilder};
 use ort::inputs;
 use ort::value::Value;
 use crate::encoder::traits::Embedder;
+use crate::encoder::Prefixes;
 
-#[derive(Copy, Clone, Debug, clap::ValueEnum)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ValueEnum)]
 pub enum Device {
     #[value(name = "cpu")] Cpu,
     #[value(name = "cuda")] Cuda,
@@ -20,22 +21,25 @@ pub enum Device {
 pub struct E5Encoder {
     tok: E5Tokenizer,
     session: Session,
+    prefixes: Prefixes,
 }
 
 impl E5Encoder {
-    pub fn new(model_id: &str, onnx_filename: Option<&str>, device: Device) -> Result<Self> {
-        let tok = E5Tokenizer::new().context("init E5 tokenizer")?;
+    pub fn new(model_id: &str, onnx_filename: Option<&str>, device: Device, prefixes: Prefixes, max_seq_len: Option<usize>) -> Result<Self> {
+        let tok = E5Tokenizer::new(max_seq_len).context("init E5 tokenizer")?.with_prefixes(prefixes.clone());
         let onnx_path = resolve_onnx(model_id, onnx_filename).context("resolve ONNX model via HF Hub")?;
         let session = build_session(&onnx_path, device)?;
-        Ok(Self { tok, session })
+        Ok(Self { tok, session, prefixes })
     }
 
     pub fn embed_queries(&mut self, queries: &[String]) -> Result<Vec<Vec<f32>>> {
-        self.embed_with_prefix(queries, "query: ")
+        let prefix = self.prefixes.query.clone();
+        self.embed_with_prefix(queries, &prefix)
     }
 
     pub fn embed_passages(&mut self, passages: &[String]) -> Result<Vec<Vec<f32>>> {
-        self.embed_with_prefix(passages, "passage: ")
+        let prefix = self.prefixes.passage.clone();
+        self.embed_with_prefix(passages, &prefix)
     }
 
     pub fn embed_query(&mut self, query: &str) -> Result<Vec<f32>> {
@@ -46,7 +50,7 @@ impl E5Encoder {
     fn embed_with_prefix(&mut self, texts: &[String], prefix: &str) -> Result<Vec<Vec<f32>>> {
         if texts.is_empty() { return Ok(vec![]); }
 
-        // Prepare inputs with E5 prefix
+        // Prepare inputs with the configured prefix
         let inputs: Vec<String> = texts.iter().map(|t| format!("{}{}", prefix, t)).collect();
         let (ids_vecs, attn_vecs, type_vecs) = self.tok.raw_batch_encode_ids(&inputs)?;
         let batch = ids_vecs.len();
@@ -130,6 +134,9 @@ impl Embedder for E5Encoder {
     fn embed_query(&mut self, query: &str) -> Result<Vec<f32>> {
         E5Encoder::embed_query(self, query)
     }
+    fn max_passage_tokens(&self) -> Option<usize> {
+        self.tok.passage_budget().ok()
+    }
 }
 
 fn l2_normalize(mut v: Vec<f32>) -> Vec<f32> {
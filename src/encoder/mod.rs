@@ -1,5 +1,7 @@
 pub mod e5_onnx;
+pub mod prefix;
 pub mod traits;
 
 pub use e5_onnx::{Device, E5Encoder};
+pub use prefix::Prefixes;
 
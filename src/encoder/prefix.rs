@@ -0,0 +1,49 @@
+/// Instruction prefixes prepended to queries and passages before encoding.
+/// E5 models expect `query: `/`passage: `; other models want their own text or none at all.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Prefixes {
+    pub query: String,
+    pub passage: String,
+}
+
+impl Default for Prefixes {
+    fn default() -> Self {
+        Self { query: "query: ".to_string(), passage: "passage: ".to_string() }
+    }
+}
+
+impl Prefixes {
+    /// E5 defaults, overridden by `RAG_QUERY_PREFIX`/`RAG_PASSAGE_PREFIX`; a set-but-empty variable means no prefix.
+    pub fn from_env() -> Self {
+        Self::from_lookup(|k| std::env::var(k).ok())
+    }
+
+    fn from_lookup(get: impl Fn(&str) -> Option<String>) -> Self {
+        let d = Self::default();
+        Self {
+            query: get("RAG_QUERY_PREFIX").unwrap_or(d.query),
+            passage: get("RAG_PASSAGE_PREFIX").unwrap_or(d.passage),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unset_env_keeps_e5_prefixes() {
+        assert_eq!(Prefixes::from_lookup(|_| None), Prefixes::default());
+    }
+
+    #[test]
+    fn empty_env_disables_a_prefix() {
+        let p = Prefixes::from_lookup(|k| match k {
+            "RAG_QUERY_PREFIX" => Some("Represent this sentence for searching relevant passages: ".to_string()),
+            "RAG_PASSAGE_PREFIX" => Some(String::new()),
+            _ => None,
+        });
+        assert_eq!(p.query, "Represent this sentence for searching relevant passages: ");
+        assert_eq!(p.passage, "");
+    }
+}
@@ -4,5 +4,7 @@ pub trait Embedder {
     fn embed_queries(&mut self, queries: &[String]) -> Result<Vec<Vec<f32>>>;
     fn embed_passages(&mut self, passages: &[String]) -> Result<Vec<Vec<f32>>>;
     fn embed_query(&mut self, query: &str) -> Result<Vec<f32>>;
+    /// Chunk tokens that fit before input truncation (after prefix and special tokens), if known.
+    fn max_passage_tokens(&self) -> Option<usize> { None }
 }
 
@@ -1,8 +1,11 @@
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use sqlx::PgPool;
 
 use crate::stats::types::StatsFeedRow;
 
+use super::types::FeedStatsRow;
+
 pub async fn upsert_feed(pool: &PgPool, url: &str, name: Option<&str>, active: bool) -> Result<bool> {
     let rec = sqlx::query!(
         r#"
@@ -21,6 +24,26 @@ pub async fn upsert_feed(pool: &PgPool, url: &str, name: Option<&str>, active: b
     Ok(rec.inserted)
 }
 
+// Which of these feed URLs are already registered
+pub async fn existing_urls(pool: &PgPool, urls: &[String]) -> Result<Vec<String>> {
+    let rows = sqlx::query_scalar!("SELECT url FROM rag.feed WHERE url = ANY($1)", urls)
+        .fetch_all(pool)
+        .await?;
+    Ok(rows)
+}
+
+// Advance the ingest watermark; never moves it backwards
+pub async fn set_last_item_at(pool: &PgPool, feed_id: i32, last_item_at: DateTime<Utc>) -> Result<()> {
+    sqlx::query!(
+        r#"UPDATE rag.feed SET last_item_at = GREATEST(last_item_at, $2) WHERE feed_id = $1"#,
+        feed_id,
+        last_item_at
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
 pub async fn list_feeds(pool: &PgPool, active: Option<bool>) -> Result<Vec<StatsFeedRow>> {
     let rows = sqlx::query!(
         r#"
@@ -50,3 +73,50 @@ pub async fn list_feeds(pool: &PgPool, active: Option<bool>) -> Result<Vec<Stats
         .collect();
     Ok(feeds)
 }
+
+// All feeds' doc/chunk/embedding counts in one grouped pass; embeddings are 1:1 with chunks,
+// so counting joined rows counts each chunk once
+pub async fn list_feeds_with_stats(pool: &PgPool, active: Option<bool>) -> Result<Vec<FeedStatsRow>> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT f.feed_id,
+               f.url,
+               f.name,
+               COALESCE(f.is_active, TRUE) AS "is_active!: bool",
+               f.added_at,
+               COUNT(DISTINCT d.doc_id)::bigint AS "docs!",
+               COUNT(c.chunk_id)::bigint AS "chunks!",
+               COUNT(e.chunk_id)::bigint AS "embedded!",
+               MAX(d.fetched_at) AS last_fetched
+        FROM rag.feed f
+        LEFT JOIN rag.document d ON d.feed_id = f.feed_id
+        LEFT JOIN rag.chunk c ON c.doc_id = d.doc_id
+        LEFT JOIN rag.embedding e ON e.chunk_id = c.chunk_id
+        WHERE ($1::bool IS NULL OR f.is_active = $1)
+        GROUP BY f.feed_id
+        ORDER BY f.feed_id
+        "#,
+        active
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let feeds = rows
+        .into_iter()
+        .map(|r| FeedStatsRow {
+            feed: StatsFeedRow {
+                feed_id: r.feed_id,
+                name: r.name,
+                url: r.url,
+                is_active: Some(r.is_active),
+                added_at: r.added_at,
+            },
+            docs: r.docs,
+            chunks: r.chunks,
+            embedded: r.embedded,
+            coverage_pct: if r.chunks > 0 { r.embedded as f64 / r.chunks as f64 * 100.0 } else { 0.0 },
+            last_fetched: r.last_fetched,
+        })
+        .collect();
+    Ok(feeds)
+}
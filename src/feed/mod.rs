@@ -1,15 +1,17 @@
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use clap::{Args, Subcommand};
 use sqlx::PgPool;
+use std::path::PathBuf;
 use url::Url;
 
 use crate::telemetry::{self};
 use crate::telemetry::ops::feed::Phase as FeedPhase;
 
-mod db;
+pub(crate) mod db;
+pub mod opml;
 pub mod types;
 
-/// rag feed add/ls
+/// rag feed add/ls/import/export
 #[derive(Args)]
 pub struct FeedCmd {
     #[command(subcommand)]
@@ -33,6 +35,26 @@ pub enum FeedSub {
         /// Filter by active status: true/false. Omit to show all.
         #[arg(long)]
         active: Option<bool>,
+        /// Add doc, chunk and embedding counts, coverage % and last fetch per feed
+        #[arg(long, default_value_t = false)]
+        with_stats: bool,
+    },
+    // bulk add/update feeds from an OPML export (plan-only by default; use --apply to write)
+    Import {
+        opml: PathBuf,
+        #[arg(long, default_value_t = true)]
+        active: bool,
+        #[arg(long, default_value_t = false)]
+        apply: bool,
+        #[arg(long, default_value_t = 10)]
+        plan_limit: usize,
+    },
+    // write the registered feeds as OPML (stdout unless --out)
+    Export {
+        #[arg(long, default_value_t = false)]
+        active_only: bool,
+        #[arg(long)]
+        out: Option<PathBuf>,
     },
 }
 
@@ -41,7 +63,9 @@ pub async fn run(pool: &PgPool, args: FeedCmd) -> Result<()> {
     let _g = log.root_span().entered();
     match args.cmd {
         FeedSub::Add { url, name, active, apply } => add_feed(pool, url, name, active, apply).await?,
-        FeedSub::Ls { active } => ls_feeds(pool, active).await?,
+        FeedSub::Ls { active, with_stats } => ls_feeds(pool, active, with_stats).await?,
+        FeedSub::Import { opml, active, apply, plan_limit } => import_feeds(pool, opml, active, apply, plan_limit).await?,
+        FeedSub::Export { active_only, out } => export_feeds(pool, active_only, out).await?,
     }
     Ok(())
 }
@@ -78,10 +102,89 @@ async fn add_feed(pool: &PgPool, url: String, name: Option<String>, active: bool
     Ok(())
 }
 
-async fn ls_feeds(pool: &PgPool, active: Option<bool>) -> Result<()> {
+async fn import_feeds(pool: &PgPool, path: PathBuf, active: bool, apply: bool, plan_limit: usize) -> Result<()> {
     let log = telemetry::feed();
-    let _g = log.root_span_kv([("active", format!("{:?}", active))]).entered();
+    let _g = log.root_span_kv([
+        ("mode", if apply { "apply".to_string() } else { "plan".to_string() }),
+        ("opml", path.display().to_string()),
+        ("active", active.to_string()),
+    ]).entered();
+
+    let xml = std::fs::read_to_string(&path).with_context(|| format!("read {}", path.display()))?;
+    let (feeds, skipped) = opml::parse(&xml).with_context(|| format!("parse {}", path.display()))?;
+    if skipped > 0 {
+        log.warn(format!("⚠️  Skipped {} outline(s) with a malformed xmlUrl", skipped));
+    }
+
+    if !apply {
+        let _s = log.span(&FeedPhase::Plan).entered();
+        let urls: Vec<String> = feeds.iter().map(|f| f.url.clone()).collect();
+        let existing = db::existing_urls(pool, &urls).await?;
+        let new = feeds.len() - existing.len();
+        // Always log plan summary
+        log.info(format!("📝 Feed import plan — feeds={} new={} existing={} skipped={} active={}", feeds.len(), new, existing.len(), skipped, active));
+        for f in feeds.iter().take(plan_limit) {
+            let tag = if existing.contains(&f.url) { "update" } else { "add" };
+            log.info(format!("  {} url={} name={:?}", tag, f.url, f.name));
+        }
+        if feeds.len() > plan_limit { log.info(format!("  ... ({} more)", feeds.len() - plan_limit)); }
+        log.info("   Use --apply to execute.");
+        let plan = types::FeedImportPlan { feeds: feeds.len(), new, existing: existing.len(), skipped, active, sample: feeds.into_iter().take(plan_limit).collect() };
+        log.plan(&plan)?;
+        return Ok(());
+    }
+
+    let _s = log.span(&FeedPhase::Add).entered();
+    let (mut inserted, mut updated) = (0usize, 0usize);
+    for f in &feeds {
+        if db::upsert_feed(pool, &f.url, f.name.as_deref(), active).await? { inserted += 1; } else { updated += 1; }
+    }
+    log.info(format!("✅ Feed import — inserted={} updated={} skipped={}", inserted, updated, skipped));
+    let result = types::FeedImportResult { inserted, updated, skipped };
+    log.result(&result)?;
+    Ok(())
+}
+
+async fn export_feeds(pool: &PgPool, active_only: bool, out: Option<PathBuf>) -> Result<()> {
+    let log = telemetry::feed();
+    let _g = log.root_span_kv([
+        ("active_only", active_only.to_string()),
+        ("out", format!("{:?}", out)),
+    ]).entered();
+    let _s = log.span(&FeedPhase::List).entered();
+    let rows = db::list_feeds(pool, active_only.then_some(true)).await?;
+    let feeds: Vec<opml::OpmlFeed> = rows.into_iter().map(|r| opml::OpmlFeed { url: r.url, name: r.name }).collect();
+    let xml = opml::render(&feeds, "rag feeds");
+    match &out {
+        // the document itself is the output; no envelope around it
+        None => print!("{}", xml),
+        Some(path) => {
+            std::fs::write(path, &xml).with_context(|| format!("write {}", path.display()))?;
+            log.info(format!("💾 Exported {} feed(s) to {}", feeds.len(), path.display()));
+            let result = types::FeedExportResult { feeds: feeds.len(), out: path.display().to_string() };
+            log.result(&result)?;
+        }
+    }
+    Ok(())
+}
+
+async fn ls_feeds(pool: &PgPool, active: Option<bool>, with_stats: bool) -> Result<()> {
+    let log = telemetry::feed();
+    let _g = log.root_span_kv([("active", format!("{:?}", active)), ("with_stats", with_stats.to_string())]).entered();
     let _s = log.span(&FeedPhase::List).entered();
+    if with_stats {
+        let feeds = db::list_feeds_with_stats(pool, active).await?;
+        log.info("📡 Feeds:");
+        for row in &feeds {
+            log.info(format!(
+                "[{}] {} ({:?}) active={:?} docs={} chunks={} embedded={} ({:.1}%) last_fetched={:?}",
+                row.feed.feed_id, row.feed.url, row.feed.name, row.feed.is_active,
+                row.docs, row.chunks, row.embedded, row.coverage_pct, row.last_fetched
+            ));
+        }
+        log.result(&types::FeedListWithStats { feeds })?;
+        return Ok(());
+    }
     let feeds = db::list_feeds(pool, active).await?;
     // Always log listing
     log.info("📡 Feeds:");
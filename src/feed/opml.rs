@@ -0,0 +1,121 @@
+use anyhow::{Context, Result};
+use quick_xml::escape::escape;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use schemars::JsonSchema;
+use serde::Serialize;
+use url::Url;
+
+/// One subscription from an OPML file: the `xmlUrl` plus its `title` (or `text`).
+#[derive(Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct OpmlFeed {
+    pub url: String,
+    pub name: Option<String>,
+}
+
+/// Feeds found in the outline (nested category outlines included, duplicates dropped)
+/// and how many `xmlUrl` entries were skipped as malformed.
+pub fn parse(xml: &str) -> Result<(Vec<OpmlFeed>, usize)> {
+    let mut reader = Reader::from_str(xml);
+    let mut feeds: Vec<OpmlFeed> = Vec::new();
+    let mut skipped = 0usize;
+    loop {
+        match reader.read_event().context("parse OPML")? {
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"outline" => {
+                match outline_feed(&e) {
+                    Some(Ok(feed)) if !feeds.iter().any(|f| f.url == feed.url) => feeds.push(feed),
+                    Some(Ok(_)) => {}
+                    Some(Err(())) => skipped += 1,
+                    // category/folder outline without xmlUrl
+                    None => {}
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok((feeds, skipped))
+}
+
+/// OPML 2.0 document with one `rss` outline per feed; the name (or the URL) goes in `text`/`title`.
+pub fn render(feeds: &[OpmlFeed], title: &str) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<opml version=\"2.0\">\n");
+    out.push_str(&format!("  <head><title>{}</title></head>\n  <body>\n", escape(title)));
+    for f in feeds {
+        let text = escape(f.name.as_deref().unwrap_or(&f.url)).into_owned();
+        out.push_str(&format!(
+            "    <outline type=\"rss\" text=\"{}\" title=\"{}\" xmlUrl=\"{}\"/>\n",
+            text, text, escape(&f.url)
+        ));
+    }
+    out.push_str("  </body>\n</opml>\n");
+    out
+}
+
+// None: not a feed outline; Err: has an xmlUrl that isn't a usable http(s) URL
+fn outline_feed(e: &BytesStart) -> Option<Result<OpmlFeed, ()>> {
+    let mut url = None;
+    let mut title = None;
+    let mut text = None;
+    for attr in e.attributes().flatten() {
+        let Ok(value) = attr.unescape_value() else { continue };
+        let value = value.trim().to_string();
+        match attr.key.local_name().as_ref() {
+            b"xmlUrl" | b"xmlurl" => url = Some(value),
+            b"title" => title = Some(value),
+            b"text" => text = Some(value),
+            _ => {}
+        }
+    }
+    let url = url?;
+    let valid = Url::parse(&url).is_ok_and(|u| matches!(u.scheme(), "http" | "https"));
+    if !valid { return Some(Err(())); }
+    // export writes the URL as text for unnamed feeds; read that back as no name
+    let name = title.or(text).filter(|s| !s.is_empty() && *s != url);
+    Some(Ok(OpmlFeed { url, name }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nested_outlines_and_skips_malformed() {
+        let xml = r#"<?xml version="1.0"?>
+<opml version="2.0">
+  <head><title>subs</title></head>
+  <body>
+    <outline text="Research">
+      <outline type="rss" text="arXiv cs.CL" title="arXiv CL" xmlUrl="https://rss.arxiv.org/rss/cs.CL"/>
+      <outline type="rss" text="Blog &amp; News" xmlUrl="https://example.com/feed.xml"></outline>
+    </outline>
+    <outline type="rss" text="broken" xmlUrl="not a url"/>
+    <outline type="rss" text="ftp" xmlUrl="ftp://example.com/feed"/>
+    <outline type="rss" text="dup" xmlUrl="https://example.com/feed.xml"/>
+  </body>
+</opml>"#;
+        let (feeds, skipped) = parse(xml).unwrap();
+        assert_eq!(feeds, vec![
+            OpmlFeed { url: "https://rss.arxiv.org/rss/cs.CL".into(), name: Some("arXiv CL".into()) },
+            OpmlFeed { url: "https://example.com/feed.xml".into(), name: Some("Blog & News".into()) },
+        ]);
+        assert_eq!(skipped, 2);
+    }
+
+    #[test]
+    fn export_then_import_preserves_the_feed_set() {
+        let feeds = vec![
+            OpmlFeed { url: "https://example.com/rss?a=1&b=2".into(), name: Some("Q&A \"weekly\" <news>".into()) },
+            OpmlFeed { url: "https://rss.arxiv.org/rss/cs.CL".into(), name: None },
+        ];
+        let xml = render(&feeds, "rag feeds");
+        let (back, skipped) = parse(&xml).unwrap();
+        assert_eq!(skipped, 0);
+        assert_eq!(back, feeds);
+    }
+
+    #[test]
+    fn broken_xml_is_an_error() {
+        assert!(parse("<opml><body><outline xmlUrl=\"https://a.b/\"></body>").is_err());
+    }
+}
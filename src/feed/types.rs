@@ -1,7 +1,9 @@
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::Serialize;
 use crate::stats::types::StatsFeedRow;
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct FeedAddPlan {
     pub action: &'static str,
     pub url: String,
@@ -9,14 +11,54 @@ pub struct FeedAddPlan {
     pub active: bool,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct FeedAddResult {
     pub inserted: bool,
     pub url: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
+pub struct FeedImportPlan {
+    pub feeds: usize,
+    pub new: usize,
+    pub existing: usize,
+    pub skipped: usize,
+    pub active: bool,
+    pub sample: Vec<super::opml::OpmlFeed>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct FeedImportResult {
+    pub inserted: usize,
+    pub updated: usize,
+    pub skipped: usize,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct FeedExportResult {
+    pub feeds: usize,
+    pub out: String,
+}
+
+#[derive(Serialize, JsonSchema)]
 pub struct FeedList {
     pub feeds: Vec<StatsFeedRow>,
 }
 
+// feed ls --with-stats: one row per feed with its corpus counts
+#[derive(Serialize, JsonSchema)]
+pub struct FeedStatsRow {
+    #[serde(flatten)]
+    pub feed: StatsFeedRow,
+    pub docs: i64,
+    pub chunks: i64,
+    pub embedded: i64,
+    // embedded / chunks, 0 when the feed has no chunks (same as stats --feed)
+    pub coverage_pct: f64,
+    pub last_fetched: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct FeedListWithStats {
+    pub feeds: Vec<FeedStatsRow>,
+}
@@ -1,16 +1,18 @@
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use sqlx::PgPool;
 
 pub struct IngestFeedRow {
     pub feed_id: i32,
     pub url: String,
     pub name: Option<String>,
+    pub last_item_at: Option<DateTime<Utc>>,
 }
 
 pub async fn select_feeds(pool: &PgPool, feed: Option<i32>, feed_url: Option<&str>) -> Result<Vec<IngestFeedRow>> {
     let rows = sqlx::query!(
         r#"
-        SELECT feed_id, url, name
+        SELECT feed_id, url, name, last_item_at
         FROM rag.feed
         WHERE
           ($1::INT4 IS NULL OR feed_id = $1::INT4) AND
@@ -26,8 +28,71 @@ pub async fn select_feeds(pool: &PgPool, feed: Option<i32>, feed_url: Option<&st
 
     let out = rows
         .into_iter()
-        .map(|r| IngestFeedRow { feed_id: r.feed_id, url: r.url, name: r.name })
+        .map(|r| IngestFeedRow { feed_id: r.feed_id, url: r.url, name: r.name, last_item_at: r.last_item_at })
         .collect();
     Ok(out)
 }
 
+pub struct IngestErrorDocRow {
+    pub doc_id: i64,
+    pub feed_id: Option<i32>,
+    pub source_url: String,
+    pub source_title: Option<String>,
+    pub published_at: Option<DateTime<Utc>>,
+    pub error_msg: Option<String>,
+}
+
+// status=error docs, oldest fetch first; `kinds` narrows to those error_msg tags
+pub async fn select_error_docs(pool: &PgPool, feed: Option<i32>, feed_url: Option<&str>, kinds: Option<&[&str]>, limit: i64) -> Result<Vec<IngestErrorDocRow>> {
+    let kinds: Option<Vec<String>> = kinds.map(|k| k.iter().map(|s| s.to_string()).collect());
+    let rows = sqlx::query_as!(
+        IngestErrorDocRow,
+        r#"
+        SELECT d.doc_id, d.feed_id, d.source_url, d.source_title, d.published_at, d.error_msg
+        FROM rag.document d
+        LEFT JOIN rag.feed f ON f.feed_id = d.feed_id
+        WHERE d.status = 'error'
+          AND ($1::INT4 IS NULL OR d.feed_id = $1::INT4)
+          AND ($2::TEXT IS NULL OR f.url = $2::TEXT)
+          AND ($3::TEXT[] IS NULL OR d.error_msg = ANY($3::TEXT[]))
+        ORDER BY d.fetched_at ASC NULLS FIRST, d.doc_id
+        LIMIT $4
+        "#,
+        feed,
+        feed_url,
+        kinds.as_deref(),
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// How far back `find_near_duplicate` looks; syndicated copies show up within days of the original.
+pub const DEDUP_WINDOW_DAYS: i32 = 30;
+
+// Oldest canonical doc whose SimHash is within `max_distance` bits, among usable docs fetched in the
+// last DEDUP_WINDOW_DAYS (errors, filtered and metadata-only rows have no text worth pointing at)
+pub async fn find_near_duplicate(pool: &PgPool, simhash: i64, source_url: &str, max_distance: i32) -> Result<Option<i64>> {
+    let doc_id = sqlx::query_scalar!(
+        r#"
+        SELECT doc_id
+        FROM rag.document
+        WHERE simhash IS NOT NULL
+          AND canonical_doc_id IS NULL
+          AND status IN ('ingest', 'chunked', 'embedded')
+          AND fetched_at >= now() - make_interval(days => $4)
+          AND source_url <> $2
+          AND bit_count((simhash # $1::bigint)::bit(64)) <= $3::bigint
+        ORDER BY doc_id
+        LIMIT 1
+        "#,
+        simhash,
+        source_url,
+        max_distance as i64,
+        DEDUP_WINDOW_DAYS
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(doc_id)
+}
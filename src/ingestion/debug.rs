@@ -0,0 +1,76 @@
+use anyhow::Result;
+use clap::Args;
+use serde::Serialize;
+use url::Url;
+
+use crate::telemetry::{self};
+use crate::telemetry::ops::extract::Phase as ExtractPhase;
+use crate::util::http;
+
+use super::{extractor, fetch, lang};
+use super::extractor::ExtractFormat;
+
+/// rag extract-debug <url>: run the extractor on one page, no DB writes
+#[derive(Args, Debug)]
+pub struct ExtractDebugCmd {
+    pub url: String,
+    #[arg(long, value_enum, default_value_t = ExtractFormat::Text)] pub extract_format: ExtractFormat,
+}
+
+#[derive(Serialize)]
+struct ExtractDebugResult {
+    url: String,
+    host: String,
+    extractor: &'static str,
+    format: ExtractFormat,
+    ok: bool,
+    error: Option<String>,
+    pdf: bool,
+    raw_bytes: usize,
+    chars: usize,
+    lang: Option<&'static str>,
+    text: Option<String>,
+}
+
+pub async fn run(args: ExtractDebugCmd) -> Result<()> {
+    let log = telemetry::extract_debug();
+    let _g = log.root_span_kv([("url", args.url.clone()), ("extract_format", format!("{:?}", args.extract_format))]).entered();
+
+    let host = Url::parse(&args.url)?.host_str().map(|s| s.to_string()).unwrap_or_default();
+    let extractor = extractor::extractor_name(&host);
+    log.info(format!("🔎 host={} extractor={}", host, extractor));
+
+    let client = http::client_builder()?.build()?;
+    let article = { let _s = log.span(&ExtractPhase::Fetch).entered(); fetch::fetch_article(&client, &args.url, fetch::resolve_max_bytes(None)).await? };
+    let extractor = if article.is_pdf() { "pdf" } else { extractor };
+    if article.is_pdf() { log.info("📄 PDF response, using the PDF extractor"); }
+
+    let text = { let _s = log.span(&ExtractPhase::Extract).entered(); extractor::extract_article(&host, &article, args.extract_format) };
+    // same acceptance rule as ingest: empty text counts as extract-empty
+    let text = text.filter(|t| !t.trim().is_empty());
+    let lang = text.as_deref().and_then(lang::detect).map(|d| d.code);
+
+    match &text {
+        Some(t) => {
+            log.info(format!("✅ extracted {} chars (lang={})", t.chars().count(), lang.unwrap_or("?")));
+            log.info(t.as_str());
+        }
+        None => log.warn(format!("⚠️  {}: {} returned no text ({} bytes)", extractor::failure_reason(&article), extractor, article.raw().len())),
+    }
+
+    let res = ExtractDebugResult {
+        url: args.url,
+        host,
+        extractor,
+        format: args.extract_format,
+        ok: text.is_some(),
+        error: if text.is_some() { None } else { Some(extractor::failure_reason(&article).to_string()) },
+        pdf: article.is_pdf(),
+        raw_bytes: article.raw().len(),
+        chars: text.as_ref().map(|t| t.chars().count()).unwrap_or(0),
+        lang,
+        text,
+    };
+    log.result(&res)?;
+    Ok(())
+}
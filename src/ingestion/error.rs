@@ -0,0 +1,133 @@
+/// Why an article ended up as `status=error`; `as_str` is what lands in `document.error_msg`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IngestError {
+    /// Connection, TLS, HTTP or body-read failure
+    FetchFailed,
+    /// The request or body download hit the client timeout
+    Timeout,
+    /// HTTP 5xx or 429: the server is down or throttling us
+    HttpUnavailable,
+    /// Any other HTTP 4xx (gone, forbidden, paywalled, ...)
+    HttpRejected,
+    /// Neither text nor PDF; rejected before download
+    NonHtml,
+    /// Body exceeded --max-bytes
+    TooLarge,
+    /// PDF response on a build without the `pdf` feature
+    PdfUnsupported,
+    /// Downloaded fine but the extractor produced no text
+    ExtractEmpty,
+}
+
+/// Error tags worth another fetch; ingest overwrites docs carrying these instead of skipping them.
+pub const TRANSIENT_TAGS: [&str; 3] = ["fetch-failed", "timeout", "http-unavailable"];
+
+impl IngestError {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IngestError::FetchFailed => "fetch-failed",
+            IngestError::Timeout => "timeout",
+            IngestError::HttpUnavailable => "http-unavailable",
+            IngestError::HttpRejected => "http-rejected",
+            IngestError::NonHtml => "non-html",
+            IngestError::TooLarge => "too-large",
+            IngestError::PdfUnsupported => "pdf-unsupported",
+            IngestError::ExtractEmpty => "extract-empty",
+        }
+    }
+
+    /// Network trouble may clear up on the next run; everything else is a property of the page.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, IngestError::FetchFailed | IngestError::Timeout | IngestError::HttpUnavailable)
+    }
+
+    /// Reads a stored `error_msg`; also accepts the tags written before the taxonomy existed.
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        match tag {
+            "fetch-failed" => Some(IngestError::FetchFailed),
+            "timeout" => Some(IngestError::Timeout),
+            "http-unavailable" => Some(IngestError::HttpUnavailable),
+            "http-rejected" => Some(IngestError::HttpRejected),
+            "non-html" | "unsupported-content-type" => Some(IngestError::NonHtml),
+            "too-large" => Some(IngestError::TooLarge),
+            "pdf-unsupported" => Some(IngestError::PdfUnsupported),
+            "extract-empty" | "extract-failed" => Some(IngestError::ExtractEmpty),
+            _ => None,
+        }
+    }
+
+    /// Classifies an article download error.
+    pub fn from_fetch(err: &anyhow::Error) -> Self {
+        let timed_out = err.chain().any(|e| e.downcast_ref::<reqwest::Error>().is_some_and(|r| r.is_timeout()));
+        if timed_out { IngestError::Timeout } else { IngestError::FetchFailed }
+    }
+
+    /// Classifies an HTTP error status; None for anything that isn't 4xx/5xx.
+    pub fn from_status(status: reqwest::StatusCode) -> Option<Self> {
+        if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            Some(IngestError::HttpUnavailable)
+        } else if status.is_client_error() {
+            Some(IngestError::HttpRejected)
+        } else {
+            None
+        }
+    }
+}
+
+impl std::fmt::Display for IngestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [IngestError; 8] = [
+        IngestError::FetchFailed,
+        IngestError::Timeout,
+        IngestError::HttpUnavailable,
+        IngestError::HttpRejected,
+        IngestError::NonHtml,
+        IngestError::TooLarge,
+        IngestError::PdfUnsupported,
+        IngestError::ExtractEmpty,
+    ];
+
+    #[test]
+    fn tags_round_trip() {
+        for kind in ALL {
+            assert_eq!(IngestError::from_tag(kind.as_str()), Some(kind));
+        }
+        assert_eq!(IngestError::from_tag("lang-mismatch:fra"), None);
+    }
+
+    #[test]
+    fn legacy_tags_map_onto_new_kinds() {
+        assert_eq!(IngestError::from_tag("unsupported-content-type"), Some(IngestError::NonHtml));
+        assert_eq!(IngestError::from_tag("extract-failed"), Some(IngestError::ExtractEmpty));
+    }
+
+    #[test]
+    fn transient_tags_match_is_transient() {
+        let transient: Vec<&str> = ALL.iter().filter(|k| k.is_transient()).map(|k| k.as_str()).collect();
+        assert_eq!(transient, TRANSIENT_TAGS);
+    }
+
+    #[test]
+    fn non_reqwest_errors_are_fetch_failures() {
+        assert_eq!(IngestError::from_fetch(&anyhow::anyhow!("connection reset")), IngestError::FetchFailed);
+    }
+
+    #[test]
+    fn http_statuses_split_transient_and_permanent() {
+        use reqwest::StatusCode;
+        assert_eq!(IngestError::from_status(StatusCode::SERVICE_UNAVAILABLE), Some(IngestError::HttpUnavailable));
+        assert_eq!(IngestError::from_status(StatusCode::TOO_MANY_REQUESTS), Some(IngestError::HttpUnavailable));
+        assert_eq!(IngestError::from_status(StatusCode::NOT_FOUND), Some(IngestError::HttpRejected));
+        assert_eq!(IngestError::from_status(StatusCode::FORBIDDEN), Some(IngestError::HttpRejected));
+        assert_eq!(IngestError::from_status(StatusCode::OK), None);
+        assert_eq!(IngestError::from_status(StatusCode::MOVED_PERMANENTLY), None);
+    }
+}
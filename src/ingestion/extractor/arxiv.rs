@@ -1,5 +1,7 @@
 use scraper::{Html, Selector};
 
+use crate::util::text::collapse_whitespace;
+
 pub fn extract(html: &str) -> Option<String> {
     let doc = Html::parse_document(html);
 
@@ -30,6 +32,14 @@ pub fn extract(html: &str) -> Option<String> {
     None
 }
 
+/// Full-text PDF URL for an arXiv abstract page (/abs/<id> → /pdf/<id>).
+pub fn pdf_url(link: &str) -> Option<String> {
+    let url = url::Url::parse(link).ok()?;
+    if url.host_str()? != "arxiv.org" { return None; }
+    let id = url.path().strip_prefix("/abs/")?.trim_end_matches('/');
+    if id.is_empty() { None } else { Some(format!("https://arxiv.org/pdf/{}", id)) }
+}
+
 fn extract_meta(doc: &Html, sel_str: &str) -> Option<String> {
     let sel = Selector::parse(sel_str).ok()?;
     let node = doc.select(&sel).next()?;
@@ -69,23 +79,6 @@ fn normalize_abstract(s: &str) -> String {
     collapse_whitespace(&out)
 }
 
-fn collapse_whitespace(s: &str) -> String {
-    let mut buf = String::with_capacity(s.len());
-    let mut in_ws = false;
-    for ch in s.chars() {
-        if ch.is_whitespace() {
-            if !in_ws {
-                if !buf.is_empty() { buf.push(' '); }
-                in_ws = true;
-            }
-        } else {
-            buf.push(ch);
-            in_ws = false;
-        }
-    }
-    buf.trim().to_string()
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -140,6 +133,12 @@ mod tests {
         assert_eq!(got, "Full variant here.");
     }
 
+    #[test]
+    fn pdf_url_from_abs_link() {
+        assert_eq!(pdf_url("https://arxiv.org/abs/1706.03762v7").as_deref(), Some("https://arxiv.org/pdf/1706.03762v7"));
+        assert_eq!(pdf_url("https://arxiv.org/list/cs.CL/new"), None);
+    }
+
     #[test]
     fn none_when_missing() {
         let html = r#"<html><head><title>No abstract</title></head><body><p>Nothing</p></body></html>"#;
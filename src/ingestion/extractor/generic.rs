@@ -1,6 +1,9 @@
 use scraper::{Html, Selector};
 
-pub fn scrape_generic(html: &str) -> Option<String> {
+use super::markdown::to_markdown;
+use super::ExtractFormat;
+
+pub fn scrape_generic(html: &str, format: ExtractFormat) -> Option<String> {
     let doc = Html::parse_document(html);
 
     // try a set of likely article containers first
@@ -13,7 +16,7 @@ pub fn scrape_generic(html: &str) -> Option<String> {
         ".post-content",
     ];
     for sel in candidates.iter() {
-        if let Some(text) = scrape_with_selector(&doc, sel) {
+        if let Some(text) = scrape_with_selector(&doc, sel, format) {
             if text.len() >= 200 { return Some(text); }
         }
     }
@@ -22,19 +25,24 @@ pub fn scrape_generic(html: &str) -> Option<String> {
     let p_sel = Selector::parse("p").ok()?;
     let mut out: Vec<String> = Vec::new();
     for p in doc.select(&p_sel) {
-        let t = p.text().collect::<String>();
-        let s = normalize(&t);
+        let s = match format {
+            ExtractFormat::Text => normalize(&p.text().collect::<String>()),
+            ExtractFormat::Markdown => to_markdown(p),
+        };
         if !s.is_empty() { out.push(s); }
     }
-    let joined = out.join("\n");
+    let sep = match format { ExtractFormat::Text => "\n", ExtractFormat::Markdown => "\n\n" };
+    let joined = out.join(sep);
     if joined.trim().is_empty() { None } else { Some(joined) }
 }
 
-fn scrape_with_selector(doc: &Html, selector: &str) -> Option<String> {
+fn scrape_with_selector(doc: &Html, selector: &str, format: ExtractFormat) -> Option<String> {
     let sel = Selector::parse(selector).ok()?;
     let node = doc.select(&sel).next()?;
-    let text = node.text().collect::<String>();
-    let s = normalize(&text);
+    let s = match format {
+        ExtractFormat::Text => normalize(&node.text().collect::<String>()),
+        ExtractFormat::Markdown => to_markdown(node),
+    };
     if s.trim().is_empty() { None } else { Some(s) }
 }
 
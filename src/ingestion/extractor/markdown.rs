@@ -0,0 +1,169 @@
+use scraper::{ElementRef, Node};
+
+/// Render an element subtree as Markdown: headings, paragraphs, lists, code, quotes, links.
+pub fn to_markdown(root: ElementRef) -> String {
+    let mut out = String::new();
+    children(root, &mut out);
+    tidy(&out)
+}
+
+fn children(el: ElementRef, out: &mut String) {
+    for child in el.children() {
+        match child.value() {
+            Node::Text(t) => push_text(out, t),
+            Node::Element(_) => { if let Some(c) = ElementRef::wrap(child) { element(c, out); } }
+            _ => {}
+        }
+    }
+}
+
+fn element(el: ElementRef, out: &mut String) {
+    let name = el.value().name();
+    match name {
+        // page chrome and non-content
+        "script" | "style" | "noscript" | "nav" | "footer" | "aside" | "form" | "svg" | "button" | "iframe" => {}
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+            let level = name[1..].parse::<usize>().unwrap_or(1);
+            let mut title = String::new();
+            children(el, &mut title);
+            let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+            if title.is_empty() { return; }
+            break_block(out);
+            out.push_str(&"#".repeat(level));
+            out.push(' ');
+            out.push_str(&title);
+            break_block(out);
+        }
+        "br" => out.push('\n'),
+        "hr" => { break_block(out); out.push_str("---"); break_block(out); }
+        "pre" => {
+            let code = el.text().collect::<String>();
+            let code = code.trim_matches('\n');
+            if code.trim().is_empty() { return; }
+            break_block(out);
+            out.push_str("```\n");
+            out.push_str(code);
+            out.push_str("\n```");
+            break_block(out);
+        }
+        "code" => {
+            let code = el.text().collect::<String>();
+            if !code.trim().is_empty() { out.push('`'); out.push_str(code.trim()); out.push('`'); }
+        }
+        "ul" | "ol" => {
+            break_block(out);
+            let ordered = name == "ol";
+            let items = el.children().filter_map(ElementRef::wrap).filter(|c| c.value().name() == "li");
+            for (i, li) in items.enumerate() {
+                let mut item = String::new();
+                children(li, &mut item);
+                let item = tidy(&item).replace("\n\n", "\n");
+                if item.is_empty() { continue; }
+                let marker = if ordered { format!("{}. ", i + 1) } else { "- ".to_string() };
+                let indent = " ".repeat(marker.len());
+                for (j, line) in item.lines().enumerate() {
+                    if j == 0 { out.push_str(&marker); } else { out.push('\n'); out.push_str(&indent); }
+                    out.push_str(line);
+                }
+                out.push('\n');
+            }
+            break_block(out);
+        }
+        "blockquote" => {
+            let mut inner = String::new();
+            children(el, &mut inner);
+            let inner = tidy(&inner);
+            if inner.is_empty() { return; }
+            break_block(out);
+            let quoted: Vec<String> = inner.lines().map(|l| if l.is_empty() { ">".to_string() } else { format!("> {}", l) }).collect();
+            out.push_str(&quoted.join("\n"));
+            break_block(out);
+        }
+        "strong" | "b" => wrap_inline(el, out, "**"),
+        "em" | "i" => wrap_inline(el, out, "_"),
+        "a" => {
+            let mut text = String::new();
+            children(el, &mut text);
+            let text = text.trim();
+            match el.value().attr("href") {
+                Some(href) if !text.is_empty() && (href.starts_with("http://") || href.starts_with("https://")) => {
+                    out.push_str(&format!("[{}]({})", text, href));
+                }
+                _ => out.push_str(text),
+            }
+        }
+        "p" | "div" | "section" | "article" | "main" | "header" | "figure" | "figcaption" | "table" | "tr" | "li" | "dl" | "dd" | "dt" => {
+            break_block(out);
+            children(el, out);
+            break_block(out);
+        }
+        _ => children(el, out),
+    }
+}
+
+fn wrap_inline(el: ElementRef, out: &mut String, mark: &str) {
+    let mut inner = String::new();
+    children(el, &mut inner);
+    let inner = inner.trim();
+    if inner.is_empty() { return; }
+    out.push_str(mark);
+    out.push_str(inner);
+    out.push_str(mark);
+}
+
+// collapse whitespace runs like a browser would; no leading space at line start
+fn push_text(out: &mut String, t: &str) {
+    for ch in t.chars() {
+        if ch.is_whitespace() {
+            if !out.is_empty() && !out.ends_with(char::is_whitespace) { out.push(' '); }
+        } else {
+            out.push(ch);
+        }
+    }
+}
+
+fn break_block(out: &mut String) {
+    while out.ends_with(' ') { out.pop(); }
+    if out.is_empty() || out.ends_with("\n\n") { return; }
+    out.push_str(if out.ends_with('\n') { "\n" } else { "\n\n" });
+}
+
+// trim each line (except inside code fences) and squeeze blank lines
+fn tidy(s: &str) -> String {
+    let mut out: Vec<&str> = Vec::new();
+    let mut in_code = false;
+    for line in s.lines() {
+        if line.trim_start().starts_with("```") { in_code = !in_code; out.push(line.trim()); continue; }
+        if in_code { out.push(line.trim_end()); continue; }
+        let t = line.trim();
+        if t.is_empty() && out.last().map_or(true, |l| l.is_empty()) { continue; }
+        out.push(t);
+    }
+    out.join("\n").trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scraper::{Html, Selector};
+
+    fn md(html: &str) -> String {
+        let doc = Html::parse_document(html);
+        let sel = Selector::parse("article").unwrap();
+        to_markdown(doc.select(&sel).next().unwrap())
+    }
+
+    #[test]
+    fn headings_paragraphs_and_code_survive() {
+        let got = md(r#"<article><h2>Intro</h2><p>Some <b>bold</b>   text.</p><pre>fn main() {
+    println!("hi");
+}</pre><script>x()</script></article>"#);
+        assert_eq!(got, "## Intro\n\nSome **bold** text.\n\n```\nfn main() {\n    println!(\"hi\");\n}\n```");
+    }
+
+    #[test]
+    fn lists_and_links() {
+        let got = md(r#"<article><ul><li>one</li><li>see <a href="https://x.org">docs</a></li></ul><ol><li>a</li><li>b</li></ol></article>"#);
+        assert_eq!(got, "- one\n- see [docs](https://x.org)\n\n1. a\n2. b");
+    }
+}
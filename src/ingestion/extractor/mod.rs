@@ -1,11 +1,53 @@
 mod generic;
-mod arxiv;
+pub mod arxiv;
+mod markdown;
+pub mod pdf;
 
-pub fn extract(host: &str, html: &str) -> Option<String> {
+use super::error::IngestError;
+use super::fetch::Article;
+
+/// Shape of the extracted `text_clean`.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExtractFormat {
+    #[default]
+    Text,
+    /// Keep headings, lists, code blocks, quotes and links as Markdown
+    Markdown,
+}
+
+/// Name of the extractor `extract` dispatches to for `host`.
+pub fn extractor_name(host: &str) -> &'static str {
     match host {
         // arXiv-specific: only handle host arxiv.org (feeds guarantee /abs/<id>)
-        "arxiv.org" => arxiv::extract(html),
-        // site-specific modules could go here, e.g., "example.com" => sites::example::extract(html)
-        _ => generic::scrape_generic(html),
+        "arxiv.org" => "arxiv",
+        // site-specific modules could go here, e.g., "example.com" => "example"
+        _ => "generic",
+    }
+}
+
+pub fn extract(host: &str, html: &str, format: ExtractFormat) -> Option<String> {
+    match extractor_name(host) {
+        // abstracts are flat prose, so both formats are the same
+        "arxiv" => arxiv::extract(html),
+        _ => generic::scrape_generic(html, format),
+    }
+}
+
+/// HTML goes through the per-host extractor, PDFs through the PDF text extractor.
+pub fn extract_article(host: &str, article: &Article, format: ExtractFormat) -> Option<String> {
+    match article {
+        Article::Html(html) => extract(host, html, format),
+        Article::Pdf(bytes) => pdf::extract(bytes),
+        Article::Rejected(_) => None,
+    }
+}
+
+/// Error kind stored on the document when extraction yields nothing.
+pub fn failure_reason(article: &Article) -> IngestError {
+    match article {
+        Article::Rejected(kind) => *kind,
+        Article::Pdf(_) if !pdf::SUPPORTED => IngestError::PdfUnsupported,
+        _ => IngestError::ExtractEmpty,
     }
 }
@@ -0,0 +1,48 @@
+/// Whether this build can read PDFs (`--features pdf`).
+pub const SUPPORTED: bool = cfg!(feature = "pdf");
+
+#[cfg(feature = "pdf")]
+pub fn extract(bytes: &[u8]) -> Option<String> {
+    let raw = pdf_extract::extract_text_from_mem(bytes).ok()?;
+    let text = normalize(&raw);
+    if text.is_empty() { None } else { Some(text) }
+}
+
+#[cfg(not(feature = "pdf"))]
+pub fn extract(_bytes: &[u8]) -> Option<String> { None }
+
+// PDF text comes hard-wrapped: join lines within a paragraph, undo end-of-line
+// hyphenation, keep blank lines as paragraph breaks
+#[cfg_attr(not(feature = "pdf"), allow(dead_code))]
+fn normalize(raw: &str) -> String {
+    let mut paras: Vec<String> = Vec::new();
+    let mut cur = String::new();
+    for line in raw.lines().map(|l| l.split_whitespace().collect::<Vec<_>>().join(" ")) {
+        if line.is_empty() {
+            if !cur.is_empty() { paras.push(std::mem::take(&mut cur)); }
+            continue;
+        }
+        if cur.is_empty() {
+            cur = line;
+        } else if cur.ends_with('-') && line.starts_with(|c: char| c.is_lowercase()) {
+            cur.pop();
+            cur.push_str(&line);
+        } else {
+            cur.push(' ');
+            cur.push_str(&line);
+        }
+    }
+    if !cur.is_empty() { paras.push(cur); }
+    paras.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn joins_wrapped_lines_and_hyphens() {
+        let raw = "Attention is all\nyou need. We pro-\npose a new   model.\n\n\nSecond para-\ngraph.\n";
+        assert_eq!(normalize(raw), "Attention is all you need. We propose a new model.\nSecond paragraph.");
+    }
+}
@@ -1,13 +1,145 @@
 use anyhow::Result;
 use reqwest::Client;
+use reqwest::header::CONTENT_TYPE;
 use bytes::Bytes;
+use encoding_rs::{Encoding, UTF_8};
+
+use super::error::IngestError;
 
 pub async fn fetch_rss(client: &Client, url: &str) -> Result<Bytes> {
     let bytes = client.get(url).send().await?.bytes().await?;
     Ok(bytes)
 }
 
-pub async fn fetch_article(client: &Client, url: &str) -> Result<String> {
-    let text = client.get(url).send().await?.text().await?;
-    Ok(text)
+/// Default body cap for article downloads; RAG_FETCH_MAX_BYTES overrides it.
+pub const DEFAULT_MAX_BYTES: usize = 10 * 1024 * 1024;
+
+pub fn resolve_max_bytes(flag: Option<usize>) -> usize {
+    flag.or_else(|| std::env::var("RAG_FETCH_MAX_BYTES").ok().and_then(|v| v.parse().ok()))
+        .unwrap_or(DEFAULT_MAX_BYTES)
+}
+
+/// Article body; PDFs are kept as bytes for the PDF extractor.
+/// `Rejected` carries the error kind for responses that were not downloaded (or failed to).
+pub enum Article {
+    Html(String),
+    Pdf(Bytes),
+    Rejected(IngestError),
+}
+
+impl Article {
+    pub fn is_pdf(&self) -> bool { matches!(self, Article::Pdf(_)) }
+
+    // stored as document.raw_html
+    pub fn raw(&self) -> &[u8] {
+        match self { Article::Html(s) => s.as_bytes(), Article::Pdf(b) => b, Article::Rejected(_) => &[] }
+    }
+}
+
+// text/* and XML-ish types are worth extracting; missing headers get the benefit of the doubt
+fn is_textual(content_type: Option<&str>) -> bool {
+    let Some(ct) = content_type else { return true; };
+    let ct = ct.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+    ct.is_empty() || ct.starts_with("text/") || ct == "application/xhtml+xml" || ct == "application/xml"
+}
+
+// application/pdf, or a generic binary type on a *.pdf URL
+fn looks_like_pdf(content_type: Option<&str>, url: &str) -> bool {
+    let ct = content_type.map(|c| c.split(';').next().unwrap_or("").trim().to_ascii_lowercase());
+    match ct.as_deref() {
+        Some("application/pdf") | Some("application/x-pdf") => true,
+        None | Some("application/octet-stream") | Some("binary/octet-stream") => {
+            url.split(['?', '#']).next().unwrap_or("").to_ascii_lowercase().ends_with(".pdf")
+        }
+        _ => false,
+    }
+}
+
+// `charset=` value from a Content-Type header or a <meta> tag's attribute text
+fn charset_param(s: &str) -> Option<&str> {
+    let lower = s.to_ascii_lowercase();
+    let at = lower.find("charset=")? + "charset=".len();
+    let value = s[at..].trim_start_matches(['"', '\'', ' ']);
+    let end = value.find(['"', '\'', ';', ' ', '>', '/']).unwrap_or(value.len());
+    Some(&value[..end]).filter(|v| !v.is_empty())
+}
+
+// <meta charset=...> or <meta http-equiv="Content-Type" content="...; charset=...">, looked for in
+// the first 1024 bytes like browsers do
+fn sniff_meta_charset(body: &[u8]) -> Option<&'static Encoding> {
+    let head = String::from_utf8_lossy(&body[..body.len().min(1024)]);
+    let lower = head.to_ascii_lowercase();
+    lower.match_indices("<meta").find_map(|(at, _)| {
+        let tag = &head[at..];
+        let tag = &tag[..tag.find('>').unwrap_or(tag.len())];
+        charset_param(tag).and_then(|label| Encoding::for_label(label.as_bytes()))
+    })
+}
+
+/// Decodes an HTML body: a BOM wins, then the Content-Type charset, then a <meta> charset, then UTF-8.
+/// Unmappable bytes become U+FFFD rather than failing the article.
+fn decode_html(body: &[u8], content_type: Option<&str>) -> String {
+    let encoding = content_type
+        .and_then(charset_param)
+        .and_then(|label| Encoding::for_label(label.as_bytes()))
+        .or_else(|| sniff_meta_charset(body))
+        .unwrap_or(UTF_8);
+    let (text, _, _) = encoding.decode(body);
+    text.into_owned()
+}
+
+// Streams the body and gives up once it passes max_bytes, so one huge page can't exhaust memory.
+pub async fn fetch_article(client: &Client, url: &str, max_bytes: usize) -> Result<Article> {
+    let mut resp = client.get(url).send().await?;
+    // an error page is not the article; don't extract it or keep its body
+    if let Some(kind) = IngestError::from_status(resp.status()) { return Ok(Article::Rejected(kind)); }
+    let content_type = resp.headers().get(CONTENT_TYPE).and_then(|v| v.to_str().ok()).map(|s| s.to_string());
+    let is_pdf = looks_like_pdf(content_type.as_deref(), resp.url().as_str());
+    if !is_pdf && !is_textual(content_type.as_deref()) { return Ok(Article::Rejected(IngestError::NonHtml)); }
+    if resp.content_length().is_some_and(|n| n > max_bytes as u64) { return Ok(Article::Rejected(IngestError::TooLarge)); }
+
+    let mut body: Vec<u8> = Vec::new();
+    while let Some(chunk) = resp.chunk().await? {
+        if body.len() + chunk.len() > max_bytes { return Ok(Article::Rejected(IngestError::TooLarge)); }
+        body.extend_from_slice(&chunk);
+    }
+    if is_pdf { return Ok(Article::Pdf(Bytes::from(body))); }
+    Ok(Article::Html(decode_html(&body, content_type.as_deref())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pdf_sniffing() {
+        assert!(looks_like_pdf(Some("application/pdf"), "https://x.org/paper"));
+        assert!(looks_like_pdf(Some("application/octet-stream"), "https://x.org/report.PDF?dl=1"));
+        assert!(looks_like_pdf(None, "https://x.org/a.pdf"));
+        assert!(!looks_like_pdf(Some("text/html; charset=utf-8"), "https://x.org/a.pdf"));
+        assert!(!looks_like_pdf(None, "https://x.org/a.html"));
+    }
+
+    #[test]
+    fn textual_content_types() {
+        assert!(is_textual(Some("text/html; charset=utf-8")));
+        assert!(is_textual(Some("application/xhtml+xml")));
+        assert!(is_textual(None));
+        assert!(!is_textual(Some("image/png")));
+        assert!(!is_textual(Some("application/zip")));
+    }
+
+    #[test]
+    fn latin1_bodies_decode_by_charset() {
+        // "café – naïve" in windows-1252 (what browsers use for latin1 labels)
+        let body = b"<p>caf\xe9 \x96 na\xefve</p>";
+        assert_eq!(decode_html(body, Some("text/html; charset=ISO-8859-1")), "<p>café – naïve</p>");
+        let page = b"<html><head><meta charset=\"latin1\"></head><p>caf\xe9</p>";
+        assert!(decode_html(page, Some("text/html")).contains("café"));
+        let page = b"<meta http-equiv=\"Content-Type\" content=\"text/html; charset=iso-8859-1\"><p>caf\xe9</p>";
+        assert!(decode_html(page, None).contains("café"));
+        // no charset anywhere: UTF-8, invalid bytes replaced
+        assert_eq!(decode_html("café".as_bytes(), None), "café");
+        assert_eq!(decode_html(b"caf\xe9", None), "caf\u{fffd}");
+    }
 }
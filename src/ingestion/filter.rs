@@ -0,0 +1,58 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+
+/// `--url-filter` / `--title-filter`: items that don't match are skipped before any fetch.
+pub struct ItemFilter {
+    url: Option<Regex>,
+    title: Option<Regex>,
+}
+
+impl ItemFilter {
+    pub fn new(url: Option<&str>, title: Option<&str>) -> Result<Self> {
+        let url = url.map(|p| Regex::new(p).with_context(|| format!("invalid --url-filter {:?}", p))).transpose()?;
+        let title = title.map(|p| Regex::new(p).with_context(|| format!("invalid --title-filter {:?}", p))).transpose()?;
+        Ok(Self { url, title })
+    }
+
+    // Skip reason for an item, or None to keep it; a missing link/title never matches
+    pub fn reject(&self, link: Option<&str>, title: Option<&str>) -> Option<&'static str> {
+        if let Some(re) = &self.url {
+            if !link.is_some_and(|l| re.is_match(l)) { return Some("url-filter"); }
+        }
+        if let Some(re) = &self.title {
+            if !title.is_some_and(|t| re.is_match(t)) { return Some("title-filter"); }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(link: &str, title: &str) -> rss::Item {
+        let mut it = rss::Item::default();
+        it.set_link(link.to_string());
+        it.set_title(title.to_string());
+        it
+    }
+
+    #[test]
+    fn non_matching_items_are_rejected_before_fetch() {
+        let f = ItemFilter::new(Some(r"arxiv\.org/abs/"), Some("(?i)transformer")).unwrap();
+        let keep = item("https://arxiv.org/abs/2401.00001", "Sparse Transformers");
+        let wrong_url = item("https://example.com/post", "Transformer notes");
+        let wrong_title = item("https://arxiv.org/abs/2401.00002", "Graph kernels");
+        assert_eq!(f.reject(keep.link(), keep.title()), None);
+        assert_eq!(f.reject(wrong_url.link(), wrong_url.title()), Some("url-filter"));
+        assert_eq!(f.reject(wrong_title.link(), wrong_title.title()), Some("title-filter"));
+        assert_eq!(f.reject(None, Some("Transformer")), Some("url-filter"));
+    }
+
+    #[test]
+    fn no_patterns_keep_everything_and_bad_patterns_error() {
+        let f = ItemFilter::new(None, None).unwrap();
+        assert_eq!(f.reject(None, None), None);
+        assert!(ItemFilter::new(Some("("), None).is_err());
+    }
+}
@@ -0,0 +1,59 @@
+use anyhow::{bail, Result};
+use whatlang::Lang;
+
+/// Detected language as an ISO 639-3 code (e.g. "eng"), plus whether whatlang trusts it.
+pub struct Detected { pub code: &'static str, pub reliable: bool }
+
+pub fn detect(text: &str) -> Option<Detected> {
+    let info = whatlang::detect(text)?;
+    Some(Detected { code: info.lang().code(), reliable: info.is_reliable() })
+}
+
+// Accept ISO 639-3 (eng) or the common 639-1 forms (en) for --only-lang
+pub fn parse_only_lang(s: &str) -> Result<Vec<&'static str>> {
+    let mut out = Vec::new();
+    for raw in s.split(',').map(|t| t.trim().to_ascii_lowercase()).filter(|t| !t.is_empty()) {
+        let code = match raw.as_str() {
+            "en" => "eng", "de" => "deu", "fr" => "fra", "es" => "spa", "it" => "ita",
+            "pt" => "por", "nl" => "nld", "ru" => "rus", "zh" => "cmn", "ja" => "jpn",
+            "ko" => "kor", other => other,
+        };
+        match Lang::from_code(code) {
+            Some(l) => out.push(l.code()),
+            None => bail!("unknown language code: {}", raw),
+        }
+    }
+    if out.is_empty() { bail!("--only-lang needs at least one language code"); }
+    Ok(out)
+}
+
+// Only reliable detections are filtered; unsure or undetected docs are kept
+pub fn is_excluded(detected: Option<&Detected>, only: &[&str]) -> bool {
+    match detected {
+        Some(d) if d.reliable => !only.contains(&d.code),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_short_and_long_codes() {
+        assert_eq!(parse_only_lang("en, fra").unwrap(), vec!["eng", "fra"]);
+        assert!(parse_only_lang("xx").is_err());
+    }
+
+    #[test]
+    fn filters_only_reliable_mismatches() {
+        let only = ["eng"];
+        let de = Detected { code: "deu", reliable: true };
+        let unsure = Detected { code: "deu", reliable: false };
+        let en = Detected { code: "eng", reliable: true };
+        assert!(is_excluded(Some(&de), &only));
+        assert!(!is_excluded(Some(&unsure), &only));
+        assert!(!is_excluded(Some(&en), &only));
+        assert!(!is_excluded(None, &only));
+    }
+}
@@ -3,17 +3,33 @@ use clap::Args;
 use sqlx::PgPool;
 use reqwest::Client;
 use chrono::{DateTime, Utc};
+use std::time::Duration;
 use url::Url;
 
+use crate::feed;
+use crate::util::{cancel, exit, http};
+use self::error::IngestError;
+use self::extractor::ExtractFormat;
+use self::types::{IngestApplied, IngestOutcome, IngestPlanned};
+use self::write::Written;
+use crate::output::types::Outcome;
 use crate::telemetry::{self};
-use crate::telemetry::ops::ingest::Phase as IngestPhase;
+use crate::telemetry::ctx::LogCtx;
+use crate::telemetry::ops::ingest::{Ingest, Phase as IngestPhase};
 
+pub(crate) mod error;
 mod fetch;
 mod parse;
 mod write;
-mod types;
+pub(crate) mod types;
 mod db;
+mod lang;
+pub(crate) mod simhash;
+mod robots;
+mod filter;
+mod retry;
 pub mod extractor;
+pub mod debug;
 
 #[derive(Args)]
 pub struct IngestCmd {
@@ -23,9 +39,77 @@ pub struct IngestCmd {
     #[arg(long)] pub force_refetch: bool,
     #[arg(long, default_value_t=false)] pub apply: bool,
     #[arg(long, default_value_t=10)] pub plan_limit: usize,
+    /// Keep only docs detected in these languages (e.g. en or eng,fra); others get status=filtered
+    #[arg(long)] pub only_lang: Option<String>,
+    /// Mark docs within this many SimHash bits (of 64) of a doc fetched in the last 30 days as duplicates; unset disables
+    #[arg(long)] pub dedup_threshold: Option<u32>,
+    /// Ignore the per-feed last_item_at watermark and walk up to --limit items
+    #[arg(long, default_value_t=false)] pub full: bool,
+    /// Store text_clean as plain text or as Markdown (keeps headings, lists, code blocks)
+    #[arg(long, value_enum, default_value_t=ExtractFormat::Text)] pub extract_format: ExtractFormat,
+    /// arXiv: fetch the paper PDF and store its full text instead of the abstract (needs --features pdf)
+    #[arg(long, default_value_t=false)] pub arxiv_pdf: bool,
+    /// Abort article downloads larger than this (doc gets error_msg=too-large); default RAG_FETCH_MAX_BYTES or 10 MiB
+    #[arg(long)] pub max_bytes: Option<usize>,
+    /// Minimum delay between requests to the same host; a longer robots.txt Crawl-delay wins
+    #[arg(long, default_value_t=500)] pub crawl_delay_ms: u64,
+    /// When an item has no feed date, take published_at from the article's meta tags / <time datetime>
+    #[arg(long, default_value_t=false)] pub parse_published_from_content: bool,
+    /// Only fetch items whose link matches this regex; others are skipped (reason=url-filter)
+    #[arg(long)] pub url_filter: Option<String>,
+    /// Only fetch items whose title matches this regex; others are skipped (reason=title-filter)
+    #[arg(long)] pub title_filter: Option<String>,
+    /// Re-fetch docs stored as status=error instead of walking feeds; those that extract cleanly go back to ingest
+    #[arg(long, default_value_t=false)] pub retry_errors: bool,
+    /// With --retry-errors, only retry transient failures (error_msg=fetch-failed, timeout or http-unavailable)
+    #[arg(long, default_value_t=false, requires="retry_errors")] pub transient_only: bool,
+    /// Store title/link/date only (status=metadata, empty text) and skip article downloads; a later --full ingest fills them in
+    #[arg(long, default_value_t=false, conflicts_with_all=["force_refetch", "retry_errors"])] pub metadata_only: bool,
+}
+
+/// Library-facing ingest parameters; `IngestCmd` maps onto these one-to-one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IngestParams {
+    pub feed: Option<i32>,
+    pub feed_url: Option<String>,
+    pub limit: usize,
+    pub force_refetch: bool,
+    pub apply: bool,
+    pub plan_limit: usize,
+    pub only_lang: Option<String>,
+    pub dedup_threshold: Option<u32>,
+    pub full: bool,
+    pub extract_format: ExtractFormat,
+    pub arxiv_pdf: bool,
+    pub max_bytes: Option<usize>,
+    pub crawl_delay_ms: u64,
+    pub parse_published_from_content: bool,
+    pub url_filter: Option<String>,
+    pub title_filter: Option<String>,
+    pub retry_errors: bool,
+    pub transient_only: bool,
+    pub metadata_only: bool,
+}
+
+impl Default for IngestParams {
+    fn default() -> Self {
+        Self { feed: None, feed_url: None, limit: 200, force_refetch: false, apply: false, plan_limit: 10, only_lang: None, dedup_threshold: None, full: false, extract_format: ExtractFormat::Text, arxiv_pdf: false, max_bytes: None, crawl_delay_ms: 500, parse_published_from_content: false, url_filter: None, title_filter: None, retry_errors: false, transient_only: false, metadata_only: false }
+    }
+}
+
+impl From<IngestCmd> for IngestParams {
+    fn from(c: IngestCmd) -> Self {
+        Self { feed: c.feed, feed_url: c.feed_url, limit: c.limit, force_refetch: c.force_refetch, apply: c.apply, plan_limit: c.plan_limit, only_lang: c.only_lang, dedup_threshold: c.dedup_threshold, full: c.full, extract_format: c.extract_format, arxiv_pdf: c.arxiv_pdf, max_bytes: c.max_bytes, crawl_delay_ms: c.crawl_delay_ms, parse_published_from_content: c.parse_published_from_content, url_filter: c.url_filter, title_filter: c.title_filter, retry_errors: c.retry_errors, transient_only: c.transient_only, metadata_only: c.metadata_only }
+    }
 }
 
 pub async fn run(pool: &PgPool, args: IngestCmd) -> Result<()> {
+    let outcome = execute(pool, args.into()).await?;
+    if matches!(&outcome, Outcome::Applied(IngestApplied::Retry(r)) if r.selected == 0) { exit::mark_empty(); }
+    telemetry::ingest().outcome(&outcome)
+}
+
+pub async fn execute(pool: &PgPool, args: IngestParams) -> Result<IngestOutcome> {
     let log = telemetry::ingest();
     let _g = log.root_span_kv([
         ("apply", args.apply.to_string()),
@@ -34,74 +118,214 @@ pub async fn run(pool: &PgPool, args: IngestCmd) -> Result<()> {
         ("force_refetch", args.force_refetch.to_string()),
         ("feed", format!("{:?}", args.feed)),
         ("feed_url", format!("{:?}", args.feed_url)),
+        ("only_lang", format!("{:?}", args.only_lang)),
+        ("dedup_threshold", format!("{:?}", args.dedup_threshold)),
+        ("full", args.full.to_string()),
+        ("extract_format", format!("{:?}", args.extract_format)),
+        ("arxiv_pdf", args.arxiv_pdf.to_string()),
+        ("max_bytes", format!("{:?}", args.max_bytes)),
+        ("crawl_delay_ms", args.crawl_delay_ms.to_string()),
+        ("parse_published_from_content", args.parse_published_from_content.to_string()),
+        ("url_filter", format!("{:?}", args.url_filter)),
+        ("title_filter", format!("{:?}", args.title_filter)),
+        ("retry_errors", args.retry_errors.to_string()),
+        ("transient_only", args.transient_only.to_string()),
+        ("metadata_only", args.metadata_only.to_string()),
     ]).entered();
+    let max_bytes = fetch::resolve_max_bytes(args.max_bytes);
+
+    if args.arxiv_pdf && !extractor::pdf::SUPPORTED {
+        log.warn("⚠️  --arxiv-pdf needs a build with --features pdf; keeping abstracts");
+    }
+
+    let only_lang = args.only_lang.as_deref().map(lang::parse_only_lang).transpose()?;
+    let item_filter = filter::ItemFilter::new(args.url_filter.as_deref(), args.title_filter.as_deref())?;
+
+    // maintenance mode: walk existing error docs instead of feed items
+    if args.retry_errors {
+        return retry::retry_errors(pool, &log, &args, only_lang.as_deref(), max_bytes).await;
+    }
 
     // resolve feeds to process
     let feeds = db::select_feeds(pool, args.feed, args.feed_url.as_deref()).await?;
 
     if !args.apply {
-        let mode = if args.force_refetch { "upsert" } else { "insert-only" };
+        let mode = if args.metadata_only { "metadata-only" } else if args.force_refetch { "upsert" } else { "insert-only" };
+        let walk = if args.full || args.force_refetch { "full" } else { "since-watermark" };
         // Always log plan summary
-        log.info(format!("📝 Ingest plan — feeds={} mode={} limit={}", feeds.len(), mode, args.limit));
-        for f in feeds.iter().take(args.plan_limit) { log.info(format!("  feed_id={} url={} name={:?}", f.feed_id, f.url, f.name)); }
+        log.info(format!("📝 Ingest plan — feeds={} mode={} walk={} limit={} extract_format={:?}", feeds.len(), mode, walk, args.limit, args.extract_format));
+        if let Some(langs) = &only_lang { log.info(format!("  only_lang={}", langs.join(","))); }
+        if let Some(t) = args.dedup_threshold { log.info(format!("  dedup_threshold={} bits", t)); }
+        if let Some(p) = &args.url_filter { log.info(format!("  url_filter={}", p)); }
+        if let Some(p) = &args.title_filter { log.info(format!("  title_filter={}", p)); }
+        for f in feeds.iter().take(args.plan_limit) { log.info(format!("  feed_id={} url={} name={:?} last_item_at={:?}", f.feed_id, f.url, f.name, f.last_item_at)); }
         if feeds.len() > args.plan_limit { log.info(format!("  ... ({} more)", feeds.len() - args.plan_limit)); }
         log.info("   Use --apply to execute.");
-        // Emit structured plan to stdout
         use types::{FeedSample, IngestPlan};
         let samples: Vec<FeedSample> = feeds.iter().take(args.plan_limit)
-            .map(|f| FeedSample { feed_id: f.feed_id, url: f.url.clone(), name: f.name.clone() })
+            .map(|f| FeedSample { feed_id: f.feed_id, url: f.url.clone(), name: f.name.clone(), last_item_at: f.last_item_at })
             .collect();
-        let plan = IngestPlan { feeds: feeds.len(), mode: mode.to_string(), limit: args.limit, sample_feeds: samples };
-        log.plan(&plan)?;
-        return Ok(());
+        let plan = IngestPlan {
+            feeds: feeds.len(),
+            mode: mode.to_string(),
+            walk: walk.to_string(),
+            limit: args.limit,
+            only_lang: only_lang.as_ref().map(|v| v.iter().map(|s| s.to_string()).collect()),
+            dedup_threshold: args.dedup_threshold,
+            url_filter: args.url_filter.clone(),
+            title_filter: args.title_filter.clone(),
+            extract_format: args.extract_format,
+            sample_feeds: samples,
+        };
+        return Ok(Outcome::Plan(IngestPlanned::Feeds(plan)));
     }
 
-    let client = Client::new();
+    let client = http::client_builder()?.user_agent(concat!("ragfeed/", env!("CARGO_PKG_VERSION"))).build()?;
+    let mut robots = robots::Robots::new(Duration::from_millis(args.crawl_delay_ms));
 
     let mut total_inserted = 0usize;
     let mut total_updated = 0usize;
     let mut total_skipped = 0usize;
+    let mut total_duplicates = 0usize;
     let mut total_errors  = 0usize;
 
     use types::FeedSummary;
     let mut per_feed: Vec<FeedSummary> = Vec::new();
+    let feeds_total = feeds.len() as u64;
 
+    let mut cancelled = false;
     for f in feeds {
+        if cancel::is_cancelled() { cancelled = true; break; }
         let _feed_span = log.span_kv(&IngestPhase::Feed, [("feed_id", f.feed_id.to_string()), ("url", f.url.clone())]).entered();
         let mut inserted = 0usize;
         let mut updated  = 0usize;
         let mut skipped  = 0usize;
+        let mut duplicates = 0usize;
         let mut errors   = 0usize;
 
         // fetch and parse RSS channel
         let xml = { let _s = log.span(&IngestPhase::FetchRss).entered(); fetch::fetch_rss(&client, &f.url).await? };
         let channel = { let _s = log.span(&IngestPhase::ParseRss).entered(); parse::parse_channel(&xml)? };
 
+        // feeds are assumed newest-first: stop at the first dated item older than the watermark;
+        // items dated exactly at it are walked again and the source_url conflict skips known ones.
+        // --force-refetch revisits known items, so it implies --full
+        let watermark = if args.full || args.force_refetch { None } else { f.last_item_at };
+        let mut newest_seen: Option<DateTime<Utc>> = None;
+        let mut reached_watermark = false;
+
         for item in channel.items().iter().take(args.limit) {
+            if cancel::is_cancelled() { cancelled = true; break; }
+            let published_at: Option<DateTime<Utc>> = parse::extract_published_at(item);
+            if let (Some(p), Some(w)) = (published_at, watermark) {
+                if p < w {
+                    reached_watermark = true;
+                    log.info_kv("⏹️ watermark reached", [("feed_id", f.feed_id.to_string()), ("last_item_at", w.to_rfc3339())]);
+                    break;
+                }
+            }
+            // the watermark only moves past items stored for good: a transient fetch failure must
+            // stay above it so the next run retries the item
+            let feed_date = published_at;
+
+            // pattern filters run before robots.txt and any download
+            if let Some(reason) = item_filter.reject(item.link(), item.title()) {
+                skipped += 1;
+                log.info_kv("↩️ skip", [("reason", reason.to_string()), ("url", item.link().unwrap_or("").to_string()), ("title", item.title().unwrap_or("").to_string())]);
+                continue;
+            }
+
+            // triage pass: index entry only, no robots.txt lookup or article download
+            if let (true, Some(link)) = (args.metadata_only, item.link()) {
+                let _ws = log.span_kv(&IngestPhase::WriteDoc, [("mode", "metadata".to_string())]).entered();
+                let written = write::insert_document(pool, f.feed_id, link, item.title(), published_at, "", &[], "metadata", None, None, None, None).await?;
+                newest_seen = newest_seen.max(feed_date);
+                match written {
+                    Written::Inserted => { inserted += 1; log.info_kv("➕ insert", [("url", link.to_string()), ("title", item.title().unwrap_or("").to_string()), ("status", "metadata".to_string())]); }
+                    Written::Updated => { updated += 1; log.info_kv("♻️ update", [("url", link.to_string()), ("title", item.title().unwrap_or("").to_string()), ("status", "metadata".to_string())]); }
+                    Written::Skipped => { skipped += 1; log.info_kv("↩️ skip", [("title", item.title().unwrap_or("").to_string())]); }
+                }
+                continue;
+            }
+
             if let Some(link) = item.link() {
+                // robots.txt + per-host politeness before touching the article
+                if let Ok(article_url) = Url::parse(link) {
+                    if !robots.allowed(&client, &article_url).await {
+                        skipped += 1;
+                        log.info_kv("↩️ skip", [("reason", "robots-disallow".to_string()), ("url", link.to_string())]);
+                        continue;
+                    }
+                    robots.wait_turn(&client, &article_url).await;
+                }
+
                 // fetch article
-                let html = { let _s = log.span_kv(&IngestPhase::FetchItem, [("url", link.to_string())]).entered(); fetch::fetch_article(&client, link).await? };
+                let article = { let _s = log.span_kv(&IngestPhase::FetchItem, [("url", link.to_string())]).entered(); fetch_or_reject(&log, &client, link, max_bytes).await };
+
+                // feed had no date: optionally recover it from the page (not used for the watermark)
+                let published_at = match (&article, published_at) {
+                    (fetch::Article::Html(html), None) if args.parse_published_from_content => parse::published_from_html(html),
+                    _ => published_at,
+                };
 
                 // per-host extraction with fallback
                 let host = Url::parse(link).ok().and_then(|u| u.host_str().map(|s| s.to_string())).unwrap_or_default();
-                let extracted = { let _s = log.span_kv(&IngestPhase::Extract, [("host", host.clone())]).entered(); extractor::extract(&host, &html) };
-                let (text, status, error_msg) = match extracted {
-                    Some(t) if !t.trim().is_empty() => (t, "ingest", None),
-                    _ => ("".to_string(), "error", Some("extract-failed".to_string())),
+                let (text, status, error_msg, detected) = {
+                    let _s = log.span_kv(&IngestPhase::Extract, [("host", host.clone())]).entered();
+                    let mut extracted = extractor::extract_article(&host, &article, args.extract_format);
+                    // optional arXiv full text; the abstract stays when the PDF can't be read
+                    if let (true, true, Some(pdf_url)) = (args.arxiv_pdf, extractor::pdf::SUPPORTED, extractor::arxiv::pdf_url(link)) {
+                        let pdf_allowed = match Url::parse(&pdf_url) {
+                            Ok(u) if robots.allowed(&client, &u).await => { robots.wait_turn(&client, &u).await; true }
+                            _ => false,
+                        };
+                        if !pdf_allowed {
+                            log.info_kv("↩️ arXiv PDF skipped, keeping abstract", [("reason", "robots-disallow".to_string()), ("url", pdf_url)]);
+                        } else {
+                            match fetch::fetch_article(&client, &pdf_url, max_bytes).await {
+                                Ok(pdf) => match extractor::extract_article(&host, &pdf, args.extract_format) {
+                                    Some(full) => extracted = Some(full),
+                                    None => log.warn_kv("⚠️  arXiv PDF unreadable, keeping abstract", [("url", pdf_url)]),
+                                },
+                                Err(e) => log.warn_kv("⚠️  arXiv PDF fetch failed, keeping abstract", [("url", pdf_url), ("error", e.to_string())]),
+                            }
+                        }
+                    }
+                    classify(&log, link, &article, extracted, only_lang.as_deref())
                 };
+                let lang_code = detected.as_ref().map(|d| d.code);
+                if status == "error" { errors += 1; }
 
-                let published_at: Option<DateTime<Utc>> = parse::extract_published_at(item);
+                // near-duplicate check: syndicated copies keep a pointer to the canonical doc instead of full content
+                let fingerprint = simhash::simhash(&text);
+                let mut status = status;
+                let mut canonical_doc_id: Option<i64> = None;
+                if let (Some(threshold), Some(fp), "ingest") = (args.dedup_threshold, fingerprint, status) {
+                    canonical_doc_id = db::find_near_duplicate(pool, fp, link, threshold as i32).await?;
+                    if canonical_doc_id.is_some() { status = "duplicate"; }
+                }
+                if !error_msg.as_deref().and_then(IngestError::from_tag).is_some_and(|k| k.is_transient()) {
+                    newest_seen = newest_seen.max(feed_date);
+                }
+                let raw_html: &[u8] = if canonical_doc_id.is_some() { &[] } else { article.raw() };
+                let dup_kv = || [("url", link.to_string()), ("canonical_doc_id", canonical_doc_id.unwrap_or_default().to_string())];
 
                 if args.force_refetch {
                     let _ws = log.span_kv(&IngestPhase::WriteDoc, [("mode", "upsert".to_string())]).entered();
-                    let inserted_row = write::upsert_document(pool, f.feed_id, link, item.title(), published_at, &text, html.as_bytes(), status, error_msg.as_deref()).await?;
-                    if inserted_row { inserted += 1; log.info_kv("➕ insert", [("url", link.to_string()), ("title", item.title().unwrap_or("").to_string())]); }
+                    let inserted_row = write::upsert_document(pool, f.feed_id, link, item.title(), published_at, &text, raw_html, status, error_msg.as_deref(), lang_code, fingerprint, canonical_doc_id).await?;
+                    if canonical_doc_id.is_some() { duplicates += 1; log.info_kv("🪞 duplicate", dup_kv()); }
+                    else if inserted_row { inserted += 1; log.info_kv("➕ insert", [("url", link.to_string()), ("title", item.title().unwrap_or("").to_string())]); }
                     else { updated += 1; log.info_kv("♻️ update", [("url", link.to_string()), ("title", item.title().unwrap_or("").to_string())]); }
                 } else {
                     let _ws = log.span_kv(&IngestPhase::WriteDoc, [("mode", "insert".to_string())]).entered();
-                    let did_insert = write::insert_document(pool, f.feed_id, link, item.title(), published_at, &text, html.as_bytes(), status, error_msg.as_deref()).await?;
-                    if did_insert { inserted += 1; log.info_kv("➕ insert", [("url", link.to_string()), ("title", item.title().unwrap_or("").to_string())]); }
-                    else { skipped += 1; log.info_kv("↩️ skip", [("title", item.title().unwrap_or("").to_string())]); }
+                    // an overwritten transient-error or metadata-only doc counts as an update, not an insert
+                    let written = write::insert_document(pool, f.feed_id, link, item.title(), published_at, &text, raw_html, status, error_msg.as_deref(), lang_code, fingerprint, canonical_doc_id).await?;
+                    match written {
+                        Written::Skipped => { skipped += 1; log.info_kv("↩️ skip", [("title", item.title().unwrap_or("").to_string())]); }
+                        _ if canonical_doc_id.is_some() => { duplicates += 1; log.info_kv("🪞 duplicate", dup_kv()); }
+                        Written::Inserted => { inserted += 1; log.info_kv("➕ insert", [("url", link.to_string()), ("title", item.title().unwrap_or("").to_string())]); }
+                        Written::Updated => { updated += 1; log.info_kv("♻️ update", [("url", link.to_string()), ("title", item.title().unwrap_or("").to_string())]); }
+                    }
                 }
             } else {
                 skipped += 1;
@@ -109,21 +333,69 @@ pub async fn run(pool: &PgPool, args: IngestCmd) -> Result<()> {
             }
         }
 
+        // only advance when the walk covered everything newer (not cut short by --limit)
+        let walked_all = !cancelled && (reached_watermark || channel.items().len() <= args.limit);
+        if let (Some(newest), true) = (newest_seen, walked_all) {
+            feed::db::set_last_item_at(pool, f.feed_id, newest).await?;
+        }
+
         total_inserted += inserted;
         total_updated  += updated;
         total_skipped  += skipped;
+        total_duplicates += duplicates;
         total_errors   += errors;
-        log.feed_summary(f.feed_id, inserted, updated, skipped, errors);
-        per_feed.push(FeedSummary { feed_id: f.feed_id, inserted, updated, skipped, errors });
+        log.feed_summary(f.feed_id, inserted, updated, skipped, duplicates, errors);
+        per_feed.push(FeedSummary { feed_id: f.feed_id, inserted, updated, skipped, duplicates, errors });
+        log.progress(per_feed.len() as u64, Some(feeds_total), "feeds")?;
     }
 
-    log.totals(total_inserted, total_updated, total_skipped, total_errors);
+    log.totals(total_inserted, total_updated, total_skipped, total_duplicates, total_errors);
+    if cancelled {
+        log.warn(format!("🛑 Cancelled after {} feed(s); rerun ingest to continue", per_feed.len()));
+    }
 
     use types::{IngestTotals, IngestApply};
     let result = IngestApply {
-        totals: IngestTotals { inserted: total_inserted, updated: total_updated, skipped: total_skipped, errors: total_errors },
+        totals: IngestTotals { inserted: total_inserted, updated: total_updated, skipped: total_skipped, duplicates: total_duplicates, errors: total_errors },
         per_feed,
+        cancelled,
     };
-    log.result(&result)?;
-    Ok(())
+    Ok(Outcome::Applied(IngestApplied::Feeds(result)))
+}
+
+// A failed download becomes a Rejected article (error doc) rather than aborting the run.
+async fn fetch_or_reject(log: &LogCtx<Ingest>, client: &Client, link: &str, max_bytes: usize) -> fetch::Article {
+    match fetch::fetch_article(client, link, max_bytes).await {
+        Ok(a) => a,
+        Err(e) => {
+            let kind = IngestError::from_fetch(&e);
+            log.warn_kv("⚠️  fetch failed", [("url", link.to_string()), ("kind", kind.to_string()), ("error", e.to_string())]);
+            fetch::Article::Rejected(kind)
+        }
+    }
+}
+
+// Extraction outcome → (text_clean, status, error_msg, detected language).
+fn classify(
+    log: &LogCtx<Ingest>,
+    link: &str,
+    article: &fetch::Article,
+    extracted: Option<String>,
+    only_lang: Option<&[&'static str]>,
+) -> (String, &'static str, Option<String>, Option<lang::Detected>) {
+    match extracted {
+        Some(t) if !t.trim().is_empty() => {
+            // detect language on successful extraction; filtered docs are skipped by chunk/embed
+            let detected = lang::detect(&t);
+            match only_lang {
+                Some(only) if lang::is_excluded(detected.as_ref(), only) => {
+                    let code = detected.as_ref().map(|d| d.code).unwrap_or("");
+                    log.info_kv("🈚 lang filtered", [("url", link.to_string()), ("lang", code.to_string())]);
+                    (t, "filtered", Some(format!("lang-mismatch:{}", code)), detected)
+                }
+                _ => (t, "ingest", None, detected),
+            }
+        }
+        _ => ("".to_string(), "error", Some(extractor::failure_reason(article).to_string()), None),
+    }
 }
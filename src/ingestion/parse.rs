@@ -1,7 +1,8 @@
 use anyhow::Result;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use rss::{Channel, Item};
 use bytes::Bytes;
+use scraper::{Html, Selector};
 
 pub fn parse_channel(xml: &Bytes) -> Result<Channel> {
     let ch = Channel::read_from(&xml[..])?;
@@ -20,3 +21,57 @@ pub fn extract_published_at(item: &Item) -> Option<DateTime<Utc>> {
     }
     None
 }
+
+// Fallback for items without a feed date: article meta tags, then the first <time datetime>
+pub fn published_from_html(html: &str) -> Option<DateTime<Utc>> {
+    let doc = Html::parse_document(html);
+    let candidates = [
+        ("meta[property='article:published_time']", "content"),
+        ("meta[name='article:published_time']", "content"),
+        ("meta[itemprop=datePublished]", "content"),
+        ("meta[name=citation_publication_date]", "content"),
+        ("meta[name=citation_date]", "content"),
+        ("time[datetime]", "datetime"),
+    ];
+    for (sel, attr) in candidates {
+        let Ok(sel) = Selector::parse(sel) else { continue; };
+        for node in doc.select(&sel) {
+            if let Some(dt) = node.value().attr(attr).and_then(parse_loose_date) { return Some(dt); }
+        }
+    }
+    None
+}
+
+// RFC 3339, RFC 2822, or a bare date (YYYY-MM-DD / YYYY/MM/DD, taken as midnight UTC)
+fn parse_loose_date(s: &str) -> Option<DateTime<Utc>> {
+    let s = s.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) { return Some(dt.with_timezone(&Utc)); }
+    if let Ok(dt) = DateTime::parse_from_rfc2822(s) { return Some(dt.with_timezone(&Utc)); }
+    ["%Y-%m-%d", "%Y/%m/%d"].iter()
+        .find_map(|f| NaiveDate::parse_from_str(s, f).ok())
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|dt| dt.and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn meta_published_time_wins() {
+        let html = r#"<html><head>
+            <meta name="citation_date" content="2020/01/02">
+            <meta property="article:published_time" content="2024-05-06T07:08:09+02:00">
+        </head><body><time datetime="2019-01-01">x</time></body></html>"#;
+        assert_eq!(published_from_html(html).unwrap().to_rfc3339(), "2024-05-06T05:08:09+00:00");
+    }
+
+    #[test]
+    fn citation_and_time_fallbacks() {
+        let html = r#"<html><head><meta name="citation_date" content="2020/01/02"></head></html>"#;
+        assert_eq!(published_from_html(html).unwrap().to_rfc3339(), "2020-01-02T00:00:00+00:00");
+        let html = r#"<html><body><time>today</time><time datetime="2021-03-04">x</time></body></html>"#;
+        assert_eq!(published_from_html(html).unwrap().to_rfc3339(), "2021-03-04T00:00:00+00:00");
+        assert!(published_from_html("<p>no date</p>").is_none());
+    }
+}
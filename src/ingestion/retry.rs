@@ -0,0 +1,116 @@
+// ingest --retry-errors: re-run fetch/extract/write over existing status=error docs.
+
+use anyhow::Result;
+use sqlx::PgPool;
+use std::time::Duration;
+use url::Url;
+
+use crate::output::types::Outcome;
+use crate::telemetry::ctx::LogCtx;
+use crate::telemetry::ops::ingest::{Ingest, Phase as IngestPhase};
+use crate::util::{cancel, http};
+
+use super::error::TRANSIENT_TAGS;
+use super::types::{IngestApplied, IngestOutcome, IngestPlanned, RetryPlan, RetryResult, RetrySample};
+use super::{classify, db, extractor, fetch, fetch_or_reject, parse, robots, simhash, write, IngestParams};
+
+pub(super) async fn retry_errors(
+    pool: &PgPool,
+    log: &LogCtx<Ingest>,
+    args: &IngestParams,
+    only_lang: Option<&[&'static str]>,
+    max_bytes: usize,
+) -> Result<IngestOutcome> {
+    let kinds = if args.transient_only { Some(&TRANSIENT_TAGS[..]) } else { None };
+    let docs = {
+        let _s = log.span(&IngestPhase::SelectErrors).entered();
+        db::select_error_docs(pool, args.feed, args.feed_url.as_deref(), kinds, args.limit as i64).await?
+    };
+
+    if !args.apply {
+        let scope = if args.transient_only { "transient" } else { "all" };
+        log.info(format!("📝 Retry plan — error_docs={} kinds={} limit={}", docs.len(), scope, args.limit));
+        for d in docs.iter().take(args.plan_limit) {
+            log.info(format!("  doc_id={} error={} url={}", d.doc_id, d.error_msg.as_deref().unwrap_or(""), d.source_url));
+        }
+        if docs.len() > args.plan_limit { log.info(format!("  ... ({} more)", docs.len() - args.plan_limit)); }
+        log.info("   Use --apply to execute.");
+        let plan = RetryPlan {
+            error_docs: docs.len(),
+            transient_only: args.transient_only,
+            limit: args.limit,
+            sample_docs: docs.iter().take(args.plan_limit)
+                .map(|d| RetrySample { doc_id: d.doc_id, url: d.source_url.clone(), error_msg: d.error_msg.clone() })
+                .collect(),
+        };
+        return Ok(Outcome::Plan(IngestPlanned::Retry(plan)));
+    }
+
+    let mut res = RetryResult { selected: docs.len(), ..Default::default() };
+    if docs.is_empty() {
+        log.info("ℹ️  No error documents to retry");
+        return Ok(Outcome::Applied(IngestApplied::Retry(res)));
+    }
+
+    let client = http::client_builder()?.user_agent(concat!("ragfeed/", env!("CARGO_PKG_VERSION"))).build()?;
+    let mut robots = robots::Robots::new(Duration::from_millis(args.crawl_delay_ms));
+    let total = docs.len() as u64;
+
+    for (i, d) in docs.iter().enumerate() {
+        if cancel::is_cancelled() { res.cancelled = true; break; }
+        let link = d.source_url.as_str();
+        if let Ok(article_url) = Url::parse(link) {
+            if !robots.allowed(&client, &article_url).await {
+                res.skipped += 1;
+                log.info_kv("↩️ skip", [("reason", "robots-disallow".to_string()), ("url", link.to_string())]);
+                continue;
+            }
+            robots.wait_turn(&client, &article_url).await;
+        }
+
+        let article = { let _s = log.span_kv(&IngestPhase::FetchItem, [("url", link.to_string())]).entered(); fetch_or_reject(log, &client, link, max_bytes).await };
+        let published_at = match (&article, d.published_at) {
+            (fetch::Article::Html(html), None) if args.parse_published_from_content => parse::published_from_html(html),
+            _ => d.published_at,
+        };
+
+        let host = Url::parse(link).ok().and_then(|u| u.host_str().map(|s| s.to_string())).unwrap_or_default();
+        let (text, status, error_msg, detected) = {
+            let _s = log.span_kv(&IngestPhase::Extract, [("host", host.clone())]).entered();
+            let extracted = extractor::extract_article(&host, &article, args.extract_format);
+            classify(log, link, &article, extracted, only_lang)
+        };
+
+        let fingerprint = simhash::simhash(&text);
+        let mut status = status;
+        let mut canonical_doc_id: Option<i64> = None;
+        if let (Some(threshold), Some(fp), "ingest") = (args.dedup_threshold, fingerprint, status) {
+            canonical_doc_id = db::find_near_duplicate(pool, fp, link, threshold as i32).await?;
+            if canonical_doc_id.is_some() { status = "duplicate"; }
+        }
+        let raw_html: &[u8] = if canonical_doc_id.is_some() { &[] } else { article.raw() };
+
+        {
+            let _ws = log.span_kv(&IngestPhase::WriteDoc, [("mode", "retry".to_string())]).entered();
+            write::rewrite_document(pool, d.doc_id, published_at, &text, raw_html, status, error_msg.as_deref(), detected.as_ref().map(|l| l.code), fingerprint, canonical_doc_id).await?;
+        }
+        let kv = [("doc_id", d.doc_id.to_string()), ("url", link.to_string())];
+        match status {
+            "ingest" => { res.recovered += 1; log.info_kv("♻️ recovered", kv); }
+            "filtered" => res.filtered += 1,
+            "duplicate" => { res.duplicates += 1; log.info_kv("🪞 duplicate", kv); }
+            _ => {
+                res.still_failing += 1;
+                log.info_kv("❌ still failing", [kv[0].clone(), kv[1].clone(), ("error", error_msg.unwrap_or_default())]);
+            }
+        }
+        log.progress(i as u64 + 1, Some(total), "docs")?;
+    }
+
+    if res.cancelled { log.warn(format!("🛑 Cancelled after {} doc(s); rerun to continue", res.recovered + res.filtered + res.duplicates + res.still_failing + res.skipped)); }
+    log.info(format!(
+        "✅ Retried {} error doc(s) — recovered={} filtered={} duplicates={} still_failing={} skipped={}",
+        res.selected, res.recovered, res.filtered, res.duplicates, res.still_failing, res.skipped
+    ));
+    Ok(Outcome::Applied(IngestApplied::Retry(res)))
+}
@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use reqwest::Client;
+use url::Url;
+
+/// Product token matched against `User-agent:` lines (falls back to `*`).
+pub const AGENT: &str = "ragfeed";
+
+/// Allow/Disallow rules and Crawl-delay for one host, from the group that applies to `AGENT`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Rules {
+    allow: Vec<String>,
+    disallow: Vec<String>,
+    pub crawl_delay: Option<Duration>,
+}
+
+impl Rules {
+    // longest matching pattern wins; Allow wins ties
+    pub fn allows(&self, path: &str) -> bool {
+        let best = |pats: &[String]| pats.iter().filter(|p| matches(p, path)).map(|p| p.len()).max();
+        match (best(&self.allow), best(&self.disallow)) {
+            (_, None) => true,
+            (None, Some(_)) => false,
+            (Some(a), Some(d)) => a >= d,
+        }
+    }
+}
+
+pub fn parse(body: &str, agent: &str) -> Rules {
+    let agent = agent.to_ascii_lowercase();
+    // (agents, rules) groups; consecutive User-agent lines share one group
+    let mut groups: Vec<(Vec<String>, Rules)> = Vec::new();
+    let mut open_group = false;
+    for line in body.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let Some((key, value)) = line.split_once(':') else { continue; };
+        let (key, value) = (key.trim().to_ascii_lowercase(), value.trim());
+        match key.as_str() {
+            "user-agent" => {
+                if !open_group { groups.push((Vec::new(), Rules::default())); open_group = true; }
+                if let Some(g) = groups.last_mut() { g.0.push(value.to_ascii_lowercase()); }
+            }
+            "allow" | "disallow" | "crawl-delay" => {
+                open_group = false;
+                let Some((_, rules)) = groups.last_mut() else { continue; };
+                match key.as_str() {
+                    // empty Disallow means "allow everything"
+                    "allow" if !value.is_empty() => rules.allow.push(value.to_string()),
+                    "disallow" if !value.is_empty() => rules.disallow.push(value.to_string()),
+                    "crawl-delay" => rules.crawl_delay = value.parse::<f64>().ok().filter(|s| *s >= 0.0).map(Duration::from_secs_f64),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+    let pick = |pred: &dyn Fn(&str) -> bool| groups.iter().find(|(agents, _)| agents.iter().any(|a| pred(a))).map(|(_, r)| r.clone());
+    pick(&|a| a != "*" && agent.contains(a)).or_else(|| pick(&|a| a == "*")).unwrap_or_default()
+}
+
+// robots pattern match: prefix match, `*` wildcard, trailing `$` anchors the end
+fn matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') { Some(p) => (p, true), None => (pattern, false) };
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    if !path.starts_with(first) { return false; }
+    let mut pos = first.len();
+    let rest: Vec<&str> = parts.collect();
+    for (i, part) in rest.iter().enumerate() {
+        // the last piece of an anchored pattern must sit at the very end
+        if anchored && i == rest.len() - 1 {
+            return path.len() >= pos + part.len() && path.ends_with(part);
+        }
+        match path[pos..].find(part) {
+            Some(at) => pos += at + part.len(),
+            None => return false,
+        }
+    }
+    !anchored || pos == path.len()
+}
+
+/// Per-host robots.txt cache plus the time of the last request, for politeness delays.
+pub struct Robots {
+    rules: HashMap<String, Rules>,
+    last_hit: HashMap<String, Instant>,
+    min_delay: Duration,
+}
+
+impl Robots {
+    pub fn new(min_delay: Duration) -> Self {
+        Self { rules: HashMap::new(), last_hit: HashMap::new(), min_delay }
+    }
+
+    // unreachable or missing robots.txt allows everything
+    async fn rules_for(&mut self, client: &Client, url: &Url) -> &Rules {
+        let key = origin(url);
+        if !self.rules.contains_key(&key) {
+            let body = match client.get(format!("{}/robots.txt", key)).send().await {
+                Ok(resp) if resp.status().is_success() => resp.text().await.unwrap_or_default(),
+                _ => String::new(),
+            };
+            self.rules.insert(key.clone(), parse(&body, AGENT));
+        }
+        &self.rules[&key]
+    }
+
+    pub async fn allowed(&mut self, client: &Client, url: &Url) -> bool {
+        let path = match url.query() { Some(q) => format!("{}?{}", url.path(), q), None => url.path().to_string() };
+        self.rules_for(client, url).await.allows(&path)
+    }
+
+    /// Sleep until the host's delay (max of --crawl-delay-ms and Crawl-delay) has passed since the last request.
+    pub async fn wait_turn(&mut self, client: &Client, url: &Url) {
+        let key = origin(url);
+        let robots_delay = self.rules_for(client, url).await.crawl_delay.unwrap_or_default();
+        let delay = robots_delay.max(self.min_delay);
+        if let Some(last) = self.last_hit.get(&key) {
+            let elapsed = last.elapsed();
+            if elapsed < delay { tokio::time::sleep(delay - elapsed).await; }
+        }
+        self.last_hit.insert(key, Instant::now());
+    }
+}
+
+fn origin(url: &Url) -> String {
+    url.origin().ascii_serialization()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROBOTS: &str = "\
+User-agent: *
+Disallow: /private/
+Allow: /private/public-note
+Crawl-delay: 2
+
+User-agent: ragfeed
+User-agent: other
+Disallow: /drafts
+Disallow: /*.pdf$
+";
+
+    #[test]
+    fn picks_specific_group_over_wildcard() {
+        let r = parse(ROBOTS, AGENT);
+        assert!(!r.allows("/drafts/x"));
+        assert!(!r.allows("/files/a.pdf"));
+        assert!(r.allows("/files/a.pdf?x=1"));
+        assert!(r.allows("/private/x"));
+        assert_eq!(r.crawl_delay, None);
+    }
+
+    #[test]
+    fn wildcard_group_longest_match_and_delay() {
+        let r = parse(ROBOTS, "someone-else");
+        assert!(!r.allows("/private/x"));
+        assert!(r.allows("/private/public-note"));
+        assert!(r.allows("/blog"));
+        assert_eq!(r.crawl_delay, Some(Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn empty_or_missing_allows_all() {
+        assert!(parse("", AGENT).allows("/anything"));
+        assert!(parse("User-agent: *\nDisallow:\n", AGENT).allows("/anything"));
+    }
+}
@@ -0,0 +1,58 @@
+// 64-bit SimHash over word 3-shingles of text_clean, used for near-duplicate detection.
+// FNV-1a keeps the fingerprint stable across builds (it is persisted in rag.document.simhash).
+
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+const SHINGLE: usize = 3;
+
+fn fnv1a(words: &[&str]) -> u64 {
+    let mut h = FNV_OFFSET;
+    for (i, w) in words.iter().enumerate() {
+        if i > 0 { h ^= b' ' as u64; h = h.wrapping_mul(FNV_PRIME); }
+        for b in w.bytes() { h ^= b as u64; h = h.wrapping_mul(FNV_PRIME); }
+    }
+    h
+}
+
+/// Fingerprint stored as BIGINT; `None` for text without any words.
+pub fn simhash(text: &str) -> Option<i64> {
+    let lowered = text.to_lowercase();
+    let words: Vec<&str> = lowered
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+    if words.is_empty() { return None; }
+
+    let mut acc = [0i32; 64];
+    let width = SHINGLE.min(words.len());
+    for shingle in words.windows(width) {
+        let h = fnv1a(shingle);
+        for (bit, slot) in acc.iter_mut().enumerate() {
+            if h >> bit & 1 == 1 { *slot += 1 } else { *slot -= 1 }
+        }
+    }
+
+    let mut out = 0u64;
+    for (bit, slot) in acc.iter().enumerate() {
+        if *slot > 0 { out |= 1 << bit; }
+    }
+    Some(out as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn distance(a: i64, b: i64) -> u32 { (a ^ b).count_ones() }
+
+    #[test]
+    fn near_duplicates_are_close_and_unrelated_text_is_far() {
+        let base = "The quick brown fox jumps over the lazy dog while the farmer sleeps in the barn near the river bank";
+        let tweak = "The quick brown fox jumps over the lazy dog while the farmer sleeps in the barn near the river bend";
+        let other = "Transformers use attention to weigh tokens; retrieval augments prompts with relevant passages from an index";
+        let (a, b, c) = (simhash(base).unwrap(), simhash(tweak).unwrap(), simhash(other).unwrap());
+        assert!(distance(a, b) < distance(a, c));
+        assert_eq!(simhash(base), simhash(&base.to_uppercase()));
+        assert!(simhash("  ... ").is_none());
+    }
+}
@@ -1,19 +1,57 @@
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::Serialize;
 
 // Plan envelope types
-#[derive(Serialize)]
-pub struct FeedSample { pub feed_id: i32, pub url: String, pub name: Option<String> }
+#[derive(Serialize, JsonSchema)]
+pub struct FeedSample { pub feed_id: i32, pub url: String, pub name: Option<String>, pub last_item_at: Option<DateTime<Utc>> }
 
-#[derive(Serialize)]
-pub struct IngestPlan { pub feeds: usize, pub mode: String, pub limit: usize, pub sample_feeds: Vec<FeedSample> }
+#[derive(Serialize, JsonSchema)]
+pub struct IngestPlan {
+    pub feeds: usize,
+    pub mode: String,
+    pub walk: String,
+    pub limit: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub only_lang: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dedup_threshold: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url_filter: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title_filter: Option<String>,
+    pub extract_format: super::extractor::ExtractFormat,
+    pub sample_feeds: Vec<FeedSample>,
+}
 
 // Apply/result envelope types
-#[derive(Serialize)]
-pub struct FeedSummary { pub feed_id: i32, pub inserted: usize, pub updated: usize, pub skipped: usize, pub errors: usize }
+#[derive(Serialize, JsonSchema)]
+pub struct FeedSummary { pub feed_id: i32, pub inserted: usize, pub updated: usize, pub skipped: usize, pub duplicates: usize, pub errors: usize }
+
+#[derive(Serialize, JsonSchema)]
+pub struct IngestTotals { pub inserted: usize, pub updated: usize, pub skipped: usize, pub duplicates: usize, pub errors: usize }
+
+#[derive(Serialize, JsonSchema)]
+pub struct IngestApply { pub totals: IngestTotals, pub per_feed: Vec<FeedSummary>, pub cancelled: bool }
+
+
+// --retry-errors envelopes
+#[derive(Serialize, JsonSchema)]
+pub struct RetrySample { pub doc_id: i64, pub url: String, pub error_msg: Option<String> }
+
+#[derive(Serialize, JsonSchema)]
+pub struct RetryPlan { pub error_docs: usize, pub transient_only: bool, pub limit: usize, pub sample_docs: Vec<RetrySample> }
+
+#[derive(Serialize, Default, JsonSchema)]
+pub struct RetryResult { pub selected: usize, pub recovered: usize, pub filtered: usize, pub duplicates: usize, pub still_failing: usize, pub skipped: usize, pub cancelled: bool }
 
+// what ingest hands back: the feed walk or the --retry-errors pass (either shape is emitted as-is)
 #[derive(Serialize)]
-pub struct IngestTotals { pub inserted: usize, pub updated: usize, pub skipped: usize, pub errors: usize }
+#[serde(untagged)]
+pub enum IngestPlanned { Feeds(IngestPlan), Retry(RetryPlan) }
 
 #[derive(Serialize)]
-pub struct IngestApply { pub totals: IngestTotals, pub per_feed: Vec<FeedSummary> }
+#[serde(untagged)]
+pub enum IngestApplied { Feeds(IngestApply), Retry(RetryResult) }
 
+pub type IngestOutcome = crate::output::types::Outcome<IngestPlanned, IngestApplied>;
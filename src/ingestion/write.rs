@@ -12,12 +12,15 @@ pub async fn upsert_document(
     raw_html: &[u8],
     status: &str,
     error_msg: Option<&str>,
+    lang: Option<&str>,
+    simhash: Option<i64>,
+    canonical_doc_id: Option<i64>,
 ) -> Result<bool> {
     let res = sqlx::query!(
         r#"
         INSERT INTO rag.document (feed_id, source_url, source_title,
-            published_at, fetched_at, content_hash, raw_html, text_clean, status, error_msg)
-        VALUES ($1, $2, $3, $4, now(), md5($5), $6, $7, $8, $9)
+            published_at, fetched_at, content_hash, raw_html, text_clean, status, error_msg, lang, simhash, canonical_doc_id)
+        VALUES ($1, $2, $3, $4, now(), md5($5), $6, $7, $8, $9, $10, $11, $12)
         ON CONFLICT (source_url) DO UPDATE
           SET source_title = EXCLUDED.source_title,
               published_at = COALESCE(EXCLUDED.published_at, rag.document.published_at),
@@ -26,7 +29,10 @@ pub async fn upsert_document(
               raw_html     = EXCLUDED.raw_html,
               text_clean   = EXCLUDED.text_clean,
               status       = EXCLUDED.status,
-              error_msg    = EXCLUDED.error_msg
+              error_msg    = EXCLUDED.error_msg,
+              lang         = EXCLUDED.lang,
+              simhash      = EXCLUDED.simhash,
+              canonical_doc_id = EXCLUDED.canonical_doc_id
         RETURNING (xmax = 0) AS inserted
         "#,
         feed_id,
@@ -37,13 +43,22 @@ pub async fn upsert_document(
         raw_html,
         text,
         status,
-        error_msg
+        error_msg,
+        lang,
+        simhash,
+        canonical_doc_id
     )
     .fetch_one(pool)
     .await?;
     Ok(res.inserted.unwrap_or(false))
 }
 
+/// What `insert_document` did with the row.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Written { Inserted, Updated, Skipped }
+
+/// Inserts a new doc; an existing one is left alone unless it is a transient error or a
+/// metadata-only entry, which is overwritten.
 pub async fn insert_document(
     pool: &PgPool,
     feed_id: i32,
@@ -54,13 +69,34 @@ pub async fn insert_document(
     raw_html: &[u8],
     status: &str,
     error_msg: Option<&str>,
-) -> Result<bool> {
-    let exec = sqlx::query!(
+    lang: Option<&str>,
+    simhash: Option<i64>,
+    canonical_doc_id: Option<i64>,
+) -> Result<Written> {
+    let row = sqlx::query!(
         r#"
         INSERT INTO rag.document (feed_id, source_url, source_title,
-            published_at, fetched_at, content_hash, raw_html, text_clean, status, error_msg)
-        VALUES ($1, $2, $3, $4, now(), md5($5), $6, $7, $8, $9)
-        ON CONFLICT (source_url) DO NOTHING
+            published_at, fetched_at, content_hash, raw_html, text_clean, status, error_msg, lang, simhash, canonical_doc_id)
+        VALUES ($1, $2, $3, $4, now(), md5($5), $6, $7, $8, $9, $10, $11, $12)
+        ON CONFLICT (source_url) DO UPDATE
+          SET source_title = EXCLUDED.source_title,
+              published_at = COALESCE(EXCLUDED.published_at, rag.document.published_at),
+              fetched_at   = now(),
+              content_hash = EXCLUDED.content_hash,
+              raw_html     = EXCLUDED.raw_html,
+              text_clean   = EXCLUDED.text_clean,
+              status       = EXCLUDED.status,
+              error_msg    = EXCLUDED.error_msg,
+              lang         = EXCLUDED.lang,
+              simhash      = EXCLUDED.simhash,
+              canonical_doc_id = EXCLUDED.canonical_doc_id
+          -- only docs that failed transiently (see ingestion::error::TRANSIENT_TAGS) are retried,
+          -- and metadata-only entries are filled in once a full ingest reaches them; a
+          -- --metadata-only pass (no download) overwrites neither
+          WHERE (rag.document.status = 'error' AND rag.document.error_msg IN ('fetch-failed', 'timeout', 'http-unavailable')
+                 AND EXCLUDED.status <> 'metadata')
+             OR (rag.document.status = 'metadata' AND EXCLUDED.status <> 'metadata')
+        RETURNING (xmax = 0) AS inserted
         "#,
         feed_id,
         link,
@@ -70,10 +106,61 @@ pub async fn insert_document(
         raw_html,
         text,
         status,
-        error_msg
+        error_msg,
+        lang,
+        simhash,
+        canonical_doc_id
     )
-    .execute(pool)
+    .fetch_optional(pool)
     .await?;
-    Ok(exec.rows_affected() == 1)
+    // no row back: the conflict's WHERE kept the stored doc
+    Ok(match row {
+        None => Written::Skipped,
+        Some(r) if r.inserted.unwrap_or(false) => Written::Inserted,
+        Some(_) => Written::Updated,
+    })
 }
 
+
+/// Rewrites an existing doc in place after a re-fetch (`ingest --retry-errors`); title and feed stay as stored.
+pub async fn rewrite_document(
+    pool: &PgPool,
+    doc_id: i64,
+    published_at: Option<DateTime<Utc>>,
+    text: &str,
+    raw_html: &[u8],
+    status: &str,
+    error_msg: Option<&str>,
+    lang: Option<&str>,
+    simhash: Option<i64>,
+    canonical_doc_id: Option<i64>,
+) -> Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE rag.document
+        SET published_at = COALESCE($2, published_at),
+            fetched_at   = now(),
+            content_hash = md5($3),
+            raw_html     = $4,
+            text_clean   = $3,
+            status       = $5,
+            error_msg    = $6,
+            lang         = $7,
+            simhash      = $8,
+            canonical_doc_id = $9
+        WHERE doc_id = $1
+        "#,
+        doc_id,
+        published_at,
+        text,
+        raw_html,
+        status,
+        error_msg,
+        lang,
+        simhash,
+        canonical_doc_id
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,135 @@
+//! Library surface of the RAG pipeline: the same operations the `rag` CLI runs,
+//! callable with plain parameter structs instead of clap commands.
+//! Each call returns its plan or result; emitting envelopes is left to the CLI.
+
+pub mod feed;
+pub mod ingestion;
+pub mod tokenizer;
+pub mod encoder;
+pub mod stats;
+pub mod query;
+pub mod util;
+pub mod maintenance;
+pub mod telemetry;
+pub mod pipeline;
+pub mod output;
+pub mod llm;
+pub mod compose;
+
+use anyhow::Result;
+use sqlx::PgPool;
+
+pub use ingestion::IngestParams;
+pub use ingestion::types::IngestOutcome;
+pub use maintenance::gc::{GcOutcome, GcParams};
+pub use maintenance::reindex::{ReindexOutcome, ReindexParams};
+pub use output::types::Outcome;
+pub use pipeline::chunk::{ChunkOutcome, ChunkParams};
+pub use pipeline::embed::{EmbedOutcome, EmbedParams};
+pub use pipeline::reembed::{ReembedOutcome, ReembedParams};
+pub use query::service::{QueryOutcome, QueryRequest};
+
+/// ANN query over stored embeddings; returns rows instead of printing them.
+pub async fn query(pool: &PgPool, req: QueryRequest<'_>) -> Result<QueryOutcome> {
+    query::service::execute(pool, req, None).await
+}
+
+/// Several queries with the same settings, embedded in one batch; outcomes in input order.
+pub async fn query_many(pool: &PgPool, req: QueryRequest<'_>, queries: &[String]) -> Result<Vec<QueryOutcome>> {
+    query::service::execute_many(pool, req, queries, None).await
+}
+
+/// Walk feeds (or retry error docs); `Outcome::Plan` unless `params.apply`.
+pub async fn ingest(pool: &PgPool, params: IngestParams) -> Result<IngestOutcome> {
+    ingestion::execute(pool, params).await
+}
+
+/// `Outcome::Nothing` when no document needs chunking.
+pub async fn chunk(pool: &PgPool, params: ChunkParams) -> Result<ChunkOutcome> {
+    pipeline::chunk::execute(pool, params).await
+}
+
+pub async fn embed(pool: &PgPool, params: EmbedParams) -> Result<EmbedOutcome> {
+    pipeline::embed::execute(pool, params).await
+}
+
+/// Re-chunk changed docs and embed just the chunks that changed.
+pub async fn reembed_changed(pool: &PgPool, params: ReembedParams) -> Result<ReembedOutcome> {
+    pipeline::reembed::execute(pool, params).await
+}
+
+pub async fn gc(pool: &PgPool, params: GcParams) -> Result<GcOutcome> {
+    maintenance::gc::execute(pool, params).await
+}
+
+/// `Outcome::Nothing` when cancelled before the index build started.
+pub async fn reindex(pool: &PgPool, params: ReindexParams) -> Result<ReindexOutcome> {
+    maintenance::reindex::execute(pool, params).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    // wraps a subcommand's flags so they can be parsed on their own
+    #[derive(Parser)]
+    struct Cli<T: clap::Args> {
+        #[command(flatten)]
+        args: T,
+    }
+
+    fn parse<T: clap::Args>(argv: &[&str]) -> T {
+        Cli::<T>::try_parse_from(std::iter::once("rag").chain(argv.iter().copied())).unwrap().args
+    }
+
+    #[test]
+    fn cli_defaults_match_library_defaults() {
+        assert_eq!(IngestParams::from(parse::<ingestion::IngestCmd>(&[])), IngestParams::default());
+        assert_eq!(ChunkParams::from(parse::<pipeline::chunk::ChunkCmd>(&[])), ChunkParams::default());
+        assert_eq!(EmbedParams::from(parse::<pipeline::embed::EmbedCmd>(&[])), EmbedParams::default());
+        assert_eq!(ReembedParams::from(parse::<pipeline::reembed::ReembedChangedCmd>(&[])), ReembedParams::default());
+        assert_eq!(GcParams::from(parse::<maintenance::gc::GcCmd>(&[])), GcParams::default());
+        assert_eq!(ReindexParams::from(parse::<maintenance::reindex::ReindexCmd>(&[])), ReindexParams::default());
+        let q = parse::<query::QueryCmd>(&["hello"]);
+        assert_eq!(q.to_request().unwrap(), QueryRequest::new("hello"));
+    }
+
+    #[test]
+    fn cli_flags_map_onto_params() {
+        let p = ChunkParams::from(parse::<pipeline::chunk::ChunkCmd>(&["--tokens-target", "200", "--overlap", "20", "--apply"]));
+        assert_eq!(p, ChunkParams { tokens_target: 200, overlap: 20, apply: true, ..ChunkParams::default() });
+        let p = EmbedParams::from(parse::<pipeline::embed::EmbedCmd>(&["--vector-type", "half"]));
+        assert_eq!(p, EmbedParams { vector_type: util::index::VectorType::Half, ..EmbedParams::default() });
+        let p = GcParams::from(parse::<maintenance::gc::GcCmd>(&["--vacuum", "off", "--feed", "3"]));
+        assert_eq!(p, GcParams { vacuum: maintenance::gc::VacuumMode::Off, feed: Some(3), ..GcParams::default() });
+        let p = GcParams::from(parse::<maintenance::gc::GcCmd>(&["--no-delete", "--apply"]));
+        assert_eq!(p, GcParams { no_delete: true, apply: true, ..GcParams::default() });
+        let p = GcParams::from(parse::<maintenance::gc::GcCmd>(&["--skip", "error-docs", "--skip", "bad-chunks"]));
+        assert_eq!(p.skip, vec![maintenance::gc::GcCategory::ErrorDocs, maintenance::gc::GcCategory::BadChunks]);
+        let q = parse::<query::QueryCmd>(&["hi", "--highlight", "--topk", "3"]);
+        let req = q.to_request().unwrap();
+        assert_eq!(req, QueryRequest { topk: 3, highlight: true, include_preview: true, ..QueryRequest::new("hi") });
+        let q = parse::<query::QueryCmd>(&["hi", "--dedup-results", "--dedup-bits", "5"]);
+        let req = q.to_request().unwrap();
+        assert_eq!(req, QueryRequest { dedup_results: true, dedup_bits: 5, ..QueryRequest::new("hi") });
+        let q = parse::<query::QueryCmd>(&["hi", "--quantized", "--quantized-pool", "400"]);
+        assert_eq!(q.to_request().unwrap(), QueryRequest { quantized: true, quantized_pool: 400, ..QueryRequest::new("hi") });
+    }
+
+    #[test]
+    fn invalid_since_is_rejected_not_ignored() {
+        let q = parse::<query::QueryCmd>(&["hi", "--since", "garbag"]);
+        assert!(q.to_request().is_err());
+    }
+
+    #[test]
+    fn queries_file_replaces_positional_query() {
+        let q = parse::<query::QueryCmd>(&["--queries-file", "qs.txt"]);
+        assert_eq!(q.to_request().unwrap().query, "");
+        let both = Cli::<query::QueryCmd>::try_parse_from(["rag", "hi", "--queries-file", "qs.txt"]);
+        assert!(both.is_err());
+        assert!(Cli::<query::QueryCmd>::try_parse_from(["rag"]).is_err());
+        assert_eq!(query::parse_queries("a\n\n  b \n# skip\n"), vec!["a".to_string(), "b".to_string()]);
+    }
+}
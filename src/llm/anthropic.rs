@@ -0,0 +1,308 @@
+//! Anthropic Messages API behind the same `LlmClient` seam as the OpenAI client.
+//! System messages become the top-level `system` field; `response_format` has no
+//! equivalent there and is not sent (callers still validate the reply themselves).
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::Client as HttpClient;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::openai::{
+    ApiErrorBody, ChatCompletionRequest, ChatCompletionResponse, ChatRole, LlmClient, OpenAiError,
+    UsageMetrics,
+};
+
+const DEFAULT_BASE_URL: &str = "https://api.anthropic.com/v1";
+const DEFAULT_MODEL: &str = "claude-3-5-haiku-latest";
+const DEFAULT_VERSION: &str = "2023-06-01";
+// the Messages API requires max_tokens
+const DEFAULT_MAX_TOKENS: u32 = 1024;
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
+#[derive(Clone, Debug)]
+pub struct AnthropicClientConfig {
+    pub api_key: Option<String>,
+    pub base_url: String,
+    pub default_model: String,
+    pub version: String,
+    pub default_max_tokens: u32,
+    pub timeout: Duration,
+}
+
+impl Default for AnthropicClientConfig {
+    fn default() -> Self {
+        Self {
+            api_key: std::env::var("ANTHROPIC_API_KEY").ok(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            default_model: std::env::var("ANTHROPIC_MODEL")
+                .unwrap_or_else(|_| DEFAULT_MODEL.to_string()),
+            version: DEFAULT_VERSION.to_string(),
+            default_max_tokens: DEFAULT_MAX_TOKENS,
+            timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+        }
+    }
+}
+
+impl AnthropicClientConfig {
+    pub fn from_env() -> Self {
+        let mut cfg = Self::default();
+        if let Ok(base) = std::env::var("ANTHROPIC_BASE_URL") {
+            cfg.base_url = base;
+        }
+        if let Ok(version) = std::env::var("ANTHROPIC_VERSION") {
+            cfg.version = version;
+        }
+        if let Ok(timeout) = std::env::var("ANTHROPIC_TIMEOUT_SECS") {
+            if let Ok(parsed) = timeout.parse::<u64>() {
+                cfg.timeout = Duration::from_secs(parsed);
+            }
+        }
+        cfg
+    }
+}
+
+#[derive(Clone)]
+pub struct AnthropicClient {
+    http: HttpClient,
+    cfg: AnthropicClientConfig,
+}
+
+impl AnthropicClient {
+    pub fn new(cfg: AnthropicClientConfig) -> Result<Self, OpenAiError> {
+        let http = crate::util::http::client_builder()
+            .map_err(OpenAiError::from_reqwest)?
+            .timeout(cfg.timeout)
+            .build()
+            .map_err(OpenAiError::from_reqwest)?;
+        Ok(Self { http, cfg })
+    }
+
+    fn resolve_api_key(&self) -> Result<String, OpenAiError> {
+        if let Some(key) = &self.cfg.api_key {
+            return Ok(key.clone());
+        }
+        std::env::var("ANTHROPIC_API_KEY").map_err(|_| OpenAiError::MissingEnv("ANTHROPIC_API_KEY"))
+    }
+
+    fn endpoint(&self) -> String {
+        format!("{}/messages", self.cfg.base_url.trim_end_matches('/'))
+    }
+
+    fn build_api_request(&self, req: &ChatCompletionRequest) -> ApiMessagesRequest {
+        let system: Vec<&str> = req
+            .messages
+            .iter()
+            .filter(|m| m.role == ChatRole::System)
+            .map(|m| m.content.as_str())
+            .collect();
+        ApiMessagesRequest {
+            model: req
+                .model
+                .clone()
+                .unwrap_or_else(|| self.cfg.default_model.clone()),
+            max_tokens: req.max_tokens.unwrap_or(self.cfg.default_max_tokens),
+            system: if system.is_empty() { None } else { Some(system.join("\n\n")) },
+            messages: req
+                .messages
+                .iter()
+                .filter(|m| m.role != ChatRole::System)
+                .map(|m| ApiMessage {
+                    role: if m.role == ChatRole::Assistant { "assistant" } else { "user" },
+                    content: m.content.clone(),
+                })
+                .collect(),
+            temperature: req.temperature,
+            top_p: req.top_p,
+        }
+    }
+}
+
+#[async_trait]
+impl LlmClient for AnthropicClient {
+    fn request_body(&self, request: &ChatCompletionRequest) -> Value {
+        serde_json::to_value(self.build_api_request(request)).unwrap_or_default()
+    }
+
+    async fn chat_completion(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, OpenAiError> {
+        if request.messages.iter().all(|m| m.role == ChatRole::System) {
+            return Err(OpenAiError::EmptyMessages);
+        }
+
+        let api_key = self.resolve_api_key()?;
+        let api_request = self.build_api_request(&request);
+
+        let response = self
+            .http
+            .post(self.endpoint())
+            .header("x-api-key", api_key)
+            .header("anthropic-version", &self.cfg.version)
+            .json(&api_request)
+            .send()
+            .await
+            .map_err(OpenAiError::from_reqwest)?;
+
+        let status = response.status();
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(OpenAiError::from_reqwest)?;
+
+        if !status.is_success() {
+            let api_err = serde_json::from_slice::<ApiErrorEnvelope>(&bytes)
+                .ok()
+                .map(|env| ApiErrorBody {
+                    message: env.error.message,
+                    r#type: env.error.r#type,
+                    param: None,
+                    code: None,
+                });
+            return Err(OpenAiError::Api {
+                status,
+                error: api_err.unwrap_or_default(),
+            });
+        }
+
+        let raw: Value = serde_json::from_slice(&bytes).map_err(OpenAiError::Decode)?;
+        let parsed: ApiMessagesResponse =
+            serde_json::from_value(raw.clone()).map_err(OpenAiError::Decode)?;
+        Ok(to_response(parsed, raw))
+    }
+}
+
+fn to_response(parsed: ApiMessagesResponse, raw: Value) -> ChatCompletionResponse {
+    let content = parsed
+        .content
+        .iter()
+        .filter(|block| block.r#type == "text")
+        .filter_map(|block| block.text.as_deref())
+        .collect::<Vec<_>>()
+        .join("");
+    ChatCompletionResponse {
+        content,
+        raw,
+        usage: parsed.usage.map(|u| UsageMetrics {
+            prompt_tokens: u.input_tokens,
+            completion_tokens: u.output_tokens,
+            total_tokens: match (u.input_tokens, u.output_tokens) {
+                (Some(i), Some(o)) => Some(i + o),
+                _ => None,
+            },
+        }),
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ApiMessagesRequest {
+    model: String,
+    max_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<String>,
+    messages: Vec<ApiMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ApiMessage {
+    role: &'static str,
+    content: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ApiMessagesResponse {
+    content: Vec<ApiContentBlock>,
+    usage: Option<ApiUsage>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ApiContentBlock {
+    r#type: String,
+    #[serde(default)]
+    text: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ApiUsage {
+    input_tokens: Option<u32>,
+    output_tokens: Option<u32>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ApiErrorEnvelope {
+    error: ApiErrorDetail,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ApiErrorDetail {
+    #[serde(default)]
+    r#type: Option<String>,
+    message: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::openai::ChatMessage;
+
+    fn client() -> AnthropicClient {
+        AnthropicClient::new(AnthropicClientConfig {
+            api_key: Some("test".into()),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            default_model: "claude-test".into(),
+            version: DEFAULT_VERSION.to_string(),
+            default_max_tokens: 512,
+            timeout: Duration::from_secs(30),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn system_messages_move_to_top_level_field() {
+        let request = ChatCompletionRequest {
+            model: None,
+            messages: vec![
+                ChatMessage::new(ChatRole::System, "You are helpful."),
+                ChatMessage::new(ChatRole::User, "Hello"),
+                ChatMessage::new(ChatRole::Assistant, "Hi"),
+                ChatMessage::new(ChatRole::User, "Again"),
+            ],
+            max_tokens: None,
+            temperature: Some(0.3),
+            top_p: None,
+            response_format: None,
+            seed: None,
+        };
+        let value = serde_json::to_value(client().build_api_request(&request)).unwrap();
+
+        assert_eq!(value["model"], "claude-test");
+        assert_eq!(value["max_tokens"], 512);
+        assert_eq!(value["system"], "You are helpful.");
+        assert_eq!(value["messages"].as_array().unwrap().len(), 3);
+        assert_eq!(value["messages"][0]["role"], "user");
+        assert_eq!(value["messages"][1]["role"], "assistant");
+        assert!(value.get("top_p").is_none());
+    }
+
+    #[test]
+    fn response_maps_text_blocks_and_usage() {
+        let raw = serde_json::json!({
+            "content": [{ "type": "text", "text": "Hello " }, { "type": "text", "text": "there" }],
+            "usage": { "input_tokens": 10, "output_tokens": 4 }
+        });
+        let parsed: ApiMessagesResponse = serde_json::from_value(raw.clone()).unwrap();
+        let out = to_response(parsed, raw);
+
+        assert_eq!(out.content, "Hello there");
+        let usage = out.usage.unwrap();
+        assert_eq!(usage.prompt_tokens, Some(10));
+        assert_eq!(usage.completion_tokens, Some(4));
+        assert_eq!(usage.total_tokens, Some(14));
+    }
+}
@@ -1 +1,39 @@
+pub mod anthropic;
 pub mod openai;
+pub mod pricing;
+pub mod schema;
+
+use clap::ValueEnum;
+
+use self::anthropic::{AnthropicClient, AnthropicClientConfig};
+use self::openai::{LlmClient, OpenAiClient, OpenAiClientConfig, OpenAiError};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum LlmProvider { Openai, Anthropic }
+
+impl LlmProvider {
+    pub fn as_str(&self) -> &'static str {
+        match self { LlmProvider::Openai => "openai", LlmProvider::Anthropic => "anthropic" }
+    }
+
+    /// `--llm-provider` flag, else `RAG_LLM_PROVIDER`, else OpenAI.
+    pub fn resolve(flag: Option<LlmProvider>) -> LlmProvider {
+        flag.or_else(|| std::env::var("RAG_LLM_PROVIDER").ok().and_then(|v| LlmProvider::from_str(&v, true).ok()))
+            .unwrap_or(LlmProvider::Openai)
+    }
+
+    /// Model used when none is given (OPENAI_MODEL / ANTHROPIC_MODEL or the built-in default).
+    pub fn default_model(&self) -> String {
+        match self {
+            LlmProvider::Openai => OpenAiClientConfig::from_env().default_model,
+            LlmProvider::Anthropic => AnthropicClientConfig::from_env().default_model,
+        }
+    }
+
+    pub fn client(&self) -> Result<Box<dyn LlmClient>, OpenAiError> {
+        Ok(match self {
+            LlmProvider::Openai => Box::new(OpenAiClient::new(OpenAiClientConfig::from_env())?),
+            LlmProvider::Anthropic => Box::new(AnthropicClient::new(AnthropicClientConfig::from_env())?),
+        })
+    }
+}
@@ -70,7 +70,8 @@ pub struct OpenAiClient {
 
 impl OpenAiClient {
     pub fn new(cfg: OpenAiClientConfig) -> Result<Self, OpenAiError> {
-        let http = HttpClient::builder()
+        let http = crate::util::http::client_builder()
+            .map_err(OpenAiError::http)?
             .timeout(cfg.timeout)
             .build()
             .map_err(OpenAiError::http)?;
@@ -102,6 +103,13 @@ impl OpenAiClient {
                 .unwrap_or(self.cfg.default_temperature),
             top_p: req.top_p.unwrap_or(self.cfg.default_top_p),
             max_tokens: req.max_tokens,
+            response_format: req.response_format.as_ref().map(|f| match f {
+                ResponseFormat::JsonSchema { name, schema } => ApiResponseFormat {
+                    r#type: "json_schema",
+                    json_schema: ApiJsonSchema { name: name.clone(), schema: schema.clone() },
+                },
+            }),
+            seed: req.seed,
             messages: req
                 .messages
                 .iter()
@@ -120,10 +128,17 @@ pub trait LlmClient: Send + Sync {
         &self,
         request: ChatCompletionRequest,
     ) -> Result<ChatCompletionResponse, OpenAiError>;
+
+    /// The JSON body `chat_completion` would POST for this request (defaults filled in).
+    fn request_body(&self, request: &ChatCompletionRequest) -> Value;
 }
 
 #[async_trait]
 impl LlmClient for OpenAiClient {
+    fn request_body(&self, request: &ChatCompletionRequest) -> Value {
+        serde_json::to_value(self.build_api_request(request)).unwrap_or_default()
+    }
+
     async fn chat_completion(
         &self,
         request: ChatCompletionRequest,
@@ -191,6 +206,15 @@ pub struct ChatCompletionRequest {
     pub max_tokens: Option<u32>,
     pub temperature: Option<f32>,
     pub top_p: Option<f32>,
+    pub response_format: Option<ResponseFormat>,
+    /// Best-effort reproducible sampling (OpenAI `seed`); ignored by providers without it
+    pub seed: Option<u64>,
+}
+
+/// Constrain the reply format (OpenAI `response_format`).
+#[derive(Clone, Debug, PartialEq)]
+pub enum ResponseFormat {
+    JsonSchema { name: String, schema: Value },
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -242,6 +266,8 @@ pub struct UsageMetrics {
 #[derive(Debug)]
 pub enum OpenAiError {
     MissingApiKey,
+    // another provider's key variable (e.g. ANTHROPIC_API_KEY)
+    MissingEnv(&'static str),
     EmptyMessages,
     Http(reqwest::Error),
     Timeout,
@@ -262,7 +288,7 @@ impl OpenAiError {
         }
     }
 
-    fn from_reqwest(err: reqwest::Error) -> Self {
+    pub(crate) fn from_reqwest(err: reqwest::Error) -> Self {
         Self::http(err)
     }
 
@@ -272,6 +298,7 @@ impl OpenAiError {
             OpenAiError::Http(_) => true,
             OpenAiError::Api { status, .. } => status.is_server_error(),
             OpenAiError::MissingApiKey
+            | OpenAiError::MissingEnv(_)
             | OpenAiError::EmptyMessages
             | OpenAiError::MockQueueEmpty
             | OpenAiError::Decode(_) => false,
@@ -283,6 +310,7 @@ impl std::fmt::Display for OpenAiError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             OpenAiError::MissingApiKey => write!(f, "OPENAI_API_KEY is not set"),
+            OpenAiError::MissingEnv(var) => write!(f, "{var} is not set"),
             OpenAiError::EmptyMessages => {
                 write!(f, "chat completion requires at least one message")
             }
@@ -361,6 +389,15 @@ impl MockClient {
 
 #[async_trait]
 impl LlmClient for MockClient {
+    fn request_body(&self, request: &ChatCompletionRequest) -> Value {
+        serde_json::json!({
+            "model": request.model,
+            "messages": request.messages.iter()
+                .map(|m| serde_json::json!({ "role": m.role.as_api_str(), "content": m.content }))
+                .collect::<Vec<_>>(),
+        })
+    }
+
     async fn chat_completion(
         &self,
         request: ChatCompletionRequest,
@@ -381,9 +418,25 @@ struct ApiChatCompletionRequest {
     top_p: f32,
     #[serde(skip_serializing_if = "Option::is_none")]
     max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<ApiResponseFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
     messages: Vec<ApiChatMessage>,
 }
 
+#[derive(Debug, Clone, Serialize)]
+struct ApiResponseFormat {
+    r#type: &'static str,
+    json_schema: ApiJsonSchema,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ApiJsonSchema {
+    name: String,
+    schema: Value,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct ApiChatMessage {
     role: String,
@@ -432,6 +485,8 @@ mod tests {
             max_tokens: Some(64),
             temperature: Some(0.3),
             top_p: Some(0.9),
+            response_format: None,
+            seed: None,
         }
     }
 
@@ -457,6 +512,32 @@ mod tests {
         assert_eq!(value["temperature"], 0.3);
         assert_eq!(value["top_p"], 0.9);
         assert_eq!(value["max_tokens"], 64);
+        assert!(value.get("response_format").is_none());
+        assert!(value.get("seed").is_none());
+
+        let schema = serde_json::json!({ "type": "object" });
+        let request = ChatCompletionRequest {
+            response_format: Some(ResponseFormat::JsonSchema { name: "answer".into(), schema: schema.clone() }),
+            ..sample_request()
+        };
+        let value = serde_json::to_value(client.build_request_for_tests(&request)).unwrap();
+        assert_eq!(value["response_format"]["type"], "json_schema");
+        assert_eq!(value["response_format"]["json_schema"]["name"], "answer");
+        assert_eq!(value["response_format"]["json_schema"]["schema"], schema);
+
+        let request = ChatCompletionRequest { seed: Some(7), ..sample_request() };
+        let value = serde_json::to_value(client.build_request_for_tests(&request)).unwrap();
+        assert_eq!(value["seed"], 7);
+    }
+
+    #[test]
+    fn request_body_is_the_wire_payload() {
+        let client = OpenAiClient::new(OpenAiClientConfig { api_key: Some("test".into()), ..OpenAiClientConfig::default() }).unwrap();
+        let request = ChatCompletionRequest { temperature: None, ..sample_request() };
+        let body = client.request_body(&request);
+        assert_eq!(body, serde_json::to_value(client.build_request_for_tests(&request)).unwrap());
+        assert_eq!(body["model"], OpenAiClientConfig::default().default_model.as_str());
+        assert!(body["temperature"].is_number());
     }
 
     #[tokio::test]
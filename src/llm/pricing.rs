@@ -0,0 +1,90 @@
+//! USD per 1K tokens by model, for compose cost estimates. Built-in prices can be
+//! extended/overridden with `RAG_LLM_PRICING`: inline JSON or a path to a JSON file of
+//! `{"<model>": {"input": <usd/1k>, "output": <usd/1k>}}`.
+
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub struct Price {
+    pub input: f64,
+    pub output: f64,
+}
+
+const BUILTIN: &[(&str, Price)] = &[
+    ("gpt-4o-mini", Price { input: 0.00015, output: 0.0006 }),
+    ("gpt-4o", Price { input: 0.0025, output: 0.01 }),
+    ("gpt-4.1-mini", Price { input: 0.0004, output: 0.0016 }),
+    ("gpt-4.1", Price { input: 0.002, output: 0.008 }),
+    ("claude-3-5-haiku", Price { input: 0.0008, output: 0.004 }),
+    ("claude-3-5-sonnet", Price { input: 0.003, output: 0.015 }),
+];
+
+pub struct PriceTable(HashMap<String, Price>);
+
+impl PriceTable {
+    pub fn builtin() -> Self {
+        PriceTable(BUILTIN.iter().map(|(m, p)| (m.to_string(), *p)).collect())
+    }
+
+    /// Built-ins plus `RAG_LLM_PRICING` entries (which win on the same model).
+    pub fn from_env() -> Result<Self> {
+        let mut table = Self::builtin();
+        if let Ok(spec) = std::env::var("RAG_LLM_PRICING") {
+            let json = if spec.trim_start().starts_with('{') {
+                spec
+            } else {
+                std::fs::read_to_string(&spec).with_context(|| format!("read RAG_LLM_PRICING file {spec}"))?
+            };
+            table.extend_json(&json).context("parse RAG_LLM_PRICING")?;
+        }
+        Ok(table)
+    }
+
+    pub fn extend_json(&mut self, json: &str) -> Result<()> {
+        let extra: HashMap<String, Price> = serde_json::from_str(json)?;
+        self.0.extend(extra);
+        Ok(())
+    }
+
+    /// Exact model name, else the longest known prefix (so dated snapshots like
+    /// `gpt-4o-mini-2024-07-18` or `claude-3-5-haiku-latest` resolve).
+    pub fn lookup(&self, model: &str) -> Option<Price> {
+        if let Some(p) = self.0.get(model) { return Some(*p); }
+        self.0
+            .iter()
+            .filter(|(name, _)| model.starts_with(name.as_str()))
+            .max_by_key(|(name, _)| name.len())
+            .map(|(_, p)| *p)
+    }
+}
+
+pub fn estimate_usd(price: Price, prompt_tokens: u32, completion_tokens: u32) -> f64 {
+    (prompt_tokens as f64 / 1000.0) * price.input + (completion_tokens as f64 / 1000.0) * price.output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup_prefers_exact_then_longest_prefix() {
+        let table = PriceTable::builtin();
+        assert_eq!(table.lookup("gpt-4o").unwrap().input, 0.0025);
+        assert_eq!(table.lookup("gpt-4o-mini-2024-07-18").unwrap().input, 0.00015);
+        assert_eq!(table.lookup("claude-3-5-haiku-latest").unwrap().output, 0.004);
+        assert!(table.lookup("llama3").is_none());
+    }
+
+    #[test]
+    fn json_overrides_builtin_and_adds_models() {
+        let mut table = PriceTable::builtin();
+        table.extend_json(r#"{"gpt-4o": {"input": 1.0, "output": 2.0}, "llama3": {"input": 0, "output": 0}}"#).unwrap();
+        assert_eq!(table.lookup("gpt-4o"), Some(Price { input: 1.0, output: 2.0 }));
+        assert_eq!(table.lookup("llama3:8b"), Some(Price { input: 0.0, output: 0.0 }));
+        let cost = estimate_usd(Price { input: 0.5, output: 1.5 }, 2000, 1000);
+        assert!((cost - 2.5).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,126 @@
+//! Minimal JSON Schema check for structured LLM replies: `type`, `properties`, `required`,
+//! `additionalProperties: false`, `items`, `enum`, `minimum`/`maximum`. Other keywords are ignored.
+
+use serde_json::Value;
+
+/// Problems found, as `path: message`; empty when `value` conforms.
+pub fn validate(schema: &Value, value: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    check(schema, value, "$", &mut errors);
+    errors
+}
+
+fn check(schema: &Value, value: &Value, path: &str, errors: &mut Vec<String>) {
+    let Some(schema) = schema.as_object() else { return };
+
+    if let Some(ty) = schema.get("type") {
+        let allowed: Vec<&str> = match ty {
+            Value::String(s) => vec![s.as_str()],
+            Value::Array(v) => v.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !allowed.is_empty() && !allowed.iter().any(|t| type_matches(t, value)) {
+            errors.push(format!("{path}: expected {}, got {}", allowed.join("|"), type_name(value)));
+            return;
+        }
+    }
+
+    if let Some(Value::Array(options)) = schema.get("enum") {
+        if !options.contains(value) {
+            errors.push(format!("{path}: not one of the allowed values"));
+        }
+    }
+
+    if let Some(n) = value.as_f64() {
+        if let Some(min) = schema.get("minimum").and_then(Value::as_f64) {
+            if n < min { errors.push(format!("{path}: {n} < minimum {min}")); }
+        }
+        if let Some(max) = schema.get("maximum").and_then(Value::as_f64) {
+            if n > max { errors.push(format!("{path}: {n} > maximum {max}")); }
+        }
+    }
+
+    if let Some(obj) = value.as_object() {
+        let props = schema.get("properties").and_then(Value::as_object);
+        if let Some(Value::Array(required)) = schema.get("required") {
+            for name in required.iter().filter_map(Value::as_str) {
+                if !obj.contains_key(name) { errors.push(format!("{path}: missing required property '{name}'")); }
+            }
+        }
+        for (name, v) in obj {
+            match props.and_then(|p| p.get(name)) {
+                Some(sub) => check(sub, v, &format!("{path}.{name}"), errors),
+                None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                    errors.push(format!("{path}: unexpected property '{name}'"));
+                }
+                None => {}
+            }
+        }
+    }
+
+    if let (Some(items), Some(arr)) = (schema.get("items"), value.as_array()) {
+        for (i, v) in arr.iter().enumerate() {
+            check(items, v, &format!("{path}[{i}]"), errors);
+        }
+    }
+}
+
+fn type_matches(ty: &str, value: &Value) -> bool {
+    match ty {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn answer_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "answer": { "type": "string" },
+                "confidence": { "type": "number", "minimum": 0, "maximum": 1 },
+                "sources": { "type": "array", "items": { "type": "integer" } }
+            },
+            "required": ["answer", "confidence"],
+            "additionalProperties": false
+        })
+    }
+
+    #[test]
+    fn accepts_conforming_value() {
+        let v = json!({ "answer": "yes", "confidence": 0.7, "sources": [1, 2] });
+        assert!(validate(&answer_schema(), &v).is_empty());
+    }
+
+    #[test]
+    fn reports_each_violation_with_path() {
+        let v = json!({ "confidence": 1.5, "sources": [1, "two"], "extra": true });
+        let errors = validate(&answer_schema(), &v);
+        assert!(errors.contains(&"$: missing required property 'answer'".to_string()));
+        assert!(errors.contains(&"$.confidence: 1.5 > maximum 1".to_string()));
+        assert!(errors.contains(&"$.sources[1]: expected integer, got string".to_string()));
+        assert!(errors.contains(&"$: unexpected property 'extra'".to_string()));
+        assert_eq!(validate(&answer_schema(), &json!("text")), vec!["$: expected object, got string".to_string()]);
+    }
+}
@@ -1,25 +1,13 @@
-use clap::{Parser, Subcommand};
-use sqlx::{PgPool};
-use anyhow::Result;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use sqlx::postgres::{PgPool, PgPoolOptions};
+use anyhow::{Context, Result};
 use dotenvy::dotenv;
 use std::env;
-use std::time::Instant;
-
-
-// mod init; // removed (hard removal of `init` subcommand)
-mod feed;
-mod ingestion;
-mod tokenizer;
-mod encoder;
-mod stats;
-mod query;
-mod util;
-mod maintenance;
-mod telemetry;
-mod pipeline;
-mod output;
-mod llm;
-mod compose;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use ragfeed::{compose, feed, ingestion, maintenance, output, pipeline, query, stats, telemetry};
+use ragfeed::util::{cancel, config, exit, http};
 
 #[derive(Parser)]
 #[command(name = "rag", about = "RAG pipeline CLI")]
@@ -27,6 +15,34 @@ struct Cli {
     #[arg(global = true, short, long)]
     dsn: Option<String>,
 
+    /// Read the DSN from a file (e.g. a mounted secret); --dsn takes precedence
+    #[arg(global = true, long)]
+    dsn_file: Option<PathBuf>,
+
+    /// Write plan/result envelopes to this file instead of stdout (truncated unless --append)
+    #[arg(global = true, long)]
+    output: Option<PathBuf>,
+
+    /// With --output: append to the file instead of truncating it
+    #[arg(global = true, long, default_value_t = false, requires = "output")]
+    append: bool,
+
+    /// Suppress info logs on stderr; plan/result envelopes still go to stdout
+    #[arg(global = true, short, long, default_value_t = false)]
+    quiet: bool,
+
+    /// Replace emoji log prefixes with ASCII tags ([plan], [ok], [del]) and disable colors
+    #[arg(global = true, long, default_value_t = false)]
+    ascii: bool,
+
+    /// TOML/JSON file of flag defaults (default: ./ragfeed.toml if present); command-line flags win
+    #[arg(global = true, long)]
+    config: Option<PathBuf>,
+
+    /// Skip TLS certificate verification on outbound HTTP (testing only; logs a warning)
+    #[arg(global = true, long, default_value_t = false)]
+    insecure: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -37,42 +53,144 @@ enum Commands {
     Ingest(ingestion::IngestCmd),
     Chunk(pipeline::chunk::ChunkCmd),
     Embed(pipeline::embed::EmbedCmd),
+    ReembedChanged(pipeline::reembed::ReembedChangedCmd),
     Stats(stats::StatsCmd),
     Reindex(maintenance::reindex::ReindexCmd),
     Gc(maintenance::gc::GcCmd),
+    PurgeFeed(maintenance::purge::PurgeFeedCmd),
+    /// Normalize document.status from chunk/embedding presence (the status step of gc --fix-status)
+    FixStatus(maintenance::fix_status::FixStatusCmd),
     Query(query::QueryCmd),
+    /// Embed a string and show its nearest/farthest chunks to sanity-check the encoder and index
+    SelftestEmbed(query::selftest::SelftestEmbedCmd),
     Compose(compose::ComposeCmd),
+    ExtractDebug(ingestion::debug::ExtractDebugCmd),
+    /// Print JSON Schemas for the output envelope and plan/result payloads
+    Schema(output::schema::SchemaCmd),
+    /// Print the version; --full adds features, ONNX Runtime, default model and pgvector/index details
+    Version(output::version::VersionCmd),
 }
 
+// exit codes for scripts: see util::exit (0 ok, 3 empty, 4 config, 5 upstream, 1 other, 130 Ctrl-C)
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() {
+    let code = match run().await {
+        // Ctrl-C: partial results were reported, but scripts should still see the interruption
+        Ok(()) if cancel::is_cancelled() => 130,
+        Ok(()) => exit::success().code(),
+        Err(err) => {
+            eprintln!("Error: {err:?}");
+            exit::classify(&err).code()
+        }
+    };
+    // flush spans on every path (errors and early returns included); process::exit skips destructors
+    #[cfg(feature = "otel")]
+    telemetry::otel::shutdown();
+    std::process::exit(code);
+}
+
+async fn run() -> Result<()> {
     dotenv().ok();
-    let cli = Cli::parse();
-    let _t0 = Instant::now();
+    let cli = parse_cli()?;
+    let t0 = Instant::now();
+
+    // initialize logging/tracing (stderr). Respect RUST_LOG, RAG_LOG_FORMAT, RAG_QUIET, RAG_ASCII, NO_COLOR
+    telemetry::config::init_tracing(cli.quiet, cli.ascii);
+    // run_id + wall clock shared by every plan/result envelope's meta
+    telemetry::emit::init_run(t0);
+    if let Some(path) = &cli.output {
+        telemetry::emit::init_output_file(path, cli.append)?;
+    }
+    // RAG_CA_BUNDLE / --insecure for every reqwest client (ingest, extract-debug, LLM calls)
+    http::init(cli.insecure)?;
+    // no database needed: fetch + extract only
+    if let Commands::ExtractDebug(args) = cli.command {
+        return ingestion::debug::run(args).await;
+    }
+    if let Commands::Schema(args) = cli.command {
+        return output::schema::run(args);
+    }
+    // the DB is optional here: --full reports it when reachable
+    if let Commands::Version(args) = cli.command {
+        return output::version::run(args, resolve_dsn(cli.dsn.clone(), cli.dsn_file.as_deref())).await;
+    }
 
-    // initialize logging/tracing (stderr). Respect RUST_LOG and RAG_LOG_FORMAT
-    telemetry::config::init_tracing();
-    let dsn = cli
-        .dsn
-        .or_else(|| env::var("DATABASE_URL").ok())
-        .expect("Please provide --dsn or set DATABASE_URL in .env");
+    let dsn = resolve_dsn(cli.dsn.clone(), cli.dsn_file.as_deref())?;
 
-    let pool = PgPool::connect(&dsn).await?;
+    // reindex/gc/fix-status (VACUUM, CREATE INDEX, bulk status updates) legitimately run long, so they skip the statement timeout
+    let long_running = matches!(cli.command, Commands::Reindex(_) | Commands::Gc(_) | Commands::FixStatus(_));
+    let pool = connect_pool(&dsn, long_running).await?;
+
+    // Ctrl-C lets ingest/embed/reembed-changed/gc/reindex stop at a batch boundary and report partial results
+    cancel::install_ctrl_c_handler();
 
     match cli.command {
         Commands::Feed(args) => feed::run(&pool, args).await?,
         Commands::Ingest(args) => ingestion::run(&pool, args).await?,
         Commands::Chunk(args) => pipeline::chunk::run(&pool, args).await?,
         Commands::Embed(args) => pipeline::embed::run(&pool, args).await?,
+        Commands::ReembedChanged(args) => pipeline::reembed::run(&pool, args).await?,
         Commands::Stats(args) => stats::run(&pool, args).await?,
         Commands::Reindex(args) => maintenance::reindex::run(&pool, args).await?,
         Commands::Gc(args) => maintenance::gc::run(&pool, args).await?,
+        Commands::PurgeFeed(args) => maintenance::purge::run(&pool, args).await?,
+        Commands::FixStatus(args) => maintenance::fix_status::run(&pool, args).await?,
         Commands::Query(args) => query::run(&pool, args).await?,
+        Commands::SelftestEmbed(args) => query::selftest::run(&pool, args).await?,
         Commands::Compose(args) => compose::run(&pool, args).await?,
+        Commands::ExtractDebug(_) | Commands::Schema(_) | Commands::Version(_) => unreachable!("handled before connecting"),
         // Commands::Eval => println!("TODO: eval"),
     }
 
+    pool.close().await;
     Ok(())
 }
 
+// clap defaults < config file < command-line flags
+fn parse_cli() -> Result<Cli> {
+    let args: Vec<std::ffi::OsString> = env::args_os().collect();
+    let Some(path) = config::find_path(&args) else { return Ok(Cli::parse_from(args)) };
+    let cmd = config::apply(Cli::command(), &config::load(&path)?)?;
+    let matches = cmd.get_matches_from(args);
+    Ok(Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit()))
+}
+
 // init_tracing moved to telemetry::config::init_tracing
+
+// precedence: --dsn > --dsn-file > DATABASE_URL_FILE > DATABASE_URL
+fn resolve_dsn(dsn: Option<String>, dsn_file: Option<&Path>) -> Result<String> {
+    fn read_dsn(path: &Path) -> Result<String> {
+        let s = std::fs::read_to_string(path).with_context(|| format!("read DSN file {}", path.display()))?;
+        Ok(s.trim_end_matches(['\n', '\r']).to_string())
+    }
+    if let Some(dsn) = dsn { return Ok(dsn); }
+    if let Some(path) = dsn_file { return read_dsn(path); }
+    if let Ok(path) = env::var("DATABASE_URL_FILE") { return read_dsn(Path::new(&path)); }
+    env::var("DATABASE_URL").map_err(|_| exit::ConfigError("Please provide --dsn/--dsn-file or set DATABASE_URL(_FILE) in .env".into()).into())
+}
+
+fn env_u64(key: &str, default: u64) -> u64 {
+    env::var(key).ok().and_then(|v| v.parse::<u64>().ok()).unwrap_or(default)
+}
+
+// pool sized from RAG_DB_MAX_CONNS / RAG_DB_ACQUIRE_TIMEOUT (secs); every connection gets
+// statement_timeout = RAG_DB_STATEMENT_TIMEOUT secs (0 disables) unless long_running
+async fn connect_pool(dsn: &str, long_running: bool) -> Result<PgPool> {
+    let max_conns = env_u64("RAG_DB_MAX_CONNS", 10).max(1) as u32;
+    let acquire_timeout = Duration::from_secs(env_u64("RAG_DB_ACQUIRE_TIMEOUT", 30));
+    let statement_timeout_ms = if long_running { 0 } else { env_u64("RAG_DB_STATEMENT_TIMEOUT", 300) * 1000 };
+
+    let pool = PgPoolOptions::new()
+        .max_connections(max_conns)
+        .acquire_timeout(acquire_timeout)
+        .after_connect(move |conn, _meta| Box::pin(async move {
+            if statement_timeout_ms > 0 {
+                let sql = format!("SET statement_timeout = {}", statement_timeout_ms);
+                sqlx::query(&sql).execute(conn).await?;
+            }
+            Ok(())
+        }))
+        .connect(dsn)
+        .await?;
+    Ok(pool)
+}
@@ -0,0 +1,63 @@
+use anyhow::Result;
+use clap::Args;
+use serde::Serialize;
+use sqlx::PgPool;
+
+use crate::maintenance::gc::status::{count_status_changes, fix_statuses, StatusChanges};
+use crate::telemetry::{self};
+use crate::telemetry::ops::fix_status::Phase as FixStatusPhase;
+use crate::util::exit;
+
+/// rag fix-status: only the status normalization from `gc --fix-status`, without any deletes
+#[derive(Args, Debug)]
+pub struct FixStatusCmd {
+    #[arg(long)] pub feed: Option<i32>,
+    /// Count a doc as embedded only when every chunk has an embedding for this model tag (default: any model)
+    #[arg(long)] pub model: Option<String>,
+    #[arg(long, default_value_t = false)] pub apply: bool,
+}
+
+pub async fn run(pool: &PgPool, args: FixStatusCmd) -> Result<()> {
+    let log = telemetry::fix_status();
+    let _g = log.root_span_kv([
+        ("feed", format!("{:?}", args.feed)),
+        ("model", format!("{:?}", args.model)),
+        ("apply", args.apply.to_string()),
+    ]).entered();
+
+    let pending = {
+        let _s = log.span(&FixStatusPhase::Count).entered();
+        count_status_changes(pool, args.feed, args.model.as_deref()).await?
+    };
+
+    if !args.apply {
+        let _sp = log.span(&FixStatusPhase::Plan).entered();
+        log.info(format!(
+            "📝 Fix-status plan — feed={:?} model={:?} embedded={} chunked={} ingest={}",
+            args.feed, args.model, pending.embedded, pending.chunked, pending.ingest
+        ));
+        log.info("   Use --apply to execute.");
+        #[derive(Serialize)]
+        struct FixStatusPlan { feed: Option<i32>, model: Option<String>, would_change: StatusChanges }
+        let plan = FixStatusPlan { feed: args.feed, model: args.model, would_change: pending };
+        log.plan(&plan)?;
+        return Ok(());
+    }
+
+    #[derive(Serialize)]
+    struct FixStatusResult { feed: Option<i32>, model: Option<String>, changed: StatusChanges }
+    if pending.total() == 0 {
+        log.info("ℹ️  Every document status already matches its chunks/embeddings");
+        exit::mark_empty();
+        log.result(&FixStatusResult { feed: args.feed, model: args.model, changed: StatusChanges::default() })?;
+        return Ok(());
+    }
+
+    let changed = {
+        let _s = log.span(&FixStatusPhase::Update).entered();
+        fix_statuses(pool, args.feed, args.model.as_deref()).await?
+    };
+    changed.log(&log, "Set");
+    log.result(&FixStatusResult { feed: args.feed, model: args.model, changed })?;
+    Ok(())
+}
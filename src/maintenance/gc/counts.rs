@@ -2,6 +2,8 @@ use anyhow::Result;
 use chrono::{DateTime, Utc};
 use sqlx::PgPool;
 
+use super::types::{GcChunkSample, GcDocSample, GcEmbeddingSample};
+
 pub async fn count_orphan_embeddings(pool: &PgPool) -> Result<i64> {
     let n = sqlx::query_scalar!(
         r#"
@@ -15,6 +17,27 @@ pub async fn count_orphan_embeddings(pool: &PgPool) -> Result<i64> {
     Ok(n.unwrap_or(0))
 }
 
+// Coverage gap: chunks with no embedding for `model` (None = no embedding at all)
+pub async fn count_unembedded_chunks(pool: &PgPool, model: Option<&str>, feed: Option<i32>) -> Result<i64> {
+    let n = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*)::bigint
+        FROM rag.chunk c
+        JOIN rag.document d ON d.doc_id = c.doc_id
+        WHERE ($2::int4 IS NULL OR d.feed_id = $2)
+          AND NOT EXISTS (
+            SELECT 1 FROM rag.embedding e
+            WHERE e.chunk_id = c.chunk_id AND ($1::text IS NULL OR e.model = $1)
+          )
+        "#,
+        model,
+        feed
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(n.unwrap_or(0))
+}
+
 pub async fn count_orphan_chunks(pool: &PgPool, feed: Option<i32>) -> Result<i64> {
     let n = match feed {
         None => sqlx::query_scalar!(
@@ -147,3 +170,120 @@ pub async fn count_bad_chunks(pool: &PgPool, feed: Option<i32>) -> Result<i64> {
     Ok(n.unwrap_or(0))
 }
 
+
+// -------- Samples (plan mode) --------
+
+pub async fn sample_orphan_embeddings(pool: &PgPool, limit: i64) -> Result<Vec<GcEmbeddingSample>> {
+    let rows = sqlx::query_as!(
+        GcEmbeddingSample,
+        r#"
+        SELECT e.chunk_id, e.model, e.created_at
+        FROM rag.embedding e
+        WHERE NOT EXISTS (SELECT 1 FROM rag.chunk c WHERE c.chunk_id = e.chunk_id)
+        ORDER BY e.chunk_id
+        LIMIT $1
+        "#,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn sample_orphan_chunks(pool: &PgPool, feed: Option<i32>, limit: i64) -> Result<Vec<GcChunkSample>> {
+    let rows = sqlx::query_as!(
+        GcChunkSample,
+        r#"
+        SELECT c.chunk_id, c.doc_id, c.token_count
+        FROM rag.chunk c
+        WHERE NOT EXISTS (SELECT 1 FROM rag.document d WHERE d.doc_id = c.doc_id)
+          AND ($1::int4 IS NULL OR EXISTS (SELECT 1 FROM rag.document d2 WHERE d2.doc_id = c.doc_id AND d2.feed_id = $1))
+        ORDER BY c.chunk_id
+        LIMIT $2
+        "#,
+        feed,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn sample_error_docs(pool: &PgPool, cutoff: Option<DateTime<Utc>>, feed: Option<i32>, limit: i64) -> Result<Vec<GcDocSample>> {
+    let rows = sqlx::query_as!(
+        GcDocSample,
+        r#"
+        SELECT d.doc_id, d.source_url AS url, d.status, d.fetched_at
+        FROM rag.document d
+        WHERE d.status = 'error'
+          AND ($1::timestamptz IS NULL OR d.fetched_at < $1)
+          AND ($2::int4 IS NULL OR d.feed_id = $2)
+        ORDER BY d.fetched_at ASC NULLS FIRST, d.doc_id
+        LIMIT $3
+        "#,
+        cutoff,
+        feed,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn sample_never_chunked_docs(pool: &PgPool, cutoff: Option<DateTime<Utc>>, feed: Option<i32>, limit: i64) -> Result<Vec<GcDocSample>> {
+    let rows = sqlx::query_as!(
+        GcDocSample,
+        r#"
+        SELECT d.doc_id, d.source_url AS url, d.status, d.fetched_at
+        FROM rag.document d
+        WHERE d.status = 'ingest'
+          AND ($1::timestamptz IS NULL OR d.fetched_at < $1)
+          AND ($2::int4 IS NULL OR d.feed_id = $2)
+          AND NOT EXISTS (SELECT 1 FROM rag.chunk c WHERE c.doc_id = d.doc_id)
+        ORDER BY d.fetched_at ASC NULLS FIRST, d.doc_id
+        LIMIT $3
+        "#,
+        cutoff,
+        feed,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn sample_bad_chunks(pool: &PgPool, feed: Option<i32>, limit: i64) -> Result<Vec<GcChunkSample>> {
+    let rows = sqlx::query_as!(
+        GcChunkSample,
+        r#"
+        SELECT c.chunk_id, c.doc_id, c.token_count
+        FROM rag.chunk c
+        LEFT JOIN rag.document d ON d.doc_id = c.doc_id
+        WHERE ($1::int4 IS NULL OR d.feed_id = $1)
+          AND (c.text IS NULL OR btrim(c.text) = '' OR c.token_count <= 0)
+        ORDER BY c.chunk_id
+        LIMIT $2
+        "#,
+        feed,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+// compose answers citing a chunk that no longer exists (deleted or re-chunked); no feed scope
+pub async fn count_stale_compose_answers(pool: &PgPool) -> Result<i64> {
+    let n = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*)::bigint FROM rag.compose_cache cc
+        WHERE EXISTS (
+          SELECT 1 FROM unnest(cc.chunk_ids) AS u(chunk_id)
+          WHERE NOT EXISTS (SELECT 1 FROM rag.chunk c WHERE c.chunk_id = u.chunk_id)
+        )
+        "#
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(n.unwrap_or(0))
+}
@@ -305,3 +305,28 @@ pub async fn delete_bad_chunks(pool: &PgPool, feed: Option<i32>, max: i64) -> Re
         .await,
     }
 }
+
+pub async fn delete_stale_compose_answers(pool: &PgPool, max: i64) -> Result<()> {
+    paged_loop(
+        pool,
+        |limit| {
+            sqlx::query(
+                r#"
+                DELETE FROM rag.compose_cache cc
+                WHERE cc.cache_key IN (
+                    SELECT cc2.cache_key FROM rag.compose_cache cc2
+                    WHERE EXISTS (
+                        SELECT 1 FROM unnest(cc2.chunk_ids) AS u(chunk_id)
+                        WHERE NOT EXISTS (SELECT 1 FROM rag.chunk c WHERE c.chunk_id = u.chunk_id)
+                    )
+                    LIMIT $1
+                )
+                "#,
+            )
+            .bind(limit)
+        },
+        max,
+        |n| { let log = telemetry::gc(); log.info(format!("  🗑️ Deleted {} stale compose answers", n)); },
+    )
+    .await
+}
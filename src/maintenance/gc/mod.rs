@@ -1,25 +1,61 @@
 pub mod counts;
 pub mod deletes;
 pub mod status;
+pub mod types;
 pub mod vacuum;
 
 use anyhow::Result;
 use clap::Args;
-use chrono::{DateTime, Utc};
 use serde::Serialize;
 use sqlx::PgPool;
 
+use crate::output::types::Outcome;
 use crate::telemetry::{self};
 use crate::telemetry::ops::gc::Phase as GcPhase;
+use crate::util::cancel;
+use crate::util::slow::timed;
 use crate::util::time::parse_cutoff_str;
 
-#[derive(clap::ValueEnum, Clone, Debug)]
+#[derive(clap::ValueEnum, Clone, Debug, PartialEq, Eq)]
 pub enum VacuumMode {
     #[value(name = "analyze")] Analyze,
     #[value(name = "full")] Full,
+    #[value(name = "index-only")] IndexOnly,
     #[value(name = "off")] Off,
 }
 
+/// Row-deleting cleanup categories, selectable with --only/--skip.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GcCategory {
+    OrphanChunks,
+    OrphanEmbeddings,
+    ErrorDocs,
+    NeverChunkedDocs,
+    BadChunks,
+    StaleComposeAnswers,
+}
+
+impl GcCategory {
+    pub const ALL: [GcCategory; 6] = [
+        GcCategory::OrphanChunks,
+        GcCategory::OrphanEmbeddings,
+        GcCategory::ErrorDocs,
+        GcCategory::NeverChunkedDocs,
+        GcCategory::BadChunks,
+        GcCategory::StaleComposeAnswers,
+    ];
+}
+
+/// Categories a run covers: `only` when given (else all), minus `skip`; kept in phase order.
+pub fn selected_categories(only: &[GcCategory], skip: &[GcCategory]) -> Vec<GcCategory> {
+    GcCategory::ALL
+        .into_iter()
+        .filter(|c| only.is_empty() || only.contains(c))
+        .filter(|c| !skip.contains(c))
+        .collect()
+}
+
 #[derive(Args, Debug)]
 pub struct GcCmd {
     #[arg(long, default_value_t = false)] pub apply: bool,
@@ -29,12 +65,88 @@ pub struct GcCmd {
     #[arg(long, value_enum, default_value_t = VacuumMode::Analyze)] pub vacuum: VacuumMode,
     #[arg(long, default_value_t = false)] pub drop_temp_indexes: bool,
     #[arg(long, default_value_t = false)] pub fix_status: bool,
+    /// Embedding model tag (e.g. intfloat/e5-small-v2@onnx-cpu) that defines "embedded" for coverage and --fix-status
+    #[arg(long)] pub model: Option<String>,
+    /// Plan mode: number of candidate rows to show per category (0 to disable)
+    #[arg(long, default_value_t = 5)] pub sample: i64,
+    /// Never delete rows, even with --apply (delete phases only report counts); also set by RAG_GC_READONLY
+    #[arg(long, default_value_t = false)] pub no_delete: bool,
+    /// Run only these cleanup categories (repeatable); default is all
+    #[arg(long, value_enum)] pub only: Vec<GcCategory>,
+    /// Leave these cleanup categories out (repeatable)
+    #[arg(long, value_enum)] pub skip: Vec<GcCategory>,
+}
+
+/// Library-facing gc parameters; `GcCmd` maps onto these one-to-one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GcParams {
+    pub apply: bool,
+    pub older_than: String,
+    pub max: i64,
+    pub feed: Option<i32>,
+    pub vacuum: VacuumMode,
+    pub drop_temp_indexes: bool,
+    pub fix_status: bool,
+    pub model: Option<String>,
+    pub sample: i64,
+    pub no_delete: bool,
+    pub only: Vec<GcCategory>,
+    pub skip: Vec<GcCategory>,
+}
+
+impl Default for GcParams {
+    fn default() -> Self {
+        Self {
+            apply: false,
+            older_than: "30d".to_string(),
+            max: 10_000,
+            feed: None,
+            vacuum: VacuumMode::Analyze,
+            drop_temp_indexes: false,
+            fix_status: false,
+            model: None,
+            sample: 5,
+            no_delete: false,
+            only: Vec::new(),
+            skip: Vec::new(),
+        }
+    }
+}
+
+impl From<GcCmd> for GcParams {
+    fn from(c: GcCmd) -> Self {
+        Self {
+            apply: c.apply,
+            older_than: c.older_than,
+            max: c.max,
+            feed: c.feed,
+            vacuum: c.vacuum,
+            drop_temp_indexes: c.drop_temp_indexes,
+            fix_status: c.fix_status,
+            model: c.model,
+            sample: c.sample,
+            no_delete: c.no_delete,
+            only: c.only,
+            skip: c.skip,
+        }
+    }
 }
 
+pub type GcOutcome = Outcome<types::GcPlan, types::GcResult>;
+
 pub async fn run(pool: &PgPool, args: GcCmd) -> Result<()> {
-    let cutoff = parse_cutoff_str(&args.older_than);
+    let outcome = execute(pool, args.into()).await?;
+    telemetry::gc().outcome(&outcome)
+}
+
+pub async fn execute(pool: &PgPool, args: GcParams) -> Result<GcOutcome> {
+    let cutoff = parse_cutoff_str(&args.older_than)?;
     let execute = args.apply;
     let mode = if execute { "apply" } else { "plan" };
+    // read-only guard: delete phases stay count-only while status fixes and vacuum still run
+    let no_delete = args.no_delete || crate::telemetry::config::env_flag("RAG_GC_READONLY");
+    let deletes = execute && !no_delete;
+    let cats = selected_categories(&args.only, &args.skip);
 
     let log = telemetry::gc();
     let _g = log.root_span_kv([
@@ -44,105 +156,184 @@ pub async fn run(pool: &PgPool, args: GcCmd) -> Result<()> {
         ("max", args.max.to_string()),
         ("vacuum", format!("{:?}", args.vacuum)),
         ("fix_status", args.fix_status.to_string()),
+        ("model", format!("{:?}", args.model)),
         ("drop_temp_indexes", args.drop_temp_indexes.to_string()),
+        ("sample", args.sample.to_string()),
+        ("no_delete", no_delete.to_string()),
+        ("categories", format!("{:?}", cats)),
     ]).entered();
     let _p = log.span(&GcPhase::Plan).entered();
     log.info(format!(
-        "📝 GC plan — mode={} feed={:?} cutoff={:?} max={} vacuum={:?} fix_status={} drop_temp_indexes={}",
-        mode, args.feed, cutoff, args.max, args.vacuum, args.fix_status, args.drop_temp_indexes
+        "📝 GC plan — mode={} feed={:?} cutoff={:?} max={} vacuum={:?} fix_status={} model={:?} drop_temp_indexes={}",
+        mode, args.feed, cutoff, args.max, args.vacuum, args.fix_status, args.model, args.drop_temp_indexes
     ));
     if !execute { log.info("   Use --apply to execute."); }
+    if cats.len() < GcCategory::ALL.len() { log.info(format!("   categories: {:?}", cats)); }
+    if no_delete { log.info("🔒 Read-only gc (--no-delete/RAG_GC_READONLY): no rows will be deleted"); }
+    // samples are only collected in plan mode
+    let sample_n = if execute { 0 } else { args.sample.max(0) };
+    let mut samples = types::GcSamples::default();
 
     // orphan chunks
-    let orphan_chunks = { let _s = log.span(&GcPhase::Count).entered(); crate::maintenance::gc::counts::count_orphan_chunks(pool, args.feed).await? };
-    log.info(format!("🧱 Orphan chunks: {}", orphan_chunks));
-    if execute && orphan_chunks > 0 { crate::maintenance::gc::deletes::delete_orphan_chunks(pool, args.feed, args.max).await?; }
+    let orphan_chunks = if cats.contains(&GcCategory::OrphanChunks) {
+        let n = { let _s = log.span(&GcPhase::Count).entered(); timed(Some(&log), "gc count orphan_chunks", crate::maintenance::gc::counts::count_orphan_chunks(pool, args.feed)).await? };
+        log.info(format!("🧱 Orphan chunks: {}", n));
+        if sample_n > 0 && n > 0 {
+            samples.orphan_chunks = crate::maintenance::gc::counts::sample_orphan_chunks(pool, args.feed, sample_n).await?;
+            for r in &samples.orphan_chunks { log.info(format!("    chunk_id={} doc_id={:?} tokens={:?}", r.chunk_id, r.doc_id, r.token_count)); }
+        }
+        if deletes && n > 0 { timed(Some(&log), "gc delete orphan_chunks", crate::maintenance::gc::deletes::delete_orphan_chunks(pool, args.feed, args.max)).await?; }
+        n
+    } else { 0 };
 
     // orphan embeddings (note: FK should prevent these; no feed scope possible)
-    let orphan_emb = { let _s = log.span(&GcPhase::Count).entered(); crate::maintenance::gc::counts::count_orphan_embeddings(pool).await? };
-    log.info(format!("🧬 Orphan embeddings: {}", orphan_emb));
-    if execute && orphan_emb > 0 { crate::maintenance::gc::deletes::delete_orphan_embeddings(pool, args.max).await?; }
+    let orphan_emb = if cats.contains(&GcCategory::OrphanEmbeddings) {
+        let n = { let _s = log.span(&GcPhase::Count).entered(); timed(Some(&log), "gc count orphan_embeddings", crate::maintenance::gc::counts::count_orphan_embeddings(pool)).await? };
+        log.info(format!("🧬 Orphan embeddings: {}", n));
+        if sample_n > 0 && n > 0 {
+            samples.orphan_embeddings = crate::maintenance::gc::counts::sample_orphan_embeddings(pool, sample_n).await?;
+            for r in &samples.orphan_embeddings { log.info(format!("    chunk_id={} model={} created_at={:?}", r.chunk_id, r.model, r.created_at)); }
+        }
+        if deletes && n > 0 { timed(Some(&log), "gc delete orphan_embeddings", crate::maintenance::gc::deletes::delete_orphan_embeddings(pool, args.max)).await?; }
+        n
+    } else { 0 };
 
     // error docs older than cutoff
-    let err_docs = { let _s = log.span(&GcPhase::Count).entered(); crate::maintenance::gc::counts::count_error_docs(pool, cutoff, args.feed).await? };
-    log.info(format!("⚠️  Error docs (> cutoff): {}", err_docs));
-    if execute && err_docs > 0 { crate::maintenance::gc::deletes::delete_error_docs(pool, cutoff, args.feed, args.max).await?; }
+    let err_docs = if cats.contains(&GcCategory::ErrorDocs) {
+        let n = { let _s = log.span(&GcPhase::Count).entered(); timed(Some(&log), "gc count error_docs", crate::maintenance::gc::counts::count_error_docs(pool, cutoff, args.feed)).await? };
+        log.info(format!("⚠️  Error docs (> cutoff): {}", n));
+        if sample_n > 0 && n > 0 {
+            samples.error_docs = crate::maintenance::gc::counts::sample_error_docs(pool, cutoff, args.feed, sample_n).await?;
+            for r in &samples.error_docs { log.info(format!("    doc_id={} status={:?} fetched={:?} {}", r.doc_id, r.status, r.fetched_at, r.url)); }
+        }
+        if deletes && n > 0 { timed(Some(&log), "gc delete error_docs", crate::maintenance::gc::deletes::delete_error_docs(pool, cutoff, args.feed, args.max)).await?; }
+        n
+    } else { 0 };
 
     // never-chunked docs older than cutoff
-    let stale_docs = { let _s = log.span(&GcPhase::Count).entered(); crate::maintenance::gc::counts::count_never_chunked_docs(pool, cutoff, args.feed).await? };
-    log.info(format!("⏳ Never-chunked docs (> cutoff): {}", stale_docs));
-    if execute && stale_docs > 0 { crate::maintenance::gc::deletes::delete_never_chunked_docs(pool, cutoff, args.feed, args.max).await?; }
+    let stale_docs = if cats.contains(&GcCategory::NeverChunkedDocs) {
+        let n = { let _s = log.span(&GcPhase::Count).entered(); timed(Some(&log), "gc count never_chunked_docs", crate::maintenance::gc::counts::count_never_chunked_docs(pool, cutoff, args.feed)).await? };
+        log.info(format!("⏳ Never-chunked docs (> cutoff): {}", n));
+        if sample_n > 0 && n > 0 {
+            samples.never_chunked_docs = crate::maintenance::gc::counts::sample_never_chunked_docs(pool, cutoff, args.feed, sample_n).await?;
+            for r in &samples.never_chunked_docs { log.info(format!("    doc_id={} status={:?} fetched={:?} {}", r.doc_id, r.status, r.fetched_at, r.url)); }
+        }
+        if deletes && n > 0 { timed(Some(&log), "gc delete never_chunked_docs", crate::maintenance::gc::deletes::delete_never_chunked_docs(pool, cutoff, args.feed, args.max)).await?; }
+        n
+    } else { 0 };
 
     // bad chunks
-    let bad_chunks = { let _s = log.span(&GcPhase::Count).entered(); crate::maintenance::gc::counts::count_bad_chunks(pool, args.feed).await? };
-    log.info(format!("🧹 Bad chunks (empty/≤0 tokens): {}", bad_chunks));
-    if execute && bad_chunks > 0 { crate::maintenance::gc::deletes::delete_bad_chunks(pool, args.feed, args.max).await?; }
-
-    // fix status
-    if args.fix_status {
-        if execute { let _s = log.span(&GcPhase::FixStatus).entered(); crate::maintenance::gc::status::fix_statuses(pool, args.feed).await?; }
-        else { log.info("🔎 Would normalize document.status based on chunk/embedding presence"); }
-    }
+    let bad_chunks = if cats.contains(&GcCategory::BadChunks) {
+        let n = { let _s = log.span(&GcPhase::Count).entered(); timed(Some(&log), "gc count bad_chunks", crate::maintenance::gc::counts::count_bad_chunks(pool, args.feed)).await? };
+        log.info(format!("🧹 Bad chunks (empty/≤0 tokens): {}", n));
+        if sample_n > 0 && n > 0 {
+            samples.bad_chunks = crate::maintenance::gc::counts::sample_bad_chunks(pool, args.feed, sample_n).await?;
+            for r in &samples.bad_chunks { log.info(format!("    chunk_id={} doc_id={:?} tokens={:?}", r.chunk_id, r.doc_id, r.token_count)); }
+        }
+        if deletes && n > 0 { timed(Some(&log), "gc delete bad_chunks", crate::maintenance::gc::deletes::delete_bad_chunks(pool, args.feed, args.max)).await?; }
+        n
+    } else { 0 };
 
-    // drop temp indexes
-    if args.drop_temp_indexes {
-        if execute { let _s = log.span(&GcPhase::DropTemp).entered(); crate::maintenance::gc::vacuum::drop_temp_indexes(pool).await?; }
-        else { log.info("🔎 Would DROP INDEX CONCURRENTLY rag.embedding_vec_ivf_idx_new if exists"); }
+    // cached compose answers whose source chunks are gone (not feed-scoped)
+    let stale_answers = if cats.contains(&GcCategory::StaleComposeAnswers) {
+        let n = { let _s = log.span(&GcPhase::Count).entered(); timed(Some(&log), "gc count stale_compose_answers", crate::maintenance::gc::counts::count_stale_compose_answers(pool)).await? };
+        log.info(format!("💬 Stale compose answers: {}", n));
+        if deletes && n > 0 { timed(Some(&log), "gc delete stale_compose_answers", crate::maintenance::gc::deletes::delete_stale_compose_answers(pool, args.max)).await?; }
+        n
+    } else { 0 };
+
+    // coverage: chunks lacking an embedding (for --model when given); informational only
+    let unembedded = { let _s = log.span(&GcPhase::Count).entered(); timed(Some(&log), "gc count unembedded_chunks", crate::maintenance::gc::counts::count_unembedded_chunks(pool, args.model.as_deref(), args.feed)).await? };
+    match &args.model {
+        Some(m) => log.info(format!("🧩 Chunks without embedding (model={}): {}", m, unembedded)),
+        None => log.info(format!("🧩 Chunks without embedding (any model): {}", unembedded)),
     }
 
-    // vacuum/Analyze
-    match args.vacuum {
-        VacuumMode::Off => {}
-        VacuumMode::Analyze => {
-            if execute { let _s = log.span(&GcPhase::Analyze).entered(); crate::maintenance::gc::vacuum::analyze_tables(pool).await?; }
-            else { log.info("🔎 Would ANALYZE rag.document, rag.chunk, rag.embedding"); }
+    // deletes stop between batches on Ctrl-C; the heavier maintenance steps are then skipped
+    let cancelled = execute && cancel::is_cancelled();
+    if cancelled { log.warn("🛑 Cancelled — skipping status fixes, temp index drops and vacuum"); }
+
+    if !cancelled {
+        // fix status
+        if args.fix_status {
+            if execute {
+                let _s = log.span(&GcPhase::FixStatus).entered();
+                crate::maintenance::gc::status::fix_statuses(pool, args.feed, args.model.as_deref()).await?.log(&log, "Set");
+            }
+            else { log.info("🔎 Would normalize document.status based on chunk/embedding presence"); }
         }
-        VacuumMode::Full => {
-            if execute { let _s = log.span(&GcPhase::Vacuum).entered(); crate::maintenance::gc::vacuum::vacuum_full(pool).await?; }
-            else { log.info("🔎 Would VACUUM (ANALYZE, FULL) rag.document, rag.chunk, rag.embedding"); }
+
+        // drop temp indexes
+        if args.drop_temp_indexes {
+            if execute { let _s = log.span(&GcPhase::DropTemp).entered(); crate::maintenance::gc::vacuum::drop_temp_indexes(pool).await?; }
+            else { log.info("🔎 Would DROP INDEX CONCURRENTLY rag.embedding_vec_ivf_idx_new if exists"); }
+        }
+
+        // vacuum/Analyze
+        match args.vacuum {
+            VacuumMode::Off => {}
+            VacuumMode::Analyze => {
+                if execute { let _s = log.span(&GcPhase::Analyze).entered(); crate::maintenance::gc::vacuum::analyze_tables(pool).await?; }
+                else { log.info("🔎 Would ANALYZE rag.document, rag.chunk, rag.embedding"); }
+            }
+            VacuumMode::Full => {
+                if execute { let _s = log.span(&GcPhase::Vacuum).entered(); crate::maintenance::gc::vacuum::vacuum_full(pool).await?; }
+                else { log.info("🔎 Would VACUUM (ANALYZE, FULL) rag.document, rag.chunk, rag.embedding"); }
+            }
+            VacuumMode::IndexOnly => {
+                if execute { let _s = log.span(&GcPhase::Reindex).entered(); crate::maintenance::gc::vacuum::reindex_embedding(pool).await?; }
+                else { log.info("🔎 Would REINDEX INDEX CONCURRENTLY rag.embedding_vec_ivf_idx and ANALYZE rag.embedding"); }
+            }
         }
     }
 
+    let counts = types::GcCounts { orphan_chunks, orphan_embeddings: orphan_emb, error_docs: err_docs, never_chunked_docs: stale_docs, bad_chunks, stale_compose_answers: stale_answers, unembedded_chunks: unembedded };
     if !execute {
-        #[derive(Serialize)]
-        struct Counts { orphan_chunks: i64, orphan_embeddings: i64, error_docs: i64, never_chunked_docs: i64, bad_chunks: i64 }
-        #[derive(Serialize)]
-        struct GcPlanOut {
-            mode: String,
-            feed: Option<i32>,
-            cutoff: Option<DateTime<Utc>>,
-            max: i64,
-            vacuum: String,
-            fix_status: bool,
-            drop_temp_indexes: bool,
-            counts: Counts,
-        }
-        let plan = GcPlanOut {
+        return Ok(Outcome::Plan(types::GcPlan {
             mode: mode.to_string(),
+            no_delete,
+            categories: cats,
             feed: args.feed,
             cutoff,
             max: args.max,
             vacuum: format!("{:?}", args.vacuum),
             fix_status: args.fix_status,
+            model: args.model,
             drop_temp_indexes: args.drop_temp_indexes,
-            counts: Counts { orphan_chunks, orphan_embeddings: orphan_emb, error_docs: err_docs, never_chunked_docs: stale_docs, bad_chunks },
-        };
-        let log = telemetry::gc();
-        log.plan(&plan)?;
-    } else if execute {
-        #[derive(Serialize)]
-        struct Counts { orphan_chunks: i64, orphan_embeddings: i64, error_docs: i64, never_chunked_docs: i64, bad_chunks: i64 }
-        #[derive(Serialize)]
-        struct GcResultOut { counts_before: Counts, fix_status: bool, drop_temp_indexes: bool, vacuum: String }
-        let res = GcResultOut {
-            counts_before: Counts { orphan_chunks, orphan_embeddings: orphan_emb, error_docs: err_docs, never_chunked_docs: stale_docs, bad_chunks },
-            fix_status: args.fix_status,
-            drop_temp_indexes: args.drop_temp_indexes,
-            vacuum: format!("{:?}", args.vacuum),
-        };
-        let log = telemetry::gc();
-        log.result(&res)?;
+            counts,
+            samples,
+        }));
     }
+    // true only when the guard actually held back rows that would have been deleted
+    let deletes_suppressed = no_delete && [orphan_chunks, orphan_emb, err_docs, stale_docs, bad_chunks, stale_answers].iter().any(|n| *n > 0);
+    Ok(Outcome::Applied(types::GcResult {
+        categories: cats,
+        counts_before: counts,
+        deletes_suppressed,
+        fix_status: args.fix_status,
+        model: args.model,
+        drop_temp_indexes: args.drop_temp_indexes,
+        vacuum: format!("{:?}", args.vacuum),
+        cancelled,
+    }))
+}
 
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_flags_select_every_category() {
+        assert_eq!(selected_categories(&[], &[]), GcCategory::ALL.to_vec());
+    }
+
+    #[test]
+    fn only_and_skip_narrow_the_set_in_phase_order() {
+        let only = [GcCategory::BadChunks, GcCategory::OrphanEmbeddings];
+        assert_eq!(selected_categories(&only, &[]), vec![GcCategory::OrphanEmbeddings, GcCategory::BadChunks]);
+        assert_eq!(selected_categories(&only, &[GcCategory::BadChunks]), vec![GcCategory::OrphanEmbeddings]);
+        let rest = selected_categories(&[], &[GcCategory::ErrorDocs]);
+        assert_eq!(rest.len(), GcCategory::ALL.len() - 1);
+        assert!(!rest.contains(&GcCategory::ErrorDocs));
+    }
 }
@@ -1,9 +1,78 @@
 use anyhow::Result;
+use serde::Serialize;
 use sqlx::PgPool;
 
-use crate::telemetry;
+use crate::telemetry::ctx::{LogCtx, OpMarker};
+
+/// Docs moved (or that would move) to each target status.
+#[derive(Serialize, Default, Debug, Clone, Copy, PartialEq)]
+pub struct StatusChanges { pub embedded: u64, pub chunked: u64, pub ingest: u64 }
+
+impl StatusChanges {
+    pub fn total(&self) -> u64 { self.embedded + self.chunked + self.ingest }
+
+    pub fn log<O: OpMarker>(&self, log: &LogCtx<O>, verb: &str) {
+        log.info(format!("✅ {} status=embedded on {} doc(s)", verb, self.embedded));
+        log.info(format!("✅ {} status=chunked on {} doc(s)", verb, self.chunked));
+        log.info(format!("✅ {} status=ingest on {} doc(s)", verb, self.ingest));
+    }
+}
+
+// Same predicates as fix_statuses, counted instead of applied (plan-only runs)
+pub async fn count_status_changes(pool: &PgPool, feed: Option<i32>, model: Option<&str>) -> Result<StatusChanges> {
+    let embedded = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "n!" FROM rag.document d
+        WHERE ($1::int4 IS NULL OR d.feed_id = $1)
+          AND EXISTS (SELECT 1 FROM rag.chunk c WHERE c.doc_id = d.doc_id)
+          AND NOT EXISTS (
+            SELECT 1 FROM rag.chunk c
+            LEFT JOIN rag.embedding e ON e.chunk_id = c.chunk_id AND ($2::text IS NULL OR e.model = $2)
+            WHERE c.doc_id = d.doc_id AND e.chunk_id IS NULL
+          )
+          AND (d.status IS DISTINCT FROM 'embedded')
+        "#,
+        feed,
+        model
+    )
+    .fetch_one(pool)
+    .await?;
+    let chunked = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "n!" FROM rag.document d
+        WHERE ($1::int4 IS NULL OR d.feed_id = $1)
+          AND EXISTS (SELECT 1 FROM rag.chunk c WHERE c.doc_id = d.doc_id)
+          AND EXISTS (
+            SELECT 1 FROM rag.chunk c
+            LEFT JOIN rag.embedding e ON e.chunk_id = c.chunk_id AND ($2::text IS NULL OR e.model = $2)
+            WHERE c.doc_id = d.doc_id AND e.chunk_id IS NULL
+          )
+          AND (d.status IS DISTINCT FROM 'chunked')
+        "#,
+        feed,
+        model
+    )
+    .fetch_one(pool)
+    .await?;
+    let ingest = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "n!" FROM rag.document d
+        WHERE ($1::int4 IS NULL OR d.feed_id = $1)
+          AND NOT EXISTS (SELECT 1 FROM rag.chunk c WHERE c.doc_id = d.doc_id)
+          AND (d.status IS DISTINCT FROM 'ingest')
+          AND COALESCE(d.status, '') NOT IN ('filtered', 'duplicate', 'metadata')
+        "#,
+        feed
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(StatusChanges { embedded: embedded as u64, chunked: chunked as u64, ingest: ingest as u64 })
+}
+
+// `model` scopes "embedded" to chunks having an embedding for that model tag (None = any model)
+pub async fn fix_statuses(pool: &PgPool, feed: Option<i32>, model: Option<&str>) -> Result<StatusChanges> {
+    let mut changes = StatusChanges::default();
 
-pub async fn fix_statuses(pool: &PgPool, feed: Option<i32>) -> Result<()> {
     // embedded
     let res = match feed {
         None => sqlx::query!(
@@ -12,11 +81,12 @@ pub async fn fix_statuses(pool: &PgPool, feed: Option<i32>) -> Result<()> {
             WHERE EXISTS (SELECT 1 FROM rag.chunk c WHERE c.doc_id = d.doc_id)
               AND NOT EXISTS (
                 SELECT 1 FROM rag.chunk c
-                LEFT JOIN rag.embedding e ON e.chunk_id = c.chunk_id
+                LEFT JOIN rag.embedding e ON e.chunk_id = c.chunk_id AND ($1::text IS NULL OR e.model = $1)
                 WHERE c.doc_id = d.doc_id AND e.chunk_id IS NULL
               )
               AND (d.status IS DISTINCT FROM 'embedded')
-            "#
+            "#,
+            model
         )
         .execute(pool)
         .await?,
@@ -27,18 +97,18 @@ pub async fn fix_statuses(pool: &PgPool, feed: Option<i32>) -> Result<()> {
               AND EXISTS (SELECT 1 FROM rag.chunk c WHERE c.doc_id = d.doc_id)
               AND NOT EXISTS (
                 SELECT 1 FROM rag.chunk c
-                LEFT JOIN rag.embedding e ON e.chunk_id = c.chunk_id
+                LEFT JOIN rag.embedding e ON e.chunk_id = c.chunk_id AND ($2::text IS NULL OR e.model = $2)
                 WHERE c.doc_id = d.doc_id AND e.chunk_id IS NULL
               )
               AND (d.status IS DISTINCT FROM 'embedded')
             "#,
-            fid
+            fid,
+            model
         )
         .execute(pool)
         .await?,
     };
-    let log = telemetry::gc();
-    log.info(format!("✅ Set status=embedded on {} doc(s)", res.rows_affected()));
+    changes.embedded = res.rows_affected();
 
     // chunked
     let res = match feed {
@@ -48,11 +118,12 @@ pub async fn fix_statuses(pool: &PgPool, feed: Option<i32>) -> Result<()> {
             WHERE EXISTS (SELECT 1 FROM rag.chunk c WHERE c.doc_id = d.doc_id)
               AND EXISTS (
                 SELECT 1 FROM rag.chunk c
-                LEFT JOIN rag.embedding e ON e.chunk_id = c.chunk_id
+                LEFT JOIN rag.embedding e ON e.chunk_id = c.chunk_id AND ($1::text IS NULL OR e.model = $1)
                 WHERE c.doc_id = d.doc_id AND e.chunk_id IS NULL
               )
               AND (d.status IS DISTINCT FROM 'chunked')
-            "#
+            "#,
+            model
         )
         .execute(pool)
         .await?,
@@ -63,18 +134,18 @@ pub async fn fix_statuses(pool: &PgPool, feed: Option<i32>) -> Result<()> {
               AND EXISTS (SELECT 1 FROM rag.chunk c WHERE c.doc_id = d.doc_id)
               AND EXISTS (
                 SELECT 1 FROM rag.chunk c
-                LEFT JOIN rag.embedding e ON e.chunk_id = c.chunk_id
+                LEFT JOIN rag.embedding e ON e.chunk_id = c.chunk_id AND ($2::text IS NULL OR e.model = $2)
                 WHERE c.doc_id = d.doc_id AND e.chunk_id IS NULL
               )
               AND (d.status IS DISTINCT FROM 'chunked')
             "#,
-            fid
+            fid,
+            model
         )
         .execute(pool)
         .await?,
     };
-    let log = telemetry::gc();
-    log.info(format!("✅ Set status=chunked on {} doc(s)", res.rows_affected()));
+    changes.chunked = res.rows_affected();
 
     // ingest
     let res = match feed {
@@ -83,6 +154,7 @@ pub async fn fix_statuses(pool: &PgPool, feed: Option<i32>) -> Result<()> {
             UPDATE rag.document d SET status='ingest'
             WHERE NOT EXISTS (SELECT 1 FROM rag.chunk c WHERE c.doc_id = d.doc_id)
               AND (d.status IS DISTINCT FROM 'ingest')
+              AND COALESCE(d.status, '') NOT IN ('filtered', 'duplicate', 'metadata')
             "#
         )
         .execute(pool)
@@ -93,14 +165,14 @@ pub async fn fix_statuses(pool: &PgPool, feed: Option<i32>) -> Result<()> {
             WHERE d.feed_id = $1
               AND NOT EXISTS (SELECT 1 FROM rag.chunk c WHERE c.doc_id = d.doc_id)
               AND (d.status IS DISTINCT FROM 'ingest')
+              AND COALESCE(d.status, '') NOT IN ('filtered', 'duplicate', 'metadata')
             "#,
             fid
         )
         .execute(pool)
         .await?,
     };
-    let log = telemetry::gc();
-    log.info(format!("✅ Set status=ingest on {} doc(s)", res.rows_affected()));
+    changes.ingest = res.rows_affected();
 
-    Ok(())
+    Ok(changes)
 }
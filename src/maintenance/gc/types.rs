@@ -0,0 +1,45 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use super::GcCategory;
+
+// Plan-mode sample rows per cleanup category
+#[derive(Serialize)]
+pub struct GcDocSample { pub doc_id: i64, pub url: String, pub status: Option<String>, pub fetched_at: Option<DateTime<Utc>> }
+
+#[derive(Serialize)]
+pub struct GcChunkSample { pub chunk_id: i64, pub doc_id: Option<i64>, pub token_count: Option<i32> }
+
+#[derive(Serialize)]
+pub struct GcEmbeddingSample { pub chunk_id: i64, pub model: String, pub created_at: Option<DateTime<Utc>> }
+
+#[derive(Serialize, Default)]
+pub struct GcSamples {
+    pub orphan_chunks: Vec<GcChunkSample>,
+    pub orphan_embeddings: Vec<GcEmbeddingSample>,
+    pub error_docs: Vec<GcDocSample>,
+    pub never_chunked_docs: Vec<GcDocSample>,
+    pub bad_chunks: Vec<GcChunkSample>,
+}
+
+#[derive(Serialize)]
+pub struct GcCounts { pub orphan_chunks: i64, pub orphan_embeddings: i64, pub error_docs: i64, pub never_chunked_docs: i64, pub bad_chunks: i64, pub stale_compose_answers: i64, pub unembedded_chunks: i64 }
+
+#[derive(Serialize)]
+pub struct GcPlan {
+    pub mode: String,
+    pub no_delete: bool,
+    pub categories: Vec<GcCategory>,
+    pub feed: Option<i32>,
+    pub cutoff: Option<DateTime<Utc>>,
+    pub max: i64,
+    pub vacuum: String,
+    pub fix_status: bool,
+    pub model: Option<String>,
+    pub drop_temp_indexes: bool,
+    pub counts: GcCounts,
+    pub samples: GcSamples,
+}
+
+#[derive(Serialize)]
+pub struct GcResult { pub categories: Vec<GcCategory>, pub counts_before: GcCounts, pub deletes_suppressed: bool, pub fix_status: bool, pub model: Option<String>, pub drop_temp_indexes: bool, pub vacuum: String, pub cancelled: bool }
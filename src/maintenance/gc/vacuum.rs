@@ -42,3 +42,14 @@ pub async fn vacuum_full(pool: &PgPool) -> Result<()> {
     log.info("🧽 Vacuumed (FULL) rag.document, rag.chunk, rag.embedding");
     Ok(())
 }
+
+pub async fn reindex_embedding(pool: &PgPool) -> Result<()> {
+    // non-blocking alternative to VACUUM FULL for ANN index bloat
+    crate::maintenance::reindex::reindex_in_place(pool).await?;
+    sqlx::query("ANALYZE rag.embedding")
+        .execute(pool)
+        .await?;
+    let log = telemetry::gc();
+    log.info("🧽 Reindexed (CONCURRENTLY) rag.embedding_vec_ivf_idx and analyzed rag.embedding");
+    Ok(())
+}
@@ -1,2 +1,4 @@
 pub mod gc;
 pub mod reindex;
+pub mod purge;
+pub mod fix_status;
@@ -0,0 +1,109 @@
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool};
+
+use crate::util::sql::paged_loop_conn;
+
+pub struct FeedRow { pub url: String, pub name: Option<String> }
+
+#[derive(Default)]
+pub struct FeedCounts { pub documents: i64, pub chunks: i64, pub embeddings: i64 }
+
+pub async fn get_feed(pool: &PgPool, feed_id: i32) -> Result<Option<FeedRow>> {
+    let row = sqlx::query_as!(FeedRow, "SELECT url, name FROM rag.feed WHERE feed_id = $1", feed_id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row)
+}
+
+pub async fn count_descendants(pool: &PgPool, feed_id: i32) -> Result<FeedCounts> {
+    let r = sqlx::query!(
+        r#"
+        SELECT
+          (SELECT COUNT(*) FROM rag.document d WHERE d.feed_id = $1)::bigint AS "documents!",
+          (SELECT COUNT(*) FROM rag.chunk c JOIN rag.document d ON d.doc_id = c.doc_id WHERE d.feed_id = $1)::bigint AS "chunks!",
+          (SELECT COUNT(*) FROM rag.embedding e JOIN rag.chunk c ON c.chunk_id = e.chunk_id
+             JOIN rag.document d ON d.doc_id = c.doc_id WHERE d.feed_id = $1)::bigint AS "embeddings!"
+        "#,
+        feed_id
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(FeedCounts { documents: r.documents, chunks: r.chunks, embeddings: r.embeddings })
+}
+
+// FK-safe order: embeddings → chunks → documents → feed, each in LIMIT-sized batches
+pub async fn delete_feed_tree(conn: &mut PgConnection, feed_id: i32, batch: i64, mut on_batch: impl FnMut(&'static str, u64)) -> Result<FeedCounts> {
+    let mut counts = FeedCounts::default();
+
+    paged_loop_conn(
+        conn,
+        |limit| {
+            sqlx::query(
+                r#"
+                DELETE FROM rag.embedding e
+                WHERE e.chunk_id IN (
+                    SELECT e2.chunk_id
+                    FROM rag.embedding e2
+                    JOIN rag.chunk c ON c.chunk_id = e2.chunk_id
+                    JOIN rag.document d ON d.doc_id = c.doc_id
+                    WHERE d.feed_id = $1
+                    LIMIT $2
+                )
+                "#,
+            )
+            .bind(feed_id)
+            .bind(limit)
+        },
+        batch,
+        |n| { counts.embeddings += n as i64; on_batch("embeddings", n); },
+    )
+    .await?;
+
+    paged_loop_conn(
+        conn,
+        |limit| {
+            sqlx::query(
+                r#"
+                DELETE FROM rag.chunk c
+                WHERE c.chunk_id IN (
+                    SELECT c2.chunk_id
+                    FROM rag.chunk c2
+                    JOIN rag.document d ON d.doc_id = c2.doc_id
+                    WHERE d.feed_id = $1
+                    LIMIT $2
+                )
+                "#,
+            )
+            .bind(feed_id)
+            .bind(limit)
+        },
+        batch,
+        |n| { counts.chunks += n as i64; on_batch("chunks", n); },
+    )
+    .await?;
+
+    paged_loop_conn(
+        conn,
+        |limit| {
+            sqlx::query(
+                r#"
+                DELETE FROM rag.document d
+                WHERE d.doc_id IN (
+                    SELECT d2.doc_id FROM rag.document d2 WHERE d2.feed_id = $1 LIMIT $2
+                )
+                "#,
+            )
+            .bind(feed_id)
+            .bind(limit)
+        },
+        batch,
+        |n| { counts.documents += n as i64; on_batch("documents", n); },
+    )
+    .await?;
+
+    sqlx::query!("DELETE FROM rag.feed WHERE feed_id = $1", feed_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(counts)
+}
@@ -0,0 +1,93 @@
+use anyhow::{bail, Result};
+use clap::Args;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::io::{BufRead, IsTerminal, Write};
+
+use crate::telemetry::{self};
+use crate::telemetry::ops::purge::Phase as PurgePhase;
+
+mod db;
+
+/// rag purge-feed <id>: delete a feed with all its documents, chunks and embeddings
+#[derive(Args, Debug)]
+pub struct PurgeFeedCmd {
+    pub feed_id: i32,
+    /// Rows per DELETE batch
+    #[arg(long, default_value_t = 1000)] pub batch: i64,
+    #[arg(long, default_value_t = false)] pub apply: bool,
+    /// Skip the interactive confirmation (required when stdin is not a terminal)
+    #[arg(long, default_value_t = false)] pub yes: bool,
+}
+
+#[derive(Serialize)]
+struct PurgeCounts { documents: i64, chunks: i64, embeddings: i64 }
+
+impl From<db::FeedCounts> for PurgeCounts {
+    fn from(c: db::FeedCounts) -> Self { Self { documents: c.documents, chunks: c.chunks, embeddings: c.embeddings } }
+}
+
+pub async fn run(pool: &PgPool, args: PurgeFeedCmd) -> Result<()> {
+    let log = telemetry::purge();
+    let _g = log.root_span_kv([
+        ("feed_id", args.feed_id.to_string()),
+        ("batch", args.batch.to_string()),
+        ("apply", args.apply.to_string()),
+        ("yes", args.yes.to_string()),
+    ]).entered();
+
+    let Some(feed) = db::get_feed(pool, args.feed_id).await? else {
+        bail!("feed_id={} not found", args.feed_id);
+    };
+    let counts = { let _s = log.span(&PurgePhase::Count).entered(); db::count_descendants(pool, args.feed_id).await? };
+
+    if !args.apply {
+        let _sp = log.span(&PurgePhase::Plan).entered();
+        // Always log plan summary
+        log.info(format!(
+            "📝 Purge plan — feed_id={} url={} name={:?} documents={} chunks={} embeddings={}",
+            args.feed_id, feed.url, feed.name, counts.documents, counts.chunks, counts.embeddings
+        ));
+        log.info("   Use --apply to execute.");
+        // Emit structured plan to stdout
+        #[derive(Serialize)]
+        struct PurgePlan { feed_id: i32, url: String, name: Option<String>, batch: i64, counts: PurgeCounts }
+        let plan = PurgePlan { feed_id: args.feed_id, url: feed.url, name: feed.name, batch: args.batch, counts: counts.into() };
+        log.plan(&plan)?;
+        return Ok(());
+    }
+
+    if !args.yes {
+        let _sc = log.span(&PurgePhase::Confirm).entered();
+        if !std::io::stdin().is_terminal() { bail!("refusing to purge without confirmation; pass --yes when stdin is not a terminal"); }
+        eprint!(
+            "Purge feed {} ({}) with {} documents, {} chunks, {} embeddings? [y/N] ",
+            args.feed_id, feed.url, counts.documents, counts.chunks, counts.embeddings
+        );
+        std::io::stderr().flush()?;
+        let mut answer = String::new();
+        std::io::stdin().lock().read_line(&mut answer)?;
+        if !matches!(answer.trim(), "y" | "Y" | "yes") {
+            log.info("ℹ️  Purge aborted");
+            return Ok(());
+        }
+    }
+
+    let _sd = log.span(&PurgePhase::Delete).entered();
+    let mut tx = pool.begin().await?;
+    let deleted = db::delete_feed_tree(&mut *tx, args.feed_id, args.batch.max(1), |table, n| {
+        log.info(format!("  🗑️ Deleted {} {}", n, table));
+    }).await?;
+    tx.commit().await?;
+    drop(_sd);
+
+    log.info(format!(
+        "✅ Purged feed_id={} — documents={} chunks={} embeddings={}",
+        args.feed_id, deleted.documents, deleted.chunks, deleted.embeddings
+    ));
+    #[derive(Serialize)]
+    struct PurgeResult { feed_id: i32, url: String, deleted: PurgeCounts }
+    let res = PurgeResult { feed_id: args.feed_id, url: feed.url, deleted: deleted.into() };
+    log.result(&res)?;
+    Ok(())
+}
@@ -1,6 +1,10 @@
 use anyhow::Result;
 use sqlx::{Executor, PgPool, Postgres};
 
+use crate::util::index::VectorType;
+
+use super::IndexSpec;
+
 pub async fn embedding_count(pool: &PgPool) -> Result<i64> {
     let n = sqlx::query!("SELECT COUNT(*)::bigint AS n FROM rag.embedding")
         .fetch_one(pool)
@@ -10,22 +14,6 @@ pub async fn embedding_count(pool: &PgPool) -> Result<i64> {
     Ok(n)
 }
 
-pub async fn index_lists(pool: &PgPool, name: &str) -> Result<Option<i32>> {
-    let row = sqlx::query!(
-        r#"
-        SELECT substring(pg_get_indexdef(i.indexrelid) from 'lists = ([0-9]+)') AS lists
-        FROM pg_index i
-        JOIN pg_class c ON c.oid = i.indexrelid
-        JOIN pg_namespace nsp ON nsp.oid = c.relnamespace
-        WHERE nsp.nspname = 'rag' AND c.relname = $1
-        "#,
-        name
-    )
-    .fetch_optional(pool)
-    .await?;
-    Ok(row.and_then(|r| r.lists).and_then(|s| s.parse::<i32>().ok()))
-}
-
 pub async fn index_exists(pool: &PgPool, name: &str) -> Result<bool> {
     let row = sqlx::query!(
         r#"
@@ -53,18 +41,37 @@ where
     Ok(())
 }
 
-pub async fn create_new_index_ex<'e, E>(ex: E, lists: i32) -> Result<()>
+pub async fn create_new_index_ex<'e, E>(ex: E, spec: &IndexSpec, vector_type: VectorType) -> Result<()>
 where
     E: Executor<'e, Database = Postgres>,
 {
     let sql = format!(
-        "CREATE INDEX CONCURRENTLY IF NOT EXISTS embedding_vec_ivf_idx_new ON embedding USING ivfflat (vec vector_cosine_ops) WITH (lists = {})",
-        lists
+        "CREATE INDEX CONCURRENTLY IF NOT EXISTS embedding_vec_ivf_idx_new ON embedding {}",
+        spec.using_clause(vector_type)
     );
     sqlx::query(&sql).execute(ex).await?;
     Ok(())
 }
 
+// Plain (non-CONCURRENTLY) variants for use inside a transaction
+pub async fn create_index_in_tx<'e, E>(ex: E, name: &str, spec: &IndexSpec, vector_type: VectorType) -> Result<()>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let sql = format!("CREATE INDEX {} ON embedding {}", name, spec.using_clause(vector_type));
+    sqlx::query(&sql).execute(ex).await?;
+    Ok(())
+}
+
+pub async fn drop_index_in_tx<'e, E>(ex: E, name: &str) -> Result<()>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let sql = format!("DROP INDEX IF EXISTS {}", name);
+    sqlx::query(&sql).execute(ex).await?;
+    Ok(())
+}
+
 pub async fn drop_index_ex<'e, E>(ex: E, name: &str) -> Result<()>
 where
     E: Executor<'e, Database = Postgres>,
@@ -74,6 +81,21 @@ where
     Ok(())
 }
 
+// Rewrites the whole table under an exclusive lock; the ANN index must be dropped first
+// since its operator class only fits the old type.
+pub async fn alter_vec_type_ex<'e, E>(ex: E, vector_type: VectorType, dim: Option<i32>) -> Result<()>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let ty = match dim {
+        Some(n) => format!("{}({})", vector_type.sql_type(), n),
+        None => vector_type.sql_type().to_string(),
+    };
+    let sql = format!("ALTER TABLE embedding ALTER COLUMN vec TYPE {} USING vec::{}", ty, ty);
+    sqlx::query(&sql).execute(ex).await?;
+    Ok(())
+}
+
 pub async fn rename_index_ex<'e, E>(ex: E, old: &str, new: &str) -> Result<()>
 where
     E: Executor<'e, Database = Postgres>,
@@ -4,3 +4,6 @@ pub fn heuristic_lists(n: i64) -> i32 {
     k.clamp(50, 8192)
 }
 
+// pgvector's own HNSW build defaults
+pub const HNSW_DEFAULT_M: i32 = 16;
+pub const HNSW_DEFAULT_EF_CONSTRUCTION: i32 = 64;
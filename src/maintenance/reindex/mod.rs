@@ -3,61 +3,189 @@ use clap::Args;
 use serde::Serialize;
 use sqlx::PgPool;
 
+use crate::output::types::Outcome;
 use crate::telemetry::{self};
 use crate::telemetry::ops::reindex::Phase as ReindexPhase;
+use crate::util::cancel;
+use crate::pipeline::embed::db::vec_column_dim;
+use crate::util::index::{describe_index, vec_column_type, IndexDef, IndexType, VectorType, EMBEDDING_INDEX};
 
 mod heuristics;
 mod db;
 
 #[derive(Args, Debug)]
 pub struct ReindexCmd {
+    /// Index access method; defaults to the type of the existing index
+    #[arg(long, value_enum)] pub index_type: Option<IndexType>,
     #[arg(long)] pub lists: Option<i32>,
+    /// HNSW: max connections per layer
+    #[arg(long)] pub m: Option<i32>,
+    /// HNSW: candidate list size while building
+    #[arg(long)] pub ef_construction: Option<i32>,
+    /// Convert rag.embedding.vec to float (vector) or half (halfvec) and rebuild the index with the matching opclass
+    #[arg(long, value_enum)] pub vector_type: Option<VectorType>,
     #[arg(long, default_value_t = false)] pub apply: bool,
 }
 
+/// Library-facing reindex parameters; `ReindexCmd` maps onto these one-to-one.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReindexParams {
+    pub index_type: Option<IndexType>,
+    pub lists: Option<i32>,
+    pub m: Option<i32>,
+    pub ef_construction: Option<i32>,
+    pub vector_type: Option<VectorType>,
+    pub apply: bool,
+}
+
+impl From<ReindexCmd> for ReindexParams {
+    fn from(c: ReindexCmd) -> Self {
+        Self { index_type: c.index_type, lists: c.lists, m: c.m, ef_construction: c.ef_construction, vector_type: c.vector_type, apply: c.apply }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IndexSpec {
+    Ivfflat { lists: i32 },
+    Hnsw { m: i32, ef_construction: i32 },
+}
+
+impl IndexSpec {
+    fn kind(&self) -> IndexType {
+        match self { IndexSpec::Ivfflat { .. } => IndexType::Ivfflat, IndexSpec::Hnsw { .. } => IndexType::Hnsw }
+    }
+
+    // parsed spec of an existing index; None when its options can't be determined
+    fn from_def(def: &IndexDef) -> Option<Self> {
+        match def.kind? {
+            IndexType::Ivfflat => def.lists.map(|lists| IndexSpec::Ivfflat { lists }),
+            IndexType::Hnsw => Some(IndexSpec::Hnsw {
+                m: def.m.unwrap_or(heuristics::HNSW_DEFAULT_M),
+                ef_construction: def.ef_construction.unwrap_or(heuristics::HNSW_DEFAULT_EF_CONSTRUCTION),
+            }),
+        }
+    }
+
+    pub fn using_clause(&self, vector_type: VectorType) -> String {
+        let ops = vector_type.cosine_ops();
+        match self {
+            IndexSpec::Ivfflat { lists } => format!("USING ivfflat (vec {}) WITH (lists = {})", ops, lists),
+            IndexSpec::Hnsw { m, ef_construction } => format!(
+                "USING hnsw (vec {}) WITH (m = {}, ef_construction = {})",
+                ops, m, ef_construction
+            ),
+        }
+    }
+
+    fn lists(&self) -> Option<i32> { match self { IndexSpec::Ivfflat { lists } => Some(*lists), _ => None } }
+    fn m(&self) -> Option<i32> { match self { IndexSpec::Hnsw { m, .. } => Some(*m), _ => None } }
+    fn ef_construction(&self) -> Option<i32> { match self { IndexSpec::Hnsw { ef_construction, .. } => Some(*ef_construction), _ => None } }
+}
+
+#[derive(Serialize)]
+pub struct MissingIndexPlan { pub rows: i64, pub index: &'static str, pub message: &'static str }
+
+#[derive(Serialize)]
+pub struct ReindexPlan {
+    pub rows: i64,
+    pub current_index_type: Option<&'static str>,
+    pub index_type: &'static str,
+    pub current_lists: Option<i32>,
+    pub desired_lists: Option<i32>,
+    pub m: Option<i32>,
+    pub ef_construction: Option<i32>,
+    pub current_vector_type: VectorType,
+    pub vector_type: VectorType,
+    pub action: String,
+    pub analyze: bool,
+}
+
+// either shape is emitted as-is in the plan envelope
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum ReindexPlanned { IndexMissing(MissingIndexPlan), Ready(ReindexPlan) }
+
+#[derive(Serialize)]
+pub struct ReindexResult {
+    pub action: String,
+    pub analyzed: bool,
+    pub index_type: &'static str,
+    pub desired_lists: Option<i32>,
+    pub current_lists: Option<i32>,
+    pub m: Option<i32>,
+    pub ef_construction: Option<i32>,
+    pub vector_type: VectorType,
+}
+
+pub type ReindexOutcome = Outcome<ReindexPlanned, ReindexResult>;
+
 pub async fn run(pool: &PgPool, args: ReindexCmd) -> Result<()> {
+    let outcome = execute(pool, args.into()).await?;
+    telemetry::reindex().outcome(&outcome)
+}
+
+pub async fn execute(pool: &PgPool, args: ReindexParams) -> Result<ReindexOutcome> {
     let log = telemetry::reindex();
     let _g = log.root_span_kv([
+        ("index_type", format!("{:?}", args.index_type)),
         ("lists", format!("{:?}", args.lists)),
+        ("m", format!("{:?}", args.m)),
+        ("ef_construction", format!("{:?}", args.ef_construction)),
+        ("vector_type", format!("{:?}", args.vector_type)),
         ("apply", args.apply.to_string()),
     ]).entered();
 
     // count embeddings to drive heuristic
     let n = db::embedding_count(pool).await?;
 
-    // discover index existence and current lists from index definition
-    let index_exists = db::index_exists(pool, "embedding_vec_ivf_idx").await?;
-    let current_lists = db::index_lists(pool, "embedding_vec_ivf_idx").await?;
+    // discover index existence and current type/options from index definition
+    let index_exists = db::index_exists(pool, EMBEDDING_INDEX).await?;
+    let current = describe_index(pool, EMBEDDING_INDEX).await?.unwrap_or_default();
+    let current_lists = current.lists;
+    let current_type = current.kind;
+    let current_vector_type = vec_column_type(pool).await?.unwrap_or_default();
+    let vector_type = args.vector_type.unwrap_or(current_vector_type);
 
-    // if base index is missing, do not create it here — migrations own schema
-    if !index_exists {
+    // if base index is missing, do not create it here — migrations own schema — unless
+    // --vector-type asks for a column type, which also tells us the opclass to rebuild it with
+    if !index_exists && args.vector_type.is_none() {
         if !args.apply {
             let _sp = log.span(&ReindexPhase::Plan).entered();
             // Always log human message
-            log.info("❌ Index rag.embedding_vec_ivf_idx not found. Run `just migrate` to create it.");
-            // Emit structured plan to stdout
-            #[derive(Serialize)]
-            struct MissingPlan { rows: i64, index: &'static str, message: &'static str }
-            let plan = MissingPlan {
+            log.info("❌ Index rag.embedding_vec_ivf_idx not found. Run `just migrate` to create it, or pass --vector-type to rebuild it.");
+            let plan = MissingIndexPlan {
                 rows: n as i64,
                 index: "rag.embedding_vec_ivf_idx",
-                message: "Index missing. Run migrations (just migrate) to create it.",
+                message: "Index missing. Run migrations (just migrate) to create it, or pass --vector-type to rebuild it.",
             };
-            log.plan(&plan)?;
-            return Ok(());
+            return Ok(Outcome::Plan(ReindexPlanned::IndexMissing(plan)));
         } else {
-            anyhow::bail!("Index rag.embedding_vec_ivf_idx not found. Run migrations (just migrate) to create it.");
+            anyhow::bail!("Index rag.embedding_vec_ivf_idx not found. Run migrations (just migrate) to create it, or pass --vector-type to rebuild it.");
         }
     }
 
-    // choose desired lists
-    let desired_lists = args.lists.map(|k| k.max(1)).unwrap_or_else(|| heuristics::heuristic_lists(n as i64));
+    // choose desired index type and build options
+    let desired_type = args.index_type.or(current_type).unwrap_or(IndexType::Ivfflat);
+    let desired = match desired_type {
+        IndexType::Ivfflat => IndexSpec::Ivfflat {
+            lists: args.lists.map(|k| k.max(1)).unwrap_or_else(|| heuristics::heuristic_lists(n as i64)),
+        },
+        IndexType::Hnsw => IndexSpec::Hnsw {
+            m: args.m.or(current.m).unwrap_or(heuristics::HNSW_DEFAULT_M).max(2),
+            ef_construction: args.ef_construction.or(current.ef_construction).unwrap_or(heuristics::HNSW_DEFAULT_EF_CONSTRUCTION).max(4),
+        },
+    };
+    let desired_lists = desired.lists();
 
-    // decide action (no Create path; only Reindex or Swap)
-    let action = if let Some(k) = current_lists {
-        if k == desired_lists { Action::Reindex } else { Action::Swap(desired_lists) }
-    } else {
-        Action::Reindex
+    // decide action: Reindex, Swap, Convert when the column type changes, or Create when the
+    // index is missing (only reachable with --vector-type)
+    let action = match IndexSpec::from_def(&current) {
+        _ if vector_type != current_vector_type => Action::Convert(desired),
+        _ if !index_exists => Action::Create(desired),
+        Some(spec) if spec == desired => Action::Reindex,
+        Some(_) => Action::Swap(desired),
+        None if current_type.is_none_or(|k| k == desired.kind()) => Action::Reindex,
+        None => Action::Swap(desired),
     };
 
     // plan-only output
@@ -65,36 +193,74 @@ pub async fn run(pool: &PgPool, args: ReindexCmd) -> Result<()> {
         let _sp = log.span(&ReindexPhase::Plan).entered();
         // Always log plan summary
         log.info(format!(
-            "📝 Reindex plan — rows={} current_lists={:?} desired_lists={} action={:?} analyze=TRUE",
-            n, current_lists, desired_lists, action
+            "📝 Reindex plan — rows={} current_type={:?} current_lists={:?} desired={:?} vector_type={}→{} action={:?} analyze=TRUE",
+            n, current_type, current_lists, desired, current_vector_type.as_str(), vector_type.as_str(), action
         ));
+        match action {
+            Action::Convert(_) => log.info(format!("   Converts rag.embedding.vec to {} in one transaction (table rewrite; queries on rag.embedding wait until it commits)", vector_type.sql_type())),
+            Action::Create(_) => log.info("   Index rag.embedding_vec_ivf_idx is missing and will be rebuilt."),
+            _ => {}
+        }
         log.info("   Use --apply to execute.");
-        // Emit structured plan to stdout
-        #[derive(Serialize)]
-        struct ReindexPlan { rows: i64, current_lists: Option<i32>, desired_lists: i32, action: String, analyze: bool }
-        let action_s = match action { Action::Reindex => "reindex", Action::Swap(_) => "swap" };
-        let plan = ReindexPlan { rows: n as i64, current_lists, desired_lists, action: action_s.to_string(), analyze: true };
-        log.plan(&plan)?;
-        return Ok(());
+        let plan = ReindexPlan {
+            rows: n as i64,
+            current_index_type: current_type.map(|k| k.as_str()),
+            index_type: desired_type.as_str(),
+            current_lists,
+            desired_lists,
+            m: desired.m(),
+            ef_construction: desired.ef_construction(),
+            current_vector_type,
+            vector_type,
+            action: action.as_str().to_string(),
+            analyze: true,
+        };
+        return Ok(Outcome::Plan(ReindexPlanned::Ready(plan)));
+    }
+
+    // the index build is a single statement; only a Ctrl-C that arrived before it can skip it
+    if cancel::is_cancelled() {
+        log.warn("🛑 Cancelled before building the index; nothing changed");
+        return Ok(Outcome::Nothing);
     }
 
     // execute
     match action {
         Action::Reindex => {
             let _s = log.span(&ReindexPhase::Reindex).entered();
-            let mut conn = pool.acquire().await?;
-            db::set_search_path(conn.as_mut()).await?;
-            db::reindex_index_ex(conn.as_mut(), "embedding_vec_ivf_idx").await?;
+            reindex_in_place(pool).await?;
         }
-        Action::Swap(k) => {
+        Action::Swap(spec) => {
             let _s1 = log.span(&ReindexPhase::CreateIndex).entered();
             let mut conn = pool.acquire().await?;
             db::set_search_path(conn.as_mut()).await?;
-            db::create_new_index_ex(conn.as_mut(), k).await?;
+            db::create_new_index_ex(conn.as_mut(), &spec, vector_type).await?;
             drop(_s1);
             let _s2 = log.span(&ReindexPhase::Swap).entered();
-            db::drop_index_ex(conn.as_mut(), "embedding_vec_ivf_idx").await?;
-            db::rename_index_ex(conn.as_mut(), "embedding_vec_ivf_idx_new", "embedding_vec_ivf_idx").await?;
+            db::drop_index_ex(conn.as_mut(), EMBEDDING_INDEX).await?;
+            db::rename_index_ex(conn.as_mut(), "embedding_vec_ivf_idx_new", EMBEDDING_INDEX).await?;
+        }
+        Action::Create(spec) => {
+            let _s = log.span(&ReindexPhase::CreateIndex).entered();
+            let mut conn = pool.acquire().await?;
+            db::set_search_path(conn.as_mut()).await?;
+            db::create_new_index_ex(conn.as_mut(), &spec, vector_type).await?;
+            db::rename_index_ex(conn.as_mut(), "embedding_vec_ivf_idx_new", EMBEDDING_INDEX).await?;
+        }
+        Action::Convert(spec) => {
+            // drop, rewrite and rebuild in one transaction (so no CONCURRENTLY): a failed ALTER or
+            // build rolls back to the old column and index instead of leaving no ANN index
+            let dim = vec_column_dim(pool).await?;
+            let _s1 = log.span(&ReindexPhase::ConvertColumn).entered();
+            let mut tx = pool.begin().await?;
+            db::set_search_path(&mut *tx).await?;
+            db::drop_index_in_tx(&mut *tx, EMBEDDING_INDEX).await?;
+            db::alter_vec_type_ex(&mut *tx, vector_type, dim).await?;
+            drop(_s1);
+            let _s2 = log.span(&ReindexPhase::CreateIndex).entered();
+            db::create_index_in_tx(&mut *tx, EMBEDDING_INDEX, &spec, vector_type).await?;
+            tx.commit().await?;
+            log.info(format!("🔁 Converted rag.embedding.vec to {}", vector_type.sql_type()));
         }
     }
 
@@ -107,12 +273,31 @@ pub async fn run(pool: &PgPool, args: ReindexCmd) -> Result<()> {
     log.info("📊 Analyzed rag.embedding");
     log.info("✅ Reindex completed.");
 
-    #[derive(Serialize)]
-    struct ReindexResult { action: String, analyzed: bool, desired_lists: i32, current_lists: Option<i32> }
-    let action_s = match action { Action::Reindex => "reindex", Action::Swap(_) => "swap" };
-    log.result(&ReindexResult { action: action_s.to_string(), analyzed: true, desired_lists, current_lists })?;
+    Ok(Outcome::Applied(ReindexResult {
+        action: action.as_str().to_string(),
+        analyzed: true,
+        index_type: desired_type.as_str(),
+        desired_lists,
+        current_lists,
+        m: desired.m(),
+        ef_construction: desired.ef_construction(),
+        vector_type,
+    }))
+}
+
+// REINDEX INDEX CONCURRENTLY on the embedding ANN index; also used by `gc --vacuum index-only`.
+pub async fn reindex_in_place(pool: &PgPool) -> Result<()> {
+    let mut conn = pool.acquire().await?;
+    db::set_search_path(conn.as_mut()).await?;
+    db::reindex_index_ex(conn.as_mut(), EMBEDDING_INDEX).await?;
     Ok(())
 }
 
 #[derive(Debug)]
-enum Action { Reindex, Swap(i32) }
+enum Action { Reindex, Swap(IndexSpec), Convert(IndexSpec), Create(IndexSpec) }
+
+impl Action {
+    fn as_str(&self) -> &'static str {
+        match self { Action::Reindex => "reindex", Action::Swap(_) => "swap", Action::Convert(_) => "convert", Action::Create(_) => "create" }
+    }
+}
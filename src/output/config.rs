@@ -4,6 +4,8 @@ use std::env;
 pub enum OutputFormat {
     Text,
     Json,
+    // one compact JSON value per line; result rows can stream (see telemetry::emit::ResultStream)
+    Ndjson,
     Mcp,
 }
 
@@ -17,6 +19,7 @@ impl OutputConfig {
     pub fn from_env() -> Self {
         let format = match env::var("RAG_OUTPUT_FORMAT").ok().as_deref() {
             Some("json") => OutputFormat::Json,
+            Some("ndjson") => OutputFormat::Ndjson,
             Some("mcp") => OutputFormat::Mcp,
             _ => OutputFormat::Text,
         };
@@ -1,5 +1,7 @@
 pub mod config;
 pub mod types;
 pub mod presenter;
+pub mod schema;
+pub mod version;
 
 pub use presenter::{Emitter};
@@ -2,10 +2,12 @@ use std::io::{self, Write};
 use serde_json::json;
 
 use super::config::{OutputConfig, OutputFormat};
-use super::types::Envelope;
+use super::types::{Envelope, Progress, SCHEMA_VERSION};
 
 pub trait Presenter: Send + Sync {
     fn emit(&self, env: &Envelope, w: &mut dyn Write) -> io::Result<()>;
+    // stdout stays plan/result only unless the presenter has a progress channel
+    fn emit_progress(&self, _op: &str, _progress: &Progress, _w: &mut dyn Write) -> io::Result<()> { Ok(()) }
 }
 
 pub struct JsonPresenter { pub pretty: bool }
@@ -16,6 +18,15 @@ impl Presenter for JsonPresenter {
     }
 }
 
+// NDJSON: always compact, one envelope per line (RAG_OUTPUT_PRETTY is ignored)
+pub struct NdjsonPresenter;
+impl Presenter for NdjsonPresenter {
+    fn emit(&self, env: &Envelope, w: &mut dyn Write) -> io::Result<()> {
+        serde_json::to_writer(&mut *w, env).map_err(to_io)?;
+        writeln!(w)
+    }
+}
+
 pub struct TextPresenter { pub pretty: bool }
 impl Presenter for TextPresenter {
     fn emit(&self, env: &Envelope, w: &mut dyn Write) -> io::Result<()> {
@@ -61,6 +72,22 @@ impl Presenter for McpPresenter {
             writeln!(w)
         }
     }
+
+    fn emit_progress(&self, op: &str, progress: &Progress, w: &mut dyn Write) -> io::Result<()> {
+        let payload = json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/progress",
+            "params": {
+                "schema_version": SCHEMA_VERSION,
+                "op": op,
+                "progress": progress.done,
+                "total": progress.total,
+                "unit": progress.unit
+            }
+        });
+        if self.pretty { serde_json::to_writer_pretty(&mut *w, &payload).map_err(to_io)?; } else { serde_json::to_writer(&mut *w, &payload).map_err(to_io)?; }
+        writeln!(w)
+    }
 }
 
 pub struct Emitter {
@@ -71,6 +98,7 @@ impl Emitter {
     pub fn from_env(cfg: OutputConfig) -> Self {
         let presenter: Box<dyn Presenter> = match cfg.format {
             OutputFormat::Json => Box::new(JsonPresenter { pretty: cfg.pretty }),
+            OutputFormat::Ndjson => Box::new(NdjsonPresenter),
             OutputFormat::Mcp => Box::new(McpPresenter { pretty: cfg.pretty }),
             OutputFormat::Text => Box::new(TextPresenter { pretty: cfg.pretty }),
         };
@@ -78,9 +106,21 @@ impl Emitter {
     }
 
     pub fn emit(&self, env: &Envelope) -> io::Result<()> {
-        let mut out = io::stdout();
-        self.presenter.emit(env, &mut out)?;
-        out.flush()
+        self.emit_to(env, &mut io::stdout())
+    }
+
+    pub fn emit_progress(&self, op: &str, progress: &Progress) -> io::Result<()> {
+        self.emit_progress_to(op, progress, &mut io::stdout())
+    }
+
+    pub fn emit_to(&self, env: &Envelope, w: &mut dyn Write) -> io::Result<()> {
+        self.presenter.emit(env, w)?;
+        w.flush()
+    }
+
+    pub fn emit_progress_to(&self, op: &str, progress: &Progress, w: &mut dyn Write) -> io::Result<()> {
+        self.presenter.emit_progress(op, progress, w)?;
+        w.flush()
     }
 }
 
@@ -122,4 +162,19 @@ mod tests {
         assert_eq!(v["params"]["op"], "Query");
         assert!(v["params"]["result"].is_object());
     }
+
+    #[test]
+    fn mcp_progress_emits_notification_and_json_stays_silent() {
+        let progress = Progress { done: 128, total: Some(512), unit: "chunks".into() };
+        let mut buf: Vec<u8> = Vec::new();
+        McpPresenter { pretty: false }.emit_progress("embed", &progress, &mut buf).unwrap();
+        let v: Value = serde_json::from_str(String::from_utf8(buf).unwrap().lines().next().unwrap()).unwrap();
+        assert_eq!(v["method"], "notifications/progress");
+        assert_eq!(v["params"]["progress"], 128);
+        assert_eq!(v["params"]["total"], 512);
+
+        let mut buf: Vec<u8> = Vec::new();
+        JsonPresenter { pretty: false }.emit_progress("embed", &progress, &mut buf).unwrap();
+        assert!(buf.is_empty());
+    }
 }
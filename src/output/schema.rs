@@ -0,0 +1,116 @@
+//! `rag schema`: JSON Schemas for the output envelope and the plan/result payloads,
+//! generated from the same `Serialize` types the commands emit.
+
+use anyhow::Result;
+use clap::Args;
+use schemars::schema::RootSchema;
+use schemars::{schema_for, JsonSchema};
+use serde::Serialize;
+
+use super::types::{Envelope, Progress, RowLine, SCHEMA_VERSION};
+use crate::feed::types::*;
+use crate::ingestion::types::{IngestApply, IngestPlan, RetryPlan, RetryResult};
+use crate::query::post::QueryResultRow;
+use crate::stats::types::*;
+
+#[derive(Args, Debug)]
+pub struct SchemaCmd {
+    /// Only payload schemas for this op (feed, ingest, stats, query)
+    #[arg(long)] op: Option<String>,
+    /// Print just the schema_version string carried by every envelope
+    #[arg(long, default_value_t = false)] version_only: bool,
+}
+
+/// Schema of one payload: what lands in the envelope's `plan` or `result` (or a streamed `row`).
+#[derive(Serialize)]
+pub struct PayloadSchema {
+    pub op: &'static str,
+    pub kind: &'static str,
+    // sub-command or view when an op has more than one payload shape
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub view: Option<&'static str>,
+    pub schema: RootSchema,
+}
+
+#[derive(Serialize)]
+pub struct SchemaDoc {
+    pub schema_version: &'static str,
+    pub envelope: RootSchema,
+    pub row_line: RootSchema,
+    pub progress: RootSchema,
+    pub payloads: Vec<PayloadSchema>,
+}
+
+fn payload<T: JsonSchema>(op: &'static str, kind: &'static str, view: Option<&'static str>) -> PayloadSchema {
+    PayloadSchema { op, kind, view, schema: schema_for!(T) }
+}
+
+// Payloads defined as named types; ops that still build theirs inline are not listed yet.
+pub fn payloads() -> Vec<PayloadSchema> {
+    vec![
+        payload::<FeedAddPlan>("feed", "plan", Some("add")),
+        payload::<FeedAddResult>("feed", "result", Some("add")),
+        payload::<FeedImportPlan>("feed", "plan", Some("import")),
+        payload::<FeedImportResult>("feed", "result", Some("import")),
+        payload::<FeedExportResult>("feed", "result", Some("export")),
+        payload::<FeedList>("feed", "result", Some("ls")),
+        payload::<FeedListWithStats>("feed", "result", Some("ls-with-stats")),
+        payload::<IngestPlan>("ingest", "plan", None),
+        payload::<IngestApply>("ingest", "result", None),
+        payload::<RetryPlan>("ingest", "plan", Some("retry-errors")),
+        payload::<RetryResult>("ingest", "result", Some("retry-errors")),
+        payload::<StatsSummary>("stats", "result", Some("summary")),
+        payload::<StatsSummaryWithDiff>("stats", "result", Some("summary-diff")),
+        payload::<StatsFeedStats>("stats", "result", Some("feed")),
+        payload::<StatsDocSnapshot>("stats", "result", Some("doc")),
+        payload::<StatsChunkSnap>("stats", "result", Some("chunk")),
+        payload::<StatsOrphans>("stats", "result", Some("orphans")),
+        payload::<StatsVectorCheck>("stats", "result", Some("verify-vectors")),
+        payload::<QueryResultRow>("query", "row", None),
+    ]
+}
+
+pub fn document(op: Option<&str>) -> SchemaDoc {
+    SchemaDoc {
+        schema_version: SCHEMA_VERSION,
+        envelope: schema_for!(Envelope),
+        row_line: schema_for!(RowLine),
+        progress: schema_for!(Progress),
+        payloads: payloads().into_iter().filter(|p| op.is_none_or(|o| p.op == o)).collect(),
+    }
+}
+
+// Printed straight to stdout: the schema document is the output, not a result envelope.
+pub fn run(args: SchemaCmd) -> Result<()> {
+    if args.version_only {
+        println!("{}", SCHEMA_VERSION);
+        return Ok(());
+    }
+    let doc = document(args.op.as_deref());
+    if doc.payloads.is_empty() {
+        anyhow::bail!("no payload schemas for op {:?} (known: feed, ingest, stats, query)", args.op.unwrap_or_default());
+    }
+    println!("{}", serde_json::to_string_pretty(&doc)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn op_filter_keeps_only_that_op() {
+        let doc = document(Some("stats"));
+        assert!(!doc.payloads.is_empty());
+        assert!(doc.payloads.iter().all(|p| p.op == "stats"));
+        assert_eq!(document(None).payloads.len(), payloads().len());
+    }
+
+    #[test]
+    fn envelope_schema_names_the_contract_fields() {
+        let env = serde_json::to_value(document(None).envelope).unwrap();
+        for field in ["schema_version", "op", "apply", "plan", "result"] {
+            assert!(env["properties"].get(field).is_some(), "missing {field}");
+        }
+    }
+}
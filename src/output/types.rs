@@ -1,11 +1,12 @@
 use chrono::{DateTime, Utc};
-use serde::Serialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use uuid::Uuid;
 
 pub const SCHEMA_VERSION: &str = "rag.v1";
 
-#[derive(Debug, Clone, Serialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
 pub struct Meta {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub duration_ms: Option<u128>,
@@ -13,7 +14,16 @@ pub struct Meta {
     pub run_id: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+// Periodic progress for long-running ops (embed batches, ingest feeds)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Progress {
+    pub done: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+    pub unit: String,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct Envelope {
     pub schema_version: &'static str,
     pub time: DateTime<Utc>,
@@ -58,6 +68,29 @@ impl Envelope {
     }
 }
 
+/// What a plan-by-default op returns to library callers: the plan (no `apply`), the result of
+/// applying it, or `Nothing` when there was nothing to plan. The CLI emits it with `LogCtx::outcome`.
+#[derive(Debug)]
+pub enum Outcome<P, R> {
+    Plan(P),
+    Applied(R),
+    Nothing,
+}
+
+// NDJSON streaming: one line per result row, emitted before the closing result envelope
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct RowLine {
+    pub schema_version: &'static str,
+    pub op: String,
+    pub row: Value,
+}
+
+impl RowLine {
+    pub fn new<T: Serialize>(op: impl Into<String>, row: &T) -> Result<Self, serde_json::Error> {
+        Ok(RowLine { schema_version: SCHEMA_VERSION, op: op.into(), row: serde_json::to_value(row)? })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -82,4 +115,11 @@ mod tests {
         assert!(s.contains("\"result\""));
         assert!(s.contains("\"apply\":true"));
     }
+
+    #[test]
+    fn serialize_row_line() {
+        let line = RowLine::new("Query", &json!({"rank": 1})).unwrap();
+        let s = serde_json::to_string(&line).unwrap();
+        assert_eq!(s, format!("{{\"schema_version\":\"{}\",\"op\":\"Query\",\"row\":{{\"rank\":1}}}}", SCHEMA_VERSION));
+    }
 }
@@ -0,0 +1,186 @@
+//! `rag version`: crate version, and with `--full` the build and database details asked for in
+//! bug reports (features, ONNX Runtime API, default model, pgvector and ANN index).
+
+use std::time::Duration;
+
+use anyhow::Result;
+use clap::Args;
+use serde::Serialize;
+use sqlx::postgres::{PgPool, PgPoolOptions};
+
+use crate::pipeline::embed::db::vec_column_dim;
+use crate::util::index::{describe_index, vec_column_type, EMBEDDING_INDEX};
+
+const DEFAULT_MODEL_ID: &str = "intfloat/e5-small-v2";
+const FEATURES: &[(&str, bool)] = &[
+    ("cuda", cfg!(feature = "cuda")),
+    ("gpt2-tokenizer", cfg!(feature = "gpt2-tokenizer")),
+    ("pdf", cfg!(feature = "pdf")),
+    ("otel", cfg!(feature = "otel")),
+];
+
+#[derive(Args, Debug)]
+pub struct VersionCmd {
+    /// Also report cargo features, ONNX Runtime, the default model and (if reachable) pgvector/index details
+    #[arg(long, default_value_t = false)] pub full: bool,
+    /// With --full: print the report as JSON instead of `key: value` lines
+    #[arg(long, default_value_t = false, requires = "full")] pub json: bool,
+}
+
+#[derive(Serialize, Default)]
+pub struct DbInfo {
+    pub server_version: Option<String>,
+    pub pgvector: Option<String>,
+    pub vector_type: Option<String>,
+    pub dim: Option<i32>,
+    pub index_type: Option<String>,
+    pub lists: Option<i32>,
+    pub m: Option<i32>,
+    pub ef_construction: Option<i32>,
+}
+
+#[derive(Serialize)]
+pub struct VersionInfo {
+    pub version: &'static str,
+    pub features: Vec<&'static str>,
+    pub onnxruntime_api: String,
+    pub default_model: String,
+    // Err carries why the database could not be inspected (no DSN, connect failure, ...)
+    #[serde(serialize_with = "ser_db")]
+    pub database: std::result::Result<DbInfo, String>,
+}
+
+fn ser_db<S: serde::Serializer>(db: &std::result::Result<DbInfo, String>, s: S) -> std::result::Result<S::Ok, S::Error> {
+    #[derive(Serialize)]
+    struct Unavailable<'a> { unavailable: &'a str }
+    match db {
+        Ok(info) => info.serialize(s),
+        Err(why) => Unavailable { unavailable: why }.serialize(s),
+    }
+}
+
+pub fn enabled_features() -> Vec<&'static str> {
+    FEATURES.iter().filter(|(_, on)| *on).map(|(name, _)| *name).collect()
+}
+
+impl VersionInfo {
+    pub fn lines(&self) -> Vec<String> {
+        fn opt<T: ToString>(v: &Option<T>) -> String {
+            v.as_ref().map(|v| v.to_string()).unwrap_or_else(|| "-".into())
+        }
+        let features = if self.features.is_empty() { "none".to_string() } else { self.features.join(", ") };
+        let mut out = vec![
+            format!("ragfeed: {}", self.version),
+            format!("features: {}", features),
+            format!("onnxruntime api: {}", self.onnxruntime_api),
+            format!("default model: {}", self.default_model),
+        ];
+        match &self.database {
+            Err(why) => out.push(format!("database: unavailable ({})", why)),
+            Ok(db) => {
+                out.push(format!("postgres: {}", opt(&db.server_version)));
+                out.push(format!("pgvector: {}", opt(&db.pgvector)));
+                out.push(format!("embedding column: {} dim={}", opt(&db.vector_type), opt(&db.dim)));
+                let params = match db.index_type.as_deref() {
+                    Some("ivfflat") => format!(" lists={}", opt(&db.lists)),
+                    Some("hnsw") => format!(" m={} ef_construction={}", opt(&db.m), opt(&db.ef_construction)),
+                    _ => String::new(),
+                };
+                out.push(format!("ann index: {}{}", opt(&db.index_type), params));
+            }
+        }
+        out
+    }
+}
+
+async fn inspect_db(dsn: &str) -> Result<DbInfo> {
+    // a short probe: `version` should not hang for RAG_DB_ACQUIRE_TIMEOUT when the DB is down
+    let pool: PgPool = PgPoolOptions::new()
+        .max_connections(1)
+        .acquire_timeout(Duration::from_secs(5))
+        .connect(dsn)
+        .await?;
+    let server_version = sqlx::query_scalar("SELECT current_setting('server_version')").fetch_optional(&pool).await?;
+    let pgvector = sqlx::query_scalar("SELECT extversion::text FROM pg_extension WHERE extname = 'vector'").fetch_optional(&pool).await?;
+    // the rag schema may not be migrated yet; report what the server has and leave the rest empty
+    let migrated: bool = sqlx::query_scalar("SELECT to_regclass('rag.embedding') IS NOT NULL").fetch_one(&pool).await?;
+    let mut info = DbInfo { server_version, pgvector, ..DbInfo::default() };
+    if migrated {
+        info.vector_type = vec_column_type(&pool).await?.map(|t| t.sql_type().to_string());
+        info.dim = vec_column_dim(&pool).await?;
+        if let Some(idx) = describe_index(&pool, EMBEDDING_INDEX).await? {
+            info.index_type = idx.kind.map(|k| k.as_str().to_string());
+            info.lists = idx.lists;
+            info.m = idx.m;
+            info.ef_construction = idx.ef_construction;
+        }
+    }
+    pool.close().await;
+    Ok(info)
+}
+
+// Printed straight to stdout like `rag schema`: the report is meant to be pasted into an issue.
+// `dsn` is Err when no DSN is configured; a DB that can't be reached is reported, not fatal.
+pub async fn run(args: VersionCmd, dsn: Result<String>) -> Result<()> {
+    if !args.full {
+        println!("ragfeed {}", env!("CARGO_PKG_VERSION"));
+        return Ok(());
+    }
+    let database = match dsn {
+        Ok(dsn) => inspect_db(&dsn).await.map_err(|e| format!("{:#}", e)),
+        Err(e) => Err(format!("{:#}", e)),
+    };
+    let info = VersionInfo {
+        version: env!("CARGO_PKG_VERSION"),
+        features: enabled_features(),
+        onnxruntime_api: format!("1.{}", ort::MINOR_VERSION),
+        default_model: std::env::var("RAG_MODEL_ID").unwrap_or_else(|_| DEFAULT_MODEL_ID.to_string()),
+        database,
+    };
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&info)?);
+    } else {
+        for line in info.lines() { println!("{}", line); }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(database: std::result::Result<DbInfo, String>) -> VersionInfo {
+        VersionInfo {
+            version: "0.1.0",
+            features: vec![],
+            onnxruntime_api: "1.22".into(),
+            default_model: DEFAULT_MODEL_ID.into(),
+            database,
+        }
+    }
+
+    #[test]
+    fn unreachable_db_is_reported_not_fatal() {
+        let lines = info(Err("connection refused".into())).lines();
+        assert_eq!(lines[1], "features: none");
+        assert_eq!(lines.last().unwrap(), "database: unavailable (connection refused)");
+        let json = serde_json::to_value(info(Err("no dsn".into()))).unwrap();
+        assert_eq!(json["database"]["unavailable"], "no dsn");
+    }
+
+    #[test]
+    fn index_params_follow_the_index_type() {
+        let db = DbInfo {
+            pgvector: Some("0.7.4".into()),
+            vector_type: Some("halfvec".into()),
+            dim: Some(384),
+            index_type: Some("hnsw".into()),
+            m: Some(16),
+            ef_construction: Some(64),
+            ..DbInfo::default()
+        };
+        let lines = info(Ok(db)).lines();
+        assert!(lines.contains(&"embedding column: halfvec dim=384".to_string()));
+        assert_eq!(lines.last().unwrap(), "ann index: hnsw m=16 ef_construction=64");
+    }
+}
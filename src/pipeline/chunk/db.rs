@@ -1,44 +1,89 @@
 use anyhow::Result;
 use sqlx::PgPool;
 
-pub async fn mark_chunked(pool: &PgPool, doc_id: i64) -> Result<()> {
-    sqlx::query!("UPDATE rag.document SET status='chunked' WHERE doc_id=$1", doc_id)
-        .execute(pool)
-        .await?;
-    Ok(())
-}
+use super::logic::ChunkSync;
 
-pub async fn delete_chunks(pool: &PgPool, doc_id: i64) -> Result<u64> {
-    let res = sqlx::query!("DELETE FROM rag.chunk WHERE doc_id = $1", doc_id)
-        .execute(pool)
-        .await?;
-    Ok(res.rows_affected())
+// char_start/char_end: [start, end) char offsets of the chunk in document.text_clean, when known
+pub struct NewChunk { pub index: i32, pub text: String, pub token_count: i32, pub char_start: Option<i32>, pub char_end: Option<i32> }
+
+// Record the chunked content_hash; status only drops back to 'chunked' when the chunk set
+// changed, so a re-chunk that kept every chunk leaves an 'embedded' doc embedded.
+pub async fn mark_chunked(pool: &PgPool, doc_id: i64, changed: bool) -> Result<()> {
+    if changed {
+        sqlx::query!("UPDATE rag.document SET status='chunked', chunked_hash=content_hash WHERE doc_id=$1", doc_id)
+            .execute(pool)
+            .await?;
+    } else {
+        sqlx::query!("UPDATE rag.document SET chunked_hash=content_hash WHERE doc_id=$1", doc_id)
+            .execute(pool)
+            .await?;
+    }
+    Ok(())
 }
 
-pub async fn insert_chunk(
-    pool: &PgPool,
-    doc_id: i64,
-    chunk_index: i32,
-    text: &str,
-    token_count: i32,
-) -> Result<i64> {
-    let row = sqlx::query!(
-        r#"
-        INSERT INTO rag.chunk (doc_id, chunk_index, text, token_count, md5)
-        VALUES ($1, $2, $3, $4, md5($3))
-        ON CONFLICT (doc_id, chunk_index) DO UPDATE
-          SET text = EXCLUDED.text,
-              token_count = EXCLUDED.token_count,
-              md5 = EXCLUDED.md5
-        RETURNING chunk_id
-        "#,
-        doc_id,
-        chunk_index,
-        text,
-        token_count
+// (chunk_id, md5) of the doc's stored chunks, in chunk order
+pub async fn existing_chunks(pool: &PgPool, doc_id: i64) -> Result<Vec<(i64, String)>> {
+    let rows = sqlx::query!(
+        "SELECT chunk_id, md5 FROM rag.chunk WHERE doc_id = $1 ORDER BY chunk_index",
+        doc_id
     )
-    .fetch_one(pool)
+    .fetch_all(pool)
     .await?;
-    Ok(row.chunk_id)
+    Ok(rows.into_iter().map(|r| (r.chunk_id, r.md5.unwrap_or_default())).collect())
 }
 
+// Apply a ChunkSync in one transaction: drop stale rows, renumber kept ones (via negative
+// indices so UNIQUE(doc_id, chunk_index) never collides) and refresh their offsets, since
+// identical text may sit elsewhere in an edited doc, then insert the new chunks.
+// Returns the chunk_ids of the inserted rows.
+pub async fn apply_chunk_sync(pool: &PgPool, doc_id: i64, sync: &ChunkSync, new: &[NewChunk]) -> Result<Vec<i64>> {
+    let mut tx = pool.begin().await?;
+
+    if !sync.delete.is_empty() {
+        sqlx::query!("DELETE FROM rag.chunk WHERE chunk_id = ANY($1)", &sync.delete)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    if !sync.keep.is_empty() {
+        sqlx::query!("UPDATE rag.chunk SET chunk_index = -chunk_index - 1 WHERE doc_id = $1", doc_id)
+            .execute(&mut *tx)
+            .await?;
+        for (chunk_id, index) in &sync.keep {
+            let c = new.iter().find(|c| c.index == *index);
+            sqlx::query!(
+                "UPDATE rag.chunk SET chunk_index = $2, char_start = $3, char_end = $4 WHERE chunk_id = $1",
+                chunk_id,
+                index,
+                c.and_then(|c| c.char_start),
+                c.and_then(|c| c.char_end)
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
+    let mut inserted = Vec::with_capacity(sync.insert.len());
+    for &pos in &sync.insert {
+        let c = &new[pos];
+        let chunk_id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO rag.chunk (doc_id, chunk_index, text, token_count, md5, char_start, char_end)
+            VALUES ($1, $2, $3, $4, md5($3), $5, $6)
+            RETURNING chunk_id
+            "#,
+            doc_id,
+            c.index,
+            c.text,
+            c.token_count,
+            c.char_start,
+            c.char_end
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        inserted.push(chunk_id);
+    }
+
+    tx.commit().await?;
+    Ok(inserted)
+}
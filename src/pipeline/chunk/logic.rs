@@ -1,23 +1,141 @@
 // Core chunking logic extracted from crate::chunk
 
-pub fn chunk_token_ids<'a>(
-    ids: &'a [u32],
-    target: usize,
-    overlap: usize,
-    max_chunks: usize,
-) -> Vec<&'a [u32]> {
+use std::ops::Range;
+
+// token index ranges of each chunk window over `len` tokens
+pub fn chunk_windows(len: usize, target: usize, overlap: usize, max_chunks: usize) -> Vec<Range<usize>> {
     let target = target.max(1);
     let overlap = overlap.min(target.saturating_sub(1));
 
     let mut out = Vec::new();
     let mut start = 0usize;
 
-    while start < ids.len() && out.len() < max_chunks {
-        let end = (start + target).min(ids.len());
-        out.push(&ids[start..end]);
-        if end == ids.len() { break; }
+    while start < len && out.len() < max_chunks {
+        let end = (start + target).min(len);
+        out.push(start..end);
+        if end == len { break; }
         start = end.saturating_sub(overlap);
     }
     out
 }
 
+pub fn chunk_token_ids<'a, T>(
+    ids: &'a [T],
+    target: usize,
+    overlap: usize,
+    max_chunks: usize,
+) -> Vec<&'a [T]> {
+    chunk_windows(ids.len(), target, overlap, max_chunks).into_iter().map(|r| &ids[r]).collect()
+}
+
+/// [char_start, char_end) of one chunk in the source text: from its first to its last token
+/// that has an offset (prefix and special tokens have none). `map` sends normalized-text char
+/// positions back to source positions; None when no token has an offset.
+pub fn chunk_span(offsets: &[Option<(usize, usize)>], map: &[usize]) -> Option<(usize, usize)> {
+    let start = offsets.iter().flatten().next()?.0;
+    let end = offsets.iter().flatten().last()?.1;
+    if end <= start { return None; }
+    Some((*map.get(start)?, *map.get(end - 1)? + 1))
+}
+
+
+// Tokens after the end of the last of `n_chunks` windows, i.e. what max_chunks cut off
+pub fn dropped_tokens(len: usize, target: usize, overlap: usize, n_chunks: usize) -> usize {
+    if n_chunks == 0 { return len; }
+    let target = target.max(1);
+    let step = target - overlap.min(target - 1);
+    let end = ((n_chunks - 1) * step + target).min(len);
+    len - end
+}
+
+// --overlap-ratio wins over --overlap; result is clamped to target-1 like chunk_token_ids does
+pub fn resolve_overlap(target: usize, overlap: usize, ratio: Option<f32>) -> anyhow::Result<usize> {
+    let overlap = match ratio {
+        Some(r) if !(0.0..1.0).contains(&r) => anyhow::bail!("--overlap-ratio must be in [0, 1), got {}", r),
+        Some(r) => (target as f32 * r).round() as usize,
+        None => overlap,
+    };
+    Ok(overlap.min(target.max(1) - 1))
+}
+
+/// How a doc's stored chunks map onto a fresh cut: `keep` (chunk_id, new index) rows
+/// survive with their embeddings, `insert` are positions in the new list, `delete` are chunk_ids.
+#[derive(Debug, Default, PartialEq)]
+pub struct ChunkSync {
+    pub keep: Vec<(i64, i32)>,
+    pub insert: Vec<usize>,
+    pub delete: Vec<i64>,
+}
+
+// existing: (chunk_id, md5); new: (chunk_index, md5). Each stored chunk is reused at most once.
+pub fn plan_chunk_sync(existing: &[(i64, String)], new: &[(i32, String)]) -> ChunkSync {
+    let mut unused: Vec<Option<&(i64, String)>> = existing.iter().map(Some).collect();
+    let mut sync = ChunkSync::default();
+    for (pos, (index, md5)) in new.iter().enumerate() {
+        let hit = unused.iter_mut().find(|e| e.is_some_and(|(_, m)| m == md5)).and_then(|e| e.take());
+        match hit {
+            Some((chunk_id, _)) => sync.keep.push((*chunk_id, *index)),
+            None => sync.insert.push(pos),
+        }
+    }
+    sync.delete = unused.into_iter().flatten().map(|(id, _)| *id).collect();
+    sync
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn s(v: &str) -> String { v.to_string() }
+
+    #[test]
+    fn dropped_tokens_matches_chunk_windows() {
+        let ids: Vec<u32> = (0..100).collect();
+        let slices = chunk_token_ids(&ids, 30, 10, 3);
+        assert_eq!(slices.last().unwrap().last(), Some(&69));
+        assert_eq!(dropped_tokens(ids.len(), 30, 10, slices.len()), 30);
+        let all = chunk_token_ids(&ids, 30, 10, usize::MAX);
+        assert_eq!(dropped_tokens(ids.len(), 30, 10, all.len()), 0);
+    }
+
+    #[test]
+    fn chunk_span_maps_token_offsets_back_to_source() {
+        // [CLS] passage : | hello world | [SEP], over normalized text "hello world"
+        let offsets = vec![None, None, None, Some((0, 5)), Some((6, 11)), None];
+        let identity: Vec<usize> = (0..11).collect();
+        assert_eq!(chunk_span(&offsets[..4], &identity), Some((0, 5)));
+        assert_eq!(chunk_span(&offsets[3..], &identity), Some((0, 11)));
+        // source had a double space after "hello"
+        let shifted: Vec<usize> = (0..6).chain(7..12).collect();
+        assert_eq!(chunk_span(&offsets[4..], &shifted), Some((7, 12)));
+        assert_eq!(chunk_span(&offsets[..3], &identity), None);
+    }
+
+    #[test]
+    fn overlap_ratio_scales_with_target() {
+        assert_eq!(resolve_overlap(350, 80, None).unwrap(), 80);
+        assert_eq!(resolve_overlap(350, 80, Some(0.2)).unwrap(), 70);
+        assert_eq!(resolve_overlap(10, 80, None).unwrap(), 9);
+        assert!(resolve_overlap(350, 80, Some(1.5)).is_err());
+    }
+
+    #[test]
+    fn identical_chunks_are_kept_even_when_shifted() {
+        let existing = vec![(10, s("a")), (11, s("b")), (12, s("c"))];
+        let new = vec![(0, s("x")), (1, s("a")), (2, s("b"))];
+        let sync = plan_chunk_sync(&existing, &new);
+        assert_eq!(sync.keep, vec![(10, 1), (11, 2)]);
+        assert_eq!(sync.insert, vec![0]);
+        assert_eq!(sync.delete, vec![12]);
+    }
+
+    #[test]
+    fn duplicate_texts_reuse_each_row_once() {
+        let existing = vec![(1, s("a"))];
+        let new = vec![(0, s("a")), (1, s("a"))];
+        let sync = plan_chunk_sync(&existing, &new);
+        assert_eq!(sync.keep, vec![(1, 0)]);
+        assert_eq!(sync.insert, vec![1]);
+        assert!(sync.delete.is_empty());
+    }
+}
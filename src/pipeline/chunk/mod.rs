@@ -7,13 +7,16 @@ use clap::Args;
 use serde::Serialize;
 use sqlx::PgPool;
 
+use crate::output::types::Outcome;
 use crate::telemetry::{self};
 use crate::telemetry::ops::chunk::Phase as ChunkPhase;
-use crate::tokenizer::E5Tokenizer;
+use crate::tokenizer::{ChunkTokenizer, E5Tokenizer, TokenizerKind};
+use crate::util::exit;
+use crate::util::text::{normalize_mapped, Normalize};
 use crate::util::time::parse_since_opt;
 
 use self::select::select_docs;
-use self::logic::chunk_token_ids;
+use self::logic::{chunk_span, chunk_windows, dropped_tokens, plan_chunk_sync, resolve_overlap};
 
 #[derive(Args)]
 pub struct ChunkCmd {
@@ -21,28 +24,226 @@ pub struct ChunkCmd {
     #[arg(long)] doc_id: Option<i64>,
     #[arg(long, default_value_t = 350)] tokens_target: usize,
     #[arg(long, default_value_t = 80)]  overlap: usize,
+    /// Overlap as a fraction of --tokens-target (e.g. 0.2), instead of --overlap
+    #[arg(long, conflicts_with = "overlap")] overlap_ratio: Option<f32>,
     #[arg(long, default_value_t = 24)]  max_chunks_per_doc: usize,
+    /// Chunk whole documents, ignoring --max-chunks-per-doc
+    #[arg(long, default_value_t = false, conflicts_with = "max_chunks_per_doc")] no_cap: bool,
+    /// Re-chunk already chunked docs whose content changed since they were last chunked
     #[arg(long, default_value_t = false)] force: bool,
+    /// Re-chunk every eligible doc, changed or not (e.g. after changing --tokens-target)
+    #[arg(long, default_value_t = false)] force_all: bool,
     #[arg(long, default_value_t = false)] apply: bool,
     #[arg(long, default_value_t = 10)] plan_limit: usize,
+    #[arg(long, value_enum, default_value_t = TokenizerKind::E5)] tokenizer: TokenizerKind,
+    /// Normalize text_clean before tokenizing (chunk text and md5 fingerprints follow it)
+    #[arg(long, value_enum, default_value_t = Normalize::None)] normalize: Normalize,
+    // GPT-2 vocab/merges source (HF Hub repo), only with --features gpt2-tokenizer
+    #[cfg(feature = "gpt2-tokenizer")]
+    #[arg(long, default_value = "openai-community/gpt2")] gpt2_repo: String,
 }
 
+/// Library-facing chunk parameters; `ChunkCmd` maps onto these one-to-one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkParams {
+    pub since: Option<String>,
+    pub doc_id: Option<i64>,
+    pub tokens_target: usize,
+    pub overlap: usize,
+    pub overlap_ratio: Option<f32>,
+    pub max_chunks_per_doc: usize,
+    pub no_cap: bool,
+    pub force: bool,
+    pub force_all: bool,
+    pub apply: bool,
+    pub plan_limit: usize,
+    pub tokenizer: TokenizerKind,
+    pub normalize: Normalize,
+    #[cfg(feature = "gpt2-tokenizer")]
+    pub gpt2_repo: String,
+}
+
+impl Default for ChunkParams {
+    fn default() -> Self {
+        Self {
+            since: None,
+            doc_id: None,
+            tokens_target: 350,
+            overlap: 80,
+            overlap_ratio: None,
+            max_chunks_per_doc: 24,
+            no_cap: false,
+            force: false,
+            force_all: false,
+            apply: false,
+            plan_limit: 10,
+            tokenizer: TokenizerKind::E5,
+            normalize: Normalize::None,
+            #[cfg(feature = "gpt2-tokenizer")]
+            gpt2_repo: "openai-community/gpt2".to_string(),
+        }
+    }
+}
+
+impl From<ChunkCmd> for ChunkParams {
+    fn from(c: ChunkCmd) -> Self {
+        Self {
+            since: c.since,
+            doc_id: c.doc_id,
+            tokens_target: c.tokens_target,
+            overlap: c.overlap,
+            overlap_ratio: c.overlap_ratio,
+            max_chunks_per_doc: c.max_chunks_per_doc,
+            no_cap: c.no_cap,
+            force: c.force,
+            force_all: c.force_all,
+            apply: c.apply,
+            plan_limit: c.plan_limit,
+            tokenizer: c.tokenizer,
+            normalize: c.normalize,
+            #[cfg(feature = "gpt2-tokenizer")]
+            gpt2_repo: c.gpt2_repo,
+        }
+    }
+}
+
+/// Chunking knobs resolved from the CLI flags (overlap already clamped, cap already applied).
+pub(crate) struct ChunkSettings {
+    pub tokens_target: usize,
+    pub overlap: usize,
+    pub max_chunks: usize,
+    pub normalize: Normalize,
+}
+
+/// What re-chunking one doc changed; `new_chunk_ids` are the inserted rows that need embeddings
+/// (kept chunks retain theirs).
+pub(crate) struct DocChunks {
+    pub inserted: usize,
+    pub kept: usize,
+    pub deleted: usize,
+    pub dropped_tokens: usize,
+    pub new_chunk_ids: Vec<i64>,
+}
+
+/// Tokenize, cut and sync one doc's chunks. A doc whose text normalizes or tokenizes to nothing
+/// still has its stale chunks deleted and its hash recorded, so it is not reselected next run.
+pub(crate) async fn chunk_doc(
+    pool: &PgPool,
+    tok: &mut dyn ChunkTokenizer,
+    doc_id: i64,
+    text: &str,
+    s: &ChunkSettings,
+) -> Result<DocChunks> {
+    let log = telemetry::chunk();
+    // char_map sends positions in the normalized text back to text_clean (None under nfkc)
+    let (text, char_map) = normalize_mapped(text, s.normalize);
+
+    let (ids, offsets) = if text.trim().is_empty() {
+        (Vec::new(), Vec::new())
+    } else {
+        let _sp = log.span(&ChunkPhase::Tokenize).entered();
+        tok.encode_passage_offsets(&text)
+            .with_context(|| format!("tokenize doc_id={}", doc_id))?
+    };
+
+    let mut new_chunks: Vec<db::NewChunk> = Vec::new();
+    let mut dropped = 0;
+    if !ids.is_empty() {
+        let windows = chunk_windows(ids.len(), s.tokens_target, s.overlap, s.max_chunks);
+        dropped = dropped_tokens(ids.len(), s.tokens_target, s.overlap, windows.len());
+        if dropped > 0 {
+            log.warn_kv(
+                &format!("⚠️  doc_id={} hit --max-chunks-per-doc={}: {} of {} tokens not chunked (use --no-cap)", doc_id, s.max_chunks, dropped, ids.len()),
+                [("doc_id", doc_id.to_string()), ("dropped_tokens", dropped.to_string())],
+            );
+        }
+
+        for (i, window) in windows.into_iter().enumerate() {
+            let id_slice = &ids[window.clone()];
+            let chunk_text = tok.decode(id_slice)
+                .with_context(|| format!("decode chunk {} for doc_id={}", i, doc_id))?;
+            if chunk_text.trim().is_empty() { continue; }
+            let span = char_map.as_deref().and_then(|map| chunk_span(&offsets[window], map));
+            new_chunks.push(db::NewChunk {
+                index: i as i32,
+                text: chunk_text,
+                token_count: id_slice.len() as i32,
+                char_start: span.map(|(start, _)| start as i32),
+                char_end: span.map(|(_, end)| end as i32),
+            });
+        }
+    }
+
+    // byte-identical chunks (same md5) keep their chunk_id and embedding; with no new chunks
+    // every stored one is deleted
+    let _ic = log.span(&ChunkPhase::InsertChunk).entered();
+    let existing = db::existing_chunks(pool, doc_id).await?;
+    let new_md5s: Vec<(i32, String)> = new_chunks.iter().map(|c| (c.index, format!("{:x}", md5::compute(c.text.as_bytes())))).collect();
+    let sync = plan_chunk_sync(&existing, &new_md5s);
+    let new_chunk_ids = db::apply_chunk_sync(pool, doc_id, &sync, &new_chunks).await?;
+    drop(_ic);
+    let (inserted, kept, deleted) = (sync.insert.len(), sync.keep.len(), sync.delete.len());
+
+    // a doc left without chunks is always marked 'chunked' so it leaves the 'ingest' queue
+    let _us = log.span(&ChunkPhase::UpdateStatus).entered();
+    db::mark_chunked(pool, doc_id, inserted + deleted > 0 || kept == 0).await?;
+    drop(_us);
+
+    log.info(format!("✅ doc_id={} → {} chunk(s) (new={} kept={} deleted={})", doc_id, inserted + kept, inserted, kept, deleted));
+    Ok(DocChunks { inserted, kept, deleted, dropped_tokens: dropped, new_chunk_ids })
+}
+
+fn load_tokenizer(args: &ChunkParams) -> Result<Box<dyn ChunkTokenizer>> {
+    match args.tokenizer {
+        TokenizerKind::E5 => Ok(Box::new(E5Tokenizer::for_chunking().context("init E5 tokenizer")?)),
+        #[cfg(feature = "gpt2-tokenizer")]
+        TokenizerKind::Gpt2 => Ok(Box::new(
+            crate::tokenizer::Gpt2Tokenizer::from_hub(&args.gpt2_repo).context("init GPT-2 tokenizer")?,
+        )),
+    }
+}
+
+#[derive(Serialize)]
+pub struct ChunkPlan { pub docs: usize, pub force: bool, pub force_all: bool, pub tokenizer: TokenizerKind, pub normalize: Normalize, pub tokens_target: usize, pub overlap: usize, pub max_chunks_per_doc: Option<usize>, pub sample_doc_ids: Vec<i64> }
+
+#[derive(Serialize)]
+pub struct DocResult { pub doc_id: i64, pub inserted: usize, pub kept: usize, pub deleted: usize, pub capped: bool, pub dropped_tokens: usize }
+
+#[derive(Serialize)]
+pub struct ChunkResult { pub totals: usize, pub kept: usize, pub capped_docs: usize, pub per_doc: Vec<DocResult> }
+
+pub type ChunkOutcome = Outcome<ChunkPlan, ChunkResult>;
+
 pub async fn run(pool: &PgPool, args: ChunkCmd) -> Result<()> {
+    let outcome = execute(pool, args.into()).await?;
+    if matches!(outcome, Outcome::Nothing) { exit::mark_empty(); }
+    telemetry::chunk().outcome(&outcome)
+}
+
+pub async fn execute(pool: &PgPool, args: ChunkParams) -> Result<ChunkOutcome> {
     let log = telemetry::chunk();
     let _g = log.root_span_kv([
         ("since", format!("{:?}", args.since)),
         ("doc_id", format!("{:?}", args.doc_id)),
         ("tokens_target", args.tokens_target.to_string()),
         ("overlap", args.overlap.to_string()),
+        ("overlap_ratio", format!("{:?}", args.overlap_ratio)),
         ("max_chunks_per_doc", args.max_chunks_per_doc.to_string()),
+        ("no_cap", args.no_cap.to_string()),
         ("force", args.force.to_string()),
+        ("force_all", args.force_all.to_string()),
         ("apply", args.apply.to_string()),
         ("plan_limit", args.plan_limit.to_string()),
+        ("tokenizer", args.tokenizer.as_str().to_string()),
+        ("normalize", args.normalize.as_str().to_string()),
     ]).entered();
 
+    let overlap = resolve_overlap(args.tokens_target, args.overlap, args.overlap_ratio)?;
+    let max_chunks = if args.no_cap { usize::MAX } else { args.max_chunks_per_doc };
+
     let _s = log.span(&ChunkPhase::SelectDocs).entered();
     let since_ts = parse_since_opt(&args.since)?;
-    let docs = select_docs(pool, args.doc_id, since_ts, args.force).await?;
+    let docs = select_docs(pool, args.doc_id, None, since_ts, args.force, args.force_all).await?;
     drop(_s);
     if docs.is_empty() {
         log.info(format!(
@@ -50,97 +251,56 @@ pub async fn run(pool: &PgPool, args: ChunkCmd) -> Result<()> {
             if args.doc_id.is_some() { ", --doc-id" } else { "" },
             if args.since.is_some() { ", --since" } else { "" }
         ));
-        return Ok(());
+        return Ok(Outcome::Nothing);
     }
 
     if !args.apply {
         let _sp = log.span(&ChunkPhase::Plan).entered();
         // Always log plan summary
         log.info(format!(
-            "📝 Chunk plan — docs={} force={} tokens_target={} overlap={} max_chunks_per_doc={}",
-            docs.len(), args.force, args.tokens_target, args.overlap, args.max_chunks_per_doc
+            "📝 Chunk plan — docs={} force={} force_all={} tokenizer={} normalize={} tokens_target={} overlap={} max_chunks_per_doc={}",
+            docs.len(), args.force, args.force_all, args.tokenizer.as_str(), args.normalize.as_str(), args.tokens_target, overlap,
+            if args.no_cap { "none".to_string() } else { args.max_chunks_per_doc.to_string() }
         ));
         for (doc_id, _text_clean) in docs.iter().take(args.plan_limit) {
             log.info(format!("  doc_id={}", doc_id));
         }
         if docs.len() > args.plan_limit { log.info(format!("  ... ({} more)", docs.len() - args.plan_limit)); }
         log.info("   Use --apply to execute.");
-        // Emit structured plan to stdout
-        #[derive(Serialize)]
-        struct ChunkPlan { docs: usize, force: bool, tokens_target: usize, overlap: usize, max_chunks_per_doc: usize, sample_doc_ids: Vec<i64> }
         let sample_doc_ids: Vec<i64> = docs.iter().take(args.plan_limit).map(|(id, _)| *id).collect();
         let plan = ChunkPlan {
             docs: docs.len(),
             force: args.force,
+            force_all: args.force_all,
+            tokenizer: args.tokenizer,
+            normalize: args.normalize,
             tokens_target: args.tokens_target,
-            overlap: args.overlap,
-            max_chunks_per_doc: args.max_chunks_per_doc,
+            overlap,
+            max_chunks_per_doc: (!args.no_cap).then_some(args.max_chunks_per_doc),
             sample_doc_ids,
         };
-        log.plan(&plan)?;
-        return Ok(());
+        return Ok(Outcome::Plan(plan));
     }
 
-    let tok: E5Tokenizer = E5Tokenizer::new()
-        .context("init E5 tokenizer")?;
+    let mut tok = load_tokenizer(&args)?;
+    if let Some(budget) = tok.max_passage_tokens().filter(|b| args.tokens_target > *b) {
+        log.warn(format!(
+            "⚠️  --tokens-target={} exceeds the {} passage budget of {} tokens; embeddings will drop each chunk's tail — use --tokens-target <= {}",
+            args.tokens_target, args.tokenizer.as_str(), budget, budget
+        ));
+    }
 
-    #[derive(Serialize)]
-    struct DocResult { doc_id: i64, inserted: usize }
     let mut per_doc: Vec<DocResult> = Vec::new();
+    let settings = ChunkSettings { tokens_target: args.tokens_target, overlap, max_chunks, normalize: args.normalize };
 
     for (doc_id, text_clean) in docs {
         let Some(text) = text_clean.as_deref() else { continue; };
-        if text.trim().is_empty() { continue; }
-
-        let _sp = log.span(&ChunkPhase::Tokenize).entered();
-        let ids: Vec<u32> = tok
-            .ids_passage(text)
-            .with_context(|| format!("tokenize doc_id={}", doc_id))?;
-        drop(_sp);
-
-        if ids.is_empty() {
-            let _us = log.span(&ChunkPhase::UpdateStatus).entered();
-            db::mark_chunked(pool, doc_id).await?;
-            drop(_us);
-            log.info(format!("✅ doc_id={} → 0 chunks (no tokens)", doc_id));
-            per_doc.push(DocResult { doc_id, inserted: 0 });
-            continue;
-        }
-
-        let slices = chunk_token_ids(&ids, args.tokens_target, args.overlap, args.max_chunks_per_doc);
-
-        let _ic = log.span(&ChunkPhase::InsertChunk).entered();
-        db::delete_chunks(pool, doc_id).await?;
-
-        let mut inserted = 0usize;
-        for (i, id_slice) in slices.into_iter().enumerate() {
-            let chunk_text = tok.decode_ids(id_slice)
-                .with_context(|| format!("decode chunk {} for doc_id={}", i, doc_id))?;
-            if chunk_text.trim().is_empty() { continue; }
-
-            let token_count = id_slice.len() as i32;
-
-            let _ = db::insert_chunk(pool, doc_id, i as i32, &chunk_text, token_count).await?;
-
-            inserted += 1;
-        }
-        drop(_ic);
-
-        if inserted > 0 {
-            let _us = log.span(&ChunkPhase::UpdateStatus).entered();
-            db::mark_chunked(pool, doc_id).await?;
-            drop(_us);
-        }
-
-        log.info(format!("✅ doc_id={} → {} chunk(s)", doc_id, inserted));
-        per_doc.push(DocResult { doc_id, inserted });
+        let d = chunk_doc(pool, tok.as_mut(), doc_id, text, &settings).await?;
+        per_doc.push(DocResult { doc_id, inserted: d.inserted, kept: d.kept, deleted: d.deleted, capped: d.dropped_tokens > 0, dropped_tokens: d.dropped_tokens });
     }
 
-    #[derive(Serialize)]
-    struct ChunkResult { totals: usize, per_doc: Vec<DocResult> }
     let totals = per_doc.iter().map(|d| d.inserted).sum();
-    let res = ChunkResult { totals, per_doc };
-    let log = telemetry::chunk();
-    log.result(&res)?;
-    Ok(())
+    let kept = per_doc.iter().map(|d| d.kept).sum();
+    let capped_docs = per_doc.iter().filter(|d| d.capped).count();
+    Ok(Outcome::Applied(ChunkResult { totals, kept, capped_docs, per_doc }))
 }
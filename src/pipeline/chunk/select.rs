@@ -4,26 +4,35 @@ use sqlx::{PgPool, Row};
 
 // Select candidate documents to chunk based on optional filters.
 // Mirrors the previous logic in crate::chunk::select_docs.
+// `force` re-chunks only docs whose content_hash moved since their last chunking;
+// `force_all` re-chunks every eligible doc. `feed_id` scopes the scan to one feed.
 pub async fn select_docs(
     pool: &PgPool,
     doc_id: Option<i64>,
+    feed_id: Option<i32>,
     since: Option<DateTime<Utc>>,
     force: bool,
+    force_all: bool,
 ) -> Result<Vec<(i64, Option<String>)>> {
     let rows = sqlx::query(
         r#"
         SELECT doc_id, text_clean
         FROM rag.document
-        WHERE ($3::bool OR status = 'ingest')
+        WHERE (status = 'ingest'
+               OR ($3::bool AND COALESCE(status, '') NOT IN ('filtered', 'duplicate', 'metadata')
+                   AND ($4::bool OR chunked_hash IS DISTINCT FROM content_hash)))
           AND ($1::bigint      IS NULL OR doc_id = $1)
           AND ($2::timestamptz IS NULL OR fetched_at >= $2)
+          AND ($5::int         IS NULL OR feed_id = $5)
         ORDER BY doc_id DESC
         LIMIT 1000
         "#,
     )
     .bind(doc_id)
     .bind(since)
-    .bind(force)
+    .bind(force || force_all)
+    .bind(force_all)
+    .bind(feed_id)
     .fetch_all(pool)
     .await?;
 
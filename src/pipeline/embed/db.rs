@@ -1,17 +1,36 @@
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use pgvector::Vector as PgVector;
 use sqlx::PgPool;
 
-pub async fn fetch_chunks(pool: &PgPool, model_tag: &str, force: bool, limit: i64) -> Result<Vec<(i64, String)>> {
+use crate::util::index::VectorType;
+use crate::util::quantize::bit_string;
+
+/// Which chunks embed may pick up: those of one feed and/or of docs fetched since a time.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ChunkScope {
+    pub feed: Option<i32>,
+    pub since: Option<DateTime<Utc>>,
+}
+
+// Keyset-paged candidates: chunk_id > after, so batches that failed to encode are not refetched
+pub async fn fetch_chunks(pool: &PgPool, model_tag: &str, force: bool, scope: ChunkScope, after: i64, limit: i64) -> Result<Vec<(i64, String)>> {
     if force {
         let rows = sqlx::query!(
             r#"
             SELECT c.chunk_id, c.text
             FROM rag.chunk c
+            JOIN rag.document d ON d.doc_id = c.doc_id
+            WHERE c.chunk_id > $1
+              AND ($3::int4 IS NULL OR d.feed_id = $3)
+              AND ($4::timestamptz IS NULL OR d.fetched_at >= $4)
             ORDER BY c.chunk_id
-            LIMIT $1
+            LIMIT $2
             "#,
-            limit
+            after,
+            limit,
+            scope.feed,
+            scope.since
         )
         .fetch_all(pool)
         .await?;
@@ -22,98 +41,131 @@ pub async fn fetch_chunks(pool: &PgPool, model_tag: &str, force: bool, limit: i6
         r#"
         SELECT c.chunk_id, c.text
         FROM rag.chunk c
+        JOIN rag.document d ON d.doc_id = c.doc_id
         LEFT JOIN rag.embedding e
           ON e.chunk_id = c.chunk_id AND e.model = $1
         WHERE e.chunk_id IS NULL
+          AND c.chunk_id > $2
+          AND ($4::int4 IS NULL OR d.feed_id = $4)
+          AND ($5::timestamptz IS NULL OR d.fetched_at >= $5)
         ORDER BY c.chunk_id
-        LIMIT $2
+        LIMIT $3
         "#,
         model_tag,
-        limit
+        after,
+        limit,
+        scope.feed,
+        scope.since
     )
     .fetch_all(pool)
     .await?;
     Ok(rows.into_iter().map(|r| (r.chunk_id, r.text)).collect())
 }
 
-pub async fn fetch_all_chunks(pool: &PgPool, limit: Option<i64>) -> Result<Vec<(i64, String)>> {
-    if let Some(limit) = limit {
-        let rows = sqlx::query!(
-            r#"
-            SELECT c.chunk_id, c.text
-            FROM rag.chunk c
-            ORDER BY c.chunk_id
-            LIMIT $1
-            "#,
-            limit
-        )
-        .fetch_all(pool)
-        .await?;
-        return Ok(rows.into_iter().map(|r| (r.chunk_id, r.text)).collect());
-    }
+// LIMIT NULL means no limit
+pub async fn fetch_all_chunks(pool: &PgPool, scope: ChunkScope, limit: Option<i64>) -> Result<Vec<(i64, String)>> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT c.chunk_id, c.text
+        FROM rag.chunk c
+        JOIN rag.document d ON d.doc_id = c.doc_id
+        WHERE ($2::int4 IS NULL OR d.feed_id = $2)
+          AND ($3::timestamptz IS NULL OR d.fetched_at >= $3)
+        ORDER BY c.chunk_id
+        LIMIT $1
+        "#,
+        limit,
+        scope.feed,
+        scope.since
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(|r| (r.chunk_id, r.text)).collect())
+}
 
+// Texts for an explicit id list (e.g. chunks a re-chunk just inserted), in chunk_id order
+pub async fn fetch_chunks_by_ids(pool: &PgPool, chunk_ids: &[i64]) -> Result<Vec<(i64, String)>> {
     let rows = sqlx::query!(
         r#"
         SELECT c.chunk_id, c.text
         FROM rag.chunk c
+        WHERE c.chunk_id = ANY($1)
         ORDER BY c.chunk_id
-        "#
+        "#,
+        chunk_ids
     )
     .fetch_all(pool)
     .await?;
     Ok(rows.into_iter().map(|r| (r.chunk_id, r.text)).collect())
 }
 
-pub async fn count_candidates(pool: &PgPool, model_tag: &str, force: bool) -> Result<i64> {
-    let n = if force {
-        sqlx::query_scalar!(r#"SELECT COUNT(*)::bigint FROM rag.chunk"#)
-            .fetch_one(pool)
-            .await?
-    } else {
-        sqlx::query_scalar!(
-            r#"
-            SELECT COUNT(*)::bigint
-            FROM rag.chunk c
-            LEFT JOIN rag.embedding e
-              ON e.chunk_id = c.chunk_id AND e.model = $1
-            WHERE e.chunk_id IS NULL
-            "#,
-            model_tag
-        )
-        .fetch_one(pool)
-        .await?
-    };
+// force counts every in-scope chunk, otherwise only those without a vector for this model
+pub async fn count_candidates(pool: &PgPool, model_tag: &str, force: bool, scope: ChunkScope) -> Result<i64> {
+    let n = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*)::bigint
+        FROM rag.chunk c
+        JOIN rag.document d ON d.doc_id = c.doc_id
+        LEFT JOIN rag.embedding e
+          ON e.chunk_id = c.chunk_id AND e.model = $1
+        WHERE ($2::bool OR e.chunk_id IS NULL)
+          AND ($3::int4 IS NULL OR d.feed_id = $3)
+          AND ($4::timestamptz IS NULL OR d.fetched_at >= $4)
+        "#,
+        model_tag,
+        force,
+        scope.feed,
+        scope.since
+    )
+    .fetch_one(pool)
+    .await?;
     Ok(n.unwrap_or(0))
 }
 
-pub async fn list_candidate_chunk_ids(pool: &PgPool, model_tag: &str, force: bool, limit: i64) -> Result<Vec<i64>> {
-    if limit <= 0 { return Ok(vec![]); }
-    if force {
-        let rows = sqlx::query!(
-            r#"
-            SELECT c.chunk_id
-            FROM rag.chunk c
-            ORDER BY c.chunk_id
-            LIMIT $1
-            "#,
-            limit
-        )
-        .fetch_all(pool)
-        .await?;
-        return Ok(rows.into_iter().map(|r| r.chunk_id).collect());
-    }
+/// Candidates whose stored token_count exceeds `budget`: (count, longest token_count).
+pub async fn count_over_budget(pool: &PgPool, model_tag: &str, force: bool, scope: ChunkScope, budget: i32) -> Result<(i64, Option<i32>)> {
+    let row = sqlx::query!(
+        r#"
+        SELECT COUNT(*)::bigint AS n, MAX(c.token_count) AS longest
+        FROM rag.chunk c
+        JOIN rag.document d ON d.doc_id = c.doc_id
+        LEFT JOIN rag.embedding e
+          ON e.chunk_id = c.chunk_id AND e.model = $1
+        WHERE ($2::bool OR e.chunk_id IS NULL)
+          AND ($3::int4 IS NULL OR d.feed_id = $3)
+          AND ($4::timestamptz IS NULL OR d.fetched_at >= $4)
+          AND c.token_count > $5
+        "#,
+        model_tag,
+        force,
+        scope.feed,
+        scope.since,
+        budget
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok((row.n.unwrap_or(0), row.longest))
+}
 
+pub async fn list_candidate_chunk_ids(pool: &PgPool, model_tag: &str, force: bool, scope: ChunkScope, limit: i64) -> Result<Vec<i64>> {
+    if limit <= 0 { return Ok(vec![]); }
     let rows = sqlx::query!(
         r#"
         SELECT c.chunk_id
         FROM rag.chunk c
+        JOIN rag.document d ON d.doc_id = c.doc_id
         LEFT JOIN rag.embedding e
           ON e.chunk_id = c.chunk_id AND e.model = $1
-        WHERE e.chunk_id IS NULL
+        WHERE ($2::bool OR e.chunk_id IS NULL)
+          AND ($3::int4 IS NULL OR d.feed_id = $3)
+          AND ($4::timestamptz IS NULL OR d.fetched_at >= $4)
         ORDER BY c.chunk_id
-        LIMIT $2
+        LIMIT $5
         "#,
         model_tag,
+        force,
+        scope.feed,
+        scope.since,
         limit
     )
     .fetch_all(pool)
@@ -121,23 +173,165 @@ pub async fn list_candidate_chunk_ids(pool: &PgPool, model_tag: &str, force: boo
     Ok(rows.into_iter().map(|r| r.chunk_id).collect())
 }
 
-pub async fn insert_embedding(pool: &PgPool, chunk_id: i64, model_tag: &str, dim: i32, vec: Vec<f32>) -> Result<()> {
-    sqlx::query(
+/// Progress marker of an unfinished paged embed run (`rag.embed_cursor`).
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct EmbedCursor {
+    pub scope: String,
+    pub last_chunk_id: i64,
+    pub done: i64,
+    pub total: i64,
+}
+
+pub async fn load_cursor(pool: &PgPool, model_tag: &str) -> Result<Option<EmbedCursor>> {
+    let row = sqlx::query!(
+        r#"SELECT scope, last_chunk_id, done, total FROM rag.embed_cursor WHERE model = $1"#,
+        model_tag
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(|r| EmbedCursor { scope: r.scope, last_chunk_id: r.last_chunk_id, done: r.done, total: r.total }))
+}
+
+pub async fn save_cursor(pool: &PgPool, model_tag: &str, cursor: &EmbedCursor) -> Result<()> {
+    sqlx::query!(
         r#"
-        INSERT INTO rag.embedding (chunk_id, model, dim, vec)
-        VALUES ($1, $2, $3, $4)
-        ON CONFLICT (chunk_id) DO UPDATE
-          SET model = EXCLUDED.model,
-              dim   = EXCLUDED.dim,
-              vec   = EXCLUDED.vec
-        "#
+        INSERT INTO rag.embed_cursor (model, scope, last_chunk_id, done, total, updated_at)
+        VALUES ($1, $2, $3, $4, $5, now())
+        ON CONFLICT (model) DO UPDATE
+          SET scope = EXCLUDED.scope,
+              last_chunk_id = EXCLUDED.last_chunk_id,
+              done = EXCLUDED.done,
+              total = EXCLUDED.total,
+              updated_at = now()
+        "#,
+        model_tag,
+        cursor.scope,
+        cursor.last_chunk_id,
+        cursor.done,
+        cursor.total
     )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn clear_cursor(pool: &PgPool, model_tag: &str) -> Result<()> {
+    sqlx::query!(r#"DELETE FROM rag.embed_cursor WHERE model = $1"#, model_tag)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+// Declared N of `rag.embedding.vec vector(N)`; None when the column is unconstrained
+pub async fn vec_column_dim(pool: &PgPool) -> Result<Option<i32>> {
+    let typmod: Option<i32> = sqlx::query_scalar(
+        r#"
+        SELECT atttypmod
+        FROM pg_attribute
+        WHERE attrelid = 'rag.embedding'::regclass
+          AND attname = 'vec'
+          AND NOT attisdropped
+        "#,
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(typmod.filter(|n| *n > 0))
+}
+
+// The vector is bound as float32 and cast to the column's element type (halfvec under --vector-type half);
+// its sign-bit code (query --quantized) is stored alongside
+pub async fn insert_embedding(pool: &PgPool, chunk_id: i64, model_tag: &str, dim: i32, vector_type: VectorType, vec: Vec<f32>) -> Result<()> {
+    let sql = format!(
+        r#"
+        INSERT INTO rag.embedding (chunk_id, model, dim, vec, vec_bits)
+        VALUES ($1, $2, $3, $4::{}, $5::text::varbit)
+        ON CONFLICT (chunk_id) DO UPDATE
+          SET model    = EXCLUDED.model,
+              dim      = EXCLUDED.dim,
+              vec      = EXCLUDED.vec,
+              vec_bits = EXCLUDED.vec_bits
+        "#,
+        vector_type.sql_type()
+    );
+    let bits = bit_string(&vec);
+    sqlx::query(&sql)
     .bind(chunk_id)
     .bind(model_tag)
     .bind(dim)
     .bind(PgVector::from(vec))
+    .bind(bits)
     .execute(pool)
     .await?;
     Ok(())
 }
 
+
+// Recompute the centroid of every doc owning one of these chunks (mean of all its chunk vectors);
+// centroids stay float32 even over a halfvec embedding column
+pub async fn refresh_doc_embeddings(pool: &PgPool, chunk_ids: &[i64]) -> Result<u64> {
+    if chunk_ids.is_empty() { return Ok(0); }
+    let res = sqlx::query(
+        r#"
+        INSERT INTO rag.doc_embedding (doc_id, model, dim, vec, chunk_count, updated_at)
+        SELECT c.doc_id, MAX(e.model), MAX(e.dim), AVG(e.vec)::vector, COUNT(*)::int, now()
+        FROM rag.chunk c
+        JOIN rag.embedding e ON e.chunk_id = c.chunk_id
+        WHERE c.doc_id IN (SELECT doc_id FROM rag.chunk WHERE chunk_id = ANY($1))
+        GROUP BY c.doc_id
+        ON CONFLICT (doc_id) DO UPDATE
+          SET model       = EXCLUDED.model,
+              dim         = EXCLUDED.dim,
+              vec         = EXCLUDED.vec,
+              chunk_count = EXCLUDED.chunk_count,
+              updated_at  = EXCLUDED.updated_at
+        "#
+    )
+    .bind(chunk_ids)
+    .execute(pool)
+    .await?;
+    Ok(res.rows_affected())
+}
+
+// Recompute the centroids of these docs after their chunk set changed, and drop the centroid
+// of any doc left with no embedded chunks so it stops matching --doc-level queries.
+// Returns the number of centroid rows written or removed.
+pub async fn refresh_doc_embeddings_for_docs(pool: &PgPool, doc_ids: &[i64]) -> Result<u64> {
+    if doc_ids.is_empty() { return Ok(0); }
+    let mut tx = pool.begin().await?;
+    let upserted = sqlx::query(
+        r#"
+        INSERT INTO rag.doc_embedding (doc_id, model, dim, vec, chunk_count, updated_at)
+        SELECT c.doc_id, MAX(e.model), MAX(e.dim), AVG(e.vec)::vector, COUNT(*)::int, now()
+        FROM rag.chunk c
+        JOIN rag.embedding e ON e.chunk_id = c.chunk_id
+        WHERE c.doc_id = ANY($1)
+        GROUP BY c.doc_id
+        ON CONFLICT (doc_id) DO UPDATE
+          SET model       = EXCLUDED.model,
+              dim         = EXCLUDED.dim,
+              vec         = EXCLUDED.vec,
+              chunk_count = EXCLUDED.chunk_count,
+              updated_at  = EXCLUDED.updated_at
+        "#
+    )
+    .bind(doc_ids)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    let removed = sqlx::query(
+        r#"
+        DELETE FROM rag.doc_embedding d
+        WHERE d.doc_id = ANY($1)
+          AND NOT EXISTS (
+            SELECT 1 FROM rag.chunk c JOIN rag.embedding e ON e.chunk_id = c.chunk_id
+            WHERE c.doc_id = d.doc_id
+          )
+        "#
+    )
+    .bind(doc_ids)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    tx.commit().await?;
+    Ok(upserted + removed)
+}
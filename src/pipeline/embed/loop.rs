@@ -2,86 +2,213 @@ use anyhow::{bail, Result};
 use sqlx::PgPool;
 
 use crate::encoder::traits::Embedder;
+use crate::util::cancel;
+use crate::util::index::VectorType;
 use crate::telemetry::{self};
 use crate::telemetry::ops::embed::Phase as EmbedPhase;
 
-use super::db;
+use super::db::{self, ChunkScope, EmbedCursor};
+
+// Outcome of an apply run: embedded count plus chunks whose batch failed to encode
+#[derive(Default)]
+pub struct EmbedTotals {
+    pub embedded: i64,
+    pub failed: Vec<i64>,
+    // chunks written this run; their docs get a fresh centroid
+    pub embedded_ids: Vec<i64>,
+    // chunks an interrupted earlier run had already embedded, and the run's overall denominator
+    pub resumed_done: i64,
+    pub total: i64,
+    // stopped early on Ctrl-C
+    pub cancelled: bool,
+}
+
+// Encode one batch, retrying up to `retries` times; Err means the batch is given up on
+fn encode_batch(encoder: &mut dyn Embedder, texts: &[String], retries: usize) -> Result<Vec<Vec<f32>>> {
+    let log = telemetry::embed();
+    let mut attempt = 0;
+    loop {
+        let _enc = log.span(&EmbedPhase::Encode).entered();
+        match encoder.embed_passages(texts) {
+            Ok(v) => return Ok(v),
+            Err(e) if attempt < retries => {
+                attempt += 1;
+                log.warn_kv("⚠️ encode failed, retrying batch", [("attempt", attempt.to_string()), ("error", e.to_string())]);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+// Encode + insert one batch; encoder failures are recorded in `totals.failed` instead of aborting
+async fn embed_batch(
+    pool: &PgPool,
+    encoder: &mut dyn Embedder,
+    model_tag: &str,
+    dim_expect: usize,
+    vector_type: VectorType,
+    retries: usize,
+    rows: &[(i64, String)],
+    totals: &mut EmbedTotals,
+) -> Result<()> {
+    let log = telemetry::embed();
+    let chunk_ids: Vec<i64> = rows.iter().map(|(id, _)| *id).collect();
+    let texts: Vec<String> = rows.iter().map(|(_, t)| t.clone()).collect();
+
+    let embeddings = match encode_batch(encoder, &texts, retries) {
+        Ok(v) => v,
+        Err(e) => {
+            log.warn_kv("❌ batch failed, skipping", [
+                ("first_chunk_id", chunk_ids.first().copied().unwrap_or_default().to_string()),
+                ("chunks", chunk_ids.len().to_string()),
+                ("error", e.to_string()),
+            ]);
+            totals.failed.extend(chunk_ids);
+            return Ok(());
+        }
+    };
+
+    // dimension mismatches are configuration errors, not glitches: still abort
+    let dim = embeddings.get(0).map(|v| v.len()).unwrap_or(0);
+    if dim == 0 { bail!("empty embedding dimension"); }
+    if dim as i32 != dim_expect as i32 { bail!("model produced dim={} but --dim={} (the rag.embedding.vec width) was specified; pick a model with that output size", dim, dim_expect); }
+
+    totals.embedded_ids.extend_from_slice(&chunk_ids);
+    for (chunk_id, vec) in chunk_ids.into_iter().zip(embeddings.into_iter()) {
+        let _ins = log.span(&EmbedPhase::InsertEmbedding).entered();
+        db::insert_embedding(pool, chunk_id, model_tag, dim_expect as i32, vector_type, vec).await?;
+        drop(_ins);
+    }
+    totals.embedded += texts.len() as i64;
+    Ok(())
+}
 
 pub async fn embed_force_once(
     pool: &PgPool,
     encoder: &mut dyn Embedder,
     model_tag: &str,
     dim_expect: usize,
+    vector_type: VectorType,
     batch: usize,
+    scope: ChunkScope,
     max: Option<i64>,
-) -> Result<i64> {
+    retries: usize,
+) -> Result<EmbedTotals> {
     let log = telemetry::embed();
-    let rows = { let _fb = log.span(&EmbedPhase::FetchBatch).entered(); db::fetch_all_chunks(pool, max).await? };
-    if rows.is_empty() { return Ok(0); }
+    let mut totals = EmbedTotals::default();
+    let rows = { let _fb = log.span(&EmbedPhase::FetchBatch).entered(); db::fetch_all_chunks(pool, scope, max).await? };
+    if rows.is_empty() { return Ok(totals); }
+    let expected = rows.len() as u64;
 
-    let mut total = 0i64;
+    let mut done = 0u64;
     for chunk in rows.chunks(batch) {
-        let chunk_ids: Vec<i64> = chunk.iter().map(|(id, _)| *id).collect();
-        let texts: Vec<String> = chunk.iter().map(|(_, t)| t.clone()).collect();
-
-        let _enc = log.span(&EmbedPhase::Encode).entered();
-        let embeddings = encoder.embed_passages(&texts)?;
-        drop(_enc);
-
-        let dim = embeddings.get(0).map(|v| v.len()).unwrap_or(0);
-        if dim == 0 { bail!("empty embedding dimension"); }
-        if dim as i32 != dim_expect as i32 { bail!("model produced dim={} but --dim={} was specified", dim, dim_expect); }
+        if cancel::is_cancelled() { totals.cancelled = true; break; }
+        embed_batch(pool, encoder, model_tag, dim_expect, vector_type, retries, chunk, &mut totals).await?;
+        done += chunk.len() as u64;
+        log.progress(done, Some(expected), "chunks")?;
+    }
+    Ok(totals)
+}
 
-        for (chunk_id, vec) in chunk_ids.into_iter().zip(embeddings.into_iter()) {
-            let _ins = log.span(&EmbedPhase::InsertEmbedding).entered();
-            db::insert_embedding(pool, chunk_id, model_tag, dim_expect as i32, vec).await?;
-            drop(_ins);
-        }
+/// Embed exactly these chunks (no candidate scan), accumulating into `totals`.
+pub async fn embed_chunk_ids(
+    pool: &PgPool,
+    encoder: &mut dyn Embedder,
+    model_tag: &str,
+    dim_expect: usize,
+    vector_type: VectorType,
+    batch: usize,
+    chunk_ids: &[i64],
+    retries: usize,
+    totals: &mut EmbedTotals,
+) -> Result<()> {
+    let log = telemetry::embed();
+    for ids in chunk_ids.chunks(batch.max(1)) {
+        if cancel::is_cancelled() { totals.cancelled = true; break; }
+        let rows = { let _fb = log.span(&EmbedPhase::FetchBatch).entered(); db::fetch_chunks_by_ids(pool, ids).await? };
+        if rows.is_empty() { continue; }
+        embed_batch(pool, encoder, model_tag, dim_expect, vector_type, retries, &rows, totals).await?;
+    }
+    Ok(())
+}
 
-        total += texts.len() as i64;
-        log.info(format!("✅ embedded {} chunk(s) (total={})", texts.len(), total));
+/// (already done, overall total) for a run with `remaining` candidates: an unfinished cursor with
+/// the same scope carries its count forward, anything else starts a fresh denominator.
+pub fn resume_point(cursor: Option<&EmbedCursor>, scope_key: &str, remaining: i64) -> (i64, i64) {
+    match cursor {
+        Some(c) if c.scope == scope_key && c.done < c.total => (c.done, c.done + remaining),
+        _ => (0, remaining),
     }
-    Ok(total)
 }
 
+// The cursor only feeds progress reporting: paging still starts at chunk_id 0 so chunks
+// skipped by a failed batch before the restart are picked up again.
 pub async fn embed_missing_paged(
     pool: &PgPool,
     encoder: &mut dyn Embedder,
     model_tag: &str,
     dim_expect: usize,
+    vector_type: VectorType,
     batch: usize,
+    scope: ChunkScope,
+    scope_key: &str,
     max: Option<i64>,
-) -> Result<i64> {
+    retries: usize,
+) -> Result<EmbedTotals> {
     let log = telemetry::embed();
-    let mut total = 0i64;
+    let candidates = { let _s = log.span(&EmbedPhase::CountCandidates).entered(); db::count_candidates(pool, model_tag, false, scope).await? };
+    let remaining_now = match max { Some(m) => candidates.min(m), None => candidates }.max(0);
+    let cursor = db::load_cursor(pool, model_tag).await?;
+    let (done_before, total) = resume_point(cursor.as_ref(), scope_key, remaining_now);
+    if done_before > 0 {
+        log.info(format!("⏳ Resuming embed: {}/{} done by an earlier run", done_before, total));
+    }
+    let expected = total as u64;
+    let mut totals = EmbedTotals { resumed_done: done_before, total, ..Default::default() };
+    let mut done = done_before as u64;
+    let mut after = 0i64;
+    let mut exhausted = false;
     let mut remaining = max.unwrap_or(i64::MAX);
     loop {
+        if cancel::is_cancelled() { totals.cancelled = true; break; }
         let n = remaining.min(batch as i64) as i64;
         if n <= 0 { break; }
 
-        let rows = { let _fb = log.span(&EmbedPhase::FetchBatch).entered(); db::fetch_chunks(pool, model_tag, false, n).await? };
-        if rows.is_empty() { break; }
+        let rows = { let _fb = log.span(&EmbedPhase::FetchBatch).entered(); db::fetch_chunks(pool, model_tag, false, scope, after, n).await? };
+        if rows.is_empty() { exhausted = true; break; }
+        after = rows.last().map(|(id, _)| *id).unwrap_or(after);
 
-        let chunk_ids: Vec<i64> = rows.iter().map(|(id, _)| *id).collect();
-        let texts: Vec<String> = rows.into_iter().map(|(_, t)| t).collect();
+        embed_batch(pool, encoder, model_tag, dim_expect, vector_type, retries, &rows, &mut totals).await?;
 
-        let _enc = log.span(&EmbedPhase::Encode).entered();
-        let embeddings = encoder.embed_passages(&texts)?;
-        drop(_enc);
+        done += rows.len() as u64;
+        remaining -= n;
+        db::save_cursor(pool, model_tag, &EmbedCursor { scope: scope_key.to_string(), last_chunk_id: after, done: done as i64, total }).await?;
+        log.progress(done, Some(expected), "chunks")?;
+    }
+    // nothing left to embed: the next run starts a fresh denominator
+    if exhausted { db::clear_cursor(pool, model_tag).await?; }
+    Ok(totals)
+}
 
-        let dim = embeddings.get(0).map(|v| v.len()).unwrap_or(0);
-        if dim == 0 { bail!("empty embedding dimension"); }
-        if dim as i32 != dim_expect as i32 { bail!("model produced dim={} but --dim={} was specified", dim, dim_expect); }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        for (chunk_id, vec) in chunk_ids.into_iter().zip(embeddings.into_iter()) {
-            let _ins = log.span(&EmbedPhase::InsertEmbedding).entered();
-            db::insert_embedding(pool, chunk_id, model_tag, dim_expect as i32, vec).await?;
-            drop(_ins);
-        }
+    fn cursor(scope: &str, done: i64, total: i64) -> EmbedCursor {
+        EmbedCursor { scope: scope.to_string(), last_chunk_id: 900, done, total }
+    }
 
-        total += texts.len() as i64;
-        remaining -= n;
-        log.info(format!("✅ embedded {} chunk(s) (total={})", texts.len(), total));
+    #[test]
+    fn unfinished_cursor_with_same_scope_is_resumed() {
+        assert_eq!(resume_point(Some(&cursor("feed=None", 400, 1000)), "feed=None", 600), (400, 1000));
+        // new chunks since the interruption widen the denominator
+        assert_eq!(resume_point(Some(&cursor("feed=None", 400, 1000)), "feed=None", 650), (400, 1050));
+    }
+
+    #[test]
+    fn other_scope_or_finished_cursor_starts_fresh() {
+        assert_eq!(resume_point(None, "feed=None", 600), (0, 600));
+        assert_eq!(resume_point(Some(&cursor("feed=Some(3)", 400, 1000)), "feed=None", 600), (0, 600));
+        assert_eq!(resume_point(Some(&cursor("feed=None", 1000, 1000)), "feed=None", 5), (0, 5));
     }
-    Ok(total)
 }
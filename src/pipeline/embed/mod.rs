@@ -3,91 +3,246 @@ use clap::Args;
 use serde::Serialize;
 use sqlx::PgPool;
 
-use crate::encoder::{Device, E5Encoder};
+use crate::encoder::{Device, E5Encoder, Prefixes};
 use crate::encoder::traits::Embedder;
+use crate::output::types::Outcome;
 use crate::telemetry::{self};
 use crate::telemetry::ops::embed::Phase as EmbedPhase;
+use crate::util::exit;
+use crate::util::index::{vec_column_type, VectorType};
+use crate::util::time::parse_since_opt;
 
-mod db;
-mod r#loop;
+pub(crate) mod db;
+pub(crate) mod r#loop;
 
 #[derive(Args, Debug)]
 pub struct EmbedCmd {
-    #[arg(long, default_value = "intfloat/e5-small-v2")] model_id: String,
+    #[arg(long, env = "RAG_MODEL_ID", default_value = "intfloat/e5-small-v2")] model_id: String,
     #[arg(long)] onnx_filename: Option<String>,
-    #[arg(long, value_enum, default_value_t = Device::Cpu)] device: Device,
+    #[arg(long, env = "RAG_DEVICE", value_enum, default_value_t = Device::Cpu)] device: Device,
+    /// Truncate encoder input at this many tokens (default: the tokenizer's model_max_length, 512 for e5)
+    #[arg(long)] max_seq_len: Option<usize>,
     #[arg(long, default_value_t = 384)] dim: usize,
+    /// Element type of rag.embedding.vec: float (vector) or half (halfvec); must match the column
+    #[arg(long, value_enum, default_value_t = VectorType::Float)] vector_type: VectorType,
     #[arg(long, default_value_t = 128)] batch: usize,
     #[arg(long)] max: Option<i64>,
     #[arg(long, default_value_t = false)] force: bool,
+    /// Only chunks of this feed's docs
+    #[arg(long)] feed: Option<i32>,
+    /// Only chunks of docs fetched since this time (same formats as chunk/query --since)
+    #[arg(long)] since: Option<String>,
+    /// Retries per batch on encoder errors before skipping it (skipped chunks are reported as failed)
+    #[arg(long, default_value_t = 1)] batch_retries: usize,
     #[arg(long, default_value_t = false)] apply: bool,
     #[arg(long, default_value_t = 10)] plan_limit: usize,
 }
 
+/// Library-facing embed parameters; `EmbedCmd` maps onto these one-to-one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmbedParams {
+    pub model_id: String,
+    pub onnx_filename: Option<String>,
+    pub device: Device,
+    pub max_seq_len: Option<usize>,
+    pub dim: usize,
+    pub vector_type: VectorType,
+    pub batch: usize,
+    pub max: Option<i64>,
+    pub force: bool,
+    pub feed: Option<i32>,
+    pub since: Option<String>,
+    pub batch_retries: usize,
+    pub apply: bool,
+    pub plan_limit: usize,
+}
+
+impl Default for EmbedParams {
+    fn default() -> Self {
+        Self {
+            model_id: "intfloat/e5-small-v2".to_string(),
+            onnx_filename: None,
+            device: Device::Cpu,
+            max_seq_len: None,
+            dim: 384,
+            vector_type: VectorType::Float,
+            batch: 128,
+            max: None,
+            force: false,
+            feed: None,
+            since: None,
+            batch_retries: 1,
+            apply: false,
+            plan_limit: 10,
+        }
+    }
+}
+
+impl From<EmbedCmd> for EmbedParams {
+    fn from(c: EmbedCmd) -> Self {
+        Self {
+            model_id: c.model_id,
+            onnx_filename: c.onnx_filename,
+            device: c.device,
+            max_seq_len: c.max_seq_len,
+            dim: c.dim,
+            vector_type: c.vector_type,
+            batch: c.batch,
+            max: c.max,
+            force: c.force,
+            feed: c.feed,
+            since: c.since,
+            batch_retries: c.batch_retries,
+            apply: c.apply,
+            plan_limit: c.plan_limit,
+        }
+    }
+}
+
+/// Fail before loading the model when --dim can't be stored in `rag.embedding.vec`.
+pub(crate) async fn check_dim(pool: &PgPool, dim: usize) -> Result<Option<i32>> {
+    let column_dim = db::vec_column_dim(pool).await?;
+    if let Some(n) = column_dim {
+        if n as usize != dim {
+            return Err(exit::ConfigError(format!(
+                "--dim={} does not match rag.embedding.vec vector({}); use a {}-dim model or migrate the column",
+                dim, n, n
+            )).into());
+        }
+    }
+    Ok(column_dim)
+}
+
+/// Fail before loading the model when --vector-type disagrees with the `rag.embedding.vec` column;
+/// switching types is a column migration (`rag reindex --vector-type`), not an embed option.
+pub(crate) async fn check_vector_type(pool: &PgPool, vector_type: VectorType) -> Result<()> {
+    let column = vec_column_type(pool).await?;
+    if column.is_some_and(|c| c != vector_type) {
+        return Err(exit::ConfigError(format!(
+            "--vector-type={} does not match rag.embedding.vec ({}); run `rag reindex --vector-type {} --apply` to convert the column",
+            vector_type.as_str(), column.map(|c| c.sql_type()).unwrap_or_default(), vector_type.as_str()
+        )).into());
+    }
+    Ok(())
+}
+
+/// Value stored in `rag.embedding.model`, e.g. `intfloat/e5-small-v2@onnx-cpu`.
+pub(crate) fn model_tag(model_id: &str, device: Device) -> String {
+    format!("{}@onnx-{}", model_id, match device { Device::Cpu => "cpu", Device::Cuda => "cuda" })
+}
+
+#[derive(Serialize)]
+pub struct EmbedPlan { pub model: String, pub dim: usize, pub column_dim: Option<i32>, pub vector_type: VectorType, pub batch: usize, pub force: bool, pub feed: Option<i32>, pub since: Option<String>, pub candidates: i64, pub planned: i64, pub resumed_done: i64, pub total: i64, pub sample_chunk_ids: Vec<i64> }
+
+#[derive(Serialize)]
+pub struct EmbedResult { pub total_embedded: i64, pub failed: usize, pub failed_chunk_ids: Vec<i64>, pub doc_centroids: u64, pub resumed_done: i64, pub total: i64, pub over_budget: i64, pub cancelled: bool }
+
+pub type EmbedOutcome = Outcome<EmbedPlan, EmbedResult>;
+
 pub async fn run(pool: &PgPool, args: EmbedCmd) -> Result<()> {
+    let outcome = execute(pool, args.into()).await?;
+    // exit code 3 is CLI policy; the library only reports zero counts
+    let empty = match &outcome {
+        Outcome::Plan(p) => p.planned == 0,
+        Outcome::Applied(r) => r.total_embedded == 0 && r.failed == 0,
+        Outcome::Nothing => true,
+    };
+    if empty { exit::mark_empty(); }
+    telemetry::embed().outcome(&outcome)
+}
+
+pub async fn execute(pool: &PgPool, args: EmbedParams) -> Result<EmbedOutcome> {
     let log = telemetry::embed();
     let _g = log
         .root_span_kv([
             ("model_id", args.model_id.clone()),
             ("onnx_filename", format!("{:?}", args.onnx_filename)),
             ("device", format!("{:?}", args.device)),
+            ("max_seq_len", format!("{:?}", args.max_seq_len)),
             ("dim", args.dim.to_string()),
+            ("vector_type", args.vector_type.as_str().to_string()),
             ("batch", args.batch.to_string()),
             ("max", format!("{:?}", args.max)),
             ("force", args.force.to_string()),
+            ("feed", format!("{:?}", args.feed)),
+            ("since", format!("{:?}", args.since)),
+            ("batch_retries", args.batch_retries.to_string()),
             ("apply", args.apply.to_string()),
             ("plan_limit", args.plan_limit.to_string()),
         ])
         .entered();
 
-    let model_tag = format!(
-        "{}@onnx-{}",
-        args.model_id,
-        match args.device { Device::Cpu => "cpu", Device::Cuda => "cuda" }
-    );
+    let model_tag = model_tag(&args.model_id, args.device);
 
     let batch = args.batch.max(1);
 
+    let column_dim = check_dim(pool, args.dim).await?;
+    check_vector_type(pool, args.vector_type).await?;
+    let scope = db::ChunkScope { feed: args.feed, since: parse_since_opt(&args.since)? };
+    // the raw --since, so a relative window still matches its own cursor after a restart
+    let scope_key = format!("feed={:?} since={:?}", args.feed, args.since);
+
     // Plan-only
     if !args.apply {
         let _sp = log.span(&EmbedPhase::Plan).entered();
-        let total_candidates = { let _s = log.span(&EmbedPhase::CountCandidates).entered(); db::count_candidates(pool, &model_tag, args.force).await? };
+        let total_candidates = { let _s = log.span(&EmbedPhase::CountCandidates).entered(); db::count_candidates(pool, &model_tag, args.force, scope).await? };
         let planned = match args.max { Some(m) => total_candidates.min(m), None => total_candidates };
-        let ids = db::list_candidate_chunk_ids(pool, &model_tag, args.force, args.plan_limit as i64).await?;
+        let ids = db::list_candidate_chunk_ids(pool, &model_tag, args.force, scope, args.plan_limit as i64).await?;
         // Always log plan summary
         log.info(format!(
-            "📝 Embed plan — model={} dim={} batch={} force={} candidates={} planned={}",
-            model_tag, args.dim, batch, args.force, total_candidates, planned
+            "📝 Embed plan — model={} dim={} vector_type={} batch={} force={} candidates={} planned={}",
+            model_tag, args.dim, args.vector_type.as_str(), batch, args.force, total_candidates, planned
         ));
+        // an unfinished earlier run keeps its denominator (paged mode only)
+        let cursor = if args.force { None } else { db::load_cursor(pool, &model_tag).await? };
+        let (resumed_done, total) = r#loop::resume_point(cursor.as_ref(), &scope_key, planned);
+        if resumed_done > 0 { log.info(format!("  resuming: {}/{} done by an earlier run", resumed_done, total)); }
+        if let Some(f) = args.feed { log.info(format!("  feed={}", f)); }
+        if let Some(s) = &args.since { log.info(format!("  since={}", s)); }
         for id in &ids { log.info(format!("  chunk_id={}", id)); }
         if (args.plan_limit as i64) < planned { log.info("  ... (more up to planned count)"); }
         log.info("   Use --apply to execute.");
-        // Emit structured plan to stdout
-        #[derive(Serialize)]
-        struct EmbedPlan { model: String, dim: usize, batch: usize, force: bool, candidates: i64, planned: i64, sample_chunk_ids: Vec<i64> }
-        let plan = EmbedPlan { model: model_tag.clone(), dim: args.dim, batch, force: args.force, candidates: total_candidates, planned, sample_chunk_ids: ids };
-        log.plan(&plan)?;
-        return Ok(());
+        let plan = EmbedPlan { model: model_tag.clone(), dim: args.dim, column_dim, vector_type: args.vector_type, batch, force: args.force, feed: args.feed, since: args.since.clone(), candidates: total_candidates, planned, resumed_done, total, sample_chunk_ids: ids };
+        return Ok(Outcome::Plan(plan));
     }
 
     // APPLY: Build encoder
     let _lm = log.span(&EmbedPhase::LoadModel).entered();
-    let mut encoder: Box<dyn Embedder> = Box::new(E5Encoder::new(&args.model_id, args.onnx_filename.as_deref(), args.device)?);
+    let mut encoder: Box<dyn Embedder> = Box::new(E5Encoder::new(&args.model_id, args.onnx_filename.as_deref(), args.device, Prefixes::from_env(), args.max_seq_len)?);
     drop(_lm);
 
-    let total = if args.force {
-        r#loop::embed_force_once(pool, encoder.as_mut(), &model_tag, args.dim, batch, args.max).await?
+    // chunks longer than the encoder input get their tail silently truncated
+    let mut over_budget = 0i64;
+    if let Some(budget) = encoder.max_passage_tokens() {
+        let (n, longest) = db::count_over_budget(pool, &model_tag, args.force, scope, budget as i32).await?;
+        if n > 0 {
+            log.warn(format!(
+                "⚠️  {} chunk(s) exceed the encoder's {}-token passage budget (longest={}); their tails won't be embedded — re-chunk with --tokens-target <= {} or raise --max-seq-len",
+                n, budget, longest.unwrap_or_default(), budget
+            ));
+        }
+        over_budget = n;
+    }
+
+    let totals = if args.force {
+        r#loop::embed_force_once(pool, encoder.as_mut(), &model_tag, args.dim, args.vector_type, batch, scope, args.max, args.batch_retries).await?
     } else {
-        r#loop::embed_missing_paged(pool, encoder.as_mut(), &model_tag, args.dim, batch, args.max).await?
+        r#loop::embed_missing_paged(pool, encoder.as_mut(), &model_tag, args.dim, args.vector_type, batch, scope, &scope_key, args.max, args.batch_retries).await?
     };
 
-    if total == 0 {
+    if totals.embedded == 0 && totals.failed.is_empty() {
         log.info(format!("ℹ️  No chunks to embed (force={} model={})", args.force, model_tag));
     }
+    if !totals.failed.is_empty() {
+        log.warn(format!("⚠️  {} chunk(s) failed to embed; rerun embed to retry them", totals.failed.len()));
+    }
+    if totals.cancelled {
+        log.warn(format!("🛑 Cancelled after {} embedding(s); rerun embed to continue", totals.embedded));
+    }
 
-    #[derive(Serialize)]
-    struct EmbedResult { total_embedded: i64 }
-    log.result(&EmbedResult { total_embedded: total })?;
+    // keep doc centroids (query --level doc) in step with the chunks just embedded
+    let doc_centroids = { let _c = log.span(&EmbedPhase::Centroids).entered(); db::refresh_doc_embeddings(pool, &totals.embedded_ids).await? };
+    if doc_centroids > 0 { log.info(format!("🧬 Refreshed {} doc centroid(s)", doc_centroids)); }
 
-    Ok(())
+    Ok(Outcome::Applied(EmbedResult { total_embedded: totals.embedded, failed: totals.failed.len(), failed_chunk_ids: totals.failed, doc_centroids, resumed_done: totals.resumed_done, total: totals.total, over_budget, cancelled: totals.cancelled }))
 }
@@ -0,0 +1,252 @@
+// Re-chunk changed docs and embed only the chunks that came out new, in one pass.
+
+use anyhow::{Context, Result};
+use clap::Args;
+use serde::Serialize;
+use sqlx::PgPool;
+
+use crate::encoder::{Device, E5Encoder, Prefixes};
+use crate::encoder::traits::Embedder;
+use crate::output::types::Outcome;
+use crate::telemetry::{self};
+use crate::telemetry::ops::reembed::Phase as ReembedPhase;
+use crate::tokenizer::E5Tokenizer;
+use crate::util::{cancel, exit};
+use crate::util::index::VectorType;
+use crate::util::text::Normalize;
+use crate::util::time::parse_since_opt;
+
+use super::chunk::logic::resolve_overlap;
+use super::chunk::select::select_docs;
+use super::chunk::{chunk_doc, ChunkSettings};
+use super::embed::{check_dim, check_vector_type, db as embed_db, model_tag};
+use super::embed::r#loop::{embed_chunk_ids, EmbedTotals};
+
+#[derive(Args, Debug)]
+pub struct ReembedChangedCmd {
+    #[arg(long)] feed: Option<i32>,
+    #[arg(long)] since: Option<String>,
+    #[arg(long)] doc_id: Option<i64>,
+    #[arg(long, default_value_t = 350)] tokens_target: usize,
+    #[arg(long, default_value_t = 80)] overlap: usize,
+    #[arg(long, default_value_t = 24)] max_chunks_per_doc: usize,
+    #[arg(long, value_enum, default_value_t = Normalize::None)] normalize: Normalize,
+    #[arg(long, env = "RAG_MODEL_ID", default_value = "intfloat/e5-small-v2")] model_id: String,
+    #[arg(long)] onnx_filename: Option<String>,
+    #[arg(long, env = "RAG_DEVICE", value_enum, default_value_t = Device::Cpu)] device: Device,
+    /// Truncate encoder input at this many tokens (default: the tokenizer's model_max_length, 512 for e5)
+    #[arg(long)] max_seq_len: Option<usize>,
+    #[arg(long, default_value_t = 384)] dim: usize,
+    /// Element type of rag.embedding.vec: float (vector) or half (halfvec); must match the column
+    #[arg(long, value_enum, default_value_t = VectorType::Float)] vector_type: VectorType,
+    #[arg(long, default_value_t = 128)] batch: usize,
+    /// Retries per batch on encoder errors before skipping it (skipped chunks are reported as failed)
+    #[arg(long, default_value_t = 1)] batch_retries: usize,
+    #[arg(long, default_value_t = false)] apply: bool,
+    #[arg(long, default_value_t = 10)] plan_limit: usize,
+}
+
+/// Library-facing reembed-changed parameters; `ReembedChangedCmd` maps onto these one-to-one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReembedParams {
+    pub feed: Option<i32>,
+    pub since: Option<String>,
+    pub doc_id: Option<i64>,
+    pub tokens_target: usize,
+    pub overlap: usize,
+    pub max_chunks_per_doc: usize,
+    pub normalize: Normalize,
+    pub model_id: String,
+    pub onnx_filename: Option<String>,
+    pub device: Device,
+    pub max_seq_len: Option<usize>,
+    pub dim: usize,
+    pub vector_type: VectorType,
+    pub batch: usize,
+    pub batch_retries: usize,
+    pub apply: bool,
+    pub plan_limit: usize,
+}
+
+impl Default for ReembedParams {
+    fn default() -> Self {
+        Self {
+            feed: None,
+            since: None,
+            doc_id: None,
+            tokens_target: 350,
+            overlap: 80,
+            max_chunks_per_doc: 24,
+            normalize: Normalize::None,
+            model_id: "intfloat/e5-small-v2".to_string(),
+            onnx_filename: None,
+            device: Device::Cpu,
+            max_seq_len: None,
+            dim: 384,
+            vector_type: VectorType::Float,
+            batch: 128,
+            batch_retries: 1,
+            apply: false,
+            plan_limit: 10,
+        }
+    }
+}
+
+impl From<ReembedChangedCmd> for ReembedParams {
+    fn from(c: ReembedChangedCmd) -> Self {
+        Self {
+            feed: c.feed,
+            since: c.since,
+            doc_id: c.doc_id,
+            tokens_target: c.tokens_target,
+            overlap: c.overlap,
+            max_chunks_per_doc: c.max_chunks_per_doc,
+            normalize: c.normalize,
+            model_id: c.model_id,
+            onnx_filename: c.onnx_filename,
+            device: c.device,
+            max_seq_len: c.max_seq_len,
+            dim: c.dim,
+            vector_type: c.vector_type,
+            batch: c.batch,
+            batch_retries: c.batch_retries,
+            apply: c.apply,
+            plan_limit: c.plan_limit,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ReembedPlan { pub changed_docs: usize, pub model: String, pub tokens_target: usize, pub overlap: usize, pub normalize: Normalize, pub sample_doc_ids: Vec<i64> }
+
+#[derive(Serialize, Default)]
+pub struct ReembedResult { pub docs: usize, pub chunks_inserted: usize, pub chunks_kept: usize, pub chunks_deleted: usize, pub embedded: i64, pub failed: usize, pub failed_chunk_ids: Vec<i64>, pub doc_centroids: u64, pub cancelled: bool }
+
+pub type ReembedOutcome = Outcome<ReembedPlan, ReembedResult>;
+
+pub async fn run(pool: &PgPool, args: ReembedChangedCmd) -> Result<()> {
+    let outcome = execute(pool, args.into()).await?;
+    if matches!(&outcome, Outcome::Applied(r) if r.docs == 0 && r.embedded == 0 && !r.cancelled) { exit::mark_empty(); }
+    telemetry::reembed().outcome(&outcome)
+}
+
+pub async fn execute(pool: &PgPool, args: ReembedParams) -> Result<ReembedOutcome> {
+    let log = telemetry::reembed();
+    let _g = log.root_span_kv([
+        ("feed", format!("{:?}", args.feed)),
+        ("since", format!("{:?}", args.since)),
+        ("doc_id", format!("{:?}", args.doc_id)),
+        ("tokens_target", args.tokens_target.to_string()),
+        ("overlap", args.overlap.to_string()),
+        ("max_chunks_per_doc", args.max_chunks_per_doc.to_string()),
+        ("normalize", args.normalize.as_str().to_string()),
+        ("model_id", args.model_id.clone()),
+        ("device", format!("{:?}", args.device)),
+        ("max_seq_len", format!("{:?}", args.max_seq_len)),
+        ("dim", args.dim.to_string()),
+        ("vector_type", args.vector_type.as_str().to_string()),
+        ("batch", args.batch.to_string()),
+        ("apply", args.apply.to_string()),
+    ]).entered();
+
+    let overlap = resolve_overlap(args.tokens_target, args.overlap, None)?;
+    let model_tag = model_tag(&args.model_id, args.device);
+    let batch = args.batch.max(1);
+
+    // new docs plus docs whose content_hash moved since they were last chunked
+    let since_ts = parse_since_opt(&args.since)?;
+    let docs = {
+        let _s = log.span(&ReembedPhase::SelectDocs).entered();
+        select_docs(pool, args.doc_id, args.feed, since_ts, true, false).await?
+    };
+
+    if !args.apply {
+        let _sp = log.span(&ReembedPhase::Plan).entered();
+        log.info(format!(
+            "📝 Reembed plan — changed_docs={} model={} tokens_target={} overlap={} normalize={}",
+            docs.len(), model_tag, args.tokens_target, overlap, args.normalize.as_str()
+        ));
+        for (doc_id, _) in docs.iter().take(args.plan_limit) {
+            log.info(format!("  doc_id={}", doc_id));
+        }
+        if docs.len() > args.plan_limit { log.info(format!("  ... ({} more)", docs.len() - args.plan_limit)); }
+        log.info("   Use --apply to execute.");
+        let plan = ReembedPlan {
+            changed_docs: docs.len(),
+            model: model_tag,
+            tokens_target: args.tokens_target,
+            overlap,
+            normalize: args.normalize,
+            sample_doc_ids: docs.iter().take(args.plan_limit).map(|(id, _)| *id).collect(),
+        };
+        return Ok(Outcome::Plan(plan));
+    }
+
+    let mut res = ReembedResult::default();
+    if docs.is_empty() {
+        log.info("ℹ️  No changed documents to re-chunk");
+        return Ok(Outcome::Applied(res));
+    }
+
+    check_dim(pool, args.dim).await?;
+    check_vector_type(pool, args.vector_type).await?;
+
+    // one tokenizer and one encoder for the whole pass
+    let _lm = log.span(&ReembedPhase::LoadModel).entered();
+    let mut tok = E5Tokenizer::for_chunking().context("init E5 tokenizer")?;
+    let mut encoder: Box<dyn Embedder> = Box::new(E5Encoder::new(&args.model_id, args.onnx_filename.as_deref(), args.device, Prefixes::from_env(), args.max_seq_len)?);
+    drop(_lm);
+
+    let settings = ChunkSettings { tokens_target: args.tokens_target, overlap, max_chunks: args.max_chunks_per_doc, normalize: args.normalize };
+    let mut totals = EmbedTotals::default();
+    let mut pending: Vec<i64> = Vec::new();
+    // docs whose chunk set changed, including ones that only lost chunks; their centroids are refreshed
+    let mut touched: Vec<i64> = Vec::new();
+    let total_docs = docs.len() as u64;
+
+    for (i, (doc_id, text_clean)) in docs.into_iter().enumerate() {
+        if cancel::is_cancelled() { totals.cancelled = true; break; }
+        if let Some(text) = text_clean.as_deref() {
+            let _c = log.span(&ReembedPhase::Chunk).entered();
+            let d = chunk_doc(pool, &mut tok, doc_id, text, &settings).await?;
+            res.docs += 1;
+            res.chunks_inserted += d.inserted;
+            res.chunks_kept += d.kept;
+            res.chunks_deleted += d.deleted;
+            if d.inserted + d.deleted > 0 { touched.push(doc_id); }
+            pending.extend(d.new_chunk_ids);
+        }
+        // embed in full batches as they fill up; the tail is flushed after the loop
+        if pending.len() >= batch {
+            let _e = log.span(&ReembedPhase::Embed).entered();
+            let ready: Vec<i64> = pending.drain(..pending.len() / batch * batch).collect();
+            embed_chunk_ids(pool, encoder.as_mut(), &model_tag, args.dim, args.vector_type, batch, &ready, args.batch_retries, &mut totals).await?;
+        }
+        log.progress(i as u64 + 1, Some(total_docs), "docs")?;
+    }
+    if !pending.is_empty() && !totals.cancelled {
+        let _e = log.span(&ReembedPhase::Embed).entered();
+        embed_chunk_ids(pool, encoder.as_mut(), &model_tag, args.dim, args.vector_type, batch, &pending, args.batch_retries, &mut totals).await?;
+    }
+    {
+        let _c = log.span(&ReembedPhase::Centroids).entered();
+        res.doc_centroids = embed_db::refresh_doc_embeddings_for_docs(pool, &touched).await?;
+    }
+
+    if !totals.failed.is_empty() {
+        log.warn(format!("⚠️  {} chunk(s) failed to embed; run embed --apply to retry them", totals.failed.len()));
+    }
+    if totals.cancelled {
+        log.warn(format!("🛑 Cancelled after {} doc(s); unembedded chunks are picked up by embed --apply", res.docs));
+    }
+    log.info(format!(
+        "✅ Re-chunked {} doc(s) (new={} kept={} deleted={}), embedded {} chunk(s)",
+        res.docs, res.chunks_inserted, res.chunks_kept, res.chunks_deleted, totals.embedded
+    ));
+
+    res.embedded = totals.embedded;
+    res.failed = totals.failed.len();
+    res.failed_chunk_ids = totals.failed;
+    res.cancelled = totals.cancelled;
+    Ok(Outcome::Applied(res))
+}
@@ -1,12 +1,20 @@
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use pgvector::Vector as PgVector;
+use sqlx::postgres::{PgArguments, PgRow};
+use sqlx::query::Query;
 use sqlx::{Executor, PgPool, Postgres, Row};
 
+use std::borrow::Cow;
+
+use crate::util::index::{describe_index, IndexDef, VectorType, EMBEDDING_INDEX};
+use crate::util::quantize::bit_string;
+
 #[derive(Clone)]
 pub struct CandRow {
     pub chunk_id: i64,
     pub doc_id: i64,
+    pub chunk_index: Option<i32>,
     pub title: Option<String>,
     pub preview: Option<String>,
     pub text: Option<String>,
@@ -16,24 +24,215 @@ pub struct CandRow {
 pub struct FetchOpts {
     pub feed: Option<i32>,
     pub since: Option<DateTime<Utc>>,
+    // ILIKE patterns on document title / feed name (see like_pattern)
+    pub title_like: Option<String>,
+    pub feed_name: Option<String>,
     pub include_preview: bool,
     pub include_text: bool,
+    pub preview_chars: i32,
+    // type of rag.embedding.vec; halfvec columns need the query vector cast (VectorType::cast_param)
+    pub vector_type: VectorType,
+    // query --quantized: Hamming pre-filter keeps this many rows before exact re-ranking (QUANTIZED_SQL)
+    pub quantized_pool: Option<i64>,
+}
+
+// Plain substrings match anywhere; patterns that already use % or _ are passed through
+pub fn like_pattern(p: &str) -> String {
+    if p.contains('%') || p.contains('_') { p.to_string() } else { format!("%{}%", p) }
+}
+
+// Stops at the first matching row, so the common (matching) case stays cheap.
+pub async fn model_has_embeddings(pool: &PgPool, model_tag: &str) -> Result<bool> {
+    let row = sqlx::query("SELECT EXISTS (SELECT 1 FROM rag.embedding WHERE model = $1)")
+        .bind(model_tag)
+        .fetch_one(pool)
+        .await?;
+    Ok(row.try_get::<bool, _>(0)?)
+}
+
+// Embeddings written before vec_bits existed are invisible to query --quantized.
+pub async fn has_unquantized(pool: &PgPool) -> Result<bool> {
+    let row = sqlx::query("SELECT EXISTS (SELECT 1 FROM rag.embedding WHERE vec_bits IS NULL)")
+        .fetch_one(pool)
+        .await?;
+    Ok(row.try_get::<bool, _>(0)?)
 }
 
-pub async fn recommend_probes(pool: &PgPool) -> Result<Option<i32>> {
-    let row = sqlx::query!(
+pub async fn embedding_models(pool: &PgPool) -> Result<Vec<String>> {
+    let rows = sqlx::query("SELECT DISTINCT model FROM rag.embedding ORDER BY model")
+        .fetch_all(pool)
+        .await?;
+    Ok(rows.iter().map(|r| r.try_get::<String, _>(0)).collect::<Result<_, _>>()?)
+}
+
+// Nearest chunks embedded by this model; the selftest-embed counterpart of fetch_farthest,
+// so both probes compare against the same vectors.
+pub async fn fetch_nearest(pool: &PgPool, qvec: &[f32], model_tag: &str, k: i64, preview_chars: i32, vector_type: VectorType) -> Result<Vec<CandRow>> {
+    let sql = vector_type.cast_param(
         r#"
-        SELECT substring(pg_get_indexdef(i.indexrelid) from 'lists = ([0-9]+)') AS lists
-        FROM pg_index i
-        JOIN pg_class c ON c.oid = i.indexrelid
-        JOIN pg_namespace nsp ON nsp.oid = c.relnamespace
-        WHERE nsp.nspname = 'rag' AND c.relname = 'embedding_vec_ivf_idx'
-        "#
+        SELECT c.chunk_id, c.doc_id, c.chunk_index, d.source_title AS title,
+               (e.vec <-> $1) AS distance,
+               substring(c.text, 1, $4) AS preview,
+               NULL::text AS text
+        FROM rag.embedding e
+        JOIN rag.chunk c ON c.chunk_id = e.chunk_id
+        JOIN rag.document d ON d.doc_id = c.doc_id
+        WHERE e.model = $2
+        ORDER BY distance ASC
+        LIMIT $3
+        "#,
+    );
+    let rows = sqlx::query(&sql)
+        .bind(PgVector::from(qvec.to_vec()))
+        .bind(model_tag)
+        .bind(k)
+        .bind(preview_chars)
+        .fetch_all(pool)
+        .await?;
+    Ok(rows.into_iter().map(cand_row).collect())
+}
+
+// Sequential scan (no index serves ORDER BY ... DESC); used by selftest-embed only.
+pub async fn fetch_farthest(pool: &PgPool, qvec: &[f32], model_tag: &str, k: i64, vector_type: VectorType) -> Result<Vec<(i64, f32)>> {
+    let sql = vector_type.cast_param(
+        r#"
+        SELECT e.chunk_id, (e.vec <-> $1) AS distance
+        FROM rag.embedding e
+        WHERE e.model = $2
+        ORDER BY distance DESC
+        LIMIT $3
+        "#,
+    );
+    let rows = sqlx::query(&sql)
+        .bind(PgVector::from(qvec.to_vec()))
+        .bind(model_tag)
+        .bind(k)
+        .fetch_all(pool)
+        .await?;
+    Ok(rows.iter().map(|r| (r.get::<i64, _>("chunk_id"), r.get::<f64, _>("distance") as f32)).collect())
+}
+
+pub async fn ann_index(pool: &PgPool) -> Result<Option<IndexDef>> {
+    describe_index(pool, EMBEDDING_INDEX).await
+}
+
+// ivfflat: search ~10% of the lists
+pub fn recommend_probes(idx: &IndexDef) -> Option<i32> {
+    idx.lists.map(|k| (k / 10).max(1))
+}
+
+// ivfflat cannot probe more lists than exist; clamp user probes to [1, lists]
+pub fn clamp_probes(probes: i32, lists: Option<i32>) -> i32 {
+    match lists {
+        Some(k) if k > 0 => probes.clamp(1, k),
+        _ => probes.max(1),
+    }
+}
+
+// hnsw: ef_search bounds how many rows the index scan can return, so keep it
+// at least as large as the candidate pool (pgvector default 40, max 1000).
+pub fn recommend_ef_search(top_n: i64) -> i32 {
+    top_n.clamp(40, 1000) as i32
+}
+
+const ANN_SQL: &str = r#"
+    SELECT c.chunk_id, c.doc_id, c.chunk_index, d.source_title AS title,
+           (e.vec <-> $1) AS distance,
+           CASE WHEN $3 THEN substring(c.text, 1, $5) ELSE NULL END AS preview,
+           CASE WHEN $4 THEN c.text ELSE NULL END AS text
+    FROM rag.embedding e
+    JOIN rag.chunk c ON c.chunk_id = e.chunk_id
+    JOIN rag.document d ON d.doc_id = c.doc_id
+    ORDER BY distance ASC
+    LIMIT $2
+"#;
+
+const ANN_FILTERED_SQL: &str = r#"
+    SELECT c.chunk_id, c.doc_id, c.chunk_index, d.source_title AS title,
+           (e.vec <-> $1) AS distance,
+           CASE WHEN $5 THEN substring(c.text, 1, $7) ELSE NULL END AS preview,
+           CASE WHEN $6 THEN c.text ELSE NULL END AS text
+    FROM rag.embedding e
+    JOIN rag.chunk c ON c.chunk_id = e.chunk_id
+    JOIN rag.document d ON d.doc_id = c.doc_id
+    LEFT JOIN rag.feed f ON f.feed_id = d.feed_id
+    WHERE ($2::int4 IS NULL OR d.feed_id = $2)
+      AND ($3::timestamptz IS NULL OR d.fetched_at >= $3)
+      AND ($8::text IS NULL OR d.source_title ILIKE $8)
+      AND ($9::text IS NULL OR f.name ILIKE $9)
+    ORDER BY distance ASC
+    LIMIT $4
+"#;
+
+// query --quantized. Stage 1 walks the HNSW Hamming index on the sign-bit codes
+// (embedding_vec_bits_hnsw_idx, `<~>`) and keeps $11 survivors, so it returns at most
+// hnsw.ef_search rows; stage 2 re-ranks those by exact distance on the float vectors.
+// Filters apply to the index walk's output, like the filtered ANN path.
+const QUANTIZED_SQL: &str = r#"
+    WITH pre AS MATERIALIZED (
+        SELECT e.chunk_id, e.vec
+        FROM rag.embedding e
+        JOIN rag.chunk c ON c.chunk_id = e.chunk_id
+        JOIN rag.document d ON d.doc_id = c.doc_id
+        LEFT JOIN rag.feed f ON f.feed_id = d.feed_id
+        WHERE e.vec_bits IS NOT NULL
+          AND ($2::int4 IS NULL OR d.feed_id = $2)
+          AND ($3::timestamptz IS NULL OR d.fetched_at >= $3)
+          AND ($8::text IS NULL OR d.source_title ILIKE $8)
+          AND ($9::text IS NULL OR f.name ILIKE $9)
+        ORDER BY e.vec_bits <~> $10::text::varbit
+        LIMIT $11
     )
-    .fetch_optional(pool)
-    .await?;
-    let lists = row.and_then(|r| r.lists).and_then(|s| s.parse::<i32>().ok());
-    Ok(lists.map(|k| (k / 10).max(1)))
+    SELECT c.chunk_id, c.doc_id, c.chunk_index, d.source_title AS title,
+           (pre.vec <-> $1) AS distance,
+           CASE WHEN $5 THEN substring(c.text, 1, $7) ELSE NULL END AS preview,
+           CASE WHEN $6 THEN c.text ELSE NULL END AS text
+    FROM pre
+    JOIN rag.chunk c ON c.chunk_id = pre.chunk_id
+    JOIN rag.document d ON d.doc_id = c.doc_id
+    ORDER BY distance ASC
+    LIMIT $4
+"#;
+
+fn is_filtered(opts: &FetchOpts) -> bool {
+    opts.feed.is_some() || opts.since.is_some() || opts.title_like.is_some() || opts.feed_name.is_some()
+}
+
+// Chunk-level candidate SQL for these options, with the query vector cast for halfvec columns
+fn candidate_sql(opts: &FetchOpts) -> Cow<'static, str> {
+    let sql = if opts.quantized_pool.is_some() {
+        QUANTIZED_SQL
+    } else if is_filtered(opts) {
+        ANN_FILTERED_SQL
+    } else {
+        ANN_SQL
+    };
+    opts.vector_type.cast_param(sql)
+}
+
+// Bind candidate params in the order candidate_sql expects; `sql` may be wrapped (EXPLAIN)
+fn ann_query<'q>(sql: &'q str, qvec: &[f32], top_n: i64, opts: &FetchOpts) -> Query<'q, Postgres, PgArguments> {
+    let q = sqlx::query(sql).bind(PgVector::from(qvec.to_vec()));
+    if !is_filtered(opts) && opts.quantized_pool.is_none() {
+        return q
+            .bind(top_n)
+            .bind(opts.include_preview)
+            .bind(opts.include_text)
+            .bind(opts.preview_chars);
+    }
+    let q = q.bind(opts.feed)
+        .bind(opts.since)
+        .bind(top_n)
+        .bind(opts.include_preview)
+        .bind(opts.include_text)
+        .bind(opts.preview_chars)
+        .bind(opts.title_like.as_deref().map(like_pattern))
+        .bind(opts.feed_name.as_deref().map(like_pattern));
+    match opts.quantized_pool {
+        // the pre-filter must keep at least the rows the final LIMIT asks for
+        Some(pool) => q.bind(bit_string(qvec)).bind(pool.max(top_n)),
+        None => q,
+    }
 }
 
 pub async fn fetch_ann_candidates<'e, E>(
@@ -45,74 +244,123 @@ pub async fn fetch_ann_candidates<'e, E>(
 where
     E: Executor<'e, Database = Postgres>,
 {
-    if opts.feed.is_none() && opts.since.is_none() {
-        let rows = sqlx::query(
-            r#"
-            SELECT c.chunk_id, c.doc_id, d.source_title AS title,
-                   (e.vec <-> $1) AS distance,
-                   CASE WHEN $3 THEN substring(c.text, 1, 300) ELSE NULL END AS preview,
-                   CASE WHEN $4 THEN c.text ELSE NULL END AS text
-            FROM rag.embedding e
-            JOIN rag.chunk c ON c.chunk_id = e.chunk_id
-            JOIN rag.document d ON d.doc_id = c.doc_id
-            ORDER BY distance ASC
-            LIMIT $2
-            "#
-        )
+    let sql = candidate_sql(opts);
+    let rows = ann_query(&sql, qvec, top_n, opts).fetch_all(executor).await?;
+    Ok(rows.into_iter().map(cand_row).collect())
+}
+
+fn cand_row(row: PgRow) -> CandRow {
+    CandRow {
+        chunk_id: row.get::<i64, _>("chunk_id"),
+        doc_id: row.get::<i64, _>("doc_id"),
+        chunk_index: row.get::<Option<i32>, _>("chunk_index"),
+        title: row.get::<Option<String>, _>("title"),
+        preview: row.get::<Option<String>, _>("preview"),
+        text: row.get::<Option<String>, _>("text"),
+        distance: row.get::<f64, _>("distance") as f32,
+    }
+}
+
+// query --level doc, stage 1: nearest doc centroids (cosine, since a mean of unit vectors is not unit length)
+const DOC_SQL: &str = r#"
+    SELECT de.doc_id, (de.vec <=> $1) AS distance
+    FROM rag.doc_embedding de
+    JOIN rag.document d ON d.doc_id = de.doc_id
+    LEFT JOIN rag.feed f ON f.feed_id = d.feed_id
+    WHERE ($2::int4 IS NULL OR d.feed_id = $2)
+      AND ($3::timestamptz IS NULL OR d.fetched_at >= $3)
+      AND ($5::text IS NULL OR d.source_title ILIKE $5)
+      AND ($6::text IS NULL OR f.name ILIKE $6)
+    ORDER BY distance ASC
+    LIMIT $4
+"#;
+
+pub async fn fetch_top_docs<'e, E>(executor: E, qvec: &[f32], n_docs: i64, opts: &FetchOpts) -> Result<Vec<(i64, f32)>>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let rows = sqlx::query(DOC_SQL)
+        .bind(PgVector::from(qvec.to_vec()))
+        .bind(opts.feed)
+        .bind(opts.since)
+        .bind(n_docs)
+        .bind(opts.title_like.as_deref().map(like_pattern))
+        .bind(opts.feed_name.as_deref().map(like_pattern))
+        .fetch_all(executor)
+        .await?;
+    Ok(rows.into_iter().map(|row| (row.get::<i64, _>("doc_id"), row.get::<f64, _>("distance") as f32)).collect())
+}
+
+// Stage 2: exact scan over the chosen docs' chunks. MATERIALIZED keeps the planner from
+// walking the ANN index and filtering afterwards, which could starve the result.
+const DOC_CHUNKS_SQL: &str = r#"
+    WITH cand AS MATERIALIZED (
+        SELECT c.chunk_id, c.doc_id, c.chunk_index, c.text AS full_text, d.source_title AS title, e.vec
+        FROM rag.chunk c
+        JOIN rag.embedding e ON e.chunk_id = c.chunk_id
+        JOIN rag.document d ON d.doc_id = c.doc_id
+        WHERE c.doc_id = ANY($2)
+    )
+    SELECT chunk_id, doc_id, chunk_index, title,
+           (vec <-> $1) AS distance,
+           CASE WHEN $4 THEN substring(full_text, 1, $6) ELSE NULL END AS preview,
+           CASE WHEN $5 THEN full_text ELSE NULL END AS text
+    FROM cand
+    ORDER BY distance ASC
+    LIMIT $3
+"#;
+
+pub async fn fetch_doc_chunks<'e, E>(executor: E, qvec: &[f32], doc_ids: &[i64], top_n: i64, opts: &FetchOpts) -> Result<Vec<CandRow>>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let sql = opts.vector_type.cast_param(DOC_CHUNKS_SQL);
+    let rows = sqlx::query(&sql)
         .bind(PgVector::from(qvec.to_vec()))
+        .bind(doc_ids)
         .bind(top_n)
         .bind(opts.include_preview)
         .bind(opts.include_text)
+        .bind(opts.preview_chars)
         .fetch_all(executor)
         .await?;
-        let out = rows
-            .into_iter()
-            .map(|row| CandRow {
-                chunk_id: row.get::<i64, _>("chunk_id"),
-                doc_id: row.get::<i64, _>("doc_id"),
-                title: row.get::<Option<String>, _>("title"),
-                preview: row.get::<Option<String>, _>("preview"),
-                text: row.get::<Option<String>, _>("text"),
-                distance: row.get::<f64, _>("distance") as f32,
-            })
-            .collect();
-        return Ok(out);
-    }
+    Ok(rows.into_iter().map(cand_row).collect())
+}
 
-    // with filters
+// EXPLAIN (ANALYZE, BUFFERS) of the exact candidate query; one plan line per row
+pub async fn explain_ann_candidates<'e, E>(
+    executor: E,
+    qvec: &[f32],
+    top_n: i64,
+    opts: &FetchOpts,
+) -> Result<Vec<String>>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let sql = format!("EXPLAIN (ANALYZE, BUFFERS) {}", candidate_sql(opts));
+    let rows = ann_query(&sql, qvec, top_n, opts).fetch_all(executor).await?;
+    Ok(rows.into_iter().map(|row| row.get::<String, _>(0)).collect())
+}
+
+// Chunk texts for a set of (doc_id, chunk_index) pairs, used for neighbor expansion
+pub async fn fetch_chunk_texts(pool: &PgPool, keys: &[(i64, i32)]) -> Result<Vec<(i64, i32, String)>> {
+    if keys.is_empty() { return Ok(Vec::new()); }
+    let doc_ids: Vec<i64> = keys.iter().map(|(d, _)| *d).collect();
+    let indexes: Vec<i32> = keys.iter().map(|(_, i)| *i).collect();
     let rows = sqlx::query(
         r#"
-        SELECT c.chunk_id, c.doc_id, d.source_title AS title,
-               (e.vec <-> $1) AS distance,
-               CASE WHEN $5 THEN substring(c.text, 1, 300) ELSE NULL END AS preview,
-               CASE WHEN $6 THEN c.text ELSE NULL END AS text
-        FROM rag.embedding e
-        JOIN rag.chunk c ON c.chunk_id = e.chunk_id
-        JOIN rag.document d ON d.doc_id = c.doc_id
-        WHERE ($2::int4 IS NULL OR d.feed_id = $2)
-          AND ($3::timestamptz IS NULL OR d.fetched_at >= $3)
-        ORDER BY distance ASC
-        LIMIT $4
+        SELECT c.doc_id, c.chunk_index, c.text
+        FROM rag.chunk c
+        JOIN UNNEST($1::bigint[], $2::int4[]) AS k(doc_id, chunk_index)
+          ON c.doc_id = k.doc_id AND c.chunk_index = k.chunk_index
         "#
     )
-    .bind(PgVector::from(qvec.to_vec()))
-    .bind(opts.feed)
-    .bind(opts.since)
-    .bind(top_n)
-    .bind(opts.include_preview)
-    .bind(opts.include_text)
-    .fetch_all(executor)
+    .bind(&doc_ids)
+    .bind(&indexes)
+    .fetch_all(pool)
     .await?;
-    let out = rows
+    Ok(rows
         .into_iter()
-        .map(|row| CandRow {
-            chunk_id: row.get::<i64, _>("chunk_id"),
-            doc_id: row.get::<i64, _>("doc_id"),
-            title: row.get::<Option<String>, _>("title"),
-            preview: row.get::<Option<String>, _>("preview"),
-            text: row.get::<Option<String>, _>("text"),
-            distance: row.get::<f64, _>("distance") as f32,
-        })
-        .collect();
-    Ok(out)
+        .map(|row| (row.get::<i64, _>("doc_id"), row.get::<i32, _>("chunk_index"), row.get::<String, _>("text")))
+        .collect())
 }
@@ -1,78 +1,211 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use std::path::PathBuf;
 use clap::Args;
+use serde::Serialize;
 use chrono::{DateTime, Utc};
 use sqlx::PgPool;
 
+use crate::util::exit;
 use crate::util::time::parse_since_opt;
 
 use crate::encoder::Device;
 use crate::telemetry::{self};
-use crate::telemetry::ops::query::Phase as QueryPhase;
+use crate::telemetry::ctx::LogCtx;
+use crate::telemetry::ops::query::{Phase as QueryPhase, Query as QueryOp};
 
 mod db;
-mod post;
+pub mod post;
+mod rerank;
 pub mod service;
+pub mod selftest;
+pub mod template;
 
 pub use post::QueryResultRow;
 
-use self::service::QueryRequest;
+use self::service::{QueryLevel, QueryRequest};
+use self::template::RowTemplate;
 
 #[derive(Args, Debug)]
 pub struct QueryCmd {
-    query: String,
+    #[arg(required_unless_present = "queries_file", conflicts_with = "queries_file")]
+    query: Option<String>,
+    /// Run every line of this file as a query (one batched embedding pass); prints a JSON array keyed by query
+    #[arg(long)] queries_file: Option<PathBuf>,
     #[arg(long, default_value_t = 100)] top_n: i64,
     #[arg(long, default_value_t = 6)] topk: usize,
     #[arg(long, default_value_t = 2)] doc_cap: usize,
     #[arg(long)] probes: Option<i32>,
+    /// HNSW search list size (used instead of --probes when the index is hnsw)
+    #[arg(long)] ef_search: Option<i32>,
     #[arg(long)] feed: Option<i32>,
     #[arg(long)] since: Option<String>,
+    /// Only docs whose title matches (ILIKE; plain text matches as a substring)
+    #[arg(long)] title_like: Option<String>,
+    /// Only docs from feeds whose name matches (ILIKE; plain text matches as a substring)
+    #[arg(long)] feed_name: Option<String>,
+    /// Drop candidates whose L2 distance exceeds D
+    #[arg(long)] max_distance: Option<f32>,
+    /// Drop candidates below this cosine similarity (converted to a distance cutoff)
+    #[arg(long)] min_similarity: Option<f32>,
     #[arg(long, default_value_t = false)] show_context: bool,
+    /// Preview length in characters for --show-context
+    #[arg(long, default_value_t = 300)] preview_chars: usize,
+    /// Center previews on the passage overlapping the most query terms (implies --show-context)
+    #[arg(long, default_value_t = false)] highlight: bool,
+    /// Include the complete chunk text in the result rows
+    #[arg(long, default_value_t = false)] full_text: bool,
+    /// Print one line per result to stdout instead of the result envelope, e.g. "{rank}\t{distance}\t{title}"
+    /// (placeholders: rank, distance, chunk_id, doc_id, title, preview, text, rerank_score)
+    #[arg(long, visible_alias = "output-template")] template: Option<String>,
+    /// With --full-text: stitch N preceding/following chunks of the same doc into each text
+    #[arg(long, default_value_t = 0)] expand_neighbors: usize,
+    /// Run EXPLAIN (ANALYZE, BUFFERS) on the candidate SQL and report scan type, probes, timing
+    #[arg(long, default_value_t = false)] explain: bool,
+    /// Rescore the best ANN candidates with the LLM (OPENAI_* config) and reorder before topk
+    #[arg(long, default_value_t = false)] rerank_llm: bool,
+    /// With --rerank-llm: how many ANN candidates get an LLM score (one request each)
+    #[arg(long, default_value_t = 20)] rerank_candidates: usize,
+    /// With --rerank-llm: chat model for scoring (defaults to OPENAI_MODEL)
+    #[arg(long)] rerank_model: Option<String>,
+    /// Re-fetch with a doubled --top-n up to this many times when doc_cap leaves fewer than topk rows (0 disables)
+    #[arg(long, default_value_t = 2)] overfetch_rounds: usize,
+    /// chunk: ANN over chunks; doc: rank doc centroids first, then return the best chunks of the top docs
+    #[arg(long, value_enum, default_value_t = QueryLevel::Chunk)] level: QueryLevel,
+    /// Fail (exit 4) instead of warning when no stored embedding was made with this --model-id
+    #[arg(long, default_value_t = false)] strict_model: bool,
+    /// Drop results whose chunk text is a near-duplicate (SimHash) of a higher-ranked result
+    #[arg(long, default_value_t = false)] dedup_results: bool,
+    /// With --dedup-results: max SimHash distance in bits that counts as a near-duplicate
+    #[arg(long, default_value_t = 3)] dedup_bits: u32,
+    /// Two-stage search: Hamming pre-filter over binary-quantized codes, then exact re-ranking (faster, slightly lower recall)
+    #[arg(long, default_value_t = false)] quantized: bool,
+    /// With --quantized: rows kept by the pre-filter for re-ranking (raised to --top-n if lower; the HNSW walk caps it at 1000)
+    #[arg(long, default_value_t = 1000)] quantized_pool: usize,
 
     // E5Encoder config
-    #[arg(long, default_value = "intfloat/e5-small-v2")] pub model_id: String,
+    #[arg(long, env = "RAG_MODEL_ID", default_value = "intfloat/e5-small-v2")] pub model_id: String,
     #[arg(long)] pub onnx_filename: Option<String>,
-    #[arg(long, value_enum, default_value_t = Device::Cpu)] pub device: Device,
+    #[arg(long, env = "RAG_DEVICE", value_enum, default_value_t = Device::Cpu)] pub device: Device,
+    /// Truncate the query encoding at this many tokens (default: the tokenizer's model_max_length)
+    #[arg(long)] pub max_seq_len: Option<usize>,
+}
+
+impl QueryCmd {
+    /// Map CLI flags onto the library request (`--highlight` implies `--show-context`).
+    pub fn to_request(&self) -> Result<QueryRequest<'_>> {
+        let since: Option<DateTime<Utc>> = parse_since_opt(&self.since)?;
+        Ok(QueryRequest {
+            query: self.query.as_deref().unwrap_or_default(),
+            top_n: self.top_n,
+            topk: self.topk,
+            doc_cap: self.doc_cap,
+            probes: self.probes,
+            ef_search: self.ef_search,
+            feed: self.feed,
+            since,
+            title_like: self.title_like.as_deref(),
+            feed_name: self.feed_name.as_deref(),
+            max_distance: post::distance_cutoff(self.max_distance, self.min_similarity),
+            include_preview: self.show_context || self.highlight,
+            include_text: self.full_text,
+            preview_chars: self.preview_chars,
+            highlight: self.highlight,
+            expand_neighbors: self.expand_neighbors,
+            explain: self.explain,
+            rerank_llm: self.rerank_llm,
+            rerank_candidates: self.rerank_candidates,
+            rerank_model: self.rerank_model.as_deref(),
+            overfetch_rounds: self.overfetch_rounds,
+            level: self.level,
+            strict_model: self.strict_model,
+            dedup_results: self.dedup_results,
+            dedup_bits: self.dedup_bits,
+            quantized: self.quantized,
+            quantized_pool: self.quantized_pool,
+            model_id: &self.model_id,
+            onnx_filename: self.onnx_filename.as_deref(),
+            device: self.device,
+            max_seq_len: self.max_seq_len,
+        })
+    }
 }
 
 pub async fn run(pool: &PgPool, args: QueryCmd) -> Result<()> {
     let log = telemetry::query();
     let _g = log
         .root_span_kv([
+            ("queries_file", format!("{:?}", args.queries_file)),
             ("top_n", args.top_n.to_string()),
             ("topk", args.topk.to_string()),
             ("doc_cap", args.doc_cap.to_string()),
             ("probes", format!("{:?}", args.probes)),
+            ("ef_search", format!("{:?}", args.ef_search)),
             ("feed", format!("{:?}", args.feed)),
             ("since", format!("{:?}", args.since)),
+            ("title_like", format!("{:?}", args.title_like)),
+            ("feed_name", format!("{:?}", args.feed_name)),
+            ("max_distance", format!("{:?}", args.max_distance)),
+            ("min_similarity", format!("{:?}", args.min_similarity)),
             ("show_context", args.show_context.to_string()),
+            ("preview_chars", args.preview_chars.to_string()),
+            ("highlight", args.highlight.to_string()),
+            ("full_text", args.full_text.to_string()),
+            ("template", format!("{:?}", args.template)),
+            ("expand_neighbors", args.expand_neighbors.to_string()),
+            ("explain", args.explain.to_string()),
+            ("rerank_llm", args.rerank_llm.to_string()),
+            ("rerank_candidates", args.rerank_candidates.to_string()),
+            ("overfetch_rounds", args.overfetch_rounds.to_string()),
+            ("level", format!("{:?}", args.level)),
+            ("strict_model", args.strict_model.to_string()),
+            ("dedup_results", args.dedup_results.to_string()),
+            ("dedup_bits", args.dedup_bits.to_string()),
+            ("quantized", args.quantized.to_string()),
+            ("quantized_pool", args.quantized_pool.to_string()),
             ("model_id", args.model_id.clone()),
             ("device", format!("{:?}", args.device)),
+            ("max_seq_len", format!("{:?}", args.max_seq_len)),
         ])
         .entered();
 
-    let since_ts: Option<DateTime<Utc>> = parse_since_opt(&args.since)?;
-
-    let outcome = service::execute(
-        pool,
-        QueryRequest {
-            query: &args.query,
-            top_n: args.top_n,
-            topk: args.topk,
-            doc_cap: args.doc_cap,
-            probes: args.probes,
-            feed: args.feed,
-            since: since_ts,
-            include_preview: args.show_context,
-            include_text: false,
-            model_id: &args.model_id,
-            onnx_filename: args.onnx_filename.as_deref(),
-            device: args.device,
-        },
-        Some(&log),
-    )
-    .await?;
-
-    if outcome.rows.is_empty() {
+    let show_context = args.show_context || args.highlight;
+    let template = args.template.as_deref().map(RowTemplate::parse).transpose()?;
+
+    if let Some(path) = &args.queries_file {
+        let raw = std::fs::read_to_string(path).with_context(|| format!("read {}", path.display()))?;
+        let queries = parse_queries(&raw);
+        log.info_kv("📄 Queries", [("file", path.display().to_string()), ("count", queries.len().to_string())]);
+        #[derive(Serialize)]
+        struct QueryBatchEntry<'a> {
+            query: &'a str,
+            rows: &'a [QueryResultRow],
+            #[serde(skip_serializing_if = "Option::is_none")]
+            explain: Option<&'a post::ExplainInfo>,
+        }
+        // each query's entry is emitted as soon as it is retrieved (streamed under ndjson)
+        let mut stream = template.is_none().then(|| log.result_stream());
+        let mut any_rows = false;
+        service::execute_each(pool, args.to_request()?, &queries, Some(&log), |i, outcome| {
+            let _out_span = log.span(&QueryPhase::Output).entered();
+            any_rows |= !outcome.rows.is_empty();
+            log.info(format!("🔍 {:?}: {} result(s)", queries[i], outcome.rows.len()));
+            log_rows(&log, &outcome.rows, show_context);
+            match (&template, stream.as_mut()) {
+                (Some(t), _) => print_rows(t, &outcome.rows),
+                (None, Some(stream)) => stream.push(&QueryBatchEntry { query: &queries[i], rows: &outcome.rows, explain: outcome.explain.as_ref() }),
+                (None, None) => Ok(()),
+            }
+        })
+        .await?;
+        if let Some(stream) = stream { stream.finish()?; }
+        if !any_rows { exit::mark_empty(); }
+        return Ok(());
+    }
+
+    let outcome = service::execute(pool, args.to_request()?, Some(&log)).await?;
+
+    if outcome.rows.is_empty() && outcome.explain.is_none() {
+        exit::mark_empty();
         return Ok(());
     }
 
@@ -80,17 +213,52 @@ pub async fn run(pool: &PgPool, args: QueryCmd) -> Result<()> {
     let _out_span = log.span(&QueryPhase::Output).entered();
     // Always log human-readable results
     log.info("🔍 Results:");
-    for r in &outcome.rows {
+    log_rows(&log, &outcome.rows, show_context);
+    // --template replaces the structured result with the user's own lines
+    if let Some(t) = &template {
+        return print_rows(t, &outcome.rows);
+    }
+    // Emit structured result to stdout (presenter-selected)
+    match &outcome.explain {
+        None => {
+            let mut stream = log.result_stream();
+            for row in &outcome.rows { stream.push(row)?; }
+            stream.finish()?;
+        }
+        Some(explain) => {
+            #[derive(Serialize)]
+            struct QueryExplainResult<'a> { rows: &'a [QueryResultRow], explain: &'a post::ExplainInfo }
+            log.result(&QueryExplainResult { rows: &outcome.rows, explain })?;
+        }
+    }
+
+    Ok(())
+}
+
+// one query per line; blank lines and #-comments are skipped
+pub fn parse_queries(raw: &str) -> Vec<String> {
+    raw.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+fn print_rows(template: &RowTemplate, rows: &[QueryResultRow]) -> Result<()> {
+    for r in rows { telemetry::emit::print_line(&template.render(r))?; }
+    Ok(())
+}
+
+fn log_rows(log: &LogCtx<QueryOp>, rows: &[QueryResultRow], show_context: bool) {
+    for r in rows {
         log.info(format!(
-            "#{}  dist={:.4}  chunk={} doc={}  {:?}",
-            r.rank, r.distance, r.chunk_id, r.doc_id, r.title
+            "#{}  dist={:.4}{}  chunk={} doc={}  {:?}",
+            r.rank, r.distance,
+            r.rerank_score.map(|s| format!("  score={:.2}", s)).unwrap_or_default(),
+            r.chunk_id, r.doc_id, r.title
         ));
-        if args.show_context {
+        if show_context {
             if let Some(p) = &r.preview { log.info(format!("  {}", p.replace('\n', " "))); }
         }
     }
-    // Emit structured result to stdout (presenter-selected)
-    log.result(&outcome.rows)?;
-
-    Ok(())
 }
@@ -1,8 +1,9 @@
-use serde::Serialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
 use super::db::CandRow;
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
 pub struct QueryResultRow {
     pub rank: usize,
     pub distance: f32,
@@ -10,12 +11,49 @@ pub struct QueryResultRow {
     pub doc_id: i64,
     pub title: Option<String>,
     pub preview: Option<String>,
+    // full chunk text (query --full-text)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    // LLM relevance score (query --rerank-llm)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rerank_score: Option<f32>,
 }
 
-pub fn shape_results(candidates: Vec<CandRow>, topk: usize, doc_cap: usize) -> Vec<QueryResultRow> {
+// query --explain: knobs used plus what the planner actually did
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct ExplainInfo {
+    pub probes: Option<i32>,
+    pub ef_search: Option<i32>,
+    pub scan: String,
+    pub index: Option<String>,
+    pub execution_ms: Option<f64>,
+    pub plan: Vec<String>,
+}
+
+// Pull scan type ("index" | "seq" | "other"), index name, and execution time out of EXPLAIN text
+pub fn summarize_plan(plan: Vec<String>, probes: Option<i32>, ef_search: Option<i32>) -> ExplainInfo {
+    let mut scan = "other".to_string();
+    let mut index = None;
+    for line in &plan {
+        if let Some(rest) = line.split("Index Scan using ").nth(1) {
+            scan = "index".to_string();
+            index = rest.split_whitespace().next().map(str::to_string);
+            break;
+        }
+        if line.contains("Seq Scan") && scan == "other" { scan = "seq".to_string(); }
+    }
+    let execution_ms = plan.iter().find_map(|l| {
+        l.trim().strip_prefix("Execution Time:")
+            .and_then(|r| r.trim().trim_end_matches("ms").trim().parse::<f64>().ok())
+    });
+    ExplainInfo { probes, ef_search, scan, index, execution_ms, plan }
+}
+
+pub fn shape_results(candidates: Vec<CandRow>, topk: usize, doc_cap: usize, max_distance: Option<f32>) -> Vec<QueryResultRow> {
     let mut per_doc_seen: std::collections::HashMap<i64, usize> = std::collections::HashMap::new();
     let mut out: Vec<QueryResultRow> = Vec::new();
     for row in candidates.into_iter() {
+        if max_distance.is_some_and(|d| row.distance > d) { continue; }
         let seen = per_doc_seen.entry(row.doc_id).or_insert(0);
         if *seen >= doc_cap { continue; }
         *seen += 1;
@@ -26,9 +64,219 @@ pub fn shape_results(candidates: Vec<CandRow>, topk: usize, doc_cap: usize) -> V
             doc_id: row.doc_id,
             title: row.title,
             preview: row.preview,
+            text: row.text,
+            rerank_score: None,
         });
         if out.len() >= topk { break; }
     }
     out
 }
 
+/// query --dedup-results: drop rows whose chunk SimHash is within `max_bits` of a better-ranked
+/// kept row, then keep the first `topk` and renumber ranks. Rows without a fingerprint are kept.
+/// Returns the kept rows and how many were dropped as near-duplicates.
+pub fn dedup_results(rows: Vec<QueryResultRow>, fingerprints: &std::collections::HashMap<i64, i64>, max_bits: u32, topk: usize) -> (Vec<QueryResultRow>, usize) {
+    let mut kept_fps: Vec<i64> = Vec::new();
+    let mut out: Vec<QueryResultRow> = Vec::new();
+    let mut dropped = 0;
+    for mut row in rows.into_iter() {
+        if out.len() >= topk { break; }
+        if let Some(&fp) = fingerprints.get(&row.chunk_id) {
+            if kept_fps.iter().any(|k| (k ^ fp).count_ones() <= max_bits) { dropped += 1; continue; }
+            kept_fps.push(fp);
+        }
+        row.rank = out.len() + 1;
+        out.push(row);
+    }
+    (out, dropped)
+}
+
+/// Whether a larger candidate pool could still fill `topk`: fewer rows survive doc_cap/max_distance,
+/// the pool came back full (more rows exist), and candidates are nearest-first, so the farthest one
+/// must still be within max_distance for anything beyond it to qualify.
+pub fn should_overfetch(candidates: &[CandRow], top_n: i64, topk: usize, doc_cap: usize, max_distance: Option<f32>) -> bool {
+    if (candidates.len() as i64) < top_n { return false; }
+    if candidates.last().is_some_and(|c| max_distance.is_some_and(|d| c.distance > d)) { return false; }
+    let mut per_doc_seen: std::collections::HashMap<i64, usize> = std::collections::HashMap::new();
+    let kept = candidates
+        .iter()
+        .filter(|c| max_distance.is_none_or(|d| c.distance <= d))
+        .filter(|c| {
+            let seen = per_doc_seen.entry(c.doc_id).or_insert(0);
+            *seen += 1;
+            *seen <= doc_cap
+        })
+        .count();
+    kept < topk
+}
+
+/// Effective distance cutoff from `--max-distance` and/or `--min-similarity` (stricter wins).
+/// Embeddings are L2-normalized, so L2 distance d and cosine similarity s satisfy d = sqrt(2(1 - s)).
+pub fn distance_cutoff(max_distance: Option<f32>, min_similarity: Option<f32>) -> Option<f32> {
+    let from_sim = min_similarity.map(|s| (2.0 * (1.0 - s.clamp(-1.0, 1.0))).sqrt());
+    match (max_distance, from_sim) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Neighbor expansion plan: for each hit (in rank order) the chunk indexes to stitch,
+/// i.e. `[i-n, i+n]` minus indexes already claimed by a better-ranked hit in the same doc.
+/// `None` means the hit's own chunk was already covered and the hit is redundant.
+pub fn plan_neighbor_expansion(hits: &[(i64, Option<i32>)], n: i32) -> Vec<Option<Vec<i32>>> {
+    let mut claimed: std::collections::HashSet<(i64, i32)> = std::collections::HashSet::new();
+    let mut out = Vec::with_capacity(hits.len());
+    for &(doc_id, idx) in hits {
+        let Some(idx) = idx else { out.push(Some(Vec::new())); continue; };
+        if claimed.contains(&(doc_id, idx)) { out.push(None); continue; }
+        let lo = (idx - n).max(0);
+        let range: Vec<i32> = (lo..=idx + n).filter(|i| !claimed.contains(&(doc_id, *i))).collect();
+        for i in &range { claimed.insert((doc_id, *i)); }
+        out.push(Some(range));
+    }
+    out
+}
+
+// Query terms used for highlighting: lowercase alphanumeric words of 3+ chars, deduped
+fn query_terms(query: &str) -> Vec<Vec<char>> {
+    let mut terms: Vec<Vec<char>> = Vec::new();
+    for w in query.split(|c: char| !c.is_alphanumeric()) {
+        let t: Vec<char> = w.chars().flat_map(|c| c.to_lowercase()).collect();
+        if t.len() >= 3 && !terms.contains(&t) { terms.push(t); }
+    }
+    terms
+}
+
+/// Preview of `chars` characters centered on the window that overlaps the most
+/// distinct query terms; falls back to the leading characters when none match.
+pub fn highlight_preview(text: &str, query: &str, chars: usize) -> String {
+    let orig: Vec<char> = text.chars().collect();
+    if chars == 0 { return String::new(); }
+    if orig.len() <= chars { return text.to_string(); }
+    // 1:1 lowercase mapping keeps indices aligned with `orig`
+    let lower: Vec<char> = orig.iter().map(|c| c.to_lowercase().next().unwrap_or(*c)).collect();
+    let terms = query_terms(query);
+
+    // (position, term index) for every term occurrence
+    let mut hits: Vec<(usize, usize)> = Vec::new();
+    for (ti, t) in terms.iter().enumerate() {
+        if t.len() > lower.len() { continue; }
+        for i in 0..=(lower.len() - t.len()) {
+            if lower[i..i + t.len()] == t[..] { hits.push((i, ti)); }
+        }
+    }
+    if hits.is_empty() { return orig[..chars].iter().collect(); }
+
+    let mut best = (0usize, 0usize); // (score, start)
+    for &(pos, ti) in &hits {
+        let start = (pos + terms[ti].len() / 2).saturating_sub(chars / 2).min(orig.len() - chars);
+        let end = start + chars;
+        let mut seen: Vec<usize> = Vec::new();
+        for &(p, tj) in &hits {
+            if p >= start && p + terms[tj].len() <= end && !seen.contains(&tj) { seen.push(tj); }
+        }
+        if seen.len() > best.0 { best = (seen.len(), start); }
+    }
+
+    let start = best.1;
+    let mut out = String::new();
+    if start > 0 { out.push('…'); }
+    out.extend(&orig[start..start + chars]);
+    if start + chars < orig.len() { out.push('…'); }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn highlight_centers_on_matching_terms() {
+        let text = format!("{} pgvector uses ivfflat lists {}", "a".repeat(200), "b".repeat(200));
+        let p = highlight_preview(&text, "How many IVFFlat lists?", 40);
+        assert!(p.starts_with('…') && p.ends_with('…'));
+        assert!(p.contains("ivfflat lists"));
+    }
+
+    fn cand(chunk_id: i64, doc_id: i64, distance: f32) -> CandRow {
+        CandRow { chunk_id, doc_id, chunk_index: None, title: None, preview: None, text: None, distance }
+    }
+
+    #[test]
+    fn shape_drops_candidates_beyond_cutoff() {
+        let rows = shape_results(vec![cand(1, 1, 0.2), cand(2, 2, 0.9), cand(3, 3, 0.4)], 10, 2, Some(0.5));
+        let ids: Vec<i64> = rows.iter().map(|r| r.chunk_id).collect();
+        assert_eq!(ids, vec![1, 3]);
+        assert_eq!(rows[1].rank, 2);
+    }
+
+    #[test]
+    fn dedup_drops_near_identical_lower_ranked_rows() {
+        let rows = shape_results(vec![cand(1, 1, 0.1), cand(2, 2, 0.2), cand(3, 3, 0.3), cand(4, 4, 0.4)], 10, 2, None);
+        // 2 is one bit off 1, 3 is far from both, 4 has no text to fingerprint
+        let fps = std::collections::HashMap::from([(1, 0b1111_0000), (2, 0b1111_0001), (3, !0b1111_0000)]);
+        let (kept, dropped) = dedup_results(rows.clone(), &fps, 3, 10);
+        assert_eq!(kept.iter().map(|r| r.chunk_id).collect::<Vec<_>>(), vec![1, 3, 4]);
+        assert_eq!(kept.iter().map(|r| r.rank).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(dropped, 1);
+        // the freed slot is refilled from lower-ranked rows before topk is applied
+        let (kept, _) = dedup_results(rows.clone(), &fps, 3, 2);
+        assert_eq!(kept.iter().map(|r| r.chunk_id).collect::<Vec<_>>(), vec![1, 3]);
+        // threshold 0 only collapses exact fingerprint matches
+        assert_eq!(dedup_results(rows, &fps, 0, 10).1, 0);
+    }
+
+    #[test]
+    fn overfetch_only_when_doc_cap_starves_a_full_pool() {
+        // one doc owns the whole pool: doc_cap=2 leaves 2 of topk=3
+        let dominated = vec![cand(1, 1, 0.1), cand(2, 1, 0.2), cand(3, 1, 0.3), cand(4, 1, 0.4)];
+        assert!(should_overfetch(&dominated, 4, 3, 2, None));
+        // pool not full: the corpus is exhausted
+        assert!(!should_overfetch(&dominated, 10, 3, 2, None));
+        // farthest candidate already past the cutoff: more rows can't qualify
+        assert!(!should_overfetch(&dominated, 4, 3, 2, Some(0.35)));
+        // enough distinct docs
+        let mixed = vec![cand(1, 1, 0.1), cand(2, 2, 0.2), cand(3, 3, 0.3), cand(4, 1, 0.4)];
+        assert!(!should_overfetch(&mixed, 4, 3, 2, None));
+    }
+
+    #[test]
+    fn summarize_plan_finds_index_and_timing() {
+        let plan = vec![
+            "Limit  (cost=1.00..2.00 rows=100 width=64) (actual time=0.5..1.2 rows=100 loops=1)".to_string(),
+            "  ->  Index Scan using embedding_vec_ivf_idx on embedding e  (cost=...)".to_string(),
+            "Planning Time: 0.210 ms".to_string(),
+            "Execution Time: 1.234 ms".to_string(),
+        ];
+        let info = summarize_plan(plan, Some(15), None);
+        assert_eq!(info.scan, "index");
+        assert_eq!(info.index.as_deref(), Some("embedding_vec_ivf_idx"));
+        assert_eq!(info.execution_ms, Some(1.234));
+
+        let seq = summarize_plan(vec!["Seq Scan on embedding e".to_string()], None, None);
+        assert_eq!(seq.scan, "seq");
+    }
+
+    #[test]
+    fn cutoff_combines_distance_and_similarity() {
+        assert_eq!(distance_cutoff(None, None), None);
+        assert!((distance_cutoff(None, Some(0.5)).unwrap() - 1.0).abs() < 1e-6);
+        assert_eq!(distance_cutoff(Some(0.3), Some(0.5)), Some(0.3));
+    }
+
+    #[test]
+    fn neighbor_expansion_dedups_within_doc() {
+        // hits: doc 1 idx 5, doc 1 idx 6 (covered by first), doc 1 idx 9, doc 2 idx 0
+        let plan = plan_neighbor_expansion(&[(1, Some(5)), (1, Some(6)), (1, Some(9)), (2, Some(0))], 1);
+        assert_eq!(plan[0], Some(vec![4, 5, 6]));
+        assert_eq!(plan[1], None);
+        assert_eq!(plan[2], Some(vec![8, 9, 10]));
+        assert_eq!(plan[3], Some(vec![0, 1]));
+    }
+
+    #[test]
+    fn highlight_falls_back_to_leading_chars() {
+        assert_eq!(highlight_preview("hello world", "nothing", 5), "hello");
+        assert_eq!(highlight_preview("short", "short", 300), "short");
+    }
+}
@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+
+use crate::llm::openai::{ChatCompletionRequest, ChatMessage, ChatRole, LlmClient, OpenAiError};
+
+use super::db::CandRow;
+
+const SYSTEM_PROMPT: &str = "You grade search results. Given a query and a passage, reply with a single number between 0 and 1: how well the passage answers or is relevant to the query. Reply with the number only.";
+
+// passages are cut so one long chunk can't blow up a scoring call
+const MAX_PASSAGE_CHARS: usize = 4000;
+
+fn scoring_request(model: Option<&str>, query: &str, passage: &str) -> ChatCompletionRequest {
+    let passage: String = passage.chars().take(MAX_PASSAGE_CHARS).collect();
+    ChatCompletionRequest {
+        model: model.map(str::to_string),
+        messages: vec![
+            ChatMessage::new(ChatRole::System, SYSTEM_PROMPT),
+            ChatMessage::new(ChatRole::User, format!("Query:\n{query}\n\nPassage:\n{passage}\n\nRelevance (0-1):")),
+        ],
+        max_tokens: Some(8),
+        temperature: Some(0.0),
+        top_p: None,
+        response_format: None,
+        seed: None,
+    }
+}
+
+/// First number in the reply, clamped to [0, 1].
+pub fn parse_score(content: &str) -> Option<f32> {
+    content
+        .split(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .find_map(|t| t.parse::<f32>().ok())
+        .map(|s| s.clamp(0.0, 1.0))
+}
+
+/// LLM relevance score per chunk_id. Unparseable replies and transient errors leave a
+/// passage unscored (counted in the second value); a missing API key fails the query.
+pub async fn score_passages(
+    client: &dyn LlmClient,
+    model: Option<&str>,
+    query: &str,
+    passages: &[(i64, &str)],
+) -> Result<(HashMap<i64, f32>, usize)> {
+    let mut scores = HashMap::new();
+    let mut failed = 0usize;
+    for (chunk_id, text) in passages {
+        match client.chat_completion(scoring_request(model, query, text)).await {
+            Ok(resp) => match parse_score(&resp.content) {
+                Some(s) => { scores.insert(*chunk_id, s); }
+                None => failed += 1,
+            },
+            Err(e @ OpenAiError::MissingApiKey) => return Err(anyhow::Error::new(e)),
+            Err(_) => failed += 1,
+        }
+    }
+    Ok((scores, failed))
+}
+
+/// Scored candidates first (highest score first, ANN order on ties), then the rest in ANN order.
+pub fn reorder(candidates: Vec<CandRow>, scores: &HashMap<i64, f32>) -> Vec<CandRow> {
+    let (mut scored, rest): (Vec<CandRow>, Vec<CandRow>) =
+        candidates.into_iter().partition(|c| scores.contains_key(&c.chunk_id));
+    scored.sort_by(|a, b| scores[&b.chunk_id].total_cmp(&scores[&a.chunk_id]));
+    scored.extend(rest);
+    scored
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::openai::{ChatCompletionResponse, MockClient};
+
+    fn cand(chunk_id: i64, distance: f32) -> CandRow {
+        CandRow { chunk_id, doc_id: chunk_id, chunk_index: Some(0), title: None, preview: None, text: Some(format!("text {chunk_id}")), distance }
+    }
+
+    fn reply(s: &str) -> Result<ChatCompletionResponse, OpenAiError> {
+        Ok(ChatCompletionResponse { content: s.into(), raw: serde_json::Value::Null, usage: None })
+    }
+
+    #[test]
+    fn parses_first_number() {
+        assert_eq!(parse_score("0.8"), Some(0.8));
+        assert_eq!(parse_score("Score: 0.35."), Some(0.35));
+        assert_eq!(parse_score("7"), Some(1.0));
+        assert_eq!(parse_score("relevant"), None);
+    }
+
+    #[tokio::test]
+    async fn scores_then_reorders() {
+        let mock = MockClient::new();
+        mock.push_response(reply("0.1"));
+        mock.push_response(reply("0.9"));
+        mock.push_response(reply("n/a"));
+        let cands = vec![cand(1, 0.1), cand(2, 0.2), cand(3, 0.3), cand(4, 0.4)];
+        let passages: Vec<(i64, &str)> = cands.iter().take(3).map(|c| (c.chunk_id, c.text.as_deref().unwrap())).collect();
+
+        let (scores, failed) = score_passages(&mock, None, "q", &passages).await.unwrap();
+        assert_eq!(failed, 1);
+        assert_eq!(mock.calls().len(), 3);
+        let order: Vec<i64> = reorder(cands, &scores).iter().map(|c| c.chunk_id).collect();
+        assert_eq!(order, vec![2, 1, 3, 4]);
+    }
+}
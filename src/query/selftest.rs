@@ -0,0 +1,152 @@
+// rag selftest-embed: embed one string and look at its nearest and farthest stored chunks,
+// a quick check that the encoder produces sensible vectors and the index returns neighbours.
+
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use serde::Serialize;
+use sqlx::PgPool;
+
+use crate::encoder::{traits::Embedder, Device, E5Encoder, Prefixes};
+use crate::pipeline::embed::model_tag;
+use crate::telemetry::{self};
+use crate::telemetry::ops::selftest::Phase as SelftestPhase;
+use crate::util::exit;
+use crate::util::index::vec_column_type;
+
+use super::db;
+
+// L2 distances between unit vectors lie in [0, 2]; a healthy model spreads real text well
+// apart, while a broken one maps everything to (nearly) the same point.
+const MIN_SPREAD: f32 = 0.1;
+const NORM_TOLERANCE: f32 = 1e-3;
+
+#[derive(Args, Debug)]
+pub struct SelftestEmbedCmd {
+    /// Text to embed (as a query)
+    #[arg(long, default_value = "how do transformers use attention")] text: String,
+    /// Nearest and farthest chunks to show
+    #[arg(long, default_value_t = 3)] k: i64,
+    #[arg(long, env = "RAG_MODEL_ID", default_value = "intfloat/e5-small-v2")] model_id: String,
+    #[arg(long)] onnx_filename: Option<String>,
+    #[arg(long, env = "RAG_DEVICE", value_enum, default_value_t = Device::Cpu)] device: Device,
+    #[arg(long)] max_seq_len: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct SelftestHit { chunk_id: i64, distance: f32, title: Option<String>, preview: Option<String> }
+
+#[derive(Serialize)]
+struct SelftestResult {
+    model: String,
+    dim: usize,
+    db_dim: usize,
+    norm: f32,
+    nearest: Vec<SelftestHit>,
+    farthest: Vec<SelftestHit>,
+    spread: Option<f32>,
+    healthy: bool,
+    problems: Vec<String>,
+}
+
+/// What looks wrong with one self-test run; empty when the vectors look sane.
+pub fn assess(dim: usize, db_dim: usize, norm: f32, nearest: Option<f32>, farthest: Option<f32>) -> Vec<String> {
+    let mut problems = Vec::new();
+    if dim != db_dim {
+        problems.push(format!("query vector dim={} but stored vectors have dim={}", dim, db_dim));
+    }
+    if !norm.is_finite() || (norm - 1.0).abs() > NORM_TOLERANCE {
+        problems.push(format!("query vector norm={} (expected 1.0 after L2 normalization)", norm));
+    }
+    if let (Some(near), Some(far)) = (nearest, farthest) {
+        if far - near < MIN_SPREAD {
+            problems.push(format!("nearest={:.4} and farthest={:.4} are nearly uniform; the model may be broken or mismatched", near, far));
+        }
+    }
+    problems
+}
+
+pub async fn run(pool: &PgPool, args: SelftestEmbedCmd) -> Result<()> {
+    let log = telemetry::selftest_embed();
+    let _g = log.root_span_kv([
+        ("model_id", args.model_id.clone()),
+        ("device", format!("{:?}", args.device)),
+        ("k", args.k.to_string()),
+    ]).entered();
+
+    let tag = model_tag(&args.model_id, args.device);
+    let db_dim = sqlx::query_scalar!("SELECT dim FROM rag.embedding LIMIT 1").fetch_optional(pool).await?;
+    let Some(db_dim) = db_dim else {
+        log.info("ℹ️  No embeddings found. Run `rag embed` first.");
+        exit::mark_empty();
+        return Ok(());
+    };
+    let db_dim = db_dim as usize;
+
+    let mut enc: Box<dyn Embedder> = {
+        let _s = log.span(&SelftestPhase::LoadModel).entered();
+        Box::new(E5Encoder::new(&args.model_id, args.onnx_filename.as_deref(), args.device, Prefixes::from_env(), args.max_seq_len).context("init encoder")?)
+    };
+    let qvec = { let _s = log.span(&SelftestPhase::Embed).entered(); enc.embed_query(&args.text).context("embed text")? };
+    let norm = qvec.iter().map(|x| x * x).sum::<f32>().sqrt();
+    log.info(format!("🩺 {} → dim={} (stored dim={}) norm={:.4}", tag, qvec.len(), db_dim, norm));
+
+    // distances against vectors of another width are meaningless (and pgvector rejects them)
+    let (nearest, farthest) = if qvec.len() == db_dim {
+        let vector_type = vec_column_type(pool).await?.unwrap_or_default();
+        let nearest = { let _s = log.span(&SelftestPhase::Nearest).entered(); db::fetch_nearest(pool, &qvec, &tag, args.k, 80, vector_type).await? };
+        let farthest = { let _s = log.span(&SelftestPhase::Farthest).entered(); db::fetch_farthest(pool, &qvec, &tag, args.k, vector_type).await? };
+        (
+            nearest.into_iter().map(|c| SelftestHit { chunk_id: c.chunk_id, distance: c.distance, title: c.title, preview: c.preview }).collect::<Vec<_>>(),
+            farthest.into_iter().map(|(chunk_id, distance)| SelftestHit { chunk_id, distance, title: None, preview: None }).collect::<Vec<_>>(),
+        )
+    } else {
+        (Vec::new(), Vec::new())
+    };
+
+    log.info("🔍 Nearest:");
+    for h in &nearest { log.info(format!("  {:.4}  chunk_id={}  {}", h.distance, h.chunk_id, h.title.as_deref().unwrap_or(""))); }
+    log.info("🔍 Farthest:");
+    for h in &farthest { log.info(format!("  {:.4}  chunk_id={}", h.distance, h.chunk_id)); }
+
+    let near = nearest.first().map(|h| h.distance);
+    let far = farthest.first().map(|h| h.distance);
+    let problems = assess(qvec.len(), db_dim, norm, near, far);
+    for p in &problems { log.warn(format!("⚠️  {}", p)); }
+    if problems.is_empty() { log.info("✅ Embeddings look healthy"); }
+
+    let res = SelftestResult {
+        model: tag,
+        dim: qvec.len(),
+        db_dim,
+        norm,
+        nearest,
+        farthest,
+        spread: near.zip(far).map(|(n, f)| f - n),
+        healthy: problems.is_empty(),
+        problems,
+    };
+    log.result(&res)?;
+    if !res.healthy { bail!("embedding self-test failed: {}", res.problems.join("; ")); }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sane_vectors_pass() {
+        assert!(assess(384, 384, 1.0, Some(0.55), Some(1.35)).is_empty());
+        // an empty table gives no distances to judge
+        assert!(assess(384, 384, 0.9999, None, None).is_empty());
+    }
+
+    #[test]
+    fn dim_norm_and_uniform_distances_are_flagged() {
+        assert_eq!(assess(768, 384, 1.0, None, None).len(), 1);
+        assert_eq!(assess(384, 384, 0.5, Some(0.5), Some(1.2)).len(), 1);
+        assert_eq!(assess(384, 384, f32::NAN, Some(0.5), Some(1.2)).len(), 1);
+        let p = assess(384, 384, 1.0, Some(1.40), Some(1.43));
+        assert!(p[0].contains("nearly uniform"));
+    }
+}
@@ -1,30 +1,114 @@
 use anyhow::{bail, Context, Result};
 use chrono::{DateTime, Utc};
-use sqlx::{Acquire, PgPool};
+use sqlx::{Acquire, PgConnection, PgPool};
 use std::collections::HashMap;
 use tracing::span::EnteredSpan;
 
-use crate::encoder::{traits::Embedder, Device, E5Encoder};
+use crate::encoder::{traits::Embedder, Device, E5Encoder, Prefixes};
+use crate::ingestion::simhash::simhash;
+use crate::llm::openai::{OpenAiClient, OpenAiClientConfig};
 use crate::telemetry::ctx::LogCtx;
 use crate::telemetry::ops::query::{Phase as QueryPhase, Query as QueryOp};
+use crate::pipeline::embed::model_tag;
+use crate::util::exit::ConfigError;
+use crate::util::index::{vec_column_type, IndexType, VectorType};
+use crate::util::slow::timed;
 
 use super::db::{self, CandRow, FetchOpts};
 use super::post;
+use super::rerank;
 use super::QueryResultRow;
 
+/// Retrieval granularity: chunks directly, or doc centroids first and then the best chunks within the top docs.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum QueryLevel {
+    #[default]
+    Chunk,
+    Doc,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct QueryRequest<'a> {
     pub query: &'a str,
     pub top_n: i64,
     pub topk: usize,
     pub doc_cap: usize,
     pub probes: Option<i32>,
+    pub ef_search: Option<i32>,
     pub feed: Option<i32>,
     pub since: Option<DateTime<Utc>>,
+    pub title_like: Option<&'a str>,
+    pub feed_name: Option<&'a str>,
+    // drop candidates farther than this (see post::distance_cutoff)
+    pub max_distance: Option<f32>,
     pub include_preview: bool,
     pub include_text: bool,
+    pub preview_chars: usize,
+    pub highlight: bool,
+    // stitch N chunks before/after each hit into its text (needs include_text)
+    pub expand_neighbors: usize,
+    // also run EXPLAIN (ANALYZE, BUFFERS) on the candidate SQL
+    pub explain: bool,
+    // LLM-score the first rerank_candidates ANN candidates and reorder by score before topk
+    pub rerank_llm: bool,
+    pub rerank_candidates: usize,
+    pub rerank_model: Option<&'a str>,
+    // double top_n up to this many times while doc_cap/max_distance leave fewer than topk rows
+    pub overfetch_rounds: usize,
+    // doc: rank rag.doc_embedding centroids, keep the best ceil(topk/doc_cap) docs, then rank their chunks
+    pub level: QueryLevel,
+    // fail instead of warn when no stored embedding carries the query's model tag
+    pub strict_model: bool,
+    // drop results whose chunk SimHash is within dedup_bits of a better-ranked result (post::dedup_results)
+    pub dedup_results: bool,
+    pub dedup_bits: u32,
+    // two-stage search: Hamming pre-filter over sign-bit codes keeps quantized_pool rows, then exact re-rank
+    pub quantized: bool,
+    pub quantized_pool: usize,
     pub model_id: &'a str,
     pub onnx_filename: Option<&'a str>,
     pub device: Device,
+    // tokenizer truncation; None keeps model_max_length
+    pub max_seq_len: Option<usize>,
+}
+
+impl<'a> QueryRequest<'a> {
+    /// Request with the same defaults as `rag query <text>`.
+    pub fn new(query: &'a str) -> Self {
+        Self {
+            query,
+            top_n: 100,
+            topk: 6,
+            doc_cap: 2,
+            probes: None,
+            ef_search: None,
+            feed: None,
+            since: None,
+            title_like: None,
+            feed_name: None,
+            max_distance: None,
+            include_preview: false,
+            include_text: false,
+            preview_chars: 300,
+            highlight: false,
+            expand_neighbors: 0,
+            explain: false,
+            rerank_llm: false,
+            rerank_candidates: 20,
+            rerank_model: None,
+            overfetch_rounds: 2,
+            level: QueryLevel::Chunk,
+            strict_model: false,
+            dedup_results: false,
+            dedup_bits: 3,
+            quantized: false,
+            quantized_pool: 1000,
+            model_id: "intfloat/e5-small-v2",
+            onnx_filename: None,
+            device: Device::Cpu,
+            max_seq_len: None,
+        }
+    }
 }
 
 pub struct QueryHit {
@@ -32,6 +116,7 @@ pub struct QueryHit {
     pub distance: f32,
     pub chunk_id: i64,
     pub doc_id: i64,
+    pub chunk_index: Option<i32>,
     pub title: Option<String>,
     pub preview: Option<String>,
     pub text: Option<String>,
@@ -41,6 +126,16 @@ pub struct QueryOutcome {
     pub rows: Vec<QueryResultRow>,
     pub hits: Vec<QueryHit>,
     pub probes: Option<i32>,
+    pub ef_search: Option<i32>,
+    // candidates dropped by max_distance
+    pub too_distant: usize,
+    pub explain: Option<post::ExplainInfo>,
+}
+
+impl QueryOutcome {
+    fn empty() -> Self {
+        QueryOutcome { rows: Vec::new(), hits: Vec::new(), probes: None, ef_search: None, too_distant: 0, explain: None }
+    }
 }
 
 pub async fn execute(
@@ -48,39 +143,162 @@ pub async fn execute(
     req: QueryRequest<'_>,
     log: Option<&LogCtx<QueryOp>>,
 ) -> Result<QueryOutcome> {
-    // ensure embeddings exist to learn dim
+    let Some((mut enc, db_dim)) = load_encoder(pool, &req, log).await? else {
+        return Ok(QueryOutcome::empty());
+    };
+
+    let _embed_span = enter_span(log, &QueryPhase::EmbedQuery);
+    let qvec = enc.embed_query(req.query).context("embed query")?;
+    if qvec.len() != db_dim {
+        bail!("query embedding dim={} != DB dim={}", qvec.len(), db_dim);
+    }
+    drop(_embed_span);
+
+    let knobs = resolve_knobs(pool, &req, log).await?;
+    let vector_type = vec_column_type(pool).await?.unwrap_or_default();
+    retrieve(pool, &req, &qvec, knobs, vector_type, log).await
+}
+
+/// Same settings for every query; all queries are embedded in one encoder batch.
+/// Outcomes come back in input order (`req.query` is ignored).
+pub async fn execute_many(
+    pool: &PgPool,
+    req: QueryRequest<'_>,
+    queries: &[String],
+    log: Option<&LogCtx<QueryOp>>,
+) -> Result<Vec<QueryOutcome>> {
+    let mut out = Vec::with_capacity(queries.len());
+    execute_each(pool, req, queries, log, |_, outcome| { out.push(outcome); Ok(()) }).await?;
+    Ok(out)
+}
+
+/// `execute_many`, handing each outcome to `on_outcome` (with its input index) as soon as
+/// it is retrieved instead of collecting them.
+pub async fn execute_each<F>(
+    pool: &PgPool,
+    req: QueryRequest<'_>,
+    queries: &[String],
+    log: Option<&LogCtx<QueryOp>>,
+    mut on_outcome: F,
+) -> Result<()>
+where
+    F: FnMut(usize, QueryOutcome) -> Result<()>,
+{
+    if queries.is_empty() { return Ok(()); }
+    let Some((mut enc, db_dim)) = load_encoder(pool, &req, log).await? else {
+        for i in 0..queries.len() { on_outcome(i, QueryOutcome::empty())?; }
+        return Ok(());
+    };
+
+    let _embed_span = enter_span(log, &QueryPhase::EmbedQuery);
+    let qvecs = enc.embed_queries(queries).context("embed queries")?;
+    if let Some(v) = qvecs.iter().find(|v| v.len() != db_dim) {
+        bail!("query embedding dim={} != DB dim={}", v.len(), db_dim);
+    }
+    drop(_embed_span);
+
+    let knobs = resolve_knobs(pool, &req, log).await?;
+    let vector_type = vec_column_type(pool).await?.unwrap_or_default();
+    for (i, (query, qvec)) in queries.iter().zip(qvecs.iter()).enumerate() {
+        let one = QueryRequest { query, ..req.clone() };
+        on_outcome(i, retrieve(pool, &one, qvec, knobs, vector_type, log).await?)?;
+    }
+    Ok(())
+}
+
+// ensure embeddings exist to learn dim, then build the encoder; None when nothing is embedded yet
+async fn load_encoder(
+    pool: &PgPool,
+    req: &QueryRequest<'_>,
+    log: Option<&LogCtx<QueryOp>>,
+) -> Result<Option<(Box<dyn Embedder>, usize)>> {
     let _prepare_span = enter_span(log, &QueryPhase::Prepare);
     let dim_row = sqlx::query!("SELECT dim FROM rag.embedding LIMIT 1")
         .fetch_optional(pool)
         .await?;
-    if dim_row.is_none() {
+    let Some(dim_row) = dim_row else {
         if let Some(ctx) = log {
             ctx.info("ℹ️  No embeddings found. Run `rag embed` first.");
         }
-        return Ok(QueryOutcome { rows: Vec::new(), hits: Vec::new(), probes: None });
+        return Ok(None);
+    };
+    let db_dim = dim_row.dim as usize;
+    check_model(pool, req, log).await?;
+    if req.quantized && db::has_unquantized(pool).await? {
+        if let Some(ctx) = log {
+            ctx.warn("⚠️  Some embeddings have no binary code (vec_bits) and are skipped by --quantized; run migrations (they backfill the codes) or re-embed them");
+        }
     }
-    let db_dim = dim_row.unwrap().dim as usize;
-    drop(_prepare_span);
 
-    // build encoder and embed the query
-    let _encoder_span = enter_span(log, &QueryPhase::Prepare);
-    let mut enc: Box<dyn Embedder> = Box::new(
-        E5Encoder::new(req.model_id, req.onnx_filename, req.device).context("init encoder")?,
+    let enc: Box<dyn Embedder> = Box::new(
+        E5Encoder::new(req.model_id, req.onnx_filename, req.device, Prefixes::from_env(), req.max_seq_len).context("init encoder")?,
     );
-    drop(_encoder_span);
+    Ok(Some((enc, db_dim)))
+}
 
-    let _embed_span = enter_span(log, &QueryPhase::EmbedQuery);
-    let qvec = enc.embed_query(req.query).context("embed query")?;
-    if qvec.len() != db_dim {
-        bail!("query embedding dim={} != DB dim={}", qvec.len(), db_dim);
-    }
-    drop(_embed_span);
+/// Model id part of a `model@onnx-device` tag.
+fn model_id_of(tag: &str) -> &str {
+    tag.rsplit_once('@').map_or(tag, |(m, _)| m)
+}
 
-    // set probes
-    let probes = match req.probes {
-        Some(p) => Some(p.max(1)),
-        None => db::recommend_probes(pool).await?,
-    };
+// Query vectors only rank correctly against passages from the same model; the device suffix
+// of the tag doesn't change the vectors, so a match on the model id alone is accepted.
+async fn check_model(pool: &PgPool, req: &QueryRequest<'_>, log: Option<&LogCtx<QueryOp>>) -> Result<()> {
+    let tag = model_tag(req.model_id, req.device);
+    if db::model_has_embeddings(pool, &tag).await? { return Ok(()); }
+    let stored = db::embedding_models(pool).await?;
+    if stored.iter().any(|m| model_id_of(m) == req.model_id) { return Ok(()); }
+    let msg = format!(
+        "query model {} has no stored embeddings (stored: {}); pass the --model-id/--device used by embed",
+        tag, stored.join(", ")
+    );
+    if req.strict_model { return Err(ConfigError(msg).into()); }
+    if let Some(ctx) = log { ctx.warn(format!("⚠️  {}; rankings will be meaningless", msg)); }
+    Ok(())
+}
+
+// probes (ivfflat) or ef_search (hnsw) depending on the index type
+async fn resolve_knobs(
+    pool: &PgPool,
+    req: &QueryRequest<'_>,
+    log: Option<&LogCtx<QueryOp>>,
+) -> Result<(Option<i32>, Option<i32>)> {
+    let index = db::ann_index(pool).await?.unwrap_or_default();
+    Ok(match index.kind {
+        Some(IndexType::Hnsw) => (
+            None,
+            Some(req.ef_search.map(|e| e.max(1)).unwrap_or_else(|| db::recommend_ef_search(req.top_n))),
+        ),
+        _ => (
+            match req.probes {
+                Some(p) => {
+                    let clamped = db::clamp_probes(p, index.lists);
+                    if clamped != p {
+                        if let Some(ctx) = log {
+                            ctx.warn_kv("⚠️ probes clamped to index lists", [
+                                ("requested", p.to_string()),
+                                ("lists", format!("{:?}", index.lists)),
+                                ("probes", clamped.to_string()),
+                            ]);
+                        }
+                    }
+                    Some(clamped)
+                }
+                None => db::recommend_probes(&index),
+            },
+            None,
+        ),
+    })
+}
+
+async fn retrieve(
+    pool: &PgPool,
+    req: &QueryRequest<'_>,
+    qvec: &[f32],
+    (probes, mut ef_search): (Option<i32>, Option<i32>),
+    vector_type: VectorType,
+    log: Option<&LogCtx<QueryOp>>,
+) -> Result<QueryOutcome> {
     let mut conn = pool.acquire().await?;
     let mut tx = conn.begin().await?;
 
@@ -90,44 +308,214 @@ pub async fn execute(
         sqlx::query(&sql).execute(&mut *tx).await?;
         drop(_set_probes_span);
     }
+    if let Some(ef) = ef_search {
+        let _set_probes_span = enter_span(log, &QueryPhase::SetProbes);
+        let sql = format!("SET LOCAL hnsw.ef_search = {}", ef);
+        sqlx::query(&sql).execute(&mut *tx).await?;
+        drop(_set_probes_span);
+    }
 
     let _fetch_span = enter_span(log, &QueryPhase::FetchCandidates);
-    let candidates = db::fetch_ann_candidates(
-        &mut *tx,
-        &qvec,
-        req.top_n.max(1),
-        &FetchOpts {
-            feed: req.feed,
-            since: req.since,
-            include_preview: req.include_preview,
-            include_text: req.include_text,
-        },
-    )
-    .await?;
+    let fetch_opts = FetchOpts {
+        feed: req.feed,
+        since: req.since,
+        title_like: req.title_like.map(str::to_string),
+        feed_name: req.feed_name.map(str::to_string),
+        include_preview: req.include_preview,
+        // highlighting, reranking and dedup need the full text
+        include_text: req.include_text || req.highlight || req.rerank_llm || req.dedup_results,
+        preview_chars: req.preview_chars.max(1) as i32,
+        vector_type,
+        // --level doc already scans just the chosen docs' chunks exactly
+        quantized_pool: (req.quantized && req.level == QueryLevel::Chunk).then_some(req.quantized_pool as i64),
+    };
+    // the --quantized pre-filter walks the HNSW bits index, which yields at most ef_search rows
+    if let (Some(pool_n), None) = (fetch_opts.quantized_pool, req.ef_search) {
+        let ef = db::recommend_ef_search(pool_n).max(ef_search.unwrap_or(0));
+        sqlx::query(&format!("SET LOCAL hnsw.ef_search = {}", ef)).execute(&mut *tx).await?;
+        ef_search = Some(ef);
+    }
+    let doc_ids: Option<Vec<i64>> = match req.level {
+        QueryLevel::Chunk => None,
+        QueryLevel::Doc => {
+            let _docs_span = enter_span(log, &QueryPhase::FetchDocs);
+            let n_docs = req.topk.div_ceil(req.doc_cap.max(1)).max(1) as i64;
+            let docs = timed(log, "query fetch_top_docs", db::fetch_top_docs(&mut *tx, qvec, n_docs, &fetch_opts)).await?;
+            if let Some(ctx) = log {
+                if docs.is_empty() {
+                    ctx.info("ℹ️  No doc centroids matched; `rag embed --apply` builds them");
+                } else {
+                    let listed: Vec<String> = docs.iter().map(|(id, d)| format!("{}:{:.4}", id, d)).collect();
+                    ctx.info_kv("📄 Top docs", [("doc_id:distance", listed.join(","))]);
+                }
+            }
+            Some(docs.into_iter().map(|(id, _)| id).collect())
+        }
+    };
+    let mut top_n = req.top_n.max(1);
+    let mut candidates = timed(log, "query fetch_candidates", fetch_candidates(&mut *tx, qvec, top_n, &fetch_opts, doc_ids.as_deref())).await?;
+    // one dominant doc can fill the pool and starve doc_cap; widen it a few times before settling for fewer rows
+    for _ in 0..req.overfetch_rounds {
+        if !post::should_overfetch(&candidates, top_n, req.topk, req.doc_cap, req.max_distance) { break; }
+        top_n *= 2;
+        // hnsw returns at most ef_search rows; keep a recommended value in step (an explicit one is left alone)
+        if let (Some(_), None) = (ef_search, req.ef_search) {
+            let ef = db::recommend_ef_search(top_n).max(ef_search.unwrap_or(0));
+            sqlx::query(&format!("SET LOCAL hnsw.ef_search = {}", ef)).execute(&mut *tx).await?;
+            ef_search = Some(ef);
+        }
+        if let Some(ctx) = log {
+            ctx.debug(format!("  doc_cap left fewer than topk={} rows; re-fetching with top_n={}", req.topk, top_n));
+        }
+        candidates = timed(log, "query fetch_candidates", fetch_candidates(&mut *tx, qvec, top_n, &fetch_opts, doc_ids.as_deref())).await?;
+    }
     drop(_fetch_span);
 
+    // same transaction, so SET LOCAL probes/ef_search apply to the explained plan too
+    if req.explain && doc_ids.is_some() {
+        if let Some(ctx) = log { ctx.warn("⚠️  --explain covers the chunk-level ANN query only; skipped under --level doc"); }
+    }
+    let explain = if req.explain && doc_ids.is_none() {
+        let _explain_span = enter_span(log, &QueryPhase::Explain);
+        let plan = db::explain_ann_candidates(&mut *tx, qvec, top_n, &fetch_opts).await?;
+        let info = post::summarize_plan(plan, probes, ef_search);
+        if let Some(ctx) = log {
+            ctx.info_kv("🔬 Explain", [
+                ("scan", info.scan.clone()),
+                ("index", info.index.clone().unwrap_or_default()),
+                ("probes", format!("{:?}", info.probes)),
+                ("ef_search", format!("{:?}", info.ef_search)),
+                ("execution_ms", format!("{:?}", info.execution_ms)),
+            ]);
+            for line in &info.plan { ctx.debug(format!("  {}", line)); }
+        }
+        Some(info)
+    } else {
+        None
+    };
+
     tx.commit().await?;
 
     if candidates.is_empty() {
         if let Some(ctx) = log {
             ctx.info("ℹ️  No results");
         }
-        return Ok(QueryOutcome { rows: Vec::new(), hits: Vec::new(), probes });
+        return Ok(QueryOutcome { rows: Vec::new(), hits: Vec::new(), probes, ef_search, too_distant: 0, explain });
+    }
+
+    let mut rerank_scores: HashMap<i64, f32> = HashMap::new();
+    if req.rerank_llm {
+        let _rerank_span = enter_span(log, &QueryPhase::Rerank);
+        let passages: Vec<(i64, &str)> = candidates
+            .iter()
+            .filter(|c| req.max_distance.is_none_or(|d| c.distance <= d))
+            .take(req.rerank_candidates)
+            .filter_map(|c| c.text.as_deref().map(|t| (c.chunk_id, t)))
+            .collect();
+        let client = OpenAiClient::new(OpenAiClientConfig::from_env()).map_err(anyhow::Error::new)?;
+        let (scores, failed) = rerank::score_passages(&client, req.rerank_model, req.query, &passages).await?;
+        if let Some(ctx) = log {
+            ctx.info_kv("🧮 Reranked", [("scored", scores.len().to_string()), ("failed", failed.to_string())]);
+            if failed > 0 { ctx.warn(format!("⚠️  {} candidate(s) could not be scored; kept in ANN order after scored ones", failed)); }
+        }
+        candidates = rerank::reorder(candidates, &scores);
+        rerank_scores = scores;
     }
 
     let _post_span = enter_span(log, &QueryPhase::PostFilter);
-    let shaped_rows: Vec<QueryResultRow> =
-        post::shape_results(candidates.clone(), req.topk, req.doc_cap);
+    let fingerprints: HashMap<i64, i64> = if req.dedup_results {
+        candidates.iter().filter_map(|c| c.text.as_deref().and_then(simhash).map(|fp| (c.chunk_id, fp))).collect()
+    } else {
+        HashMap::new()
+    };
+    if req.highlight || req.rerank_llm || req.dedup_results {
+        for cand in candidates.iter_mut() {
+            if let (true, Some(text)) = (req.highlight, &cand.text) {
+                cand.preview = Some(post::highlight_preview(text, req.query, req.preview_chars));
+            }
+            if !req.include_text { cand.text = None; }
+        }
+    }
+    let too_distant = match req.max_distance {
+        Some(d) => candidates.iter().filter(|c| c.distance > d).count(),
+        None => 0,
+    };
+    let mut shaped_rows: Vec<QueryResultRow> = if req.dedup_results {
+        // shape without the topk cap so dropped near-duplicates leave room for the next rows
+        let rows = post::shape_results(candidates.clone(), usize::MAX, req.doc_cap, req.max_distance);
+        let (rows, dropped) = post::dedup_results(rows, &fingerprints, req.dedup_bits, req.topk);
+        if let (Some(ctx), true) = (log, dropped > 0) {
+            ctx.info(format!("ℹ️  Dropped {} near-duplicate result(s) (within {} bits)", dropped, req.dedup_bits));
+        }
+        rows
+    } else {
+        post::shape_results(candidates.clone(), req.topk, req.doc_cap, req.max_distance)
+    };
+    for row in shaped_rows.iter_mut() { row.rerank_score = rerank_scores.get(&row.chunk_id).copied(); }
     drop(_post_span);
+    if shaped_rows.is_empty() && too_distant > 0 {
+        if let Some(ctx) = log {
+            ctx.info(format!("ℹ️  No results within max distance ({} candidate(s) too distant)", too_distant));
+        }
+    }
 
     let mut by_chunk: HashMap<i64, CandRow> = HashMap::new();
     for cand in candidates {
         by_chunk.insert(cand.chunk_id, cand);
     }
 
-    let hits = build_hits(&shaped_rows, &by_chunk);
+    let mut hits = build_hits(&shaped_rows, &by_chunk);
+    if req.expand_neighbors > 0 && req.include_text {
+        hits = expand_neighbors(pool, hits, req.expand_neighbors as i32).await?;
+        // keep row text (query --full-text) in sync with the stitched hit text
+        for row in shaped_rows.iter_mut().filter(|r| r.text.is_some()) {
+            if let Some(hit) = hits.iter().find(|h| h.chunk_id == row.chunk_id) { row.text = hit.text.clone(); }
+        }
+    }
 
-    Ok(QueryOutcome { rows: shaped_rows, hits, probes })
+    Ok(QueryOutcome { rows: shaped_rows, hits, probes, ef_search, too_distant, explain })
+}
+
+// Chunk-level ANN, or an exact scan within the top docs' chunks under --level doc
+async fn fetch_candidates(
+    conn: &mut PgConnection,
+    qvec: &[f32],
+    top_n: i64,
+    opts: &FetchOpts,
+    doc_ids: Option<&[i64]>,
+) -> Result<Vec<CandRow>> {
+    match doc_ids {
+        Some(ids) => db::fetch_doc_chunks(conn, qvec, ids, top_n, opts).await,
+        None => db::fetch_ann_candidates(conn, qvec, top_n, opts).await,
+    }
+}
+
+// Replace each hit's text with its stitched neighborhood; hits already covered by a
+// better-ranked hit's expansion are dropped so the same passage is not repeated.
+async fn expand_neighbors(pool: &PgPool, hits: Vec<QueryHit>, n: i32) -> Result<Vec<QueryHit>> {
+    let keys: Vec<(i64, Option<i32>)> = hits.iter().map(|h| (h.doc_id, h.chunk_index)).collect();
+    let plan = post::plan_neighbor_expansion(&keys, n);
+
+    let wanted: Vec<(i64, i32)> = hits
+        .iter()
+        .zip(plan.iter())
+        .filter_map(|(h, p)| p.as_ref().map(|idxs| idxs.iter().map(|i| (h.doc_id, *i)).collect::<Vec<_>>()))
+        .flatten()
+        .collect();
+    let texts: HashMap<(i64, i32), String> = db::fetch_chunk_texts(pool, &wanted)
+        .await?
+        .into_iter()
+        .map(|(d, i, t)| ((d, i), t))
+        .collect();
+
+    let mut out = Vec::with_capacity(hits.len());
+    for (mut hit, p) in hits.into_iter().zip(plan.into_iter()) {
+        let Some(idxs) = p else { continue };
+        let parts: Vec<&str> = idxs.iter().filter_map(|i| texts.get(&(hit.doc_id, *i)).map(|t| t.as_str())).collect();
+        if !parts.is_empty() { hit.text = Some(parts.join("\n")); }
+        out.push(hit);
+    }
+    Ok(out)
 }
 
 fn enter_span<'a>(
@@ -145,6 +533,7 @@ fn build_hits(rows: &[QueryResultRow], candidates: &HashMap<i64, CandRow>) -> Ve
                 distance: row.distance,
                 chunk_id: row.chunk_id,
                 doc_id: row.doc_id,
+                chunk_index: cand.chunk_index,
                 title: row.title.clone(),
                 preview: row.preview.clone(),
                 text: cand.text.clone(),
@@ -158,6 +547,13 @@ mod tests {
     use super::*;
     use crate::query::db::CandRow;
 
+    #[test]
+    fn model_id_ignores_the_device_suffix() {
+        assert_eq!(model_id_of("intfloat/e5-small-v2@onnx-cuda"), "intfloat/e5-small-v2");
+        assert_eq!(model_id_of(&model_tag("intfloat/e5-small-v2", Device::Cpu)), "intfloat/e5-small-v2");
+        assert_eq!(model_id_of("legacy-tag"), "legacy-tag");
+    }
+
     #[test]
     fn build_hits_includes_chunk_text() {
         let rows = vec![QueryResultRow {
@@ -167,6 +563,8 @@ mod tests {
             doc_id: 7,
             title: Some("Doc".into()),
             preview: Some("prev".into()),
+            text: None,
+            rerank_score: None,
         }];
         let mut candidates = HashMap::new();
         candidates.insert(
@@ -174,6 +572,7 @@ mod tests {
             CandRow {
                 chunk_id: 42,
                 doc_id: 7,
+                chunk_index: Some(0),
                 title: Some("Doc".into()),
                 preview: Some("prev".into()),
                 text: Some("full text".into()),
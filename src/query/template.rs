@@ -0,0 +1,116 @@
+// query --template: one line of user-formatted text per result row, e.g.
+// "{rank}\t{distance}\t{title}". Placeholders are QueryResultRow fields; `{{`/`}}` are literal
+// braces and `\n`/`\t` in the template become newline/tab. Missing optional fields render empty.
+
+use anyhow::Result;
+
+use crate::util::exit::ConfigError;
+
+use super::post::QueryResultRow;
+
+const FIELDS: &[&str] = &["rank", "distance", "chunk_id", "doc_id", "title", "preview", "text", "rerank_score"];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Lit(String),
+    Field(&'static str),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowTemplate(Vec<Segment>);
+
+impl RowTemplate {
+    /// Unknown placeholders and unbalanced braces are config errors, caught before the query runs.
+    pub fn parse(tpl: &str) -> Result<Self> {
+        let tpl = tpl.replace("\\n", "\n").replace("\\t", "\t");
+        let mut segs = Vec::new();
+        let mut lit = String::new();
+        let mut chars = tpl.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => { chars.next(); lit.push('{'); }
+                '}' if chars.peek() == Some(&'}') => { chars.next(); lit.push('}'); }
+                '{' => {
+                    let mut name = String::new();
+                    let mut closed = false;
+                    for c in chars.by_ref() {
+                        if c == '}' { closed = true; break; }
+                        name.push(c);
+                    }
+                    if !closed {
+                        return Err(ConfigError(format!("--template: unclosed placeholder {{{}", name)).into());
+                    }
+                    let Some(field) = FIELDS.iter().find(|f| **f == name) else {
+                        return Err(ConfigError(format!(
+                            "--template: unknown placeholder {{{}}} (known: {})", name, FIELDS.join(", ")
+                        )).into());
+                    };
+                    if !lit.is_empty() { segs.push(Segment::Lit(std::mem::take(&mut lit))); }
+                    segs.push(Segment::Field(field));
+                }
+                '}' => return Err(ConfigError("--template: unmatched '}' (use '}}' for a literal brace)".into()).into()),
+                c => lit.push(c),
+            }
+        }
+        if !lit.is_empty() { segs.push(Segment::Lit(lit)); }
+        Ok(RowTemplate(segs))
+    }
+
+    pub fn render(&self, row: &QueryResultRow) -> String {
+        let mut out = String::new();
+        for seg in &self.0 {
+            match seg {
+                Segment::Lit(s) => out.push_str(s),
+                Segment::Field(f) => out.push_str(&field(row, f)),
+            }
+        }
+        out
+    }
+}
+
+fn field(row: &QueryResultRow, name: &str) -> String {
+    match name {
+        "rank" => row.rank.to_string(),
+        "distance" => format!("{:.4}", row.distance),
+        "chunk_id" => row.chunk_id.to_string(),
+        "doc_id" => row.doc_id.to_string(),
+        "title" => row.title.clone().unwrap_or_default(),
+        // previews are cut mid-text; keep each on one line like the human output
+        "preview" => row.preview.as_deref().map(|p| p.replace('\n', " ")).unwrap_or_default(),
+        "text" => row.text.clone().unwrap_or_default(),
+        "rerank_score" => row.rerank_score.map(|s| format!("{:.2}", s)).unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row() -> QueryResultRow {
+        QueryResultRow {
+            rank: 2,
+            distance: 0.41237,
+            chunk_id: 17,
+            doc_id: 5,
+            title: Some("Attention".into()),
+            preview: Some("line one\nline two".into()),
+            text: None,
+            rerank_score: None,
+        }
+    }
+
+    #[test]
+    fn renders_placeholders_escapes_and_braces() {
+        let t = RowTemplate::parse("{rank}\\t{distance} {{doc {doc_id}}} {title}: {preview}|{text}|").unwrap();
+        assert_eq!(t.render(&row()), "2\t0.4124 {doc 5} Attention: line one line two||");
+    }
+
+    #[test]
+    fn bad_templates_are_config_errors() {
+        for tpl in ["{score}", "{rank", "oops }"] {
+            let err = RowTemplate::parse(tpl).unwrap_err();
+            assert!(err.is::<ConfigError>(), "template {tpl:?}");
+        }
+    }
+}
@@ -13,6 +13,9 @@ pub async fn snapshot_chunk(pool: &PgPool, id: i64) -> Result<()> {
     log.info(format!("🧩 Chunk {} (Doc {:?}):", row.chunk_id, row.doc_id));
     log.info(format!("  Index: {:?}", row.chunk_index));
     log.info(format!("  Tokens: {:?}", row.token_count));
+    if let (Some(start), Some(end)) = (row.char_start, row.char_end) {
+        log.info(format!("  Source chars: {}..{} of text_clean", start, end));
+    }
     log.info(format!("  Preview: {:?}", row.preview));
 
     log.result(&row)?;
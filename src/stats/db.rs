@@ -2,7 +2,9 @@ use anyhow::Result;
 use chrono::{DateTime, Utc};
 use sqlx::PgPool;
 
+use crate::ingestion::error::IngestError;
 use crate::stats::types::*;
+use crate::util::index::{describe_index, IndexType, EMBEDDING_INDEX};
 
 // -------- Summary helpers --------
 
@@ -33,6 +35,29 @@ pub async fn docs_by_status(pool: &PgPool) -> Result<Vec<StatsDocStatus>> {
     Ok(rows.into_iter().map(|r| StatsDocStatus { status: r.status.unwrap_or_default(), cnt: r.cnt.unwrap_or(0) }).collect())
 }
 
+// error docs grouped by error_msg; tags outside the ingest taxonomy count as permanent
+pub async fn errors_by_kind(pool: &PgPool) -> Result<Vec<StatsErrorKind>> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT COALESCE(error_msg,'') AS kind, COUNT(*)::bigint AS cnt
+        FROM rag.document
+        WHERE status = 'error'
+        GROUP BY error_msg
+        ORDER BY error_msg
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|r| {
+            let kind = r.kind.unwrap_or_default();
+            let transient = IngestError::from_tag(&kind).is_some_and(|k| k.is_transient());
+            StatsErrorKind { kind, transient, cnt: r.cnt.unwrap_or(0) }
+        })
+        .collect())
+}
+
 pub async fn last_fetched(pool: &PgPool) -> Result<Option<DateTime<Utc>>> {
     let row = sqlx::query!("SELECT MAX(fetched_at) AS last_fetched FROM rag.document")
         .fetch_one(pool)
@@ -40,17 +65,58 @@ pub async fn last_fetched(pool: &PgPool) -> Result<Option<DateTime<Utc>>> {
     Ok(row.last_fetched)
 }
 
+// Histogram bucket upper edges; the last bucket is open-ended (>= 768)
+const TOKEN_BUCKET_EDGES: [i32; 5] = [128, 256, 384, 512, 768];
+
 pub async fn chunks_summary(pool: &PgPool) -> Result<StatsChunksSummary> {
     let row = sqlx::query!(
         r#"
         SELECT COUNT(*)::bigint AS total,
-               AVG(token_count)::float8 AS avg
+               AVG(token_count)::float8 AS avg,
+               MIN(token_count) AS min_tokens,
+               MAX(token_count) AS max_tokens,
+               percentile_cont(0.5)  WITHIN GROUP (ORDER BY token_count)::float8 AS median,
+               percentile_cont(0.95) WITHIN GROUP (ORDER BY token_count)::float8 AS p95
         FROM rag.chunk
         "#
     )
     .fetch_one(pool)
     .await?;
-    Ok(StatsChunksSummary { total: row.total.unwrap_or(0), avg_tokens: row.avg.unwrap_or(0.0) })
+
+    let buckets = sqlx::query!(
+        r#"
+        SELECT width_bucket(token_count, $1::int4[]) AS "bucket!", COUNT(*)::bigint AS "cnt!"
+        FROM rag.chunk
+        WHERE token_count IS NOT NULL
+        GROUP BY 1
+        ORDER BY 1
+        "#,
+        &TOKEN_BUCKET_EDGES[..]
+    )
+    .fetch_all(pool)
+    .await?;
+
+    // fill every bucket (zeros included) so the shape is stable across runs
+    let mut histogram: Vec<StatsTokenBucket> = (0..=TOKEN_BUCKET_EDGES.len())
+        .map(|b| StatsTokenBucket {
+            lo: if b == 0 { 0 } else { TOKEN_BUCKET_EDGES[b - 1] },
+            hi: TOKEN_BUCKET_EDGES.get(b).copied(),
+            cnt: 0,
+        })
+        .collect();
+    for r in buckets {
+        if let Some(slot) = histogram.get_mut(r.bucket as usize) { slot.cnt = r.cnt; }
+    }
+
+    Ok(StatsChunksSummary {
+        total: row.total.unwrap_or(0),
+        avg_tokens: row.avg.unwrap_or(0.0),
+        min_tokens: row.min_tokens,
+        max_tokens: row.max_tokens,
+        median_tokens: row.median,
+        p95_tokens: row.p95,
+        histogram,
+    })
 }
 
 pub async fn embeddings_totals(pool: &PgPool) -> Result<StatsEmbeddings> {
@@ -74,18 +140,12 @@ pub async fn embeddings_totals(pool: &PgPool) -> Result<StatsEmbeddings> {
 }
 
 pub async fn index_meta(pool: &PgPool) -> Result<StatsIndexMeta> {
-    let idx_row = sqlx::query!(
-        r#"
-        SELECT substring(pg_get_indexdef(i.indexrelid) from 'lists = ([0-9]+)') AS lists
-        FROM pg_index i
-        JOIN pg_class c ON c.oid = i.indexrelid
-        JOIN pg_namespace nsp ON nsp.oid = c.relnamespace
-        WHERE nsp.nspname = 'rag' AND c.relname = 'embedding_vec_ivf_idx'
-        "#
-    )
-    .fetch_optional(pool)
-    .await?;
-    let lists: Option<i32> = idx_row.as_ref().and_then(|r| r.lists.as_ref()).and_then(|s| s.parse::<i32>().ok());
+    let def = describe_index(pool, EMBEDDING_INDEX).await?.unwrap_or_default();
+    let index_type = def.kind.map(|k| k.as_str().to_string());
+    let (lists, m, ef_construction) = match def.kind {
+        Some(IndexType::Hnsw) => (None, def.m, def.ef_construction),
+        _ => (def.lists, None, None),
+    };
 
     let size_row = sqlx::query!(r#"SELECT pg_size_pretty(pg_relation_size('rag.embedding_vec_ivf_idx')) AS size"#)
         .fetch_optional(pool)
@@ -102,7 +162,7 @@ pub async fn index_meta(pool: &PgPool) -> Result<StatsIndexMeta> {
     .fetch_optional(pool)
     .await?;
     let last_analyze = analyze_row.and_then(|r| r.last_analyze);
-    Ok(StatsIndexMeta { lists, size_pretty, last_analyze })
+    Ok(StatsIndexMeta { index_type, lists, m, ef_construction, size_pretty, last_analyze })
 }
 
 pub async fn coverage(pool: &PgPool) -> Result<StatsCoverage> {
@@ -189,7 +249,15 @@ pub async fn feed_chunks_summary(pool: &PgPool, feed_id: i32) -> Result<StatsChu
     )
     .fetch_one(pool)
     .await?;
-    Ok(StatsChunksSummary { total: row.total_chunks.unwrap_or(0), avg_tokens: row.avg_tokens.unwrap_or(0.0) })
+    Ok(StatsChunksSummary {
+        total: row.total_chunks.unwrap_or(0),
+        avg_tokens: row.avg_tokens.unwrap_or(0.0),
+        min_tokens: None,
+        max_tokens: None,
+        median_tokens: None,
+        p95_tokens: None,
+        histogram: Vec::new(),
+    })
 }
 
 pub async fn feed_coverage(pool: &PgPool, feed_id: i32) -> Result<StatsFeedCoverage> {
@@ -298,7 +366,7 @@ pub async fn latest_docs(pool: &PgPool, feed_id: i32, limit: i64) -> Result<Vec<
 pub async fn chunk_snap(pool: &PgPool, id: i64) -> Result<StatsChunkSnap> {
     let row = sqlx::query!(
         r#"
-        SELECT chunk_id, doc_id, chunk_index, token_count,
+        SELECT chunk_id, doc_id, chunk_index, token_count, char_start, char_end,
                substring(text, 1, 400) AS preview
         FROM rag.chunk
         WHERE chunk_id = $1
@@ -307,7 +375,7 @@ pub async fn chunk_snap(pool: &PgPool, id: i64) -> Result<StatsChunkSnap> {
     )
     .fetch_one(pool)
     .await?;
-    Ok(StatsChunkSnap { chunk_id: row.chunk_id, doc_id: row.doc_id, chunk_index: row.chunk_index, token_count: row.token_count, preview: row.preview })
+    Ok(StatsChunkSnap { chunk_id: row.chunk_id, doc_id: row.doc_id, chunk_index: row.chunk_index, token_count: row.token_count, char_start: row.char_start, char_end: row.char_end, preview: row.preview })
 }
 
 pub async fn doc_snapshot(pool: &PgPool, id: i64, chunk_limit: i64) -> Result<StatsDocSnapshot> {
@@ -351,3 +419,49 @@ pub async fn doc_snapshot(pool: &PgPool, id: i64, chunk_limit: i64) -> Result<St
     Ok(StatsDocSnapshot { doc, chunks })
 }
 
+// -------- Vector verification --------
+
+// Per model tag over `sample` arbitrary rows (all when None): dim vs the declared width
+// (skipped when the column is unconstrained), dim vs vector_dims(vec), and vec_bits length.
+pub async fn verify_vectors(pool: &PgPool, sample: Option<i64>, column_dim: Option<i32>) -> Result<Vec<StatsVectorModelCheck>> {
+    let rows = sqlx::query!(
+        r#"
+        WITH e AS (
+            SELECT chunk_id, model, dim, vector_dims(vec) AS len, bit_length(vec_bits) AS bits
+            FROM rag.embedding
+            LIMIT $1
+        ), checked AS (
+            SELECT *,
+                   ($2::int4 IS NOT NULL AND dim <> $2) AS bad_dim,
+                   (len <> dim) AS bad_len,
+                   (bits IS NOT NULL AND bits <> dim) AS bad_bits
+            FROM e
+        )
+        SELECT model AS "model!",
+               COUNT(*)::bigint AS "checked!",
+               array_agg(DISTINCT dim ORDER BY dim) AS "dims!",
+               COUNT(*) FILTER (WHERE bad_dim)::bigint AS "dim_mismatch!",
+               COUNT(*) FILTER (WHERE bad_len)::bigint AS "len_mismatch!",
+               COUNT(*) FILTER (WHERE bad_bits)::bigint AS "bits_mismatch!",
+               COUNT(*) FILTER (WHERE bad_dim OR bad_len OR bad_bits)::bigint AS "mismatched!",
+               COALESCE((array_agg(chunk_id ORDER BY chunk_id) FILTER (WHERE bad_dim OR bad_len OR bad_bits))[1:5], '{}') AS "sample_chunk_ids!"
+        FROM checked
+        GROUP BY model
+        ORDER BY model
+        "#,
+        sample,
+        column_dim
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(|r| StatsVectorModelCheck {
+        model: r.model,
+        checked: r.checked,
+        dims: r.dims,
+        dim_mismatch: r.dim_mismatch,
+        len_mismatch: r.len_mismatch,
+        bits_mismatch: r.bits_mismatch,
+        mismatched: r.mismatched,
+        sample_chunk_ids: r.sample_chunk_ids,
+    }).collect())
+}
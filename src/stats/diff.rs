@@ -0,0 +1,82 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::stats::types::*;
+
+pub fn read_snapshot(path: &Path) -> Result<StatsSummary> {
+    let raw = std::fs::read_to_string(path).with_context(|| format!("read snapshot {}", path.display()))?;
+    let snap = serde_json::from_str(&raw).with_context(|| format!("parse snapshot {}", path.display()))?;
+    Ok(snap)
+}
+
+pub fn write_snapshot(path: &Path, summary: &StatsSummary) -> Result<()> {
+    let raw = serde_json::to_string_pretty(summary)?;
+    std::fs::write(path, raw).with_context(|| format!("write snapshot {}", path.display()))?;
+    Ok(())
+}
+
+fn count(before: i64, after: i64) -> StatsCountDelta { StatsCountDelta { before, after, delta: after - before } }
+
+// Deltas from `before` (older snapshot) to `after` (current); statuses missing on one side count as 0
+pub fn diff(before: &StatsSummary, after: &StatsSummary) -> StatsSummaryDiff {
+    let mut statuses: BTreeMap<&str, (i64, i64)> = BTreeMap::new();
+    for r in &before.documents_by_status { statuses.entry(r.status.as_str()).or_default().0 = r.cnt; }
+    for r in &after.documents_by_status { statuses.entry(r.status.as_str()).or_default().1 = r.cnt; }
+    let documents_by_status = statuses
+        .into_iter()
+        .map(|(status, (b, a))| StatsStatusDelta { status: status.to_string(), before: b, after: a, delta: a - b })
+        .collect();
+
+    StatsSummaryDiff {
+        documents_by_status,
+        chunks: count(before.chunks.total, after.chunks.total),
+        embeddings: count(before.embeddings.total, after.embeddings.total),
+        coverage_embedded: count(before.coverage.embedded, after.coverage.embedded),
+        coverage_pct: StatsPctDelta { before: before.coverage.pct, after: after.coverage.pct, delta: after.coverage.pct - before.coverage.pct },
+        missing: count(before.coverage.missing, after.coverage.missing),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(statuses: &[(&str, i64)], chunks: i64, embedded: i64) -> StatsSummary {
+        let pct = if chunks > 0 { embedded as f64 * 100.0 / chunks as f64 } else { 0.0 };
+        StatsSummary {
+            feeds: vec![],
+            documents_by_status: statuses.iter().map(|(s, c)| StatsDocStatus { status: s.to_string(), cnt: *c }).collect(),
+            errors_by_kind: vec![],
+            last_fetched: None,
+            chunks: StatsChunksSummary { total: chunks, avg_tokens: 0.0, min_tokens: None, max_tokens: None, median_tokens: None, p95_tokens: None, histogram: vec![] },
+            embeddings: StatsEmbeddings { total: embedded, models: vec![] },
+            index: StatsIndexMeta { index_type: None, lists: None, m: None, ef_construction: None, size_pretty: None, last_analyze: None },
+            coverage: StatsCoverage { chunks, embedded, pct, missing: chunks - embedded },
+        }
+    }
+
+    #[test]
+    fn diff_merges_statuses_and_counts() {
+        let before = summary(&[("ingest", 5), ("error", 1)], 100, 50);
+        let after = summary(&[("ingest", 2), ("embedded", 4)], 140, 140);
+        let d = diff(&before, &after);
+
+        let by: Vec<(&str, i64)> = d.documents_by_status.iter().map(|r| (r.status.as_str(), r.delta)).collect();
+        assert_eq!(by, vec![("embedded", 4), ("error", -1), ("ingest", -3)]);
+        assert_eq!(d.chunks.delta, 40);
+        assert_eq!(d.embeddings.delta, 90);
+        assert_eq!(d.missing.delta, -50);
+        assert!((d.coverage_pct.delta - 50.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn snapshot_roundtrips_through_json() {
+        let s = summary(&[("ingest", 3)], 10, 7);
+        let raw = serde_json::to_string(&s).unwrap();
+        let back: StatsSummary = serde_json::from_str(&raw).unwrap();
+        assert_eq!(back.coverage.embedded, 7);
+        assert_eq!(back.documents_by_status[0].status, "ingest");
+    }
+}
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use anyhow::Result;
 use clap::Args;
 use sqlx::PgPool;
@@ -8,12 +10,21 @@ pub mod doc;
 pub mod chunk;
 pub mod types;
 pub mod db;
+pub mod diff;
+pub mod orphans;
+pub mod verify;
 
 #[derive(Args, Debug)]
 pub struct StatsCmd {
     #[arg(long)] pub feed: Option<i32>,
     #[arg(long)] pub doc: Option<i64>,
     #[arg(long)] pub chunk: Option<i64>,
+    /// Read-only health view: orphan/bad-row counts from gc (scoped by --feed when given)
+    #[arg(long, default_value_t = false)] pub orphans: bool,
+    /// Read-only integrity scan: each embedding's dim against the column width and its actual vector length, per model
+    #[arg(long, visible_alias = "strict-dim", default_value_t = false)] pub verify_vectors: bool,
+    /// With --verify-vectors: check only this many (arbitrary) rows instead of the whole table
+    #[arg(long, requires = "verify_vectors")] pub sample: Option<i64>,
 
     /// Number of docs to list in --feed view (default: 10)
     #[arg(long, default_value_t = 10)]
@@ -22,11 +33,21 @@ pub struct StatsCmd {
     /// Number of chunks to list in --doc view (default: 10)
     #[arg(long, default_value_t = 10)]
     pub chunk_limit: i64,
+
+    /// Write the summary as a JSON snapshot to this path
+    #[arg(long)]
+    pub snapshot_out: Option<PathBuf>,
+
+    /// Compare the summary against a prior snapshot and report deltas
+    #[arg(long)]
+    pub snapshot_in: Option<PathBuf>,
 }
 
 pub async fn run(pool: &PgPool, args: StatsCmd) -> Result<()> {
     if let Some(id) = args.doc { return doc::snapshot_doc(pool, id, args.chunk_limit).await; }
     if let Some(id) = args.chunk { return chunk::snapshot_chunk(pool, id).await; }
+    if args.orphans { return orphans::orphans(pool, args.feed).await; }
+    if args.verify_vectors { return verify::verify_vectors(pool, args.sample).await; }
     if let Some(feed_id) = args.feed { return feed::feed_stats(pool, feed_id, args.doc_limit).await; }
-    summary::summary(pool).await
+    summary::summary(pool, args.snapshot_in.as_deref(), args.snapshot_out.as_deref()).await
 }
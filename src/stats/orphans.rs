@@ -0,0 +1,40 @@
+use anyhow::Result;
+use sqlx::PgPool;
+
+use crate::maintenance::gc::counts;
+use crate::telemetry::{self};
+use crate::telemetry::ops::stats::Phase as StatsPhase;
+use crate::stats::types::StatsOrphans;
+
+// Same counts `gc` reports in plan mode, without a cutoff (all ages)
+pub async fn orphans(pool: &PgPool, feed: Option<i32>) -> Result<()> {
+    let log = telemetry::stats();
+    let _s = log.span(&StatsPhase::Orphans).entered();
+
+    let out = StatsOrphans {
+        feed,
+        orphan_chunks: counts::count_orphan_chunks(pool, feed).await?,
+        orphan_embeddings: counts::count_orphan_embeddings(pool).await?,
+        error_docs: counts::count_error_docs(pool, None, feed).await?,
+        never_chunked_docs: counts::count_never_chunked_docs(pool, None, feed).await?,
+        bad_chunks: counts::count_bad_chunks(pool, feed).await?,
+        unembedded_chunks: counts::count_unembedded_chunks(pool, None, feed).await?,
+    };
+
+    match feed {
+        Some(fid) => log.info(format!("🩺 Health (feed {}):", fid)),
+        None => log.info("🩺 Health:"),
+    }
+    log.info(format!("  🧱 Orphan chunks: {}", out.orphan_chunks));
+    log.info(format!("  🧬 Orphan embeddings: {}", out.orphan_embeddings));
+    log.info(format!("  ⚠️  Error docs: {}", out.error_docs));
+    log.info(format!("  ⏳ Never-chunked docs: {}", out.never_chunked_docs));
+    log.info(format!("  🧹 Bad chunks (empty/≤0 tokens): {}", out.bad_chunks));
+    log.info(format!("  🧩 Chunks without embedding: {}", out.unembedded_chunks));
+    if out.orphan_chunks + out.orphan_embeddings + out.bad_chunks > 0 {
+        log.info("   Run `rag gc` to review cleanup.");
+    }
+
+    log.result(&out)?;
+    Ok(())
+}
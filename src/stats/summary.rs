@@ -1,3 +1,5 @@
+use std::path::Path;
+
 use anyhow::Result;
 use sqlx::PgPool;
 
@@ -5,11 +7,15 @@ use crate::telemetry::{self};
 use crate::telemetry::ops::stats::Phase as StatsPhase;
 use crate::stats::types::*;
 use crate::stats::db;
+use crate::stats::diff;
 
-pub async fn summary(pool: &PgPool) -> Result<()> {
+pub async fn summary(pool: &PgPool, snapshot_in: Option<&Path>, snapshot_out: Option<&Path>) -> Result<()> {
     let log = telemetry::stats();
     let _s = log.span(&StatsPhase::Summary).entered();
 
+    // read the prior snapshot up front so --snapshot-in and --snapshot-out may name the same file
+    let prior = snapshot_in.map(diff::read_snapshot).transpose()?;
+
     // feeds listing
     log.info("📡 Feeds:");
     let feeds = db::fetch_feeds(pool).await?;
@@ -30,11 +36,28 @@ pub async fn summary(pool: &PgPool) -> Result<()> {
     for r in &docs {
         log.info(format!("  {:10} {}", r.status, r.cnt));
     }
+    let errors = db::errors_by_kind(pool).await?;
+    if !errors.is_empty() {
+        log.info("❌ Errors by kind:");
+        for e in &errors {
+            log.info(format!("  {:16} {} ({})", e.kind, e.cnt, if e.transient { "transient, retried on next ingest" } else { "permanent" }));
+        }
+    }
     if let Ok(last) = db::last_fetched(pool).await { log.info(format!("  Last fetched: {:?}", last)); }
 
     // chunks summary
     if let Ok(cs) = db::chunks_summary(pool).await {
         log.info(format!("🧩 Chunks: total={} avg_tokens={:.1}", cs.total, cs.avg_tokens));
+        if cs.total > 0 {
+            log.info(format!(
+                "   Tokens: min={} median={:.0} p95={:.0} max={}",
+                cs.min_tokens.unwrap_or(0), cs.median_tokens.unwrap_or(0.0), cs.p95_tokens.unwrap_or(0.0), cs.max_tokens.unwrap_or(0)
+            ));
+            for b in &cs.histogram {
+                let range = match b.hi { Some(hi) => format!("{}–{}", b.lo, hi), None => format!("{}+", b.lo) };
+                log.info(format!("   {:>9} {}", range, b.cnt));
+            }
+        }
     }
 
     // embeddings summary
@@ -63,8 +86,10 @@ pub async fn summary(pool: &PgPool) -> Result<()> {
     let size_pretty = idx.size_pretty.clone();
     let analyze_row_last = idx.last_analyze.clone();
 
-    let mut line = String::from("ivfflat");
+    let mut line = idx.index_type.clone().unwrap_or_else(|| "(missing)".to_string());
     if let Some(k) = lists_val { line.push_str(&format!(" lists={}", k)); }
+    if let Some(m) = idx.m { line.push_str(&format!(" m={}", m)); }
+    if let Some(ef) = idx.ef_construction { line.push_str(&format!(" ef_construction={}", ef)); }
     if let Some(s) = size_pretty.as_deref() { line.push_str(&format!(" size={}", s)); }
     if let Some(ts) = analyze_row_last.as_ref() { line.push_str(&format!(" last_analyze={:?}", ts)); }
     log.info(format!("🧭 Index: {}", line));
@@ -82,8 +107,27 @@ pub async fn summary(pool: &PgPool) -> Result<()> {
     let embeddings_out = db::embeddings_totals(pool).await?;
     let index_out = db::index_meta(pool).await?;
     let coverage_out = db::coverage(pool).await?;
-    let result = StatsSummary { feeds: feeds_out, documents_by_status: docs_out, last_fetched, chunks: chunks_out, embeddings: embeddings_out, index: index_out, coverage: coverage_out };
-    log.result(&result)?;
+    let result = StatsSummary { feeds: feeds_out, documents_by_status: docs_out, errors_by_kind: errors, last_fetched, chunks: chunks_out, embeddings: embeddings_out, index: index_out, coverage: coverage_out };
+
+    if let Some(path) = snapshot_out {
+        diff::write_snapshot(path, &result)?;
+        log.info(format!("💾 Snapshot written: {}", path.display()));
+    }
+
+    let Some(prior) = prior else {
+        log.result(&result)?;
+        return Ok(());
+    };
+
+    let d = diff::diff(&prior, &result);
+    log.info("🔀 Changes since snapshot:");
+    for r in &d.documents_by_status {
+        if r.delta != 0 { log.info(format!("  {:10} {} → {} ({:+})", r.status, r.before, r.after, r.delta)); }
+    }
+    log.info(format!("  chunks     {} → {} ({:+})", d.chunks.before, d.chunks.after, d.chunks.delta));
+    log.info(format!("  embeddings {} → {} ({:+})", d.embeddings.before, d.embeddings.after, d.embeddings.delta));
+    log.info(format!("  coverage   {:.1}% → {:.1}% ({:+.1} pts), missing {:+}", d.coverage_pct.before, d.coverage_pct.after, d.coverage_pct.delta, d.missing.delta));
+    log.result(&StatsSummaryWithDiff { summary: result, diff: d })?;
 
     Ok(())
 }
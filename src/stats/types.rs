@@ -1,25 +1,54 @@
-use serde::Serialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 
 // Summary view types
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct StatsFeedRow { pub feed_id: i32, pub name: Option<String>, pub url: String, pub is_active: Option<bool>, pub added_at: Option<DateTime<Utc>> }
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct StatsDocStatus { pub status: String, pub cnt: i64 }
-#[derive(Serialize)]
-pub struct StatsChunksSummary { pub total: i64, pub avg_tokens: f64 }
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct StatsErrorKind { pub kind: String, pub transient: bool, pub cnt: i64 }
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct StatsTokenBucket { pub lo: i32, pub hi: Option<i32>, pub cnt: i64 }
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct StatsChunksSummary {
+    pub total: i64,
+    pub avg_tokens: f64,
+    // distribution (summary view only; absent in older snapshots)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_tokens: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub median_tokens: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub p95_tokens: Option<f64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub histogram: Vec<StatsTokenBucket>,
+}
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct StatsModelInfo { pub model: String, pub cnt: i64, pub last: Option<DateTime<Utc>> }
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct StatsEmbeddings { pub total: i64, pub models: Vec<StatsModelInfo> }
-#[derive(Serialize)]
-pub struct StatsIndexMeta { pub lists: Option<i32>, pub size_pretty: Option<String>, pub last_analyze: Option<DateTime<Utc>> }
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct StatsIndexMeta {
+    pub index_type: Option<String>,
+    pub lists: Option<i32>,
+    pub m: Option<i32>,
+    pub ef_construction: Option<i32>,
+    pub size_pretty: Option<String>,
+    pub last_analyze: Option<DateTime<Utc>>,
+}
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct StatsCoverage { pub chunks: i64, pub embedded: i64, pub pct: f64, pub missing: i64 }
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct StatsSummary {
     pub feeds: Vec<StatsFeedRow>,
     pub documents_by_status: Vec<StatsDocStatus>,
+    // absent from snapshots written before the ingest error taxonomy
+    #[serde(default)]
+    pub errors_by_kind: Vec<StatsErrorKind>,
     pub last_fetched: Option<DateTime<Utc>>,
     pub chunks: StatsChunksSummary,
     pub embeddings: StatsEmbeddings,
@@ -27,16 +56,39 @@ pub struct StatsSummary {
     pub coverage: StatsCoverage,
 }
 
+// Snapshot diff types (stats --snapshot-in)
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct StatsCountDelta { pub before: i64, pub after: i64, pub delta: i64 }
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct StatsPctDelta { pub before: f64, pub after: f64, pub delta: f64 }
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct StatsStatusDelta { pub status: String, pub before: i64, pub after: i64, pub delta: i64 }
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct StatsSummaryDiff {
+    pub documents_by_status: Vec<StatsStatusDelta>,
+    pub chunks: StatsCountDelta,
+    pub embeddings: StatsCountDelta,
+    pub coverage_embedded: StatsCountDelta,
+    pub coverage_pct: StatsPctDelta,
+    pub missing: StatsCountDelta,
+}
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct StatsSummaryWithDiff {
+    #[serde(flatten)]
+    pub summary: StatsSummary,
+    pub diff: StatsSummaryDiff,
+}
+
 // Feed view types
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct StatsFeedMeta { pub feed_id: i32, pub name: Option<String>, pub url: String, pub is_active: Option<bool>, pub added_at: Option<DateTime<Utc>> }
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct StatsFeedCoverage { pub chunks: i64, pub embedded: i64, pub pct: f64, pub last: Option<DateTime<Utc>> }
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct StatsPendingTopDoc { pub doc_id: i64, pub source_title: Option<String>, pub pending: i64 }
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct StatsLatestDoc { pub doc_id: i64, pub status: Option<String>, pub fetched_at: Option<DateTime<Utc>>, pub source_title: Option<String> }
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct StatsFeedStats {
     pub feed: StatsFeedMeta,
     pub documents_by_status: Vec<StatsDocStatus>,
@@ -49,12 +101,50 @@ pub struct StatsFeedStats {
     pub latest_docs: Vec<StatsLatestDoc>,
 }
 
+// Orphans view (read-only gc counts)
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct StatsOrphans {
+    pub feed: Option<i32>,
+    pub orphan_chunks: i64,
+    pub orphan_embeddings: i64,
+    pub error_docs: i64,
+    pub never_chunked_docs: i64,
+    pub bad_chunks: i64,
+    pub unembedded_chunks: i64,
+}
+
+// Vector verification view (stats --verify-vectors)
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct StatsVectorModelCheck {
+    pub model: String,
+    pub checked: i64,
+    // distinct values of the dim column seen for this model
+    pub dims: Vec<i32>,
+    // dim differs from the declared vector(N) width
+    pub dim_mismatch: i64,
+    // dim differs from the stored vector's actual length
+    pub len_mismatch: i64,
+    // vec_bits present but not one bit per dimension
+    pub bits_mismatch: i64,
+    // rows failing any of the checks above
+    pub mismatched: i64,
+    pub sample_chunk_ids: Vec<i64>,
+}
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct StatsVectorCheck {
+    pub column_dim: Option<i32>,
+    pub sample: Option<i64>,
+    pub checked: i64,
+    pub mismatched: i64,
+    pub models: Vec<StatsVectorModelCheck>,
+}
+
 // Chunk/doc snapshots
-#[derive(Serialize)]
-pub struct StatsChunkSnap { pub chunk_id: i64, pub doc_id: Option<i64>, pub chunk_index: Option<i32>, pub token_count: Option<i32>, pub preview: Option<String> }
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct StatsChunkSnap { pub chunk_id: i64, pub doc_id: Option<i64>, pub chunk_index: Option<i32>, pub token_count: Option<i32>, pub char_start: Option<i32>, pub char_end: Option<i32>, pub preview: Option<String> }
 
 // Doc view snapshot types
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct StatsDocInfo {
     pub doc_id: i64,
     pub feed_id: Option<i32>,
@@ -67,8 +157,8 @@ pub struct StatsDocInfo {
     pub preview: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct StatsDocChunkInfo { pub chunk_id: i64, pub chunk_index: Option<i32>, pub token_count: Option<i32> }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct StatsDocSnapshot { pub doc: StatsDocInfo, pub chunks: Vec<StatsDocChunkInfo> }
@@ -0,0 +1,53 @@
+use anyhow::{bail, Result};
+use sqlx::PgPool;
+
+use crate::pipeline::embed::db::vec_column_dim;
+use crate::telemetry::{self};
+use crate::telemetry::ops::stats::Phase as StatsPhase;
+use crate::stats::db;
+use crate::stats::types::StatsVectorCheck;
+use crate::util::exit;
+
+// Read-only integrity scan of rag.embedding; fails (exit 1) after reporting when any row is off,
+// so it can gate a deploy after a model swap or an interrupted migration
+pub async fn verify_vectors(pool: &PgPool, sample: Option<i64>) -> Result<()> {
+    let log = telemetry::stats();
+    let _s = log.span(&StatsPhase::VerifyVectors).entered();
+
+    let column_dim = vec_column_dim(pool).await?;
+    let models = db::verify_vectors(pool, sample, column_dim).await?;
+    let out = StatsVectorCheck {
+        column_dim,
+        sample,
+        checked: models.iter().map(|m| m.checked).sum(),
+        mismatched: models.iter().map(|m| m.mismatched).sum(),
+        models,
+    };
+
+    match column_dim {
+        Some(n) => log.info(format!("🩺 Vector check: {} embedding(s), column vector({})", out.checked, n)),
+        None => log.info(format!("🩺 Vector check: {} embedding(s), column width unconstrained", out.checked)),
+    }
+    for m in &out.models {
+        let dims: Vec<String> = m.dims.iter().map(|d| d.to_string()).collect();
+        log.info(format!(
+            "  🧬 {} — checked={} dims=[{}] dim≠column={} dim≠len={} bits≠dim={}",
+            m.model, m.checked, dims.join(","), m.dim_mismatch, m.len_mismatch, m.bits_mismatch
+        ));
+        if m.mismatched > 0 {
+            log.warn(format!("⚠️  {}: {} bad row(s), e.g. chunk_id {:?}", m.model, m.mismatched, m.sample_chunk_ids));
+        }
+    }
+    if out.checked == 0 {
+        log.info("ℹ️  No embeddings to verify");
+        exit::mark_empty();
+    } else if out.mismatched == 0 {
+        log.info("✅ Every checked vector matches its dim and the column");
+    }
+
+    log.result(&out)?;
+    if out.mismatched > 0 {
+        bail!("{} of {} checked embedding(s) failed verification; re-embed them with `rag embed --force`", out.mismatched, out.checked);
+    }
+    Ok(())
+}
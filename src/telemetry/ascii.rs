@@ -0,0 +1,95 @@
+//! Emoji → ASCII tags for human logs (`--ascii` / `RAG_ASCII`). Call sites keep their emoji;
+//! LogCtx rewrites the leading one here, so the mapping lives in one place.
+
+use std::borrow::Cow;
+
+const TAGS: &[(&str, &str)] = &[
+    ("📝", "[plan]"),
+    ("✅", "[ok]"),
+    ("🗑", "[del]"),
+    ("⚠", "[warn]"),
+    ("❌", "[fail]"),
+    ("🛑", "[stop]"),
+    ("ℹ", "[info]"),
+    ("🔎", "[would]"),
+    ("🔍", "[results]"),
+    ("🔬", "[explain]"),
+    ("⏳", "[progress]"),
+    ("⏹", "[watermark]"),
+    ("➕", "[insert]"),
+    ("♻", "[update]"),
+    ("↩", "[skip]"),
+    ("🪞", "[dup]"),
+    ("🈚", "[lang]"),
+    ("📊", "[totals]"),
+    ("📈", "[stats]"),
+    ("📄", "[doc]"),
+    ("📡", "[feed]"),
+    ("📚", "[sources]"),
+    ("📜", "[log]"),
+    ("🧩", "[chunk]"),
+    ("🧱", "[chunk]"),
+    ("🧬", "[embed]"),
+    ("🧮", "[score]"),
+    ("🧠", "[llm]"),
+    ("💡", "[answer]"),
+    ("💵", "[cost]"),
+    ("💬", "[cache]"),
+    ("💾", "[save]"),
+    ("🧹", "[clean]"),
+    ("🧽", "[clean]"),
+    ("🧼", "[clean]"),
+    ("🩺", "[check]"),
+    ("🧭", "[route]"),
+    ("🔀", "[swap]"),
+    ("🔢", "[count]"),
+    ("🔒", "[readonly]"),
+    ("🐢", "[slow]"),
+    ("🔁", "[convert]"),
+];
+
+// emoji presentation selector that follows many of the symbols above
+const VS16: char = '\u{FE0F}';
+
+/// Human log text as it should be printed: tagged under `--ascii`/`RAG_ASCII`, untouched otherwise.
+pub fn human(msg: &str) -> Cow<'_, str> {
+    if super::config::logs_are_ascii() { tag(msg) } else { Cow::Borrowed(msg) }
+}
+
+/// Replace the leading emoji (after any indentation) with its tag; unknown pictographs become `[*]`.
+pub fn tag(msg: &str) -> Cow<'_, str> {
+    let body = msg.trim_start_matches(' ');
+    let indent = &msg[..msg.len() - body.len()];
+    let Some(first) = body.chars().next() else { return Cow::Borrowed(msg) };
+    if first.is_ascii() || first.is_alphanumeric() {
+        return Cow::Borrowed(msg);
+    }
+    let label = TAGS
+        .iter()
+        .find(|(emoji, _)| body.starts_with(emoji))
+        .map(|(_, label)| *label)
+        .unwrap_or("[*]");
+    let rest = body[first.len_utf8()..].trim_start_matches(VS16).trim_start();
+    Cow::Owned(format!("{indent}{label} {rest}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_leading_emoji_and_keeps_indent() {
+        assert_eq!(tag("📝 GC plan — mode=plan"), "[plan] GC plan — mode=plan");
+        assert_eq!(tag("⚠️  probes clamped"), "[warn] probes clamped");
+        assert_eq!(tag("  🗑️ Deleted 3 rows"), "  [del] Deleted 3 rows");
+        assert_eq!(tag("🦀 crab"), "[*] crab");
+    }
+
+    #[test]
+    fn leaves_plain_messages_alone() {
+        assert!(matches!(tag("plain text"), Cow::Borrowed(_)));
+        assert!(matches!(tag("  chunk_id=3"), Cow::Borrowed(_)));
+        assert!(matches!(tag("Über"), Cow::Borrowed(_)));
+        assert_eq!(tag(""), "");
+    }
+}
@@ -0,0 +1,56 @@
+//! Progress bar on stderr for interactive runs, driven by `LogCtx::progress`. Only drawn for
+//! text logs on a terminal without --quiet; JSON logs, pipes and stdout envelopes are untouched.
+//! Log lines go through `BarWriter`, which hides the bar while they print so it isn't torn.
+
+use std::io::{IsTerminal, Write};
+use std::sync::Mutex;
+
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+
+static BAR: Mutex<Option<ProgressBar>> = Mutex::new(None);
+
+pub fn enabled(json: bool, quiet: bool) -> bool {
+    !json && !quiet && std::io::stderr().is_terminal()
+}
+
+/// Move the bar to `done` of `total`, creating it on first use; reaching the total clears it.
+pub fn update(op: &str, done: u64, total: u64, unit: &str) {
+    let mut slot = BAR.lock().unwrap_or_else(|e| e.into_inner());
+    let bar = slot.get_or_insert_with(|| {
+        let bar = ProgressBar::with_draw_target(Some(total), ProgressDrawTarget::stderr());
+        let style = ProgressStyle::with_template("⏳ {prefix} [{bar:30}] {pos}/{len} {msg} ({eta})")
+            .unwrap_or_else(|_| ProgressStyle::default_bar())
+            .progress_chars("=> ");
+        bar.set_style(style);
+        bar
+    });
+    bar.set_prefix(op.to_string());
+    bar.set_message(unit.to_string());
+    bar.set_length(total);
+    bar.set_position(done);
+    if done >= total {
+        bar.finish_and_clear();
+        *slot = None;
+    }
+}
+
+/// Drop a bar that never reached its total (Ctrl-C, failed batches) before the result is printed.
+pub fn clear() {
+    let mut slot = BAR.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(bar) = slot.take() { bar.finish_and_clear(); }
+}
+
+/// stderr writer for the tracing text layer.
+pub struct BarWriter;
+
+impl Write for BarWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let slot = BAR.lock().unwrap_or_else(|e| e.into_inner());
+        match slot.as_ref() {
+            Some(bar) => bar.suspend(|| std::io::stderr().write(buf)),
+            None => std::io::stderr().write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> { std::io::stderr().flush() }
+}
@@ -1,25 +1,78 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+static ASCII: AtomicBool = AtomicBool::new(false);
+
 pub fn logs_are_json() -> bool {
     matches!(std::env::var("RAG_LOG_FORMAT").as_deref(), Ok("json"))
 }
 
+/// True when human info/debug logs are suppressed (`--quiet` or `RAG_QUIET`).
+pub fn logs_are_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// True when human logs swap emoji for ASCII tags (`--ascii` or `RAG_ASCII`).
+pub fn logs_are_ascii() -> bool {
+    ASCII.load(Ordering::Relaxed)
+}
+
+/// `1`, `true` or `yes` (case-insensitive) in env var `key`.
+pub(crate) fn env_flag(key: &str) -> bool {
+    match std::env::var(key).ok().as_deref() {
+        Some(v) => v == "1" || v.eq_ignore_ascii_case("true") || v.eq_ignore_ascii_case("yes"),
+        None => false,
+    }
+}
+
+fn quiet_from_env() -> bool {
+    env_flag("RAG_QUIET")
+}
+
+// https://no-color.org: any non-empty NO_COLOR disables ANSI styling
+fn no_color_from_env() -> bool {
+    std::env::var("NO_COLOR").is_ok_and(|v| !v.is_empty())
+}
+
 /// Initialize tracing/logging according to RUST_LOG and RAG_LOG_FORMAT.
 /// - Defaults to `info` if `RUST_LOG` is unset
 /// - Supports `RAG_LOG_FORMAT=json` for JSON logs (stderr)
-pub fn init_tracing() {
+/// - `quiet` (or `RAG_QUIET=1`) forces `warn`, leaving stdout envelopes untouched
+/// - `ascii` (or `RAG_ASCII=1`) swaps emoji for ASCII tags and drops ANSI colors; `NO_COLOR` drops colors only
+/// - With feature `otel`, exports spans via OTLP when `OTEL_EXPORTER_OTLP_ENDPOINT` is set
+pub fn init_tracing(quiet: bool, ascii: bool) {
     use tracing_subscriber::{fmt, EnvFilter};
     use tracing_subscriber::prelude::*; // for .with()
 
-    // Default filter if RUST_LOG unset
-    let filter = EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| EnvFilter::new("info"));
+    let quiet = quiet || quiet_from_env();
+    QUIET.store(quiet, Ordering::Relaxed);
+    let ascii = ascii || env_flag("RAG_ASCII");
+    ASCII.store(ascii, Ordering::Relaxed);
+    let ansi = !ascii && !no_color_from_env();
+
+    // Default filter if RUST_LOG unset; quiet overrides it
+    let filter = if quiet {
+        EnvFilter::new("warn")
+    } else {
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"))
+    };
 
     let builder = tracing_subscriber::registry().with(filter);
+    // Optional OTLP export of op/phase spans (feature `otel` + OTEL_EXPORTER_OTLP_ENDPOINT)
+    #[cfg(feature = "otel")]
+    let (otel_layer, otel_err) = match super::otel::layer() {
+        Ok(layer) => (layer, None),
+        Err(e) => (None, Some(e)),
+    };
+    #[cfg(feature = "otel")]
+    let builder = builder.with(otel_layer);
 
     match std::env::var("RAG_LOG_FORMAT").as_deref() {
         Ok("json") => {
             let json_layer = fmt::layer()
                 .with_target(false)
                 .with_writer(std::io::stderr)
+                .with_ansi(false)
                 .json()
                 .flatten_event(true);
             let _ = builder.with(json_layer).try_init();
@@ -28,9 +81,16 @@ pub fn init_tracing() {
             // human-friendly compact text
             let text_layer = fmt::layer()
                 .with_target(false)
-                .with_writer(std::io::stderr)
+                .with_writer(|| super::bar::BarWriter)
+                .with_ansi(ansi)
                 .compact();
             let _ = builder.with(text_layer).try_init();
         }
     }
+
+    // reported only now that the fmt layer is installed
+    #[cfg(feature = "otel")]
+    if let Some(e) = otel_err {
+        tracing::warn!("{}", super::ascii::human(&format!("⚠️  OTLP exporter disabled: {e}")));
+    }
 }
@@ -3,7 +3,10 @@ use serde::Serialize;
 use std::marker::PhantomData;
 use tracing::{info, debug, warn, error, Span};
 
-use super::{emit};
+use super::ascii::human as text;
+use super::bar;
+use super::emit;
+use crate::output::types::{Outcome, Progress};
 
 pub trait PhaseSpan {
     fn name(&self) -> &'static str;
@@ -18,6 +21,7 @@ pub trait OpMarker {
 
 pub struct LogCtx<O: OpMarker> {
     pub(crate) json: bool,
+    pub(crate) quiet: bool,
     pub(crate) _marker: PhantomData<O>,
 }
 
@@ -56,25 +60,27 @@ impl<O: OpMarker> LogCtx<O> {
         span
     }
 
-    pub fn info(&self, msg: impl AsRef<str>) { if self.json { info!(op = %self.op_name(), "{}", msg.as_ref()); } else { info!("{}", msg.as_ref()); } }
-    pub fn debug(&self, msg: impl AsRef<str>) { if self.json { debug!(op = %self.op_name(), "{}", msg.as_ref()); } else { debug!("{}", msg.as_ref()); } }
-    pub fn warn(&self, msg: impl AsRef<str>) { if self.json { warn!(op = %self.op_name(), "{}", msg.as_ref()); } else { warn!("{}", msg.as_ref()); } }
-    pub fn error(&self, msg: impl AsRef<str>) { if self.json { error!(op = %self.op_name(), "{}", msg.as_ref()); } else { error!("{}", msg.as_ref()); } }
+    pub fn info(&self, msg: impl AsRef<str>) { if self.quiet { return; } if self.json { info!(op = %self.op_name(), "{}", msg.as_ref()); } else { info!("{}", text(msg.as_ref())); } }
+    pub fn debug(&self, msg: impl AsRef<str>) { if self.quiet { return; } if self.json { debug!(op = %self.op_name(), "{}", msg.as_ref()); } else { debug!("{}", text(msg.as_ref())); } }
+    pub fn warn(&self, msg: impl AsRef<str>) { if self.json { warn!(op = %self.op_name(), "{}", msg.as_ref()); } else { warn!("{}", text(msg.as_ref())); } }
+    pub fn error(&self, msg: impl AsRef<str>) { if self.json { error!(op = %self.op_name(), "{}", msg.as_ref()); } else { error!("{}", text(msg.as_ref())); } }
 
     pub fn info_kv<'a, D>(&self, msg: &str, kv: D)
     where
         D: IntoIterator<Item = (&'a str, String)>,
     {
+        if self.quiet { return; }
         if self.json { let details = kv_to_string(kv); info!(op = %self.op_name(), details = %details, "{}", msg); }
-        else { info!("{}", msg); }
+        else { info!("{}", text(msg)); }
     }
 
     pub fn debug_kv<'a, D>(&self, msg: &str, kv: D)
     where
         D: IntoIterator<Item = (&'a str, String)>,
     {
+        if self.quiet { return; }
         if self.json { let details = kv_to_string(kv); debug!(op = %self.op_name(), details = %details, "{}", msg); }
-        else { debug!("{}", msg); }
+        else { debug!("{}", text(msg)); }
     }
 
     pub fn warn_kv<'a, D>(&self, msg: &str, kv: D)
@@ -82,7 +88,7 @@ impl<O: OpMarker> LogCtx<O> {
         D: IntoIterator<Item = (&'a str, String)>,
     {
         if self.json { let details = kv_to_string(kv); warn!(op = %self.op_name(), details = %details, "{}", msg); }
-        else { warn!("{}", msg); }
+        else { warn!("{}", text(msg)); }
     }
 
     pub fn error_kv<'a, D>(&self, msg: &str, kv: D)
@@ -90,23 +96,55 @@ impl<O: OpMarker> LogCtx<O> {
         D: IntoIterator<Item = (&'a str, String)>,
     {
         if self.json { let details = kv_to_string(kv); error!(op = %self.op_name(), details = %details, "{}", msg); }
-        else { error!("{}", msg); }
+        else { error!("{}", text(msg)); }
     }
 
-    pub fn plan<T: Serialize>(&self, plan: &T) -> Result<()> { emit::print_plan(self.op_name(), plan, None) }
-    pub fn result<T: Serialize>(&self, result: &T) -> Result<()> { emit::print_result(self.op_name(), result, None) }
+    // Structured progress: typed fields in JSON logs, a bar on an interactive terminal,
+    // a notification for MCP output.
+    pub fn progress(&self, done: u64, total: Option<u64>, unit: &str) -> Result<()> {
+        if self.json {
+            match total {
+                Some(t) => info!(op = %self.op_name(), done, total = t, unit, "progress"),
+                None => info!(op = %self.op_name(), done, unit, "progress"),
+            }
+        } else if let (Some(t), true) = (total, bar::enabled(self.json, self.quiet)) {
+            bar::update(self.op_name(), done, t, unit);
+        } else {
+            match total {
+                Some(t) => info!("{}", text(&format!("⏳ {} progress — {}/{} {}", self.op_name(), done, t, unit))),
+                None => info!("{}", text(&format!("⏳ {} progress — {} {}", self.op_name(), done, unit))),
+            }
+        }
+        emit::print_progress(self.op_name(), &Progress { done, total, unit: unit.to_string() })
+    }
+
+    pub fn plan<T: Serialize>(&self, plan: &T) -> Result<()> { emit::print_plan(self.op_name(), plan, emit::run_meta()) }
+    pub fn result<T: Serialize>(&self, result: &T) -> Result<()> {
+        bar::clear();
+        emit::print_result(self.op_name(), result, emit::run_meta())
+    }
+    /// Plan or result envelope for an op's outcome; `Nothing` emits no envelope.
+    pub fn outcome<P: Serialize, R: Serialize>(&self, outcome: &Outcome<P, R>) -> Result<()> {
+        match outcome {
+            Outcome::Plan(plan) => self.plan(plan),
+            Outcome::Applied(result) => self.result(result),
+            Outcome::Nothing => Ok(()),
+        }
+    }
+    /// Row-by-row result; streams under NDJSON output, otherwise one array result on `finish`.
+    pub fn result_stream(&self) -> emit::ResultStream { emit::ResultStream::new(self.op_name()) }
 }
 
 // Ingest-specific helpers remain available on the typed context
 impl LogCtx<crate::telemetry::ops::ingest::Ingest> {
-    pub fn feed_summary(&self, feed_id: i32, inserted: usize, updated: usize, skipped: usize, errors: usize) {
-        if self.json { info!(op = %self.op_name(), feed_id, inserted, updated, skipped, errors, "feed_summary"); }
-        else { info!("✅ Feed {} — inserted={} updated={} skipped={} errors={}", feed_id, inserted, updated, skipped, errors); }
+    pub fn feed_summary(&self, feed_id: i32, inserted: usize, updated: usize, skipped: usize, duplicates: usize, errors: usize) {
+        if self.json { info!(op = %self.op_name(), feed_id, inserted, updated, skipped, duplicates, errors, "feed_summary"); }
+        else { info!("{}", text(&format!("✅ Feed {} — inserted={} updated={} skipped={} duplicates={} errors={}", feed_id, inserted, updated, skipped, duplicates, errors))); }
     }
 
-    pub fn totals(&self, inserted: usize, updated: usize, skipped: usize, errors: usize) {
-        if self.json { info!(op = %self.op_name(), inserted, updated, skipped, errors, "ingest_totals"); }
-        else { info!("📊 Ingest totals — inserted={} updated={} skipped={} errors={}", inserted, updated, skipped, errors); }
+    pub fn totals(&self, inserted: usize, updated: usize, skipped: usize, duplicates: usize, errors: usize) {
+        if self.json { info!(op = %self.op_name(), inserted, updated, skipped, duplicates, errors, "ingest_totals"); }
+        else { info!("{}", text(&format!("📊 Ingest totals — inserted={} updated={} skipped={} duplicates={} errors={}", inserted, updated, skipped, duplicates, errors))); }
     }
 }
 
@@ -1,17 +1,75 @@
-use anyhow::Result;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+use anyhow::{Context, Result};
 use serde::Serialize;
+use serde_json::Value;
+use uuid::Uuid;
 
 use crate::output::config::OutputConfig;
-use crate::output::types::Envelope;
+use crate::output::config::OutputFormat;
+use crate::output::types::{Envelope, Progress, RowLine};
 use crate::output::Emitter;
 
 pub type Meta = crate::output::types::Meta;
 
+// One run id + start instant per process, shared by every envelope
+static RUN: OnceLock<(String, Instant)> = OnceLock::new();
+
+/// Start the invocation clock and mint its run_id. Call once from main.
+pub fn init_run(t0: Instant) -> &'static str {
+    let (run_id, _) = RUN.get_or_init(|| (Uuid::new_v4().to_string(), t0));
+    run_id
+}
+
+// --output <path>: plan/result/progress envelopes go here instead of stdout
+static OUTPUT_FILE: OnceLock<Mutex<File>> = OnceLock::new();
+
+/// Send envelopes to `path` (truncated, or appended to with `append`). Call once from main.
+pub fn init_output_file(path: &Path, append: bool) -> Result<()> {
+    let file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(path)
+        .with_context(|| format!("open output file {}", path.display()))?;
+    let _ = OUTPUT_FILE.set(Mutex::new(file));
+    Ok(())
+}
+
+fn with_output(write: impl FnOnce(&mut dyn Write) -> io::Result<()>) -> io::Result<()> {
+    match OUTPUT_FILE.get() {
+        Some(file) => {
+            let mut file = file.lock().unwrap_or_else(|e| e.into_inner());
+            write(&mut *file)
+        }
+        None => write(&mut io::stdout()),
+    }
+}
+
+/// Plain text line to stdout (or --output) in place of an envelope, e.g. `query --template` rows.
+pub fn print_line(line: &str) -> Result<()> {
+    with_output(|w| writeln!(w, "{}", line))?;
+    Ok(())
+}
+
+/// Meta for the current invocation (`None` before `init_run`).
+pub fn run_meta() -> Option<Meta> {
+    RUN.get().map(|(run_id, t0)| Meta {
+        duration_ms: Some(t0.elapsed().as_millis()),
+        run_id: Some(run_id.clone()),
+    })
+}
+
 pub fn print_plan<T: Serialize>(op: &str, plan: &T, meta: Option<Meta>) -> Result<()> {
     let env = Envelope::plan(op, plan, meta)?;
     let cfg = OutputConfig::from_env();
     let emitter = Emitter::from_env(cfg);
-    emitter.emit(&env)?;
+    with_output(|w| emitter.emit_to(&env, w))?;
     Ok(())
 }
 
@@ -19,6 +77,53 @@ pub fn print_result<T: Serialize>(op: &str, result: &T, meta: Option<Meta>) -> R
     let env = Envelope::result(op, result, meta)?;
     let cfg = OutputConfig::from_env();
     let emitter = Emitter::from_env(cfg);
-    emitter.emit(&env)?;
+    with_output(|w| emitter.emit_to(&env, w))?;
+    Ok(())
+}
+
+/// Result rows emitted as they are produced. With `RAG_OUTPUT_FORMAT=ndjson` each `push`
+/// writes a `{schema_version, op, row}` line immediately and `finish` closes with a result
+/// envelope of `{"rows": n}`; every other format buffers and emits the rows as one array result.
+pub struct ResultStream {
+    op: &'static str,
+    ndjson: bool,
+    buffered: Vec<Value>,
+    count: usize,
+}
+
+impl ResultStream {
+    pub fn new(op: &'static str) -> Self {
+        let ndjson = OutputConfig::from_env().format == OutputFormat::Ndjson;
+        ResultStream { op, ndjson, buffered: Vec::new(), count: 0 }
+    }
+
+    pub fn push<T: Serialize>(&mut self, row: &T) -> Result<()> {
+        self.count += 1;
+        if self.ndjson {
+            let line = RowLine::new(self.op, row)?;
+            with_output(|w| {
+                serde_json::to_writer(&mut *w, &line).map_err(io::Error::other)?;
+                writeln!(w)?;
+                w.flush()
+            })?;
+        } else {
+            self.buffered.push(serde_json::to_value(row)?);
+        }
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<()> {
+        if self.ndjson {
+            print_result(self.op, &serde_json::json!({ "rows": self.count }), run_meta())
+        } else {
+            print_result(self.op, &self.buffered, run_meta())
+        }
+    }
+}
+
+pub fn print_progress(op: &str, progress: &Progress) -> Result<()> {
+    let cfg = OutputConfig::from_env();
+    let emitter = Emitter::from_env(cfg);
+    with_output(|w| emitter.emit_progress_to(op, progress, w))?;
     Ok(())
 }
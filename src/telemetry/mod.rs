@@ -1,18 +1,27 @@
+pub mod ascii;
+pub mod bar;
 pub mod config;
 pub mod ctx;
 pub mod emit;
 pub mod macros;
 pub mod ops;
+#[cfg(feature = "otel")]
+pub mod otel;
 
 use ctx::LogCtx;
 
 // Factory helpers mirroring the old out::{ingest, init, ...} API
-pub fn ingest() -> LogCtx<ops::ingest::Ingest> { LogCtx { json: config::logs_are_json(), _marker: std::marker::PhantomData } }
-pub fn feed() -> LogCtx<ops::feed::Feed> { LogCtx { json: config::logs_are_json(), _marker: std::marker::PhantomData } }
-pub fn chunk() -> LogCtx<ops::chunk::Chunk> { LogCtx { json: config::logs_are_json(), _marker: std::marker::PhantomData } }
-pub fn embed() -> LogCtx<ops::embed::Embed> { LogCtx { json: config::logs_are_json(), _marker: std::marker::PhantomData } }
-pub fn reindex() -> LogCtx<ops::reindex::Reindex> { LogCtx { json: config::logs_are_json(), _marker: std::marker::PhantomData } }
-pub fn gc() -> LogCtx<ops::gc::Gc> { LogCtx { json: config::logs_are_json(), _marker: std::marker::PhantomData } }
-pub fn stats() -> LogCtx<ops::stats::Stats> { LogCtx { json: config::logs_are_json(), _marker: std::marker::PhantomData } }
-pub fn query() -> LogCtx<ops::query::Query> { LogCtx { json: config::logs_are_json(), _marker: std::marker::PhantomData } }
-pub fn compose() -> LogCtx<ops::compose::Compose> { LogCtx { json: config::logs_are_json(), _marker: std::marker::PhantomData } }
+pub fn ingest() -> LogCtx<ops::ingest::Ingest> { LogCtx { json: config::logs_are_json(), quiet: config::logs_are_quiet(), _marker: std::marker::PhantomData } }
+pub fn feed() -> LogCtx<ops::feed::Feed> { LogCtx { json: config::logs_are_json(), quiet: config::logs_are_quiet(), _marker: std::marker::PhantomData } }
+pub fn chunk() -> LogCtx<ops::chunk::Chunk> { LogCtx { json: config::logs_are_json(), quiet: config::logs_are_quiet(), _marker: std::marker::PhantomData } }
+pub fn embed() -> LogCtx<ops::embed::Embed> { LogCtx { json: config::logs_are_json(), quiet: config::logs_are_quiet(), _marker: std::marker::PhantomData } }
+pub fn reindex() -> LogCtx<ops::reindex::Reindex> { LogCtx { json: config::logs_are_json(), quiet: config::logs_are_quiet(), _marker: std::marker::PhantomData } }
+pub fn gc() -> LogCtx<ops::gc::Gc> { LogCtx { json: config::logs_are_json(), quiet: config::logs_are_quiet(), _marker: std::marker::PhantomData } }
+pub fn stats() -> LogCtx<ops::stats::Stats> { LogCtx { json: config::logs_are_json(), quiet: config::logs_are_quiet(), _marker: std::marker::PhantomData } }
+pub fn query() -> LogCtx<ops::query::Query> { LogCtx { json: config::logs_are_json(), quiet: config::logs_are_quiet(), _marker: std::marker::PhantomData } }
+pub fn compose() -> LogCtx<ops::compose::Compose> { LogCtx { json: config::logs_are_json(), quiet: config::logs_are_quiet(), _marker: std::marker::PhantomData } }
+pub fn purge() -> LogCtx<ops::purge::Purge> { LogCtx { json: config::logs_are_json(), quiet: config::logs_are_quiet(), _marker: std::marker::PhantomData } }
+pub fn fix_status() -> LogCtx<ops::fix_status::FixStatus> { LogCtx { json: config::logs_are_json(), quiet: config::logs_are_quiet(), _marker: std::marker::PhantomData } }
+pub fn reembed() -> LogCtx<ops::reembed::Reembed> { LogCtx { json: config::logs_are_json(), quiet: config::logs_are_quiet(), _marker: std::marker::PhantomData } }
+pub fn selftest_embed() -> LogCtx<ops::selftest::SelftestEmbed> { LogCtx { json: config::logs_are_json(), quiet: config::logs_are_quiet(), _marker: std::marker::PhantomData } }
+pub fn extract_debug() -> LogCtx<ops::extract::ExtractDebug> { LogCtx { json: config::logs_are_json(), quiet: config::logs_are_quiet(), _marker: std::marker::PhantomData } }
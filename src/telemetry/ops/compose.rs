@@ -8,8 +8,10 @@ pub struct Compose;
 #[derive(Copy, Clone, Debug)]
 pub enum Phase {
     Prepare,
+    Expand,
     Retrieve,
     Prompt,
+    Cache,
     CallLlm,
     Output,
 }
@@ -18,8 +20,10 @@ impl PhaseSpan for Phase {
     fn name(&self) -> &'static str {
         match self {
             Phase::Prepare => "prepare",
+            Phase::Expand => "expand",
             Phase::Retrieve => "retrieve",
             Phase::Prompt => "prompt",
+            Phase::Cache => "cache",
             Phase::CallLlm => "call_llm",
             Phase::Output => "output",
         }
@@ -28,8 +32,10 @@ impl PhaseSpan for Phase {
     fn span(&self) -> Span {
         match self {
             Phase::Prepare => info_span!("prepare"),
+            Phase::Expand => info_span!("expand"),
             Phase::Retrieve => info_span!("retrieve"),
             Phase::Prompt => info_span!("prompt"),
+            Phase::Cache => info_span!("cache"),
             Phase::CallLlm => info_span!("call_llm"),
             Phase::Output => info_span!("output"),
         }
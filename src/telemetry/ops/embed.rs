@@ -7,7 +7,7 @@ use crate::telemetry::ctx::{OpMarker, PhaseSpan};
 pub struct Embed;
 
 #[derive(Copy, Clone, Debug)]
-pub enum Phase { Plan, CountCandidates, LoadModel, FetchBatch, Encode, InsertEmbedding }
+pub enum Phase { Plan, CountCandidates, LoadModel, FetchBatch, Encode, InsertEmbedding, Centroids }
 
 impl PhaseSpan for Phase {
     fn name(&self) -> &'static str { match self {
@@ -17,6 +17,7 @@ impl PhaseSpan for Phase {
         Phase::FetchBatch => "fetch_batch",
         Phase::Encode => "encode",
         Phase::InsertEmbedding => "insert_embedding",
+        Phase::Centroids => "centroids",
     }}
     fn span(&self) -> Span { match self {
         Phase::Plan => info_span!("plan"),
@@ -25,6 +26,7 @@ impl PhaseSpan for Phase {
         Phase::FetchBatch => info_span!("fetch_batch"),
         Phase::Encode => info_span!("encode"),
         Phase::InsertEmbedding => info_span!("insert_embedding"),
+        Phase::Centroids => info_span!("centroids"),
     }}
 }
 
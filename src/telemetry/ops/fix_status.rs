@@ -0,0 +1,29 @@
+use tracing::Span;
+use tracing::info_span;
+
+use crate::telemetry::ctx::{OpMarker, PhaseSpan};
+
+#[derive(Copy, Clone, Debug)]
+pub struct FixStatus;
+
+#[derive(Copy, Clone, Debug)]
+pub enum Phase { Plan, Count, Update }
+
+impl PhaseSpan for Phase {
+    fn name(&self) -> &'static str { match self {
+        Phase::Plan => "plan",
+        Phase::Count => "count",
+        Phase::Update => "update",
+    }}
+    fn span(&self) -> Span { match self {
+        Phase::Plan => info_span!("plan"),
+        Phase::Count => info_span!("count"),
+        Phase::Update => info_span!("update"),
+    }}
+}
+
+impl OpMarker for FixStatus {
+    const NAME: &'static str = "fix_status";
+    type Phase = Phase;
+    fn root_span() -> Span { info_span!("fix_status") }
+}
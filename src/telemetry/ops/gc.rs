@@ -7,7 +7,7 @@ use crate::telemetry::ctx::{OpMarker, PhaseSpan};
 pub struct Gc;
 
 #[derive(Copy, Clone, Debug)]
-pub enum Phase { Plan, Count, Delete, FixStatus, DropTemp, Analyze, Vacuum }
+pub enum Phase { Plan, Count, Delete, FixStatus, DropTemp, Analyze, Vacuum, Reindex }
 
 impl PhaseSpan for Phase {
     fn name(&self) -> &'static str { match self {
@@ -18,6 +18,7 @@ impl PhaseSpan for Phase {
         Phase::DropTemp => "drop_temp",
         Phase::Analyze => "analyze",
         Phase::Vacuum => "vacuum",
+        Phase::Reindex => "reindex",
     }}
     fn span(&self) -> Span { match self {
         Phase::Plan => info_span!("plan"),
@@ -27,6 +28,7 @@ impl PhaseSpan for Phase {
         Phase::DropTemp => info_span!("drop_temp"),
         Phase::Analyze => info_span!("analyze"),
         Phase::Vacuum => info_span!("vacuum"),
+        Phase::Reindex => info_span!("reindex"),
     }}
 }
 
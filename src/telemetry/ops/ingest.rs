@@ -7,7 +7,7 @@ use crate::telemetry::ctx::{OpMarker, PhaseSpan};
 pub struct Ingest;
 
 #[derive(Copy, Clone, Debug)]
-pub enum Phase { Feed, FetchRss, ParseRss, FetchItem, Extract, WriteDoc }
+pub enum Phase { Feed, FetchRss, ParseRss, FetchItem, Extract, WriteDoc, SelectErrors }
 
 impl PhaseSpan for Phase {
     fn name(&self) -> &'static str { match self {
@@ -17,6 +17,7 @@ impl PhaseSpan for Phase {
         Phase::FetchItem => "fetch_item",
         Phase::Extract => "extract",
         Phase::WriteDoc => "write_doc",
+        Phase::SelectErrors => "select_errors",
     }}
     fn span(&self) -> Span { match self {
         Phase::Feed => info_span!("feed"),
@@ -25,6 +26,7 @@ impl PhaseSpan for Phase {
         Phase::FetchItem => info_span!("fetch_item"),
         Phase::Extract => info_span!("extract"),
         Phase::WriteDoc => info_span!("write_doc"),
+        Phase::SelectErrors => info_span!("select_errors"),
     }}
 }
 
@@ -7,3 +7,8 @@ pub mod gc;
 pub mod stats;
 pub mod query;
 pub mod compose;
+pub mod purge;
+pub mod extract;
+pub mod reembed;
+pub mod selftest;
+pub mod fix_status;
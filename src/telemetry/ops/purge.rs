@@ -0,0 +1,31 @@
+use tracing::Span;
+use tracing::info_span;
+
+use crate::telemetry::ctx::{OpMarker, PhaseSpan};
+
+#[derive(Copy, Clone, Debug)]
+pub struct Purge;
+
+#[derive(Copy, Clone, Debug)]
+pub enum Phase { Plan, Count, Confirm, Delete }
+
+impl PhaseSpan for Phase {
+    fn name(&self) -> &'static str { match self {
+        Phase::Plan => "plan",
+        Phase::Count => "count",
+        Phase::Confirm => "confirm",
+        Phase::Delete => "delete",
+    }}
+    fn span(&self) -> Span { match self {
+        Phase::Plan => info_span!("plan"),
+        Phase::Count => info_span!("count"),
+        Phase::Confirm => info_span!("confirm"),
+        Phase::Delete => info_span!("delete"),
+    }}
+}
+
+impl OpMarker for Purge {
+    const NAME: &'static str = "purge";
+    type Phase = Phase;
+    fn root_span() -> Span { info_span!("purge") }
+}
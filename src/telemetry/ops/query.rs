@@ -7,7 +7,7 @@ use crate::telemetry::ctx::{OpMarker, PhaseSpan};
 pub struct Query;
 
 #[derive(Copy, Clone, Debug)]
-pub enum Phase { Prepare, EmbedQuery, SetProbes, FetchCandidates, PostFilter, Output }
+pub enum Phase { Prepare, EmbedQuery, SetProbes, FetchCandidates, Explain, Rerank, PostFilter, Output, FetchDocs }
 
 impl PhaseSpan for Phase {
     fn name(&self) -> &'static str { match self {
@@ -15,16 +15,22 @@ impl PhaseSpan for Phase {
         Phase::EmbedQuery => "embed_query",
         Phase::SetProbes => "set_probes",
         Phase::FetchCandidates => "fetch_candidates",
+        Phase::Explain => "explain",
+        Phase::Rerank => "rerank",
         Phase::PostFilter => "post_filter",
         Phase::Output => "output",
+        Phase::FetchDocs => "fetch_docs",
     }}
     fn span(&self) -> Span { match self {
         Phase::Prepare => info_span!("prepare"),
         Phase::EmbedQuery => info_span!("embed_query"),
         Phase::SetProbes => info_span!("set_probes"),
         Phase::FetchCandidates => info_span!("fetch_candidates"),
+        Phase::Explain => info_span!("explain"),
+        Phase::Rerank => info_span!("rerank"),
         Phase::PostFilter => info_span!("post_filter"),
         Phase::Output => info_span!("output"),
+        Phase::FetchDocs => info_span!("fetch_docs"),
     }}
 }
 
@@ -0,0 +1,35 @@
+use tracing::Span;
+use tracing::info_span;
+
+use crate::telemetry::ctx::{OpMarker, PhaseSpan};
+
+#[derive(Copy, Clone, Debug)]
+pub struct Reembed;
+
+#[derive(Copy, Clone, Debug)]
+pub enum Phase { Plan, SelectDocs, LoadModel, Chunk, Embed, Centroids }
+
+impl PhaseSpan for Phase {
+    fn name(&self) -> &'static str { match self {
+        Phase::Plan => "plan",
+        Phase::SelectDocs => "select_docs",
+        Phase::LoadModel => "load_model",
+        Phase::Chunk => "chunk",
+        Phase::Embed => "embed",
+        Phase::Centroids => "centroids",
+    }}
+    fn span(&self) -> Span { match self {
+        Phase::Plan => info_span!("plan"),
+        Phase::SelectDocs => info_span!("select_docs"),
+        Phase::LoadModel => info_span!("load_model"),
+        Phase::Chunk => info_span!("chunk"),
+        Phase::Embed => info_span!("embed"),
+        Phase::Centroids => info_span!("centroids"),
+    }}
+}
+
+impl OpMarker for Reembed {
+    const NAME: &'static str = "reembed_changed";
+    type Phase = Phase;
+    fn root_span() -> Span { info_span!("reembed_changed") }
+}
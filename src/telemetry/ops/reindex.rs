@@ -7,11 +7,12 @@ use crate::telemetry::ctx::{OpMarker, PhaseSpan};
 pub struct Reindex;
 
 #[derive(Copy, Clone, Debug)]
-pub enum Phase { Plan, CreateIndex, Reindex, Swap, Analyze }
+pub enum Phase { Plan, ConvertColumn, CreateIndex, Reindex, Swap, Analyze }
 
 impl PhaseSpan for Phase {
     fn name(&self) -> &'static str { match self {
         Phase::Plan => "plan",
+        Phase::ConvertColumn => "convert_column",
         Phase::CreateIndex => "create_index",
         Phase::Reindex => "reindex",
         Phase::Swap => "swap",
@@ -19,6 +20,7 @@ impl PhaseSpan for Phase {
     }}
     fn span(&self) -> Span { match self {
         Phase::Plan => info_span!("plan"),
+        Phase::ConvertColumn => info_span!("convert_column"),
         Phase::CreateIndex => info_span!("create_index"),
         Phase::Reindex => info_span!("reindex"),
         Phase::Swap => info_span!("swap"),
@@ -0,0 +1,31 @@
+use tracing::Span;
+use tracing::info_span;
+
+use crate::telemetry::ctx::{OpMarker, PhaseSpan};
+
+#[derive(Copy, Clone, Debug)]
+pub struct SelftestEmbed;
+
+#[derive(Copy, Clone, Debug)]
+pub enum Phase { LoadModel, Embed, Nearest, Farthest }
+
+impl PhaseSpan for Phase {
+    fn name(&self) -> &'static str { match self {
+        Phase::LoadModel => "load_model",
+        Phase::Embed => "embed",
+        Phase::Nearest => "nearest",
+        Phase::Farthest => "farthest",
+    }}
+    fn span(&self) -> Span { match self {
+        Phase::LoadModel => info_span!("load_model"),
+        Phase::Embed => info_span!("embed"),
+        Phase::Nearest => info_span!("nearest"),
+        Phase::Farthest => info_span!("farthest"),
+    }}
+}
+
+impl OpMarker for SelftestEmbed {
+    const NAME: &'static str = "selftest_embed";
+    type Phase = Phase;
+    fn root_span() -> Span { info_span!("selftest_embed") }
+}
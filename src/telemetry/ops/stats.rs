@@ -7,7 +7,7 @@ use crate::telemetry::ctx::{OpMarker, PhaseSpan};
 pub struct Stats;
 
 #[derive(Copy, Clone, Debug)]
-pub enum Phase { Summary, FeedStats, DocSnapshot, ChunkSnapshot }
+pub enum Phase { Summary, FeedStats, DocSnapshot, ChunkSnapshot, Orphans, VerifyVectors }
 
 impl PhaseSpan for Phase {
     fn name(&self) -> &'static str { match self {
@@ -15,12 +15,16 @@ impl PhaseSpan for Phase {
         Phase::FeedStats => "feed_stats",
         Phase::DocSnapshot => "doc_snapshot",
         Phase::ChunkSnapshot => "chunk_snapshot",
+        Phase::Orphans => "orphans",
+        Phase::VerifyVectors => "verify_vectors",
     }}
     fn span(&self) -> Span { match self {
         Phase::Summary => info_span!("summary"),
         Phase::FeedStats => info_span!("feed_stats"),
         Phase::DocSnapshot => info_span!("doc_snapshot"),
         Phase::ChunkSnapshot => info_span!("chunk_snapshot"),
+        Phase::Orphans => info_span!("orphans"),
+        Phase::VerifyVectors => info_span!("verify_vectors"),
     }}
 }
 
@@ -0,0 +1,33 @@
+use opentelemetry::trace::TraceError;
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{runtime, trace, Resource};
+use tracing::Subscriber;
+use tracing_opentelemetry::OpenTelemetryLayer;
+use tracing_subscriber::registry::LookupSpan;
+
+/// Build the OTLP tracing layer when `OTEL_EXPORTER_OTLP_ENDPOINT` is set.
+/// Must run inside the tokio runtime (batch exporter spawns a task).
+/// Install errors are returned rather than logged: the subscriber isn't up yet.
+pub fn layer<S>() -> Result<Option<OpenTelemetryLayer<S, trace::Tracer>>, TraceError>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let Some(endpoint) = std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok().filter(|s| !s.is_empty()) else {
+        return Ok(None);
+    };
+    let service = std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| "rag".to_string());
+
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(opentelemetry_otlp::new_exporter().tonic().with_endpoint(endpoint))
+        .with_trace_config(trace::config().with_resource(Resource::new(vec![KeyValue::new("service.name", service)])))
+        .install_batch(runtime::Tokio);
+
+    Ok(Some(tracing_opentelemetry::layer().with_tracer(tracer?)))
+}
+
+/// Flush pending spans before exit.
+pub fn shutdown() {
+    opentelemetry::global::shutdown_tracer_provider();
+}
@@ -2,14 +2,20 @@ use anyhow::{anyhow, Result};
 use hf_hub::api::sync::Api;
 use tokenizers::Tokenizer;
 
+use crate::encoder::Prefixes;
+
 #[derive(Debug, Clone)]
 pub struct E5Tokenizer {
     inner: Tokenizer,
+    prefixes: Prefixes,
+    // encoder input limit: --max-seq-len or model_max_length (kept by for_chunking, which doesn't truncate)
+    max_len: usize,
 }
 
 impl E5Tokenizer {
     // force loads intfloat/e5-small-v2 tokenizer from the HF Hub + applies padding/truncation
-    pub fn new() -> Result<Self> {
+    // truncates at `max_seq_len` when given, else at tokenizer_config's model_max_length
+    pub fn new(max_seq_len: Option<usize>) -> Result<Self> {
         let mut tok = Tokenizer::from_pretrained("intfloat/e5-small-v2", None)
             .map_err(|e| anyhow!("{}", e))?;
 
@@ -37,9 +43,11 @@ impl E5Tokenizer {
             (model_max_len, padding_side_is_right, pad_id, u32::try_from(pad_type_id_cfg).unwrap_or(0), pad_token_str)
         };
 
+        let max_len = max_seq_len.unwrap_or(model_max_len);
+
         // apply truncation and padding based on tokenizer_config
         tok.with_truncation(Some(tokenizers::TruncationParams {
-            max_length: model_max_len,
+            max_length: max_len,
             stride: 0,
             strategy: tokenizers::TruncationStrategy::LongestFirst,
             direction: tokenizers::TruncationDirection::Right,
@@ -55,24 +63,58 @@ impl E5Tokenizer {
             pad_token,
         }));
 
-        Ok(Self { inner: tok })
+        Ok(Self { inner: tok, prefixes: Prefixes::default(), max_len })
+    }
+
+    /// same tokenizer without model_max_length truncation, so chunking sees the whole document
+    pub fn for_chunking() -> Result<Self> {
+        let mut tok = Self::new(None)?;
+        tok.inner.with_truncation(None).map_err(|e| anyhow!("{}", e))?;
+        Ok(tok)
+    }
+
+    /// swap the E5 `query: `/`passage: ` prefixes used by `ids_query`/`ids_passage`
+    pub fn with_prefixes(mut self, prefixes: Prefixes) -> Self {
+        self.prefixes = prefixes;
+        self
     }
 
-    /// encode a query: adds "query: " and special tokens
+    /// token limit the encoder truncates its input at
+    pub fn max_len(&self) -> usize { self.max_len }
+
+    /// chunk tokens that fit before truncation, after the passage prefix and special tokens
+    pub fn passage_budget(&self) -> Result<usize> {
+        let overhead = self.ids_passage("")?.len();
+        Ok(self.max_len.saturating_sub(overhead))
+    }
+
+    /// encode a query: adds the query prefix and special tokens
     pub fn ids_query(&self, text: &str) -> Result<Vec<u32>> {
         let enc = self.inner
-            .encode(format!("query: {text}"), true)
+            .encode(format!("{}{text}", self.prefixes.query), true)
             .map_err(|e| anyhow!("{}", e))?;
         Ok(enc.get_ids().to_vec())
     }
 
-    /// encode a passage: adds "passage: " and special tokens
+    /// encode a passage: adds the passage prefix and special tokens
     pub fn ids_passage(&self, text: &str) -> Result<Vec<u32>> {
-        let enc = self.inner.encode(format!("passage: {text}"), true)
+        let enc = self.inner.encode(format!("{}{text}", self.prefixes.passage), true)
         .map_err(|e| anyhow!("{}", e))?;
         Ok(enc.get_ids().to_vec())
     }
 
+    /// `ids_passage` plus each token's char offsets in `text` (not counting the prefix);
+    /// None for prefix and special tokens
+    pub fn ids_passage_offsets(&self, text: &str) -> Result<(Vec<u32>, Vec<Option<(usize, usize)>>)> {
+        let prefix_len = self.prefixes.passage.chars().count();
+        let enc = self.inner.encode_char_offsets(format!("{}{text}", self.prefixes.passage), true)
+            .map_err(|e| anyhow!("{}", e))?;
+        let offsets = enc.get_offsets().iter().zip(enc.get_special_tokens_mask())
+            .map(|(&(start, end), &special)| (special == 0 && start >= prefix_len).then(|| (start - prefix_len, end - prefix_len)))
+            .collect();
+        Ok((enc.get_ids().to_vec(), offsets))
+    }
+
     /// decode token IDs back to text, keeping special tokens and prefixes
     pub fn decode_ids(&self, ids: &[u32]) -> Result<String> {
         self.inner.decode(ids, false)
@@ -86,6 +86,15 @@ impl Gpt2Tokenizer {
         })
     }
 
+    /// Fetch `vocab.json` and `merges.txt` from a HF Hub repo (e.g. `openai-community/gpt2`).
+    pub fn from_hub(repo: &str) -> Result<Self> {
+        let api = hf_hub::api::sync::Api::new()?;
+        let repo = api.model(repo.to_string());
+        let vocab = repo.get("vocab.json").context("fetch vocab.json")?;
+        let merges = repo.get("merges.txt").context("fetch merges.txt")?;
+        Self::from_files(vocab, merges)
+    }
+
     /// Encode user-visible text into GPT-2 style token IDs.
     pub fn encode(&mut self, text: &str) -> Result<Vec<usize>> {
         let mut ids: Vec<usize> = Vec::new();
@@ -1,4 +1,5 @@
 pub mod e5;
+pub mod traits;
 
 #[cfg(feature = "gpt2-tokenizer")]
 pub mod bytes;
@@ -8,4 +9,22 @@ pub mod gpt2;
 pub use e5::E5Tokenizer;
 #[cfg(feature = "gpt2-tokenizer")]
 pub use gpt2::Gpt2Tokenizer;
+pub use traits::ChunkTokenizer;
 
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenizerKind {
+    E5,
+    #[cfg(feature = "gpt2-tokenizer")]
+    Gpt2,
+}
+
+impl TokenizerKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenizerKind::E5 => "e5",
+            #[cfg(feature = "gpt2-tokenizer")]
+            TokenizerKind::Gpt2 => "gpt2",
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use anyhow::Result;
+
+/// Tokenizer used by the chunker to count and slice passages.
+pub trait ChunkTokenizer {
+    fn encode_passage(&mut self, text: &str) -> Result<Vec<u32>>;
+    fn decode(&self, ids: &[u32]) -> Result<String>;
+    /// `encode_passage` plus each token's [start, end) char offsets in `text`; None for prefix and
+    /// special tokens, or for every token when the tokenizer has no offset mapping.
+    fn encode_passage_offsets(&mut self, text: &str) -> Result<(Vec<u32>, Vec<Option<(usize, usize)>>)> {
+        let ids = self.encode_passage(text)?;
+        let offsets = vec![None; ids.len()];
+        Ok((ids, offsets))
+    }
+    /// Longest chunk the embedding model sees whole, when the tokenizer knows it.
+    fn max_passage_tokens(&self) -> Option<usize> { None }
+}
+
+impl ChunkTokenizer for super::E5Tokenizer {
+    fn encode_passage(&mut self, text: &str) -> Result<Vec<u32>> { self.ids_passage(text) }
+    fn decode(&self, ids: &[u32]) -> Result<String> { self.decode_ids(ids) }
+    fn encode_passage_offsets(&mut self, text: &str) -> Result<(Vec<u32>, Vec<Option<(usize, usize)>>)> { self.ids_passage_offsets(text) }
+    fn max_passage_tokens(&self) -> Option<usize> { self.passage_budget().ok() }
+}
+
+#[cfg(feature = "gpt2-tokenizer")]
+impl ChunkTokenizer for super::Gpt2Tokenizer {
+    fn encode_passage(&mut self, text: &str) -> Result<Vec<u32>> {
+        Ok(self.encode(text)?.into_iter().map(|id| id as u32).collect())
+    }
+    fn decode(&self, ids: &[u32]) -> Result<String> {
+        let ids: Vec<usize> = ids.iter().map(|&id| id as usize).collect();
+        super::Gpt2Tokenizer::decode(self, &ids)
+    }
+}
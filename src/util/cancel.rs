@@ -0,0 +1,29 @@
+//! Process-wide cancellation. The CLI's Ctrl-C handler sets the flag; long-running loops
+//! (ingest, embed, gc deletes, reindex) poll it between batches, stop at a clean boundary,
+//! and still emit a result marked `cancelled`. Library callers can use `request()` too.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::telemetry::ascii::human;
+
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+
+pub fn request() {
+    CANCELLED.store(true, Ordering::SeqCst);
+}
+
+pub fn is_cancelled() -> bool {
+    CANCELLED.load(Ordering::SeqCst)
+}
+
+/// First Ctrl-C requests cancellation; a second one exits immediately (status 130).
+pub fn install_ctrl_c_handler() {
+    tokio::spawn(async {
+        if tokio::signal::ctrl_c().await.is_err() { return; }
+        request();
+        tracing::warn!("{}", human("🛑 Cancel requested — finishing the current batch (Ctrl-C again to abort)"));
+        if tokio::signal::ctrl_c().await.is_ok() {
+            std::process::exit(130);
+        }
+    });
+}
@@ -0,0 +1,172 @@
+//! `--config <file>` / `./ragfeed.toml`: flag defaults for the CLI. Values replace clap's
+//! built-in defaults, so anything passed on the command line still wins.
+//!
+//! Top-level keys apply to every subcommand that has that flag; a `[query]` (or `[feed.add]`)
+//! table applies to that subcommand only and wins over the top level. Keys are long flag names
+//! (`model-id` or `model_id`). `.json` files use the same layout.
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::Command;
+use serde_json::{Map, Value};
+
+use crate::util::exit::ConfigError;
+
+pub const DEFAULT_FILE: &str = "ragfeed.toml";
+
+/// `--config <file>` / `--config=<file>` from raw args (needed before clap parses them),
+/// else `./ragfeed.toml` when it exists.
+pub fn find_path(args: &[OsString]) -> Option<PathBuf> {
+    let mut it = args.iter().skip(1);
+    while let Some(a) = it.next() {
+        let a = a.to_string_lossy();
+        if a == "--" { break; }
+        if a == "--config" { return it.next().map(PathBuf::from); }
+        if let Some(p) = a.strip_prefix("--config=") { return Some(PathBuf::from(p)); }
+    }
+    let local = PathBuf::from(DEFAULT_FILE);
+    local.is_file().then_some(local)
+}
+
+pub fn load(path: &Path) -> Result<Map<String, Value>> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("read config {}", path.display()))?;
+    parse(&text, path.extension().is_some_and(|e| e == "json"))
+        .with_context(|| format!("parse config {}", path.display()))
+}
+
+fn parse(text: &str, json: bool) -> Result<Map<String, Value>> {
+    let value: Value = if json {
+        serde_json::from_str(text).map_err(|e| ConfigError(e.to_string()))?
+    } else {
+        toml::from_str(text).map_err(|e| ConfigError(e.to_string()))?
+    };
+    match value {
+        Value::Object(map) => Ok(map),
+        _ => Err(ConfigError("config must be a table of flag defaults".into()).into()),
+    }
+}
+
+/// Install the config values as defaults on `cmd` and its subcommands.
+pub fn apply(cmd: Command, config: &Map<String, Value>) -> Result<Command> {
+    apply_level(cmd, config, &Map::new(), true)
+}
+
+fn apply_level(mut cmd: Command, section: &Map<String, Value>, inherited: &Map<String, Value>, top: bool) -> Result<Command> {
+    // this level's scalars override what was inherited from outer levels
+    let mut scope = inherited.clone();
+    for (key, value) in section.iter().filter(|(_, v)| !v.is_object()) {
+        scope.insert(key.replace('_', "-"), value.clone());
+    }
+
+    for (key, value) in &scope {
+        let explicit = !top && section.keys().any(|k| k.replace('_', "-") == *key);
+        let id = cmd.get_arguments().find(|a| a.get_long() == Some(key.as_str())).map(|a| a.get_id().clone());
+        match id {
+            Some(id) => {
+                let values = flag_values(key, value)?;
+                cmd = cmd.mut_arg(id, |a| a.default_values(values));
+            }
+            // a section key must name one of its own flags; top-level keys only hit commands that have them
+            None if explicit => {
+                return Err(ConfigError(format!("config [{}]: unknown flag {:?}", cmd.get_name(), key)).into());
+            }
+            None => {}
+        }
+    }
+
+    let names: Vec<String> = cmd.get_subcommands().map(|s| s.get_name().to_string()).collect();
+    for key in section.iter().filter(|(_, v)| v.is_object()).map(|(k, _)| k) {
+        if !names.iter().any(|n| n == key) {
+            return Err(ConfigError(format!("config: unknown command section [{}]", key)).into());
+        }
+    }
+    for name in names {
+        let empty = Map::new();
+        let sub_section = section.get(&name).and_then(Value::as_object).unwrap_or(&empty);
+        let sub = cmd.find_subcommand(&name).cloned().expect("listed subcommand");
+        let sub = apply_level(sub, sub_section, &scope, false)?;
+        cmd = cmd.mut_subcommand(&name, move |_| sub);
+    }
+    Ok(cmd)
+}
+
+fn flag_values(key: &str, value: &Value) -> Result<Vec<String>> {
+    let scalar = |v: &Value| match v {
+        Value::String(s) => Ok(s.clone()),
+        Value::Bool(_) | Value::Number(_) => Ok(v.to_string()),
+        _ => Err(ConfigError(format!("config: {:?} must be a string, number, bool or list of those", key))),
+    };
+    match value {
+        Value::Array(items) => items.iter().map(scalar).collect::<Result<_, _>>().map_err(Into::into),
+        v => Ok(vec![scalar(v)?]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::{Arg, ArgAction};
+
+    fn cli() -> Command {
+        Command::new("rag")
+            .subcommand(
+                Command::new("query")
+                    .arg(Arg::new("text"))
+                    .arg(Arg::new("topk").long("topk").default_value("6"))
+                    .arg(Arg::new("model_id").long("model-id").default_value("intfloat/e5-small-v2")),
+            )
+            .subcommand(
+                Command::new("embed")
+                    .arg(Arg::new("model_id").long("model-id").default_value("intfloat/e5-small-v2"))
+                    .arg(Arg::new("apply").long("apply").action(ArgAction::SetTrue)),
+            )
+            .subcommand(Command::new("feed").subcommand(Command::new("ls").arg(Arg::new("active").long("active"))))
+    }
+
+    fn run(config: &str, args: &[&str]) -> Result<clap::ArgMatches> {
+        let cmd = apply(cli(), &parse(config, false)?)?;
+        Ok(cmd.try_get_matches_from(std::iter::once("rag").chain(args.iter().copied()))?)
+    }
+
+    fn get(m: &clap::ArgMatches, sub: &str, id: &str) -> String {
+        m.subcommand_matches(sub).unwrap().get_one::<String>(id).unwrap().clone()
+    }
+
+    #[test]
+    fn section_beats_top_level_and_flags_beat_both() {
+        let cfg = "model-id = \"bge\"\n[query]\ntopk = 10\nmodel_id = \"gte\"\n";
+        let m = run(cfg, &["query", "x"]).unwrap();
+        assert_eq!(get(&m, "query", "topk"), "10");
+        assert_eq!(get(&m, "query", "model_id"), "gte");
+        assert_eq!(get(&run(cfg, &["embed"]).unwrap(), "embed", "model_id"), "bge");
+        assert_eq!(get(&run(cfg, &["query", "x", "--topk", "3"]).unwrap(), "query", "topk"), "3");
+    }
+
+    #[test]
+    fn nested_sections_and_bool_flags() {
+        let m = run("[feed.ls]\nactive = true\n[embed]\napply = true\n", &["feed", "ls"]).unwrap();
+        let ls = m.subcommand_matches("feed").unwrap().subcommand_matches("ls").unwrap();
+        assert_eq!(ls.get_one::<String>("active").unwrap(), "true");
+        let m = run("[embed]\napply = true\n", &["embed"]).unwrap();
+        assert!(m.subcommand_matches("embed").unwrap().get_flag("apply"));
+    }
+
+    #[test]
+    fn unknown_section_or_flag_is_a_config_error() {
+        let err = run("[qeury]\ntopk = 1\n", &["query", "x"]).unwrap_err();
+        assert!(err.is::<ConfigError>());
+        let err = run("[query]\ntop-k = 1\n", &["query", "x"]).unwrap_err();
+        assert!(err.is::<ConfigError>());
+        // top-level keys are shared, so one that no command has is not an error
+        assert!(run("dim = 384\n", &["query", "x"]).is_ok());
+    }
+
+    #[test]
+    fn config_path_from_args() {
+        let args = |v: &[&str]| v.iter().map(OsString::from).collect::<Vec<_>>();
+        assert_eq!(find_path(&args(&["rag", "--config", "a.toml", "stats"])), Some(PathBuf::from("a.toml")));
+        assert_eq!(find_path(&args(&["rag", "query", "--config=b.json", "x"])), Some(PathBuf::from("b.json")));
+    }
+}
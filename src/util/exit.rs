@@ -0,0 +1,103 @@
+//! Process exit codes for scripting. Commands still return `anyhow::Result<()>`; `main`
+//! maps the outcome: 0 success, 3 nothing to do / no results, 4 configuration error,
+//! 5 upstream (LLM API, database, HTTP) error, 1 anything else, 130 Ctrl-C.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::llm::openai::OpenAiError;
+
+static EMPTY: AtomicBool = AtomicBool::new(false);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ExitStatus {
+    Success,
+    Empty,
+    Config,
+    Upstream,
+    Failure,
+}
+
+impl ExitStatus {
+    pub fn code(self) -> i32 {
+        match self {
+            ExitStatus::Success => 0,
+            ExitStatus::Failure => 1,
+            ExitStatus::Empty => 3,
+            ExitStatus::Config => 4,
+            ExitStatus::Upstream => 5,
+        }
+    }
+}
+
+/// A problem with flags, env or files the user has to fix; exits with 4.
+#[derive(Debug)]
+pub struct ConfigError(pub String);
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { f.write_str(&self.0) }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Record that the command succeeded without results or work (empty query, nothing to embed).
+pub fn mark_empty() {
+    EMPTY.store(true, Ordering::Relaxed);
+}
+
+/// Status for a command that returned Ok.
+pub fn success() -> ExitStatus {
+    if EMPTY.load(Ordering::Relaxed) { ExitStatus::Empty } else { ExitStatus::Success }
+}
+
+/// Status for a command that returned Err; the first recognized cause in the chain wins.
+pub fn classify(err: &anyhow::Error) -> ExitStatus {
+    for cause in err.chain() {
+        if cause.is::<ConfigError>() { return ExitStatus::Config; }
+        if let Some(e) = cause.downcast_ref::<OpenAiError>() {
+            return match e {
+                OpenAiError::MissingApiKey | OpenAiError::MissingEnv(_) => ExitStatus::Config,
+                OpenAiError::EmptyMessages | OpenAiError::MockQueueEmpty => ExitStatus::Failure,
+                OpenAiError::Http(_) | OpenAiError::Timeout | OpenAiError::Api { .. } | OpenAiError::Decode(_) => ExitStatus::Upstream,
+            };
+        }
+        if let Some(e) = cause.downcast_ref::<sqlx::Error>() {
+            return classify_sqlx(e);
+        }
+        if cause.is::<reqwest::Error>() { return ExitStatus::Upstream; }
+    }
+    ExitStatus::Failure
+}
+
+fn classify_sqlx(err: &sqlx::Error) -> ExitStatus {
+    match err {
+        sqlx::Error::Configuration(_) => ExitStatus::Config,
+        // 28xxx: bad credentials, 3D000: unknown database — both fixed in the DSN
+        sqlx::Error::Database(db) if db.code().is_some_and(|c| c.starts_with("28") || c == "3D000") => ExitStatus::Config,
+        sqlx::Error::Io(_) | sqlx::Error::Tls(_) | sqlx::Error::PoolTimedOut | sqlx::Error::PoolClosed => ExitStatus::Upstream,
+        _ => ExitStatus::Failure,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn classify_walks_the_context_chain() {
+        let err = Err::<(), _>(OpenAiError::MissingApiKey).context("call chat completion").unwrap_err();
+        assert_eq!(classify(&err), ExitStatus::Config);
+        let err = Err::<(), _>(OpenAiError::Timeout).context("call chat completion").unwrap_err();
+        assert_eq!(classify(&err), ExitStatus::Upstream);
+        let err = anyhow::Error::new(ConfigError("no DSN".into())).context("startup");
+        assert_eq!(classify(&err).code(), 4);
+        assert_eq!(classify(&anyhow::anyhow!("boom")), ExitStatus::Failure);
+    }
+
+    #[test]
+    fn sqlx_errors_split_config_and_upstream() {
+        assert_eq!(classify(&anyhow::Error::new(sqlx::Error::Configuration("bad url".into()))), ExitStatus::Config);
+        assert_eq!(classify(&anyhow::Error::new(sqlx::Error::PoolTimedOut)), ExitStatus::Upstream);
+        assert_eq!(classify(&anyhow::Error::new(sqlx::Error::RowNotFound)), ExitStatus::Failure);
+    }
+}
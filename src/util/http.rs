@@ -0,0 +1,92 @@
+//! One place to build `reqwest` clients so feeds, articles and LLM APIs share proxy and TLS settings.
+//! reqwest already honors `HTTP_PROXY`/`HTTPS_PROXY`/`NO_PROXY`; `RAG_PROXY` overrides them for
+//! every scheme (hosts in `NO_PROXY` still connect directly). `RAG_CA_BUNDLE` adds trusted roots
+//! (internal gateways with a private CA) and `--insecure` turns verification off; both are loaded
+//! once by `init`, which the CLI calls at startup and library callers may call themselves.
+
+use std::path::Path;
+use std::sync::OnceLock;
+
+use anyhow::{Context, Result};
+use reqwest::{Certificate, ClientBuilder, NoProxy, Proxy};
+
+use crate::telemetry::ascii::human;
+use crate::util::exit::ConfigError;
+
+struct Tls {
+    roots: Vec<Certificate>,
+    insecure: bool,
+}
+
+static TLS: OnceLock<Tls> = OnceLock::new();
+
+fn load_ca_bundle(path: &Path) -> Result<Vec<Certificate>> {
+    let pem = std::fs::read(path).with_context(|| format!("read RAG_CA_BUNDLE {}", path.display()))?;
+    let certs = Certificate::from_pem_bundle(&pem)
+        .map_err(|e| ConfigError(format!("RAG_CA_BUNDLE {}: not a PEM certificate bundle ({})", path.display(), e)))?;
+    if certs.is_empty() {
+        return Err(ConfigError(format!("RAG_CA_BUNDLE {}: no certificates found", path.display())).into());
+    }
+    Ok(certs)
+}
+
+/// Load `RAG_CA_BUNDLE` and record `insecure` for every client built afterwards; later calls are no-ops.
+pub fn init(insecure: bool) -> Result<()> {
+    if TLS.get().is_some() { return Ok(()); }
+    let roots = match std::env::var("RAG_CA_BUNDLE").ok().filter(|p| !p.trim().is_empty()) {
+        Some(path) => load_ca_bundle(Path::new(&path))?,
+        None => Vec::new(),
+    };
+    if insecure {
+        tracing::warn!("{}", human("⚠️  --insecure: TLS certificate verification is DISABLED for all outbound requests (feeds, articles, LLM APIs); never use this in production"));
+    }
+    let _ = TLS.set(Tls { roots, insecure });
+    Ok(())
+}
+
+// unset or empty means "use the standard proxy variables"
+fn proxy_from(url: Option<&str>) -> reqwest::Result<Option<Proxy>> {
+    let Some(url) = url.map(str::trim).filter(|u| !u.is_empty()) else { return Ok(None) };
+    Ok(Some(Proxy::all(url)?.no_proxy(NoProxy::from_env())))
+}
+
+/// A client builder with the crate's proxy and TLS settings applied; callers add timeouts, user agent, etc.
+pub fn client_builder() -> reqwest::Result<ClientBuilder> {
+    let mut builder = reqwest::Client::builder();
+    if let Some(proxy) = proxy_from(std::env::var("RAG_PROXY").ok().as_deref())? {
+        builder = builder.proxy(proxy);
+    }
+    if let Some(tls) = TLS.get() {
+        for cert in &tls.roots {
+            builder = builder.add_root_certificate(cert.clone());
+        }
+        if tls.insecure {
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+    }
+    Ok(builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rag_proxy_is_optional_and_validated() {
+        assert!(proxy_from(None).unwrap().is_none());
+        assert!(proxy_from(Some("  ")).unwrap().is_none());
+        assert!(proxy_from(Some("http://proxy.corp:3128")).unwrap().is_some());
+        assert!(proxy_from(Some("not a url")).is_err());
+    }
+
+    #[test]
+    fn bad_ca_bundles_are_config_errors() {
+        let dir = std::env::temp_dir().join(format!("ragfeed-ca-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let empty = dir.join("empty.pem");
+        std::fs::write(&empty, "no certificates here\n").unwrap();
+        assert!(load_ca_bundle(&empty).unwrap_err().is::<ConfigError>());
+        assert!(load_ca_bundle(&dir.join("missing.pem")).is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
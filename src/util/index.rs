@@ -0,0 +1,151 @@
+use std::borrow::Cow;
+
+use anyhow::Result;
+use regex::Regex;
+use serde::Serialize;
+use sqlx::PgPool;
+
+// Canonical name of the ANN index over rag.embedding(vec). The name is kept
+// regardless of the access method so migrations, reindex, and gc agree on it.
+pub const EMBEDDING_INDEX: &str = "embedding_vec_ivf_idx";
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IndexType {
+    #[value(name = "ivfflat")] Ivfflat,
+    #[value(name = "hnsw")] Hnsw,
+}
+
+impl IndexType {
+    pub fn as_str(&self) -> &'static str {
+        match self { IndexType::Ivfflat => "ivfflat", IndexType::Hnsw => "hnsw" }
+    }
+
+    pub fn from_am(am: &str) -> Option<Self> {
+        match am { "ivfflat" => Some(IndexType::Ivfflat), "hnsw" => Some(IndexType::Hnsw), _ => None }
+    }
+}
+
+// Element type of rag.embedding.vec: float32 `vector` or float16 `halfvec` (half the storage and
+// index size). The column type is the source of truth; embed --vector-type is checked against it.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VectorType {
+    #[default]
+    #[value(name = "float")] Float,
+    #[value(name = "half")] Half,
+}
+
+impl VectorType {
+    pub fn as_str(&self) -> &'static str {
+        match self { VectorType::Float => "float", VectorType::Half => "half" }
+    }
+
+    pub fn sql_type(&self) -> &'static str {
+        match self { VectorType::Float => "vector", VectorType::Half => "halfvec" }
+    }
+
+    pub fn from_sql_type(name: &str) -> Option<Self> {
+        match name { "vector" => Some(VectorType::Float), "halfvec" => Some(VectorType::Half), _ => None }
+    }
+
+    // operator class for the ANN index; must match the column type
+    pub fn cosine_ops(&self) -> &'static str {
+        match self { VectorType::Float => "vector_cosine_ops", VectorType::Half => "halfvec_cosine_ops" }
+    }
+
+    // Query vectors are bound as `vector`; against a halfvec column the parameter is cast so the
+    // halfvec operators (and the index) apply. Rewrites every `vec <-> $1` in `sql`; doc centroids
+    // (`<=>` on rag.doc_embedding) stay float32 and are left alone.
+    pub fn cast_param<'a>(&self, sql: &'a str) -> Cow<'a, str> {
+        match self {
+            VectorType::Float => Cow::Borrowed(sql),
+            VectorType::Half => Cow::Owned(sql.replace("vec <-> $1", "vec <-> $1::halfvec")),
+        }
+    }
+}
+
+/// Type of rag.embedding.vec; None when it is neither vector nor halfvec.
+pub async fn vec_column_type(pool: &PgPool) -> Result<Option<VectorType>> {
+    let name: Option<String> = sqlx::query_scalar(
+        r#"
+        SELECT t.typname::text
+        FROM pg_attribute a
+        JOIN pg_type t ON t.oid = a.atttypid
+        WHERE a.attrelid = 'rag.embedding'::regclass
+          AND a.attname = 'vec'
+          AND NOT a.attisdropped
+        "#,
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(name.as_deref().and_then(VectorType::from_sql_type))
+}
+
+// Access method and build options of an existing index, parsed from pg_get_indexdef.
+#[derive(Clone, Debug, Default)]
+pub struct IndexDef {
+    pub kind: Option<IndexType>,
+    pub lists: Option<i32>,
+    pub m: Option<i32>,
+    pub ef_construction: Option<i32>,
+}
+
+pub async fn describe_index(pool: &PgPool, name: &str) -> Result<Option<IndexDef>> {
+    let row = sqlx::query!(
+        r#"
+        SELECT am.amname::text AS method, pg_get_indexdef(i.indexrelid) AS def
+        FROM pg_index i
+        JOIN pg_class c ON c.oid = i.indexrelid
+        JOIN pg_am am ON am.oid = c.relam
+        JOIN pg_namespace nsp ON nsp.oid = c.relnamespace
+        WHERE nsp.nspname = 'rag' AND c.relname = $1
+        "#,
+        name
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(|r| {
+        let def = r.def.unwrap_or_default();
+        IndexDef {
+            kind: r.method.as_deref().and_then(IndexType::from_am),
+            lists: parse_option(&def, "lists"),
+            m: parse_option(&def, "m"),
+            ef_construction: parse_option(&def, "ef_construction"),
+        }
+    }))
+}
+
+// Postgres renders reloptions as `lists='150'`; accept the unquoted form as well.
+pub fn parse_option(def: &str, key: &str) -> Option<i32> {
+    let re = Regex::new(&format!(r"\b{}\s*=\s*'?([0-9]+)", regex::escape(key))).ok()?;
+    re.captures(def)?.get(1)?.as_str().parse::<i32>().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_option_reads_ivfflat_lists() {
+        let def = "CREATE INDEX embedding_vec_ivf_idx ON rag.embedding USING ivfflat (vec vector_cosine_ops) WITH (lists='150')";
+        assert_eq!(parse_option(def, "lists"), Some(150));
+        assert_eq!(parse_option(def, "m"), None);
+    }
+
+    #[test]
+    fn half_vectors_cast_the_query_parameter() {
+        let sql = "SELECT (e.vec <-> $1) AS distance FROM rag.embedding e WHERE e.model = $2";
+        assert_eq!(VectorType::Float.cast_param(sql), sql);
+        assert_eq!(VectorType::Half.cast_param(sql), "SELECT (e.vec <-> $1::halfvec) AS distance FROM rag.embedding e WHERE e.model = $2");
+        assert_eq!(VectorType::from_sql_type("halfvec"), Some(VectorType::Half));
+        assert_eq!(VectorType::from_sql_type("sparsevec"), None);
+    }
+
+    #[test]
+    fn parse_option_reads_hnsw_params() {
+        let def = "CREATE INDEX embedding_vec_ivf_idx ON rag.embedding USING hnsw (vec vector_cosine_ops) WITH (m='16', ef_construction='64')";
+        assert_eq!(parse_option(def, "m"), Some(16));
+        assert_eq!(parse_option(def, "ef_construction"), Some(64));
+        assert_eq!(parse_option("... WITH (lists = 42)", "lists"), Some(42));
+    }
+}
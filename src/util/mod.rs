@@ -1,2 +1,10 @@
 pub mod time;
 pub mod sql;
+pub mod index;
+pub mod cancel;
+pub mod text;
+pub mod exit;
+pub mod config;
+pub mod slow;
+pub mod quantize;
+pub mod http;
@@ -0,0 +1,25 @@
+// Binary quantization for query --quantized: one sign bit per dimension, rendered as a
+// '0'/'1' string that Postgres casts to the vec_bits bit string. Same rule as pgvector's
+// binary_quantize and the backfill in migration 20250909000000_embedding_bits, so codes written by
+// embed and ones backfilled in SQL agree.
+
+pub fn bit_string(vec: &[f32]) -> String {
+    vec.iter().map(|x| if *x > 0.0 { '1' } else { '0' }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hamming(a: &str, b: &str) -> usize { a.bytes().zip(b.bytes()).filter(|(x, y)| x != y).count() }
+
+    #[test]
+    fn sign_bits_track_direction() {
+        assert_eq!(bit_string(&[0.3, -0.1, 0.0, 1e-6]), "1001");
+        let a = bit_string(&[0.2, 0.1, -0.4, 0.5]);
+        let near = bit_string(&[0.25, 0.05, -0.3, -0.01]);
+        let far = bit_string(&[-0.2, -0.1, 0.4, -0.5]);
+        assert_eq!(hamming(&a, &near), 1);
+        assert_eq!(hamming(&a, &far), 4);
+    }
+}
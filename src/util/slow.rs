@@ -0,0 +1,50 @@
+//! `RAG_SLOW_QUERY_MS`: time labelled DB calls and warn when one takes longer than the threshold.
+
+use std::future::Future;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use crate::telemetry::ctx::{LogCtx, OpMarker};
+
+static THRESHOLD: OnceLock<Option<Duration>> = OnceLock::new();
+
+// unset, empty, 0 or unparseable all mean off
+fn parse_threshold(v: Option<&str>) -> Option<Duration> {
+    v.and_then(|s| s.trim().parse::<u64>().ok()).filter(|ms| *ms > 0).map(Duration::from_millis)
+}
+
+/// Slow-query threshold, read once from the environment.
+pub fn threshold() -> Option<Duration> {
+    *THRESHOLD.get_or_init(|| parse_threshold(std::env::var("RAG_SLOW_QUERY_MS").ok().as_deref()))
+}
+
+/// Await `fut`; when it outlasts `RAG_SLOW_QUERY_MS`, log a warning naming `label`.
+pub async fn timed<O: OpMarker, T>(log: Option<&LogCtx<O>>, label: &str, fut: impl Future<Output = T>) -> T {
+    let Some(limit) = threshold() else { return fut.await };
+    let t0 = Instant::now();
+    let out = fut.await;
+    let elapsed = t0.elapsed();
+    if elapsed >= limit {
+        if let Some(log) = log {
+            log.warn_kv(
+                &format!("🐢 Slow query: {} took {} ms (RAG_SLOW_QUERY_MS={})", label, elapsed.as_millis(), limit.as_millis()),
+                [("label", label.to_string()), ("elapsed_ms", elapsed.as_millis().to_string())],
+            );
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn threshold_parsing() {
+        assert_eq!(parse_threshold(Some("250")), Some(Duration::from_millis(250)));
+        assert_eq!(parse_threshold(Some(" 40 ")), Some(Duration::from_millis(40)));
+        for off in [None, Some(""), Some("0"), Some("fast"), Some("-5")] {
+            assert_eq!(parse_threshold(off), None, "{off:?}");
+        }
+    }
+}
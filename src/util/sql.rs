@@ -1,16 +1,36 @@
 use anyhow::Result;
-use sqlx::{postgres::PgArguments, Postgres, PgPool};
+use sqlx::{postgres::PgArguments, PgConnection, Postgres, PgPool};
 use sqlx::query::Query;
 
+use super::cancel;
+
 // Generic paged execution loop for DELETEs (or any query that returns rows_affected).
 // The `build` closure should produce a query with a LIMIT placeholder bound last.
+// Stops between batches once cancellation is requested (each batch commits on its own).
 pub async fn paged_loop<F, C>(pool: &PgPool, mut build: F, batch: i64, mut on_batch: C) -> Result<()>
+where
+    F: FnMut(i64) -> Query<'static, Postgres, PgArguments>,
+    C: FnMut(u64),
+{
+    let mut conn = pool.acquire().await?;
+    while !cancel::is_cancelled() {
+        let res = build(batch).execute(&mut *conn).await?;
+        let n = res.rows_affected();
+        if n == 0 { break; }
+        on_batch(n);
+    }
+    Ok(())
+}
+
+// Same loop on a single connection, so the batches can share a transaction (`&mut *tx`).
+// Not cancellable: a transaction is meant to finish or roll back as a whole.
+pub async fn paged_loop_conn<F, C>(conn: &mut PgConnection, mut build: F, batch: i64, mut on_batch: C) -> Result<()>
 where
     F: FnMut(i64) -> Query<'static, Postgres, PgArguments>,
     C: FnMut(u64),
 {
     loop {
-        let res = build(batch).execute(pool).await?;
+        let res = build(batch).execute(&mut *conn).await?;
         let n = res.rows_affected();
         if n == 0 { break; }
         on_batch(n);
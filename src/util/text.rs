@@ -0,0 +1,179 @@
+//! Text normalization shared by extractors and the chunker.
+
+use std::borrow::Cow;
+
+use unicode_normalization::UnicodeNormalization;
+
+/// How `text_clean` is normalized before chunking.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Normalize {
+    /// Chunk the stored text as-is
+    None,
+    /// Strip zero-width chars, fold odd spaces and smart quotes, collapse whitespace
+    Basic,
+    /// NFKC compatibility normalization, then `basic`
+    Nfkc,
+}
+
+impl Normalize {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Normalize::None => "none",
+            Normalize::Basic => "basic",
+            Normalize::Nfkc => "nfkc",
+        }
+    }
+}
+
+pub fn normalize(text: &str, mode: Normalize) -> Cow<'_, str> {
+    match mode {
+        Normalize::None => Cow::Borrowed(text),
+        Normalize::Basic => Cow::Owned(basic(text)),
+        Normalize::Nfkc => Cow::Owned(basic(&text.nfkc().collect::<String>())),
+    }
+}
+
+/// `normalize`, plus for each char of the result the index of the `text` char it came from, so
+/// positions in the normalized text map back to `text_clean`. None under nfkc, whose
+/// compositions don't map char-to-char.
+pub fn normalize_mapped(text: &str, mode: Normalize) -> (Cow<'_, str>, Option<Vec<usize>>) {
+    match mode {
+        Normalize::None => (Cow::Borrowed(text), Some((0..text.chars().count()).collect())),
+        Normalize::Basic => {
+            let (out, map) = basic_mapped(text);
+            (Cow::Owned(out), Some(map))
+        }
+        Normalize::Nfkc => (normalize(text, mode), None),
+    }
+}
+
+/// Collapse every whitespace run (newlines included) to one space and trim.
+pub fn collapse_whitespace(s: &str) -> String {
+    let mut buf = String::with_capacity(s.len());
+    let mut in_ws = false;
+    for ch in s.chars() {
+        if ch.is_whitespace() {
+            if !in_ws {
+                if !buf.is_empty() { buf.push(' '); }
+                in_ws = true;
+            }
+        } else {
+            buf.push(ch);
+            in_ws = false;
+        }
+    }
+    buf.trim().to_string()
+}
+
+// Paragraph breaks survive as a single blank line; everything else collapses like collapse_whitespace
+fn basic(s: &str) -> String {
+    let folded: String = s.chars().filter_map(fold_char).collect();
+    let mut out = String::with_capacity(folded.len());
+    let mut blank_run = false;
+    for line in folded.lines() {
+        let line = collapse_whitespace(line);
+        if line.is_empty() {
+            blank_run = !out.is_empty();
+            continue;
+        }
+        if !out.is_empty() { out.push_str(if blank_run { "\n\n" } else { "\n" }); }
+        out.push_str(&line);
+        blank_run = false;
+    }
+    out
+}
+
+// `basic` over (char, source index) pairs; must produce exactly the same string
+fn basic_mapped(s: &str) -> (String, Vec<usize>) {
+    let folded: Vec<(char, usize)> = s.chars().enumerate().filter_map(|(i, c)| fold_char(c).map(|c| (c, i))).collect();
+    let mut out: Vec<(char, usize)> = Vec::with_capacity(folded.len());
+    let mut blank_run = false;
+    for line in folded.split(|(c, _)| *c == '\n') {
+        // collapse_whitespace: a run becomes one space at its first char, then trim
+        let mut buf: Vec<(char, usize)> = Vec::with_capacity(line.len());
+        let mut in_ws = false;
+        for &(ch, i) in line {
+            if ch.is_whitespace() {
+                if !in_ws {
+                    if !buf.is_empty() { buf.push((' ', i)); }
+                    in_ws = true;
+                }
+            } else {
+                buf.push((ch, i));
+                in_ws = false;
+            }
+        }
+        if buf.last().is_some_and(|(c, _)| *c == ' ') { buf.pop(); }
+        if buf.is_empty() {
+            blank_run = !out.is_empty();
+            continue;
+        }
+        // line breaks point at the first char of the line they introduce
+        if !out.is_empty() {
+            let at = buf[0].1;
+            out.push(('\n', at));
+            if blank_run { out.push(('\n', at)); }
+        }
+        out.extend(buf);
+        blank_run = false;
+    }
+    (out.iter().map(|(c, _)| *c).collect(), out.iter().map(|(_, i)| *i).collect())
+}
+
+fn fold_char(ch: char) -> Option<char> {
+    match ch {
+        // zero-width space/joiners, word joiner, BOM, soft hyphen
+        '\u{200B}' | '\u{200C}' | '\u{200D}' | '\u{2060}' | '\u{FEFF}' | '\u{00AD}' => None,
+        // no-break and fixed-width spaces
+        '\u{00A0}' | '\u{2000}'..='\u{200A}' | '\u{202F}' | '\u{205F}' | '\u{3000}' => Some(' '),
+        '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' | '\u{2032}' => Some('\''),
+        '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' | '\u{2033}' => Some('"'),
+        '\r' => None,
+        c => Some(c),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collapse_whitespace_flattens_runs_and_trims() {
+        assert_eq!(collapse_whitespace("  a \n\t b  c "), "a b c");
+        assert_eq!(collapse_whitespace(" \n "), "");
+    }
+
+    #[test]
+    fn basic_folds_invisible_chars_quotes_and_spacing() {
+        let raw = "It\u{2019}s\u{00A0}a  \u{201C}test\u{201D}\u{200B}.\r\n  second   line \n\n\n\nnext para";
+        assert_eq!(normalize(raw, Normalize::Basic), "It's a \"test\".\nsecond line\n\nnext para");
+    }
+
+    #[test]
+    fn nfkc_also_folds_compatibility_forms() {
+        assert_eq!(normalize("ﬁle ＡＢＣ ①", Normalize::Nfkc), "file ABC 1");
+        assert_eq!(normalize("ﬁle", Normalize::Basic), "ﬁle");
+    }
+
+    #[test]
+    fn mapped_normalization_matches_and_points_back() {
+        let raw = "It\u{2019}s\u{00A0}a  \u{201C}test\u{201D}\u{200B}.\r\n  second   line \n\n\n\nnext para\n";
+        let (text, map) = normalize_mapped(raw, Normalize::Basic);
+        assert_eq!(text, normalize(raw, Normalize::Basic));
+        let map = map.unwrap();
+        assert_eq!(map.len(), text.chars().count());
+        let src: Vec<char> = raw.chars().collect();
+        let at = text.find("second").unwrap();
+        let start = map[text[..at].chars().count()];
+        assert_eq!(src[start..start + 6].iter().collect::<String>(), "second");
+        assert!(normalize_mapped(raw, Normalize::Nfkc).1.is_none());
+        assert_eq!(normalize_mapped("a\u{00A0} b", Normalize::None).1.unwrap(), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn none_is_untouched() {
+        let raw = "a\u{00A0} b";
+        assert!(matches!(normalize(raw, Normalize::None), Cow::Borrowed(s) if s == raw));
+    }
+}
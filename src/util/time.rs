@@ -1,38 +1,130 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use chrono::{DateTime, Duration, NaiveDate, Utc};
 
-// Parse a window string like "2d", "YYYY-MM-DD", or RFC3339 into a UTC timestamp.
-// Returns Some(ts) on success; None if unparseable.
-pub fn parse_window_str(s: &str) -> Option<DateTime<Utc>> {
-    // "2d" -> now - 2 days
-    if let Some(stripped) = s.strip_suffix('d') {
-        if let Ok(days) = stripped.parse::<i64>() {
-            if days > 0 {
-                return Some(Utc::now() - Duration::days(days));
-            }
-        }
+use crate::util::exit::ConfigError;
+
+// Parse a relative span like "90m", "6h", "2d", "2w", "1mo", "1y" or a compound "1d12h".
+// Units: s, m (minutes), h, d, w, mo (30 days), y (365 days). None if not of that shape.
+pub fn parse_duration(s: &str) -> Option<Duration> {
+    let mut rest = s.trim();
+    if rest.is_empty() { return None; }
+    let mut total = Duration::zero();
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        if digits == 0 { return None; }
+        let n: i64 = rest[..digits].parse().ok()?;
+        rest = &rest[digits..];
+        let unit_len = rest.find(|c: char| c.is_ascii_digit()).unwrap_or(rest.len());
+        let step = match &rest[..unit_len] {
+            "s" => Duration::try_seconds(n)?,
+            "m" => Duration::try_minutes(n)?,
+            "h" => Duration::try_hours(n)?,
+            "d" => Duration::try_days(n)?,
+            "w" => Duration::try_weeks(n)?,
+            "mo" => Duration::try_days(n.checked_mul(30)?)?,
+            "y" => Duration::try_days(n.checked_mul(365)?)?,
+            _ => return None,
+        };
+        total = total.checked_add(&step)?;
+        rest = &rest[unit_len..];
+    }
+    Some(total)
+}
+
+// Parse a window string (relative span, "YYYY-MM-DD" or RFC3339) into a UTC timestamp;
+// relative spans count back from `now`. Anything else is a ConfigError.
+pub fn parse_window_at(s: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    let s = s.trim();
+    if let Some(d) = parse_duration(s) {
+        return Ok(now - d);
     }
     // "YYYY-MM-DD"
     if let Ok(nd) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
         if let Some(dt) = nd.and_hms_opt(0, 0, 0) {
-            return Some(DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc));
+            return Ok(DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc));
         }
     }
     // RFC3339
     if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
-        return Some(dt.with_timezone(&Utc));
+        return Ok(dt.with_timezone(&Utc));
     }
-    None
+    Err(ConfigError(format!(
+        "unrecognized time window {:?} (use e.g. 90m, 6h, 2d, 2w, 1mo, 1d12h, YYYY-MM-DD or RFC3339)", s
+    )).into())
 }
 
-// Helper for Option<String> inputs used by CLI flags like --since
+pub fn parse_window(s: &str) -> Result<DateTime<Utc>> {
+    parse_window_at(s, Utc::now())
+}
+
+// Helper for Option<String> inputs used by CLI flags like --since:
+// Ok(None) when the flag is absent, Err when it is present but invalid
 pub fn parse_since_opt(since: &Option<String>) -> Result<Option<DateTime<Utc>>> {
     let Some(s) = since.as_ref() else { return Ok(None) };
-    Ok(parse_window_str(s))
+    parse_window(s).map(Some).context("invalid --since")
 }
 
-// Specific name used by gc for older_than/cutoff parsing
-pub fn parse_cutoff_str(s: &str) -> Option<DateTime<Utc>> {
-    parse_window_str(s)
+// Specific name used by gc for older_than/cutoff parsing; an empty string means no cutoff
+pub fn parse_cutoff_str(s: &str) -> Result<Option<DateTime<Utc>>> {
+    if s.trim().is_empty() { return Ok(None); }
+    parse_window(s).map(Some).context("invalid --older-than")
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2025-09-10T12:00:00Z").unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn accepted_formats() {
+        let cases = [
+            ("30s", "2025-09-10T11:59:30Z"),
+            ("90m", "2025-09-10T10:30:00Z"),
+            ("6h", "2025-09-10T06:00:00Z"),
+            ("2d", "2025-09-08T12:00:00Z"),
+            ("2w", "2025-08-27T12:00:00Z"),
+            ("1mo", "2025-08-11T12:00:00Z"),
+            ("1y", "2024-09-10T12:00:00Z"),
+            ("1d12h", "2025-09-09T00:00:00Z"),
+            ("1w2d", "2025-09-01T12:00:00Z"),
+            ("0d", "2025-09-10T12:00:00Z"),
+            (" 6h ", "2025-09-10T06:00:00Z"),
+            ("2025-09-01", "2025-09-01T00:00:00Z"),
+            ("2025-09-01T08:30:00+02:00", "2025-09-01T06:30:00Z"),
+        ];
+        for (input, want) in cases {
+            let want = DateTime::parse_from_rfc3339(want).unwrap().with_timezone(&Utc);
+            assert_eq!(parse_window_at(input, now()).unwrap(), want, "input {input:?}");
+        }
+    }
+
+    #[test]
+    fn malformed_input_is_a_config_error() {
+        for input in ["", "d", "2", "2x", "6hours", "h6", "-2d", "2025-13-01", "yesterday", "1mo2"] {
+            let err = parse_window_at(input, now()).unwrap_err();
+            assert!(err.is::<ConfigError>(), "input {input:?}");
+        }
+    }
+
+    #[test]
+    fn since_and_cutoff_helpers() {
+        assert_eq!(parse_since_opt(&None).unwrap(), None);
+        assert!(parse_since_opt(&Some("2d".into())).unwrap().is_some());
+        assert!(parse_since_opt(&Some("2 days".into())).is_err());
+        assert_eq!(parse_cutoff_str("").unwrap(), None);
+        assert!(parse_cutoff_str("30d").unwrap().is_some());
+    }
+
+    #[test]
+    fn invalid_flag_values_name_the_flag_and_stay_config_errors() {
+        let err = parse_since_opt(&Some("garbag".into())).unwrap_err();
+        assert!(format!("{err:#}").starts_with("invalid --since: unrecognized time window \"garbag\""));
+        assert_eq!(crate::util::exit::classify(&err), crate::util::exit::ExitStatus::Config);
+        let err = parse_cutoff_str("oops").unwrap_err();
+        assert!(format!("{err:#}").starts_with("invalid --older-than"));
+        assert_eq!(crate::util::exit::classify(&err), crate::util::exit::ExitStatus::Config);
+    }
+}
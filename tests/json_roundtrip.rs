@@ -0,0 +1,76 @@
+//! JSON contract for the result DTOs: what a command prints must read back into the same struct.
+
+use chrono::{TimeZone, Utc};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use ragfeed::output::types::Progress;
+use ragfeed::query::post::{ExplainInfo, QueryResultRow};
+use ragfeed::stats::types::*;
+
+// serialize → deserialize → serialize must be a fixed point
+fn round_trip<T: Serialize + DeserializeOwned>(value: &T) -> T {
+    let json = serde_json::to_value(value).expect("serialize");
+    let back: T = serde_json::from_value(json.clone()).expect("deserialize");
+    assert_eq!(serde_json::to_value(&back).expect("re-serialize"), json);
+    back
+}
+
+fn summary() -> StatsSummary {
+    StatsSummary {
+        feeds: vec![StatsFeedRow { feed_id: 1, name: Some("arXiv cs.CL".into()), url: "https://rss.arxiv.org/rss/cs.CL".into(), is_active: Some(true), added_at: Some(Utc.with_ymd_and_hms(2025, 9, 1, 12, 0, 0).unwrap()) }],
+        documents_by_status: vec![StatsDocStatus { status: "embedded".into(), cnt: 40 }, StatsDocStatus { status: "error".into(), cnt: 2 }],
+        errors_by_kind: vec![StatsErrorKind { kind: "timeout".into(), transient: true, cnt: 2 }],
+        last_fetched: Some(Utc.with_ymd_and_hms(2025, 9, 2, 8, 30, 0).unwrap()),
+        chunks: StatsChunksSummary { total: 120, avg_tokens: 310.5, min_tokens: Some(12), max_tokens: Some(350), median_tokens: Some(340.0), p95_tokens: Some(350.0), histogram: vec![StatsTokenBucket { lo: 0, hi: Some(128), cnt: 4 }, StatsTokenBucket { lo: 256, hi: None, cnt: 116 }] },
+        embeddings: StatsEmbeddings { total: 118, models: vec![StatsModelInfo { model: "intfloat/e5-small-v2@cpu".into(), cnt: 118, last: None }] },
+        index: StatsIndexMeta { index_type: Some("hnsw".into()), lists: None, m: Some(16), ef_construction: Some(64), size_pretty: Some("1 MB".into()), last_analyze: None },
+        coverage: StatsCoverage { chunks: 120, embedded: 118, pct: 98.3, missing: 2 },
+    }
+}
+
+#[test]
+fn query_rows_round_trip() {
+    let rows = vec![
+        QueryResultRow { rank: 1, distance: 0.125, chunk_id: 7, doc_id: 3, title: Some("Doc".into()), preview: Some("preview".into()), text: None, rerank_score: None },
+        QueryResultRow { rank: 2, distance: 0.25, chunk_id: 9, doc_id: 4, title: None, preview: None, text: Some("full text".into()), rerank_score: Some(0.75) },
+    ];
+    assert_eq!(round_trip(&rows), rows);
+
+    let explain = ExplainInfo { probes: Some(10), ef_search: None, scan: "index".into(), index: Some("embedding_vec_idx".into()), execution_ms: Some(1.5), plan: vec!["Index Scan using embedding_vec_idx".into()] };
+    assert_eq!(round_trip(&explain), explain);
+}
+
+#[test]
+fn stats_views_round_trip() {
+    round_trip(&summary());
+
+    let with_diff = StatsSummaryWithDiff {
+        summary: summary(),
+        diff: StatsSummaryDiff {
+            documents_by_status: vec![StatsStatusDelta { status: "embedded".into(), before: 30, after: 40, delta: 10 }],
+            chunks: StatsCountDelta { before: 100, after: 120, delta: 20 },
+            embeddings: StatsCountDelta { before: 90, after: 118, delta: 28 },
+            coverage_embedded: StatsCountDelta { before: 90, after: 118, delta: 28 },
+            coverage_pct: StatsPctDelta { before: 90.0, after: 98.3, delta: 8.3 },
+            missing: StatsCountDelta { before: 10, after: 2, delta: -8 },
+        },
+    };
+    round_trip(&with_diff);
+
+    let doc = StatsDocSnapshot {
+        doc: StatsDocInfo { doc_id: 3, feed_id: Some(1), source_url: "https://example.org/a".into(), source_title: None, published_at: None, fetched_at: None, status: Some("error".into()), error_msg: Some("fetch-failed".into()), preview: None },
+        chunks: vec![StatsDocChunkInfo { chunk_id: 7, chunk_index: Some(0), token_count: Some(340) }],
+    };
+    round_trip(&doc);
+
+    round_trip(&Progress { done: 3, total: None, unit: "docs".into() });
+}
+
+#[test]
+fn snapshots_without_error_kinds_still_load() {
+    let mut json = serde_json::to_value(summary()).unwrap();
+    json.as_object_mut().unwrap().remove("errors_by_kind");
+    let back: StatsSummary = serde_json::from_value(json).unwrap();
+    assert!(back.errors_by_kind.is_empty());
+}